@@ -2,7 +2,10 @@
 
 use crate::format_err;
 use crate::utils::to_err;
-use js_sys::{Reflect, UriError};
+use js_sys::{Map, Reflect, UriError};
+use oxigraph::model::canonicalization::{
+    CanonicalizationHashAlgorithm, CanonicalizedDataset, Canonicalizer,
+};
 use oxigraph::model::*;
 use oxigraph::sparql::Variable;
 use wasm_bindgen::prelude::*;
@@ -94,6 +97,67 @@ pub fn from_quad(original: &JsValue) -> Result<JsValue, JsValue> {
     })
 }
 
+#[wasm_bindgen(js_name = canonicalize)]
+pub fn canonicalize(
+    quads: Box<[JsValue]>,
+    hash_algorithm: Option<String>,
+    work_limit: Option<u32>,
+) -> Result<JsCanonicalizedDataset, JsValue> {
+    let mut canonicalizer = Canonicalizer::new();
+    if let Some(hash_algorithm) = hash_algorithm {
+        canonicalizer = canonicalizer.with_hash_algorithm(
+            CanonicalizationHashAlgorithm::from_name(&hash_algorithm)
+                .ok_or_else(|| format_err!("Not supported hash algorithm: {}", hash_algorithm))?,
+        );
+    }
+    if let Some(work_limit) = work_limit {
+        canonicalizer = canonicalizer.with_work_limit(work_limit.try_into().map_err(to_err)?);
+    }
+    let quads = quads
+        .iter()
+        .map(|quad| FROM_JS.with(|c| c.to_quad(quad)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(JsCanonicalizedDataset {
+        inner: canonicalizer.canonicalize_quads(quads).map_err(to_err)?,
+    })
+}
+
+#[wasm_bindgen(js_name = CanonicalizedDataset)]
+pub struct JsCanonicalizedDataset {
+    inner: CanonicalizedDataset,
+}
+
+#[wasm_bindgen(js_class = CanonicalizedDataset)]
+impl JsCanonicalizedDataset {
+    #[wasm_bindgen(getter = nQuads)]
+    pub fn n_quads(&self) -> String {
+        self.inner.n_quads().to_owned()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn quads(&self) -> Box<[JsValue]> {
+        self.inner
+            .quads()
+            .iter()
+            .map(|quad| JsQuad::from(quad.clone()).into())
+            .collect()
+    }
+
+    #[wasm_bindgen(getter = issuedIdentifiers)]
+    pub fn issued_identifiers(&self) -> Map {
+        let identifiers = Map::new();
+        for (original, canonical) in self.inner.issued_identifiers() {
+            identifiers.set(&original.as_str().into(), &canonical.as_str().into());
+        }
+        identifiers
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hash(&self) -> String {
+        self.inner.hash().to_owned()
+    }
+}
+
 #[wasm_bindgen(js_name = NamedNode)]
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub struct JsNamedNode {
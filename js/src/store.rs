@@ -1,8 +1,8 @@
 use crate::format_err;
 use crate::model::*;
 use crate::utils::to_err;
-use js_sys::{Array, Map};
-use oxigraph::io::{DatasetFormat, GraphFormat};
+use js_sys::{Array, Map, Object};
+use oxigraph::io::{DatasetFormat, DatasetSerializer, GraphFormat, GraphSerializer};
 use oxigraph::model::*;
 use oxigraph::sparql::QueryResults;
 use oxigraph::store::Store;
@@ -188,32 +188,102 @@ impl JsStore {
         }
     }
 
-    pub fn dump(&self, mime_type: &str, from_graph_name: &JsValue) -> Result<String, JsValue> {
+    pub fn dump(
+        &self,
+        mime_type: &str,
+        from_graph_name: &JsValue,
+        prefixes: &JsValue,
+        base_iri: &JsValue,
+    ) -> Result<String, JsValue> {
         let from_graph_name =
             if let Some(graph_name) = FROM_JS.with(|c| c.to_optional_term(from_graph_name))? {
                 Some(graph_name.try_into()?)
             } else {
                 None
             };
+        let prefixes = if prefixes.is_null() || prefixes.is_undefined() {
+            None
+        } else if prefixes.is_object() {
+            let mut result = Vec::new();
+            for entry in Object::entries(&Object::from(prefixes.clone())).iter() {
+                let entry = Array::from(&entry);
+                match (entry.get(0).as_string(), entry.get(1).as_string()) {
+                    (Some(prefix_name), Some(prefix_iri)) => result.push((prefix_name, prefix_iri)),
+                    _ => return Err(format_err!("The prefix IRIs should be strings")),
+                }
+            }
+            Some(result)
+        } else {
+            return Err(format_err!(
+                "If provided, the prefixes should be an object mapping prefix names to IRIs"
+            ));
+        };
+        let base_iri = if base_iri.is_null() || base_iri.is_undefined() {
+            None
+        } else if base_iri.is_string() {
+            base_iri.as_string()
+        } else if let JsTerm::NamedNode(base_iri) = FROM_JS.with(|c| c.to_term(base_iri))? {
+            Some(base_iri.value())
+        } else {
+            return Err(format_err!(
+                "If provided, the base IRI should be a NamedNode or a string"
+            ));
+        };
+        let pretty = prefixes.is_some() || base_iri.is_some();
 
         let mut buffer = Vec::new();
         if let Some(graph_format) = GraphFormat::from_media_type(mime_type) {
-            self.store
-                .dump_graph(
-                    &mut buffer,
-                    graph_format,
-                    &from_graph_name.unwrap_or(GraphName::DefaultGraph),
-                )
-                .map_err(to_err)?;
+            let mut serializer = GraphSerializer::from_format(graph_format);
+            for (prefix_name, prefix_iri) in prefixes.into_iter().flatten() {
+                serializer = serializer
+                    .with_prefix(prefix_name, prefix_iri)
+                    .map_err(to_err)?;
+            }
+            if let Some(base_iri) = base_iri {
+                serializer = serializer.with_base_iri(base_iri).map_err(to_err)?;
+            }
+            if pretty {
+                serializer = serializer.pretty();
+            }
+            let mut writer = serializer.triple_writer(&mut buffer).map_err(to_err)?;
+            for quad in self.store.quads_for_pattern(
+                None,
+                None,
+                None,
+                Some(
+                    from_graph_name
+                        .as_ref()
+                        .map_or(GraphNameRef::DefaultGraph, GraphName::as_ref),
+                ),
+            ) {
+                writer
+                    .write(quad.map_err(to_err)?.as_ref())
+                    .map_err(to_err)?;
+            }
+            writer.finish().map_err(to_err)?;
         } else if let Some(dataset_format) = DatasetFormat::from_media_type(mime_type) {
             if from_graph_name.is_some() {
                 return Err(format_err!(
                     "The target graph name parameter is not available for dataset formats"
                 ));
             }
-            self.store
-                .dump_dataset(&mut buffer, dataset_format)
-                .map_err(to_err)?;
+            let mut serializer = DatasetSerializer::from_format(dataset_format);
+            for (prefix_name, prefix_iri) in prefixes.into_iter().flatten() {
+                serializer = serializer
+                    .with_prefix(prefix_name, prefix_iri)
+                    .map_err(to_err)?;
+            }
+            if let Some(base_iri) = base_iri {
+                serializer = serializer.with_base_iri(base_iri).map_err(to_err)?;
+            }
+            if pretty {
+                serializer = serializer.pretty();
+            }
+            let mut writer = serializer.quad_writer(&mut buffer).map_err(to_err)?;
+            for quad in self.store.iter() {
+                writer.write(&quad.map_err(to_err)?).map_err(to_err)?;
+            }
+            writer.finish().map_err(to_err)?;
         } else {
             return Err(format_err!("Not supported MIME type: {}", mime_type));
         }
@@ -35,4 +35,30 @@ describe('DataModel', function () {
       )
     })
   })
+
+  describe('#canonicalize()', function () {
+    it('canonicalize() should give the same output for isomorphic datasets', function () {
+      const p = oxigraph.namedNode('http://example.com/p')
+      const x = oxigraph.blankNode()
+      const y = oxigraph.blankNode()
+      const result1 = oxigraph.canonicalize([oxigraph.quad(x, p, y)])
+      const result2 = oxigraph.canonicalize([oxigraph.quad(y, p, x)])
+      assert.strictEqual('_:c14n1 <http://example.com/p> _:c14n0 .\n', result1.nQuads)
+      assert.strictEqual(result1.hash, result2.hash)
+      assert.strictEqual('c14n1', result1.issuedIdentifiers.get(x.value))
+      assert(oxigraph.quad(oxigraph.blankNode('c14n1'), p, oxigraph.blankNode('c14n0')).equals(result1.quads[0]))
+    })
+
+    it('canonicalize() should support SHA-384', function () {
+      const p = oxigraph.namedNode('http://example.com/p')
+      assert.strictEqual(96, oxigraph.canonicalize([oxigraph.quad(oxigraph.blankNode(), p, p)], 'sha384').hash.length)
+    })
+
+    it('canonicalize() should enforce the work limit', function () {
+      const p = oxigraph.namedNode('http://example.com/p')
+      const nodes = [...Array(6).keys()].map(() => oxigraph.blankNode())
+      const quads = nodes.flatMap(s => nodes.filter(o => s !== o).map(o => oxigraph.quad(s, p, o)))
+      assert.throws(() => oxigraph.canonicalize(quads, undefined, 100))
+    })
+  })
 })
@@ -157,5 +157,10 @@ describe('Store', function () {
       const store = new Store([dataModel.quad(ex, ex, ex, ex)])
       assert.strictEqual('', store.dump('application/n-triples'))
     })
+
+    it('dump with prefixes and base IRI', function () {
+      const store = new Store([dataModel.quad(dataModel.namedNode('http://example.com/s'), dataModel.namedNode('http://xmlns.com/foaf/0.1/name'), dataModel.literal('foo'))])
+      assert.strictEqual('@base <http://example.com/> .\n@prefix foaf: <http://xmlns.com/foaf/0.1/> .\n\n<s> foaf:name "foo" .\n', store.dump('text/turtle', null, { foaf: 'http://xmlns.com/foaf/0.1/' }, 'http://example.com/'))
+    })
   })
 })
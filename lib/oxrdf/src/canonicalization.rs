@@ -0,0 +1,850 @@
+//! Implementation of the [RDF Dataset Canonicalization (RDFC-1.0)](https://www.w3.org/TR/rdf-canon/) algorithm.
+//!
+//! The algorithm relabels the blank nodes of a dataset in a deterministic way
+//! and serializes the result as [canonical N-Quads](https://www.w3.org/TR/rdf-canon/#canonical-quads).
+//!
+//! Usage example:
+//! ```
+//! use oxrdf::*;
+//! use oxrdf::canonicalization::Canonicalizer;
+//!
+//! let iri = NamedNodeRef::new("http://example.com")?;
+//!
+//! let mut dataset = Dataset::new();
+//! dataset.insert(QuadRef::new(&BlankNode::default(), iri, iri, GraphNameRef::DefaultGraph));
+//!
+//! let canonicalized = Canonicalizer::new().canonicalize_dataset(&dataset)?;
+//! assert_eq!(
+//!     canonicalized.n_quads(),
+//!     "_:c14n0 <http://example.com> <http://example.com> .\n"
+//! );
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+//!
+//! Some datasets (often called "poison graphs") require an exponential amount of work to be canonicalized.
+//! A work limit is enforced to prevent them from blocking the canonicalization forever.
+//! It could be set using [`Canonicalizer::with_work_limit`].
+
+#[cfg(feature = "rdf-star")]
+use crate::Triple;
+use crate::{
+    BlankNode, Dataset, Graph, GraphName, Literal, NamedNode, Quad, QuadRef, Subject, Term,
+};
+use sha2::{Digest, Sha256, Sha384};
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// The default maximal number of [Hash N-Degree Quads](https://www.w3.org/TR/rdf-canon/#hash-nd-quads) calls and permutations evaluations allowed during a canonicalization.
+pub const DEFAULT_WORK_LIMIT: usize = 100_000;
+
+/// The hash function used during the canonicalization.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum CanonicalizationHashAlgorithm {
+    /// [SHA-256](https://en.wikipedia.org/wiki/SHA-2), the default hash function of RDFC-1.0.
+    Sha256,
+    /// [SHA-384](https://en.wikipedia.org/wiki/SHA-2).
+    Sha384,
+}
+
+impl CanonicalizationHashAlgorithm {
+    /// Looks for a hash algorithm from its name (`sha256` or `sha384`, case insensitive, an optional `-` is allowed).
+    ///
+    /// ```
+    /// use oxrdf::canonicalization::CanonicalizationHashAlgorithm;
+    ///
+    /// assert_eq!(CanonicalizationHashAlgorithm::from_name("SHA-384"), Some(CanonicalizationHashAlgorithm::Sha384));
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Some(Self::Sha256),
+            "sha384" => Some(Self::Sha384),
+            _ => None,
+        }
+    }
+
+    fn hash(self, data: &str) -> String {
+        match self {
+            Self::Sha256 => to_hex(&Sha256::digest(data.as_bytes())),
+            Self::Sha384 => to_hex(&Sha384::digest(data.as_bytes())),
+        }
+    }
+}
+
+impl Default for CanonicalizationHashAlgorithm {
+    #[inline]
+    fn default() -> Self {
+        Self::Sha256
+    }
+}
+
+/// A [RDFC-1.0](https://www.w3.org/TR/rdf-canon/) canonicalizer.
+///
+/// ```
+/// use oxrdf::*;
+/// use oxrdf::canonicalization::{CanonicalizationHashAlgorithm, Canonicalizer};
+///
+/// let iri = NamedNodeRef::new("http://example.com")?;
+/// let bnode1 = BlankNode::default();
+/// let bnode2 = BlankNode::default();
+///
+/// let mut graph1 = Graph::new();
+/// graph1.insert(TripleRef::new(&bnode1, iri, &bnode2));
+/// let mut graph2 = Graph::new();
+/// graph2.insert(TripleRef::new(&bnode2, iri, &bnode1));
+///
+/// let canonicalizer = Canonicalizer::new()
+///     .with_hash_algorithm(CanonicalizationHashAlgorithm::Sha384)
+///     .with_work_limit(1000);
+/// assert_eq!(
+///     canonicalizer.canonicalize_graph(&graph1)?.n_quads(),
+///     canonicalizer.canonicalize_graph(&graph2)?.n_quads()
+/// );
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Canonicalizer {
+    hash_algorithm: CanonicalizationHashAlgorithm,
+    work_limit: usize,
+}
+
+impl Canonicalizer {
+    /// Builds a canonicalizer using SHA-256 and [`DEFAULT_WORK_LIMIT`].
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            hash_algorithm: CanonicalizationHashAlgorithm::default(),
+            work_limit: DEFAULT_WORK_LIMIT,
+        }
+    }
+
+    /// Sets the hash function used to compute the blank node hashes and the output hash.
+    #[inline]
+    #[must_use]
+    pub fn with_hash_algorithm(mut self, hash_algorithm: CanonicalizationHashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    /// Sets the maximal number of [Hash N-Degree Quads](https://www.w3.org/TR/rdf-canon/#hash-nd-quads) calls and permutations evaluations.
+    ///
+    /// If it is exceeded the canonicalization fails with a [`CanonicalizationError`].
+    #[inline]
+    #[must_use]
+    pub fn with_work_limit(mut self, work_limit: usize) -> Self {
+        self.work_limit = work_limit;
+        self
+    }
+
+    /// Canonicalizes a dataset.
+    pub fn canonicalize_dataset(
+        &self,
+        dataset: &Dataset,
+    ) -> Result<CanonicalizedDataset, CanonicalizationError> {
+        self.canonicalize_quads(dataset.iter().map(QuadRef::into_owned))
+    }
+
+    /// Canonicalizes a graph, considering it as the default graph of a dataset.
+    pub fn canonicalize_graph(
+        &self,
+        graph: &Graph,
+    ) -> Result<CanonicalizedDataset, CanonicalizationError> {
+        self.canonicalize_quads(
+            graph
+                .iter()
+                .map(|t| t.into_owned().in_graph(GraphName::DefaultGraph)),
+        )
+    }
+
+    /// Canonicalizes the dataset composed of the given quads.
+    ///
+    /// Duplicated quads are ignored.
+    pub fn canonicalize_quads(
+        &self,
+        quads: impl IntoIterator<Item = Quad>,
+    ) -> Result<CanonicalizedDataset, CanonicalizationError> {
+        let quads = quads
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let mut state = CanonicalizationState::new(quads, self);
+
+        // We compute the first degree hashes and issue the identifiers of the blank nodes with unique hashes
+        let mut hash_to_blank_nodes = BTreeMap::<String, Vec<BlankNode>>::new();
+        for blank_node in state.blank_node_to_quads.keys() {
+            hash_to_blank_nodes
+                .entry(state.hash_first_degree_quads(blank_node))
+                .or_default()
+                .push(blank_node.clone());
+        }
+        let mut non_unique = Vec::new();
+        for blank_nodes in hash_to_blank_nodes.into_values() {
+            if blank_nodes.len() == 1 {
+                state.canonical_issuer.issue(&blank_nodes[0]);
+            } else {
+                non_unique.push(blank_nodes);
+            }
+        }
+
+        // We disambiguate the other blank nodes using their neighborhood
+        for blank_nodes in non_unique {
+            let mut hash_path_list = Vec::new();
+            for blank_node in blank_nodes {
+                if state.canonical_issuer.get(&blank_node).is_some() {
+                    continue;
+                }
+                let mut issuer = IdentifierIssuer::new("b");
+                issuer.issue(&blank_node);
+                hash_path_list.push(state.hash_n_degree_quads(&blank_node, issuer)?);
+            }
+            hash_path_list.sort_by(|(h1, _), (h2, _)| h1.cmp(h2));
+            for (_, issuer) in hash_path_list {
+                for blank_node in issuer.issued {
+                    state.canonical_issuer.issue(&blank_node);
+                }
+            }
+        }
+
+        // We relabel and serialize the dataset
+        let issued_identifiers = state
+            .canonical_issuer
+            .ids
+            .iter()
+            .map(|(original, id)| (original.clone(), BlankNode::new_unchecked(id.clone())))
+            .collect::<HashMap<_, _>>();
+        let mut lines = state
+            .quads
+            .iter()
+            .map(|quad| {
+                let quad = relabel_quad(quad, &issued_identifiers);
+                let mut line = String::new();
+                write_quad(&mut line, &quad, &|b| b.as_str());
+                (line, quad)
+            })
+            .collect::<Vec<_>>();
+        lines.sort_by(|(l1, _), (l2, _)| l1.cmp(l2));
+        let n_quads = lines.iter().map(|(l, _)| l.as_str()).collect::<String>();
+        Ok(CanonicalizedDataset {
+            hash: self.hash_algorithm.hash(&n_quads),
+            n_quads,
+            quads: lines.into_iter().map(|(_, q)| q).collect(),
+            issued_identifiers,
+        })
+    }
+}
+
+impl Default for Canonicalizer {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The output of a [`Canonicalizer`].
+#[derive(Debug, Clone)]
+pub struct CanonicalizedDataset {
+    n_quads: String,
+    quads: Vec<Quad>,
+    issued_identifiers: HashMap<BlankNode, BlankNode>,
+    hash: String,
+}
+
+impl CanonicalizedDataset {
+    /// The canonical [N-Quads](https://www.w3.org/TR/n-quads/) serialization of the dataset.
+    #[inline]
+    pub fn n_quads(&self) -> &str {
+        &self.n_quads
+    }
+
+    /// The relabeled quads in the order of the canonical N-Quads serialization.
+    #[inline]
+    pub fn quads(&self) -> &[Quad] {
+        &self.quads
+    }
+
+    /// The mapping from the input blank nodes to the canonical ones.
+    #[inline]
+    pub fn issued_identifiers(&self) -> &HashMap<BlankNode, BlankNode> {
+        &self.issued_identifiers
+    }
+
+    /// The hexadecimal hash of the canonical N-Quads serialization computed with the canonicalizer hash function.
+    #[inline]
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Returns the relabeled dataset.
+    #[inline]
+    pub fn into_dataset(self) -> Dataset {
+        self.quads.into_iter().collect()
+    }
+}
+
+/// An error raised when the [`Canonicalizer`] work limit is exceeded.
+#[derive(Debug, Clone)]
+pub struct CanonicalizationError {
+    work_limit: usize,
+}
+
+impl fmt::Display for CanonicalizationError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The canonicalization has exceeded its work limit of {} steps",
+            self.work_limit
+        )
+    }
+}
+
+impl Error for CanonicalizationError {}
+
+struct CanonicalizationState {
+    quads: Vec<Quad>,
+    blank_node_to_quads: HashMap<BlankNode, Vec<usize>>,
+    canonical_issuer: IdentifierIssuer,
+    hash_algorithm: CanonicalizationHashAlgorithm,
+    work_limit: usize,
+    work_done: Cell<usize>,
+}
+
+impl CanonicalizationState {
+    fn new(quads: Vec<Quad>, canonicalizer: &Canonicalizer) -> Self {
+        let mut blank_node_to_quads = HashMap::<_, Vec<_>>::new();
+        for (i, quad) in quads.iter().enumerate() {
+            for (_, blank_node) in quad_blank_nodes(quad) {
+                let quads = blank_node_to_quads.entry(blank_node.clone()).or_default();
+                if quads.last() != Some(&i) {
+                    quads.push(i);
+                }
+            }
+        }
+        Self {
+            quads,
+            blank_node_to_quads,
+            canonical_issuer: IdentifierIssuer::new("c14n"),
+            hash_algorithm: canonicalizer.hash_algorithm,
+            work_limit: canonicalizer.work_limit,
+            work_done: Cell::new(0),
+        }
+    }
+
+    fn consume_work(&self) -> Result<(), CanonicalizationError> {
+        let work_done = self.work_done.get() + 1;
+        if work_done > self.work_limit {
+            return Err(CanonicalizationError {
+                work_limit: self.work_limit,
+            });
+        }
+        self.work_done.set(work_done);
+        Ok(())
+    }
+
+    /// [Hash First Degree Quads](https://www.w3.org/TR/rdf-canon/#hash-1d-quads)
+    fn hash_first_degree_quads(&self, reference: &BlankNode) -> String {
+        let mut lines = self.blank_node_to_quads[reference]
+            .iter()
+            .map(|i| {
+                let mut line = String::new();
+                write_quad(&mut line, &self.quads[*i], &|b| {
+                    if b == reference {
+                        "a"
+                    } else {
+                        "z"
+                    }
+                });
+                line
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        self.hash_algorithm.hash(&lines.concat())
+    }
+
+    /// [Hash Related Blank Node](https://www.w3.org/TR/rdf-canon/#hash-related-blank-node)
+    fn hash_related_blank_node(
+        &self,
+        related: &BlankNode,
+        quad: &Quad,
+        issuer: &IdentifierIssuer,
+        position: char,
+    ) -> String {
+        let mut input = String::new();
+        input.push(position);
+        if position != 'g' {
+            input.push('<');
+            input.push_str(quad.predicate.as_str());
+            input.push('>');
+        }
+        if let Some(id) = self
+            .canonical_issuer
+            .get(related)
+            .or_else(|| issuer.get(related))
+        {
+            input.push_str("_:");
+            input.push_str(id);
+        } else {
+            input.push_str(&self.hash_first_degree_quads(related));
+        }
+        self.hash_algorithm.hash(&input)
+    }
+
+    /// [Hash N-Degree Quads](https://www.w3.org/TR/rdf-canon/#hash-nd-quads)
+    fn hash_n_degree_quads(
+        &self,
+        identifier: &BlankNode,
+        mut issuer: IdentifierIssuer,
+    ) -> Result<(String, IdentifierIssuer), CanonicalizationError> {
+        self.consume_work()?;
+        let mut hash_to_related = BTreeMap::<String, Vec<BlankNode>>::new();
+        for i in &self.blank_node_to_quads[identifier] {
+            let quad = &self.quads[*i];
+            for (position, related) in quad_blank_nodes(quad) {
+                if related != identifier {
+                    hash_to_related
+                        .entry(self.hash_related_blank_node(related, quad, &issuer, position))
+                        .or_default()
+                        .push(related.clone());
+                }
+            }
+        }
+
+        let mut data_to_hash = String::new();
+        for (related_hash, blank_nodes) in hash_to_related {
+            data_to_hash.push_str(&related_hash);
+            let mut chosen_path = String::new();
+            let mut chosen_issuer = None;
+            let mut permutation = (0..blank_nodes.len()).collect::<Vec<_>>();
+            loop {
+                self.consume_work()?;
+                if let Some((path, issuer)) =
+                    self.permutation_path(&permutation, &blank_nodes, &issuer, &chosen_path)?
+                {
+                    if chosen_issuer.is_none() || path < chosen_path {
+                        chosen_path = path;
+                        chosen_issuer = Some(issuer);
+                    }
+                }
+                if !next_permutation(&mut permutation) {
+                    break;
+                }
+            }
+            data_to_hash.push_str(&chosen_path);
+            if let Some(chosen_issuer) = chosen_issuer {
+                issuer = chosen_issuer;
+            }
+        }
+        Ok((self.hash_algorithm.hash(&data_to_hash), issuer))
+    }
+
+    /// Computes the path of a permutation or returns `None` if it is already known to be greater than the chosen path
+    fn permutation_path(
+        &self,
+        permutation: &[usize],
+        blank_nodes: &[BlankNode],
+        issuer: &IdentifierIssuer,
+        chosen_path: &str,
+    ) -> Result<Option<(String, IdentifierIssuer)>, CanonicalizationError> {
+        let mut issuer_copy = issuer.clone();
+        let mut path = String::new();
+        let mut recursion_list = Vec::new();
+        for related in permutation.iter().map(|i| &blank_nodes[*i]) {
+            if let Some(id) = self.canonical_issuer.get(related) {
+                path.push_str("_:");
+                path.push_str(id);
+            } else {
+                if issuer_copy.get(related).is_none() {
+                    recursion_list.push(related);
+                }
+                path.push_str("_:");
+                path.push_str(issuer_copy.issue(related));
+            }
+            if is_worse_path(&path, chosen_path) {
+                return Ok(None);
+            }
+        }
+        for related in recursion_list {
+            let (hash, result_issuer) = self.hash_n_degree_quads(related, issuer_copy.clone())?;
+            path.push_str("_:");
+            path.push_str(issuer_copy.issue(related));
+            path.push('<');
+            path.push_str(&hash);
+            path.push('>');
+            issuer_copy = result_issuer;
+            if is_worse_path(&path, chosen_path) {
+                return Ok(None);
+            }
+        }
+        Ok(Some((path, issuer_copy)))
+    }
+}
+
+fn is_worse_path(path: &str, chosen_path: &str) -> bool {
+    !chosen_path.is_empty() && path.len() >= chosen_path.len() && path > chosen_path
+}
+
+/// Rearranges the slice into the next permutation in the lexicographic order and returns `false` if it was the last one
+fn next_permutation(permutation: &mut [usize]) -> bool {
+    let pivot = match permutation.windows(2).rposition(|w| w[0] < w[1]) {
+        Some(pivot) => pivot,
+        None => return false,
+    };
+    let successor = match permutation.iter().rposition(|v| *v > permutation[pivot]) {
+        Some(successor) => successor,
+        None => return false,
+    };
+    permutation.swap(pivot, successor);
+    permutation[pivot + 1..].reverse();
+    true
+}
+
+/// [Identifier Issuer](https://www.w3.org/TR/rdf-canon/#issue-identifier)
+#[derive(Clone)]
+struct IdentifierIssuer {
+    prefix: &'static str,
+    issued: Vec<BlankNode>,
+    ids: HashMap<BlankNode, String>,
+}
+
+impl IdentifierIssuer {
+    fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix,
+            issued: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    fn get(&self, blank_node: &BlankNode) -> Option<&str> {
+        self.ids.get(blank_node).map(String::as_str)
+    }
+
+    fn issue(&mut self, blank_node: &BlankNode) -> &str {
+        if !self.ids.contains_key(blank_node) {
+            let id = format!("{}{}", self.prefix, self.issued.len());
+            self.issued.push(blank_node.clone());
+            self.ids.insert(blank_node.clone(), id);
+        }
+        &self.ids[blank_node]
+    }
+}
+
+/// Returns the blank nodes of the quad with their position (`s`, `o` or `g`).
+///
+/// Blank nodes inside of quoted triples are returned with the position of the quoted triple.
+pub(crate) fn quad_blank_nodes(quad: &Quad) -> Vec<(char, &BlankNode)> {
+    let mut blank_nodes = Vec::new();
+    add_subject_blank_nodes(&quad.subject, 's', &mut blank_nodes);
+    add_term_blank_nodes(&quad.object, 'o', &mut blank_nodes);
+    if let GraphName::BlankNode(blank_node) = &quad.graph_name {
+        blank_nodes.push(('g', blank_node));
+    }
+    blank_nodes
+}
+
+fn add_subject_blank_nodes<'a>(
+    subject: &'a Subject,
+    position: char,
+    blank_nodes: &mut Vec<(char, &'a BlankNode)>,
+) {
+    match subject {
+        Subject::NamedNode(_) => (),
+        Subject::BlankNode(blank_node) => blank_nodes.push((position, blank_node)),
+        #[cfg(feature = "rdf-star")]
+        Subject::Triple(triple) => add_triple_blank_nodes(triple, position, blank_nodes),
+    }
+}
+
+fn add_term_blank_nodes<'a>(
+    term: &'a Term,
+    position: char,
+    blank_nodes: &mut Vec<(char, &'a BlankNode)>,
+) {
+    match term {
+        Term::NamedNode(_) | Term::Literal(_) => (),
+        Term::BlankNode(blank_node) => blank_nodes.push((position, blank_node)),
+        #[cfg(feature = "rdf-star")]
+        Term::Triple(triple) => add_triple_blank_nodes(triple, position, blank_nodes),
+    }
+}
+
+#[cfg(feature = "rdf-star")]
+fn add_triple_blank_nodes<'a>(
+    triple: &'a Triple,
+    position: char,
+    blank_nodes: &mut Vec<(char, &'a BlankNode)>,
+) {
+    add_subject_blank_nodes(&triple.subject, position, blank_nodes);
+    add_term_blank_nodes(&triple.object, position, blank_nodes);
+}
+
+fn relabel_quad(quad: &Quad, mapping: &HashMap<BlankNode, BlankNode>) -> Quad {
+    Quad {
+        subject: relabel_subject(&quad.subject, mapping),
+        predicate: quad.predicate.clone(),
+        object: relabel_term(&quad.object, mapping),
+        graph_name: match &quad.graph_name {
+            GraphName::BlankNode(blank_node) => mapping[blank_node].clone().into(),
+            graph_name => graph_name.clone(),
+        },
+    }
+}
+
+fn relabel_subject(subject: &Subject, mapping: &HashMap<BlankNode, BlankNode>) -> Subject {
+    match subject {
+        Subject::BlankNode(blank_node) => mapping[blank_node].clone().into(),
+        #[cfg(feature = "rdf-star")]
+        Subject::Triple(triple) => relabel_triple(triple, mapping).into(),
+        subject => subject.clone(),
+    }
+}
+
+fn relabel_term(term: &Term, mapping: &HashMap<BlankNode, BlankNode>) -> Term {
+    match term {
+        Term::BlankNode(blank_node) => mapping[blank_node].clone().into(),
+        #[cfg(feature = "rdf-star")]
+        Term::Triple(triple) => relabel_triple(triple, mapping).into(),
+        term => term.clone(),
+    }
+}
+
+#[cfg(feature = "rdf-star")]
+fn relabel_triple(triple: &Triple, mapping: &HashMap<BlankNode, BlankNode>) -> Triple {
+    Triple {
+        subject: relabel_subject(&triple.subject, mapping),
+        predicate: triple.predicate.clone(),
+        object: relabel_term(&triple.object, mapping),
+    }
+}
+
+/// Writes a quad following the [canonical N-Quads](https://www.w3.org/TR/rdf-canon/#canonical-quads) rules
+fn write_quad(output: &mut String, quad: &Quad, label: &impl Fn(&BlankNode) -> &str) {
+    write_subject(output, &quad.subject, label);
+    output.push(' ');
+    write_named_node(output, &quad.predicate);
+    output.push(' ');
+    write_term(output, &quad.object, label);
+    match &quad.graph_name {
+        GraphName::NamedNode(node) => {
+            output.push(' ');
+            write_named_node(output, node);
+        }
+        GraphName::BlankNode(node) => {
+            output.push(' ');
+            write_blank_node(output, node, label);
+        }
+        GraphName::DefaultGraph => (),
+    }
+    output.push_str(" .\n");
+}
+
+fn write_subject(output: &mut String, subject: &Subject, label: &impl Fn(&BlankNode) -> &str) {
+    match subject {
+        Subject::NamedNode(node) => write_named_node(output, node),
+        Subject::BlankNode(node) => write_blank_node(output, node, label),
+        #[cfg(feature = "rdf-star")]
+        Subject::Triple(triple) => write_triple(output, triple, label),
+    }
+}
+
+fn write_term(output: &mut String, term: &Term, label: &impl Fn(&BlankNode) -> &str) {
+    match term {
+        Term::NamedNode(node) => write_named_node(output, node),
+        Term::BlankNode(node) => write_blank_node(output, node, label),
+        Term::Literal(literal) => write_literal(output, literal),
+        #[cfg(feature = "rdf-star")]
+        Term::Triple(triple) => write_triple(output, triple, label),
+    }
+}
+
+#[cfg(feature = "rdf-star")]
+fn write_triple(output: &mut String, triple: &Triple, label: &impl Fn(&BlankNode) -> &str) {
+    output.push_str("<< ");
+    write_subject(output, &triple.subject, label);
+    output.push(' ');
+    write_named_node(output, &triple.predicate);
+    output.push(' ');
+    write_term(output, &triple.object, label);
+    output.push_str(" >>");
+}
+
+fn write_named_node(output: &mut String, node: &NamedNode) {
+    write_iri(output, node.as_str())
+}
+
+fn write_iri(output: &mut String, iri: &str) {
+    output.push('<');
+    output.push_str(iri);
+    output.push('>');
+}
+
+fn write_blank_node(output: &mut String, node: &BlankNode, label: &impl Fn(&BlankNode) -> &str) {
+    output.push_str("_:");
+    output.push_str(label(node));
+}
+
+fn write_literal(output: &mut String, literal: &Literal) {
+    output.push('"');
+    for c in literal.value().chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{08}' => output.push_str("\\b"),
+            '\u{0C}' => output.push_str("\\f"),
+            '\u{00}'..='\u{1F}' | '\u{7F}' => output.push_str(&format!("\\u{:04X}", u32::from(c))),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    if let Some(language) = literal.language() {
+        output.push('@');
+        output.push_str(language);
+    } else if !literal.is_plain() {
+        output.push_str("^^");
+        write_iri(output, literal.datatype().as_str());
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = String::with_capacity(2 * bytes.len());
+    for b in bytes {
+        hex.push(char::from(DIGITS[usize::from(b >> 4)]));
+        hex.push(char::from(DIGITS[usize::from(b & 0xF)]));
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vocab::xsd;
+    use crate::{GraphNameRef, NamedNodeRef, TripleRef};
+
+    #[test]
+    fn test_canonicalize_simple_graph() {
+        let p = NamedNodeRef::new_unchecked("http://example.com/p");
+        let x = BlankNode::new_unchecked("x");
+        let y = BlankNode::new_unchecked("y");
+        let mut graph = Graph::new();
+        graph.insert(TripleRef::new(&x, p, &y));
+
+        let canonicalized = Canonicalizer::new().canonicalize_graph(&graph).unwrap();
+        assert_eq!(
+            canonicalized.n_quads(),
+            "_:c14n1 <http://example.com/p> _:c14n0 .\n"
+        );
+        assert_eq!(
+            canonicalized.hash(),
+            "f236432b7d089cf6ce01c3405f26cca2d6ed5af567162c046410db98ac8ec69f"
+        );
+        assert_eq!(
+            canonicalized.issued_identifiers()[&x],
+            BlankNode::new_unchecked("c14n1")
+        );
+        assert_eq!(
+            canonicalized.issued_identifiers()[&y],
+            BlankNode::new_unchecked("c14n0")
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_isomorphic_datasets() {
+        let p = NamedNodeRef::new_unchecked("http://example.com/p");
+        let g = NamedNodeRef::new_unchecked("http://example.com/g");
+        let bnodes = (0..6).map(|_| BlankNode::default()).collect::<Vec<_>>();
+
+        // Two cycles of three blank nodes, one of them being in a named graph
+        let mut dataset1 = Dataset::new();
+        let mut dataset2 = Dataset::new();
+        for i in 0..3 {
+            dataset1.insert(QuadRef::new(
+                &bnodes[i],
+                p,
+                &bnodes[(i + 1) % 3],
+                GraphNameRef::DefaultGraph,
+            ));
+            dataset1.insert(QuadRef::new(&bnodes[3 + i], p, &bnodes[3 + (i + 1) % 3], g));
+            dataset2.insert(QuadRef::new(
+                &bnodes[5 - i],
+                p,
+                &bnodes[5 - (i + 1) % 3],
+                GraphNameRef::DefaultGraph,
+            ));
+            dataset2.insert(QuadRef::new(&bnodes[2 - i], p, &bnodes[2 - (i + 1) % 3], g));
+        }
+        assert_ne!(dataset1, dataset2);
+
+        let canonicalizer = Canonicalizer::new();
+        let canonicalized1 = canonicalizer.canonicalize_dataset(&dataset1).unwrap();
+        let canonicalized2 = canonicalizer.canonicalize_dataset(&dataset2).unwrap();
+        assert_eq!(canonicalized1.n_quads(), canonicalized2.n_quads());
+        assert_eq!(canonicalized1.hash(), canonicalized2.hash());
+        assert_eq!(canonicalized1.issued_identifiers().len(), 6);
+        assert_eq!(canonicalized1.into_dataset(), canonicalized2.into_dataset());
+    }
+
+    #[test]
+    fn test_canonicalize_sha384() {
+        let p = NamedNodeRef::new_unchecked("http://example.com/p");
+        let mut graph = Graph::new();
+        graph.insert(TripleRef::new(&BlankNode::default(), p, p));
+        let canonicalized = Canonicalizer::new()
+            .with_hash_algorithm(CanonicalizationHashAlgorithm::Sha384)
+            .canonicalize_graph(&graph)
+            .unwrap();
+        assert_eq!(canonicalized.hash().len(), 96);
+    }
+
+    #[test]
+    fn test_canonicalize_work_limit() {
+        // A clique of blank nodes is a well-known poison graph
+        let p = NamedNodeRef::new_unchecked("http://example.com/p");
+        let bnodes = (0..6).map(|_| BlankNode::default()).collect::<Vec<_>>();
+        let mut graph = Graph::new();
+        for s in &bnodes {
+            for o in &bnodes {
+                if s != o {
+                    graph.insert(TripleRef::new(s, p, o));
+                }
+            }
+        }
+        assert!(Canonicalizer::new()
+            .with_work_limit(100)
+            .canonicalize_graph(&graph)
+            .is_err());
+    }
+
+    #[test]
+    fn test_canonical_literal_escaping() {
+        let p = NamedNodeRef::new_unchecked("http://example.com/p");
+        let mut graph = Graph::new();
+        graph.insert(TripleRef::new(
+            p,
+            p,
+            &Literal::new_simple_literal("a\"\\\n\r\t\u{08}\u{0C}\u{00}\u{1F}\u{7F}é"),
+        ));
+        graph.insert(TripleRef::new(
+            p,
+            p,
+            &Literal::new_typed_literal("1", xsd::INTEGER),
+        ));
+        graph.insert(TripleRef::new(
+            p,
+            p,
+            &Literal::new_language_tagged_literal_unchecked("a", "en"),
+        ));
+        assert_eq!(
+            Canonicalizer::new()
+                .canonicalize_graph(&graph)
+                .unwrap()
+                .n_quads(),
+            "<http://example.com/p> <http://example.com/p> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n<http://example.com/p> <http://example.com/p> \"a\"@en .\n<http://example.com/p> <http://example.com/p> \"a\\\"\\\\\\n\\r\\t\\b\\f\\u0000\\u001F\\u007Fé\" .\n"
+        );
+    }
+}
@@ -23,7 +23,9 @@
 //!
 //! See also [`Graph`](super::Graph) if you only care about plain triples.
 
+use crate::diff::DatasetDiff;
 use crate::interning::*;
+use crate::patch::{Patch, PatchOperation};
 use crate::SubjectRef;
 use crate::*;
 use std::collections::hash_map::DefaultHasher;
@@ -520,6 +522,8 @@ impl Dataset {
     /// Warning 2: The canonicalization algorithm is not stable and canonical blank node ids might change between Oxigraph version.
     ///
     /// Warning 3: This implementation worst-case complexity is in *O(b!)* with *b* the number of blank nodes in the input dataset.
+    ///
+    /// If you need a standard and stable canonical form, use the [RDFC-1.0](https://www.w3.org/TR/rdf-canon/) implementation in the [`canonicalization`](crate::canonicalization) module.
     pub fn canonicalize(&mut self) {
         let bnodes = self.blank_nodes();
         let (hash, partition) =
@@ -531,6 +535,51 @@ impl Dataset {
         }
     }
 
+    /// Computes the differences between this dataset and `other`.
+    ///
+    /// Blank nodes are matched by the structure of their neighborhood and not by their identifiers.
+    /// See the [`diff`](crate::diff) module for more details.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxrdf::*;
+    ///
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// let mut old = Dataset::new();
+    /// old.insert(QuadRef::new(ex, ex, &BlankNode::default(), GraphNameRef::DefaultGraph));
+    /// let mut new = Dataset::new();
+    /// new.insert(QuadRef::new(ex, ex, &BlankNode::default(), GraphNameRef::DefaultGraph));
+    /// new.insert(QuadRef::new(ex, ex, ex, ex));
+    ///
+    /// let diff = old.diff(&new);
+    /// assert!(diff.removed().is_empty());
+    /// assert_eq!(diff.added(), [QuadRef::new(ex, ex, ex, ex).into_owned()]);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn diff(&self, other: &Self) -> DatasetDiff {
+        DatasetDiff::new(
+            self.iter().map(QuadRef::into_owned),
+            other.iter().map(QuadRef::into_owned),
+        )
+    }
+
+    /// Applies the committed operations of an [RDF Patch](crate::patch) to this dataset.
+    ///
+    /// Prefix operations are ignored.
+    pub fn apply_patch(&mut self, patch: &Patch) {
+        for operation in patch.committed_operations() {
+            match operation {
+                PatchOperation::AddQuad(quad) => {
+                    self.insert(quad);
+                }
+                PatchOperation::DeleteQuad(quad) => {
+                    self.remove(quad);
+                }
+                _ => (),
+            }
+        }
+    }
+
     fn blank_nodes(&self) -> HashSet<InternedBlankNode> {
         let mut bnodes = HashSet::new();
         for (g, s, _, o) in &self.gspo {
@@ -1224,7 +1273,7 @@ impl<'a> IntoIterator for GraphView<'a> {
     }
 }
 
-impl<'a, 'b> IntoIterator for &'b GraphView<'a> {
+impl<'a> IntoIterator for &GraphView<'a> {
     type Item = TripleRef<'a>;
     type IntoIter = GraphViewIter<'a>;
 
@@ -0,0 +1,296 @@
+//! Computation of the differences between two [`Graph`](crate::Graph) or [`Dataset`](crate::Dataset).
+//!
+//! The diff is blank node aware: the triples and quads containing blank nodes are grouped into connected components
+//! (two triples are in the same component if they share a blank node)
+//! and a component is considered unchanged if the other side contains an isomorphic component.
+//! Changed components are reported as a whole.
+//! Isomorphism is checked using the [RDFC-1.0](crate::canonicalization) canonicalization.
+//! If a component exceeds the canonicalization work limit it is always considered as changed.
+//!
+//! Usage example:
+//! ```
+//! use oxrdf::*;
+//!
+//! let ex = NamedNodeRef::new("http://example.com")?;
+//! let mut old = Graph::new();
+//! old.insert(TripleRef::new(ex, ex, &BlankNode::default()));
+//! old.insert(TripleRef::new(ex, ex, LiteralRef::new_simple_literal("old")));
+//! let mut new = Graph::new();
+//! new.insert(TripleRef::new(ex, ex, &BlankNode::default()));
+//! new.insert(TripleRef::new(ex, ex, LiteralRef::new_simple_literal("new")));
+//!
+//! let diff = old.diff(&new);
+//! assert_eq!(diff.removed(), [TripleRef::new(ex, ex, LiteralRef::new_simple_literal("old")).into_owned()]);
+//! assert_eq!(diff.added(), [TripleRef::new(ex, ex, LiteralRef::new_simple_literal("new")).into_owned()]);
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+
+use crate::canonicalization::{quad_blank_nodes, Canonicalizer};
+use crate::patch::{Patch, PatchOperation};
+use crate::{BlankNode, GraphName, Quad, Triple};
+use std::collections::{HashMap, HashSet};
+
+/// The differences between two [`Dataset`](crate::Dataset).
+///
+/// It is returned by [`Dataset::diff`](crate::Dataset::diff).
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct DatasetDiff {
+    removed: Vec<Quad>,
+    added: Vec<Quad>,
+}
+
+impl DatasetDiff {
+    pub(crate) fn new(
+        old: impl IntoIterator<Item = Quad>,
+        new: impl IntoIterator<Item = Quad>,
+    ) -> Self {
+        let (removed, added) = diff_quads(old, new);
+        Self { removed, added }
+    }
+
+    /// The quads of the original dataset that are not in the new one.
+    #[inline]
+    pub fn removed(&self) -> &[Quad] {
+        &self.removed
+    }
+
+    /// The quads of the new dataset that are not in the original one.
+    #[inline]
+    pub fn added(&self) -> &[Quad] {
+        &self.added
+    }
+
+    /// Returns `true` if the two datasets are isomorphic.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+
+    /// Builds an [RDF Patch](crate::patch) transaction that transforms the original dataset into the new one.
+    pub fn to_patch(&self) -> Patch {
+        build_patch(self.removed.iter().cloned(), self.added.iter().cloned())
+    }
+}
+
+/// The differences between two [`Graph`](crate::Graph).
+///
+/// It is returned by [`Graph::diff`](crate::Graph::diff).
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct GraphDiff {
+    removed: Vec<Triple>,
+    added: Vec<Triple>,
+}
+
+impl GraphDiff {
+    pub(crate) fn new(
+        old: impl IntoIterator<Item = Triple>,
+        new: impl IntoIterator<Item = Triple>,
+    ) -> Self {
+        let (removed, added) = diff_quads(
+            old.into_iter().map(|t| t.in_graph(GraphName::DefaultGraph)),
+            new.into_iter().map(|t| t.in_graph(GraphName::DefaultGraph)),
+        );
+        Self {
+            removed: removed.into_iter().map(Triple::from).collect(),
+            added: added.into_iter().map(Triple::from).collect(),
+        }
+    }
+
+    /// The triples of the original graph that are not in the new one.
+    #[inline]
+    pub fn removed(&self) -> &[Triple] {
+        &self.removed
+    }
+
+    /// The triples of the new graph that are not in the original one.
+    #[inline]
+    pub fn added(&self) -> &[Triple] {
+        &self.added
+    }
+
+    /// Returns `true` if the two graphs are isomorphic.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+
+    /// Builds an [RDF Patch](crate::patch) transaction that transforms the original graph into the new one,
+    /// the graph being stored in the dataset graph named `graph_name`.
+    pub fn to_patch(&self, graph_name: impl Into<GraphName>) -> Patch {
+        let graph_name = graph_name.into();
+        build_patch(
+            self.removed
+                .iter()
+                .map(|t| t.clone().in_graph(graph_name.clone())),
+            self.added
+                .iter()
+                .map(|t| t.clone().in_graph(graph_name.clone())),
+        )
+    }
+}
+
+fn build_patch(removed: impl Iterator<Item = Quad>, added: impl Iterator<Item = Quad>) -> Patch {
+    let mut patch = Patch::new();
+    patch.push(PatchOperation::TransactionStart);
+    patch.extend(removed.map(PatchOperation::DeleteQuad));
+    patch.extend(added.map(PatchOperation::AddQuad));
+    patch.push(PatchOperation::TransactionCommit);
+    patch
+}
+
+fn diff_quads(
+    old: impl IntoIterator<Item = Quad>,
+    new: impl IntoIterator<Item = Quad>,
+) -> (Vec<Quad>, Vec<Quad>) {
+    let (old_ground, old_components) = split_components(old);
+    let (new_ground, new_components) = split_components(new);
+    let mut removed = old_ground
+        .difference(&new_ground)
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut added = new_ground
+        .difference(&old_ground)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let canonicalizer = Canonicalizer::new();
+    let mut old_components_by_key = HashMap::<_, Vec<_>>::new();
+    for component in old_components {
+        if let Ok(canonical) = canonicalizer.canonicalize_quads(component.iter().cloned()) {
+            old_components_by_key
+                .entry(canonical.n_quads().to_owned())
+                .or_default()
+                .push(component);
+        } else {
+            removed.extend(component);
+        }
+    }
+    for component in new_components {
+        if let Ok(canonical) = canonicalizer.canonicalize_quads(component.iter().cloned()) {
+            if let Some(candidates) = old_components_by_key.get_mut(canonical.n_quads()) {
+                if candidates.pop().is_some() {
+                    continue;
+                }
+            }
+        }
+        added.extend(component);
+    }
+    removed.extend(old_components_by_key.into_values().flatten().flatten());
+
+    // We sort the output to make it deterministic
+    removed.sort_by_cached_key(Quad::to_string);
+    added.sort_by_cached_key(Quad::to_string);
+    (removed, added)
+}
+
+/// Splits the quads between the ones without blank nodes and the connected components of the ones with blank nodes
+fn split_components(quads: impl IntoIterator<Item = Quad>) -> (HashSet<Quad>, Vec<Vec<Quad>>) {
+    let mut ground = HashSet::new();
+    let mut with_blank_nodes = Vec::new();
+    let mut blank_node_ids = HashMap::<BlankNode, usize>::new();
+    let mut parents = Vec::new();
+    for quad in quads {
+        let mut root = None;
+        for (_, blank_node) in quad_blank_nodes(&quad) {
+            let id = *blank_node_ids.entry(blank_node.clone()).or_insert_with(|| {
+                parents.push(parents.len());
+                parents.len() - 1
+            });
+            let id = find_root(&mut parents, id);
+            if let Some(root) = root {
+                parents[id] = root;
+            } else {
+                root = Some(id);
+            }
+        }
+        if let Some(root) = root {
+            with_blank_nodes.push((root, quad));
+        } else {
+            ground.insert(quad);
+        }
+    }
+
+    let mut components = HashMap::<_, HashSet<_>>::new();
+    for (id, quad) in with_blank_nodes {
+        components
+            .entry(find_root(&mut parents, id))
+            .or_default()
+            .insert(quad);
+    }
+    (
+        ground,
+        components
+            .into_values()
+            .map(|c| c.into_iter().collect())
+            .collect(),
+    )
+}
+
+fn find_root(parents: &mut [usize], mut id: usize) -> usize {
+    while parents[id] != id {
+        parents[id] = parents[parents[id]];
+        id = parents[id];
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dataset, Graph, GraphNameRef, NamedNodeRef, QuadRef, TripleRef};
+
+    #[test]
+    fn test_diff_blank_node_components() {
+        let p = NamedNodeRef::new_unchecked("http://example.com/p");
+        let q = NamedNodeRef::new_unchecked("http://example.com/q");
+        let (a1, b1, c1) = (
+            BlankNode::default(),
+            BlankNode::default(),
+            BlankNode::default(),
+        );
+        let (a2, b2, c2) = (
+            BlankNode::default(),
+            BlankNode::default(),
+            BlankNode::default(),
+        );
+
+        let mut old = Graph::new();
+        old.insert(TripleRef::new(&a1, p, &b1));
+        old.insert(TripleRef::new(&b1, p, &a1));
+        old.insert(TripleRef::new(p, p, &c1));
+        old.insert(TripleRef::new(&c1, p, p));
+
+        let mut new = Graph::new();
+        new.insert(TripleRef::new(&b2, p, &a2));
+        new.insert(TripleRef::new(&a2, p, &b2));
+        new.insert(TripleRef::new(p, p, &c2));
+        new.insert(TripleRef::new(&c2, q, p));
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.removed().len(), 2);
+        assert_eq!(diff.added().len(), 2);
+        assert!(diff
+            .removed()
+            .contains(&TripleRef::new(&c1, p, p).into_owned()));
+        assert!(diff
+            .added()
+            .contains(&TripleRef::new(&c2, q, p).into_owned()));
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_diff_to_patch() {
+        let ex = NamedNodeRef::new_unchecked("http://example.com");
+        let mut old = Dataset::new();
+        old.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph));
+        let mut new = Dataset::new();
+        new.insert(QuadRef::new(ex, ex, ex, ex));
+
+        let patch = old.diff(&new).to_patch();
+        assert_eq!(patch.to_string(), "TX .\nD <http://example.com> <http://example.com> <http://example.com> .\nA <http://example.com> <http://example.com> <http://example.com> <http://example.com> .\nTC .\n");
+
+        let mut patched: Dataset = old.iter().collect();
+        patched.apply_patch(&patch);
+        assert_eq!(patched, new);
+    }
+}
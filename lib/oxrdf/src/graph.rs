@@ -20,6 +20,7 @@
 //! See also [`Dataset`](super::Dataset) if you want to get support of multiple RDF graphs at the same time.
 
 use crate::dataset::*;
+use crate::diff::GraphDiff;
 use crate::*;
 use std::fmt;
 
@@ -211,6 +212,32 @@ impl Graph {
     pub fn canonicalize(&mut self) {
         self.dataset.canonicalize()
     }
+
+    /// Computes the differences between this graph and `other`.
+    ///
+    /// Blank nodes are matched by the structure of their neighborhood and not by their identifiers.
+    /// See the [`diff`](crate::diff) module for more details.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxrdf::*;
+    ///
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// let mut old = Graph::new();
+    /// old.insert(TripleRef::new(ex, ex, ex));
+    /// let new = Graph::new();
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.removed(), [TripleRef::new(ex, ex, ex).into_owned()]);
+    /// assert!(diff.added().is_empty());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn diff(&self, other: &Self) -> GraphDiff {
+        GraphDiff::new(
+            self.iter().map(TripleRef::into_owned),
+            other.iter().map(TripleRef::into_owned),
+        )
+    }
 }
 
 impl PartialEq for Graph {
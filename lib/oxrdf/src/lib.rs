@@ -5,12 +5,15 @@
 #![doc(html_logo_url = "https://raw.githubusercontent.com/oxigraph/oxigraph/main/logo.svg")]
 
 mod blank_node;
+pub mod canonicalization;
 pub mod dataset;
+pub mod diff;
 pub mod graph;
 mod interning;
 mod literal;
 mod named_node;
 mod parser;
+pub mod patch;
 mod triple;
 mod variable;
 pub mod vocab;
@@ -133,7 +133,7 @@ impl FromStr for Variable {
     }
 }
 
-fn read_named_node(s: &str) -> Result<(NamedNode, &str), TermParseError> {
+pub(crate) fn read_named_node(s: &str) -> Result<(NamedNode, &str), TermParseError> {
     let s = s.trim();
     if let Some(remain) = s.strip_prefix('<') {
         let end = remain
@@ -241,7 +241,7 @@ fn read_literal(s: &str) -> Result<(Literal, &str), TermParseError> {
             return Err(TermParseError::msg("Empty term serialization"));
         }
 
-        let mut cursor = match input.get(0) {
+        let mut cursor = match input.first() {
             Some(b'+' | b'-') => 1,
             _ => 0,
         };
@@ -297,7 +297,10 @@ fn read_literal(s: &str) -> Result<(Literal, &str), TermParseError> {
     }
 }
 
-fn read_term(s: &str, number_of_recursive_calls: usize) -> Result<(Term, &str), TermParseError> {
+pub(crate) fn read_term(
+    s: &str,
+    number_of_recursive_calls: usize,
+) -> Result<(Term, &str), TermParseError> {
     let s = s.trim();
     if let Some(remain) = s.strip_prefix("<<") {
         if number_of_recursive_calls == MAX_NUMBER_OF_NESTED_TRIPLES {
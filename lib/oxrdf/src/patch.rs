@@ -0,0 +1,626 @@
+//! Implementation of the [RDF Patch](https://afs.github.io/rdf-delta/rdf-patch.html) format.
+//!
+//! An RDF Patch is a sequence of rows describing changes to an RDF dataset:
+//! quad additions (`A`) and deletions (`D`), prefix additions (`PA`) and deletions (`PD`)
+//! and transaction boundaries (`TX`, `TC` and `TA`), optionally preceded by headers (`H`).
+//!
+//! Usage example:
+//! ```
+//! use oxrdf::*;
+//! use oxrdf::patch::{Patch, PatchOperation};
+//! use std::str::FromStr;
+//!
+//! let patch = Patch::from_str(
+//!     "TX .\nPA ex <http://example.com/> .\nA ex:s ex:p \"o\" .\nD <http://example.com/s> <http://example.com/p> <http://example.com/o> <http://example.com/g> .\nTC .\n",
+//! )?;
+//! let ex = NamedNode::new("http://example.com/s")?;
+//! assert_eq!(patch.operations()[2], PatchOperation::AddQuad(Quad::new(
+//!     ex.clone(),
+//!     NamedNode::new("http://example.com/p")?,
+//!     Literal::new_simple_literal("o"),
+//!     GraphName::DefaultGraph
+//! )));
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+
+use crate::parser::{read_named_node, read_term};
+#[cfg(feature = "rdf-star")]
+use crate::Subject;
+use crate::{BlankNode, GraphName, Literal, NamedNode, Quad, Term};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+/// An [RDF Patch](https://afs.github.io/rdf-delta/rdf-patch.html) document.
+///
+/// It is composed of a list of headers and of a list of [`PatchOperation`].
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct Patch {
+    headers: Vec<(String, Term)>,
+    operations: Vec<PatchOperation>,
+}
+
+impl Patch {
+    /// Creates an empty patch.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The patch headers (`H` rows).
+    #[inline]
+    pub fn headers(&self) -> &[(String, Term)] {
+        &self.headers
+    }
+
+    /// The patch operations in their order of appearance.
+    #[inline]
+    pub fn operations(&self) -> &[PatchOperation] {
+        &self.operations
+    }
+
+    /// Adds a header to the patch.
+    #[inline]
+    pub fn add_header(&mut self, key: impl Into<String>, value: impl Into<Term>) {
+        self.headers.push((key.into(), value.into()));
+    }
+
+    /// Appends an operation to the patch.
+    #[inline]
+    pub fn push(&mut self, operation: PatchOperation) {
+        self.operations.push(operation);
+    }
+
+    /// Returns `true` if the patch does not contain any operation.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Returns the operations that should be applied to a dataset.
+    ///
+    /// The transaction boundaries are removed and the operations of the aborted transactions (`TA`) are skipped,
+    /// just like the ones of a transaction that is not closed at the end of the patch.
+    pub fn committed_operations(&self) -> Vec<&PatchOperation> {
+        let mut committed = Vec::new();
+        let mut transaction = None;
+        for operation in &self.operations {
+            match operation {
+                PatchOperation::TransactionStart => transaction = Some(Vec::new()),
+                PatchOperation::TransactionCommit => {
+                    committed.extend(transaction.take().into_iter().flatten())
+                }
+                PatchOperation::TransactionAbort => transaction = None,
+                operation => {
+                    if let Some(transaction) = &mut transaction {
+                        transaction.push(operation);
+                    } else {
+                        committed.push(operation)
+                    }
+                }
+            }
+        }
+        committed
+    }
+
+    /// Parses a patch from a [`BufRead`] implementation.
+    ///
+    /// ```
+    /// use oxrdf::patch::Patch;
+    ///
+    /// let patch = Patch::from_reader("A _:s <http://example.com/p> _:o .".as_bytes())?;
+    /// assert_eq!(patch.operations().len(), 1);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, PatchParseError> {
+        let mut parser = PatchParser::default();
+        let mut line_number = 0;
+        for line in reader.lines() {
+            line_number += 1;
+            parser
+                .parse_row(&line?)
+                .map_err(|message| PatchParseError {
+                    kind: PatchParseErrorKind::Syntax {
+                        line: line_number,
+                        message,
+                    },
+                })?;
+        }
+        if parser.in_transaction {
+            return Err(PatchParseError {
+                kind: PatchParseErrorKind::Syntax {
+                    line: line_number,
+                    message: "The patch ends inside of a transaction".into(),
+                },
+            });
+        }
+        Ok(parser.patch)
+    }
+
+    /// Writes the patch into a [`Write`] implementation.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "{}", self)
+    }
+}
+
+impl FromStr for Patch {
+    type Err = PatchParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, PatchParseError> {
+        Self::from_reader(s.as_bytes())
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.headers {
+            writeln!(f, "H {} {} .", key, value)?;
+        }
+        for operation in &self.operations {
+            writeln!(f, "{} .", operation)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<PatchOperation> for Patch {
+    fn from_iter<I: IntoIterator<Item = PatchOperation>>(iter: I) -> Self {
+        Self {
+            headers: Vec::new(),
+            operations: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<PatchOperation> for Patch {
+    fn extend<I: IntoIterator<Item = PatchOperation>>(&mut self, iter: I) {
+        self.operations.extend(iter)
+    }
+}
+
+/// An operation of a [`Patch`].
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+pub enum PatchOperation {
+    /// Start of a transaction (`TX`).
+    TransactionStart,
+    /// Commit of the current transaction (`TC`).
+    TransactionCommit,
+    /// Abort of the current transaction (`TA`).
+    TransactionAbort,
+    /// Addition of a prefix (`PA`).
+    AddPrefix { prefix: String, iri: NamedNode },
+    /// Deletion of a prefix (`PD`).
+    DeletePrefix { prefix: String },
+    /// Addition of a quad (`A`).
+    AddQuad(Quad),
+    /// Deletion of a quad (`D`).
+    DeleteQuad(Quad),
+}
+
+impl fmt::Display for PatchOperation {
+    /// Writes the operation row without its final `.`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TransactionStart => f.write_str("TX"),
+            Self::TransactionCommit => f.write_str("TC"),
+            Self::TransactionAbort => f.write_str("TA"),
+            Self::AddPrefix { prefix, iri } => write!(f, "PA {} {}", prefix, iri),
+            Self::DeletePrefix { prefix } => write!(f, "PD {}", prefix),
+            Self::AddQuad(quad) => write!(f, "A {}", quad),
+            Self::DeleteQuad(quad) => write!(f, "D {}", quad),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PatchParser {
+    patch: Patch,
+    prefixes: HashMap<String, String>,
+    in_transaction: bool,
+}
+
+impl PatchParser {
+    fn parse_row(&mut self, row: &str) -> Result<(), String> {
+        let row = row.trim();
+        if row.is_empty() || row.starts_with('#') {
+            return Ok(());
+        }
+        let (code, remain) = row.split_at(
+            row.find(|c: char| c.is_whitespace() || c == '.')
+                .unwrap_or(row.len()),
+        );
+        let (operation, remain) = match code {
+            "H" => {
+                if !self.patch.operations.is_empty() {
+                    return Err("Headers are only allowed at the beginning of the patch".into());
+                }
+                let remain = remain.trim_start();
+                let (key, remain) =
+                    remain.split_at(remain.find(char::is_whitespace).unwrap_or(remain.len()));
+                if key.is_empty() {
+                    return Err("Headers should have a key".into());
+                }
+                let (value, remain) = self.read_term(remain)?;
+                self.patch.headers.push((key.into(), value));
+                return end_row(remain);
+            }
+            "TX" => {
+                if self.in_transaction {
+                    return Err("Nested transactions are not allowed".into());
+                }
+                self.in_transaction = true;
+                (PatchOperation::TransactionStart, remain)
+            }
+            "TC" | "TA" => {
+                if !self.in_transaction {
+                    return Err(format!("{} is not allowed outside of a transaction", code));
+                }
+                self.in_transaction = false;
+                (
+                    if code == "TC" {
+                        PatchOperation::TransactionCommit
+                    } else {
+                        PatchOperation::TransactionAbort
+                    },
+                    remain,
+                )
+            }
+            "PA" => {
+                let remain = remain.trim_start();
+                let (prefix, remain) =
+                    remain.split_at(remain.find(char::is_whitespace).unwrap_or(remain.len()));
+                let prefix = prefix.strip_suffix(':').unwrap_or(prefix);
+                let (iri, remain) = read_named_node(remain).map_err(|e| e.to_string())?;
+                self.prefixes.insert(prefix.into(), iri.as_str().into());
+                (
+                    PatchOperation::AddPrefix {
+                        prefix: prefix.into(),
+                        iri,
+                    },
+                    remain,
+                )
+            }
+            "PD" => {
+                let remain = remain.trim_start();
+                let (prefix, remain) = remain.split_at(
+                    remain
+                        .find(|c: char| c.is_whitespace() || c == '.')
+                        .unwrap_or(remain.len()),
+                );
+                let prefix = prefix.strip_suffix(':').unwrap_or(prefix);
+                self.prefixes.remove(prefix);
+                (
+                    PatchOperation::DeletePrefix {
+                        prefix: prefix.into(),
+                    },
+                    remain,
+                )
+            }
+            "A" | "D" => {
+                let (quad, remain) = self.read_quad(remain)?;
+                (
+                    if code == "A" {
+                        PatchOperation::AddQuad(quad)
+                    } else {
+                        PatchOperation::DeleteQuad(quad)
+                    },
+                    remain,
+                )
+            }
+            _ => return Err(format!("Unknown row code '{}'", code)),
+        };
+        self.patch.operations.push(operation);
+        end_row(remain)
+    }
+
+    fn read_quad<'a>(&self, s: &'a str) -> Result<(Quad, &'a str), String> {
+        let (subject, remain) = self.read_term(s)?;
+        let (predicate, remain) = self.read_term(remain)?;
+        let (object, remain) = self.read_term(remain)?;
+        let (graph_name, remain) = if remain.trim_start().is_empty() || remain.trim() == "." {
+            (GraphName::DefaultGraph, remain)
+        } else {
+            let (graph_name, remain) = self.read_term(remain)?;
+            (
+                match graph_name {
+                    Term::NamedNode(g) => g.into(),
+                    Term::BlankNode(g) => g.into(),
+                    _ => return Err("The graph name should be an IRI or a blank node".into()),
+                },
+                remain,
+            )
+        };
+        Ok((
+            Quad {
+                subject: match subject {
+                    Term::NamedNode(s) => s.into(),
+                    Term::BlankNode(s) => s.into(),
+                    #[cfg(feature = "rdf-star")]
+                    Term::Triple(s) => Subject::Triple(s),
+                    Term::Literal(_) => {
+                        return Err("Literals are not allowed in subject position".into())
+                    }
+                },
+                predicate: if let Term::NamedNode(p) = predicate {
+                    p
+                } else {
+                    return Err("The predicate should be an IRI".into());
+                },
+                object,
+                graph_name,
+            },
+            remain,
+        ))
+    }
+
+    fn read_term<'a>(&self, s: &'a str) -> Result<(Term, &'a str), String> {
+        let s = s.trim_start();
+        if let Some(remain) = s.strip_prefix("<_:") {
+            // Blank nodes could also be written as IRIs with the _ scheme
+            let end = remain
+                .find('>')
+                .ok_or("Blank node serialization should end with a >")?;
+            let blank_node = BlankNode::new(&remain[..end]).map_err(|e| e.to_string())?;
+            return Ok((blank_node.into(), &remain[end + 1..]));
+        }
+        if s.starts_with('"') {
+            // Literals with a prefixed name datatype like "1"^^xsd:integer
+            if let Some(end) = literal_end(s) {
+                if let Some(datatype) = s[end..].strip_prefix("^^") {
+                    if !datatype.starts_with('<') {
+                        let (datatype, remain) = self.read_prefixed_name(datatype)?;
+                        let value = match read_term(&s[..end], 0).map_err(|e| e.to_string())? {
+                            (Term::Literal(value), _) => value,
+                            _ => return Err(format!("Invalid literal '{}'", &s[..end])),
+                        };
+                        return Ok((
+                            Literal::new_typed_literal(value.value(), datatype).into(),
+                            remain,
+                        ));
+                    }
+                }
+            }
+        } else if s.starts_with(|c: char| c.is_alphabetic() || c == ':') {
+            // The keywords true and false or a prefixed name
+            let name = term_token(s);
+            if name == "true" || name == "false" {
+                return read_term(s, 0).map_err(|e| e.to_string());
+            }
+            return self.read_prefixed_name(s).map(|(n, r)| (n.into(), r));
+        }
+        read_term(s, 0).map_err(|e| e.to_string())
+    }
+
+    fn read_prefixed_name<'a>(&self, s: &'a str) -> Result<(NamedNode, &'a str), String> {
+        let name = term_token(s);
+        let remain = &s[name.len()..];
+        let (prefix, local) = name
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid term '{}'", name))?;
+        let namespace = self
+            .prefixes
+            .get(prefix)
+            .ok_or_else(|| format!("The prefix '{}' has not been declared", prefix))?;
+        let iri = NamedNode::new(format!("{}{}", namespace, local)).map_err(|e| e.to_string())?;
+        Ok((iri, remain))
+    }
+}
+
+/// Returns the term at the beginning of the row, without the final dot of the row
+fn term_token(s: &str) -> &str {
+    let token = &s[..s.find(char::is_whitespace).unwrap_or(s.len())];
+    token.strip_suffix('.').unwrap_or(token)
+}
+
+/// Returns the position just after the closing quote of the literal at the beginning of `s`
+fn literal_end(s: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+fn end_row(remain: &str) -> Result<(), String> {
+    let remain = remain.trim();
+    if remain.is_empty() || remain == "." {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unexpected content at the end of the row: '{}'",
+            remain
+        ))
+    }
+}
+
+/// An error raised while parsing an RDF Patch with [`Patch::from_reader`] or [`FromStr`].
+#[derive(Debug)]
+pub struct PatchParseError {
+    kind: PatchParseErrorKind,
+}
+
+#[derive(Debug)]
+enum PatchParseErrorKind {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for PatchParseError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PatchParseErrorKind::Io(e) => e.fmt(f),
+            PatchParseErrorKind::Syntax { line, message } => {
+                write!(f, "Invalid RDF Patch on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl Error for PatchParseError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            PatchParseErrorKind::Io(e) => Some(e),
+            PatchParseErrorKind::Syntax { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for PatchParseError {
+    #[inline]
+    fn from(error: io::Error) -> Self {
+        Self {
+            kind: PatchParseErrorKind::Io(error),
+        }
+    }
+}
+
+impl From<PatchParseError> for io::Error {
+    #[inline]
+    fn from(error: PatchParseError) -> Self {
+        match error.kind {
+            PatchParseErrorKind::Io(error) => error,
+            PatchParseErrorKind::Syntax { .. } => {
+                Self::new(io::ErrorKind::InvalidData, error.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Literal;
+
+    #[test]
+    fn test_round_trip() {
+        let s = NamedNode::new_unchecked("http://example.com/s");
+        let p = NamedNode::new_unchecked("http://example.com/p");
+        let g = NamedNode::new_unchecked("http://example.com/g");
+        let mut patch = Patch::new();
+        patch.add_header("id", NamedNode::new_unchecked("uuid:0"));
+        patch.push(PatchOperation::TransactionStart);
+        patch.push(PatchOperation::AddPrefix {
+            prefix: "ex".into(),
+            iri: NamedNode::new_unchecked("http://example.com/"),
+        });
+        patch.push(PatchOperation::AddQuad(Quad::new(
+            s.clone(),
+            p.clone(),
+            Literal::new_language_tagged_literal_unchecked("a \"b\"\n", "en"),
+            g,
+        )));
+        patch.push(PatchOperation::DeleteQuad(Quad::new(
+            BlankNode::new_unchecked("b"),
+            p,
+            s,
+            GraphName::DefaultGraph,
+        )));
+        patch.push(PatchOperation::DeletePrefix {
+            prefix: "ex".into(),
+        });
+        patch.push(PatchOperation::TransactionCommit);
+        let serialization = patch.to_string();
+        assert_eq!(serialization, "H id <uuid:0> .\nTX .\nPA ex <http://example.com/> .\nA <http://example.com/s> <http://example.com/p> \"a \\\"b\\\"\\n\"@en <http://example.com/g> .\nD _:b <http://example.com/p> <http://example.com/s> .\nPD ex .\nTC .\n");
+        assert_eq!(Patch::from_str(&serialization).unwrap(), patch);
+    }
+
+    #[test]
+    fn test_prefixed_names_and_blank_nodes() {
+        let patch = Patch::from_str(
+            "# comment\nPA ex: <http://example.com/> .\nA ex:s ex:p <_:o> ex:g .\nPD ex: .",
+        )
+        .unwrap();
+        assert_eq!(
+            patch.operations()[1],
+            PatchOperation::AddQuad(Quad::new(
+                NamedNode::new_unchecked("http://example.com/s"),
+                NamedNode::new_unchecked("http://example.com/p"),
+                BlankNode::new_unchecked("o"),
+                NamedNode::new_unchecked("http://example.com/g"),
+            ))
+        );
+        assert!(Patch::from_str("PD ex .\nA ex:s ex:p ex:o .").is_err());
+    }
+
+    #[test]
+    fn test_prefixed_datatypes_and_booleans() {
+        let patch = Patch::from_str(
+            "PA xsd: <http://www.w3.org/2001/XMLSchema#> .\nPA truex: <http://example.com/> .\nA truex:s truex:p \"1\"^^xsd:integer .\nA truex:s truex:p true .\nA truex:s truex:p truex:o.",
+        )
+        .unwrap();
+        let s = NamedNode::new_unchecked("http://example.com/s");
+        let p = NamedNode::new_unchecked("http://example.com/p");
+        assert_eq!(
+            patch.operations()[2..],
+            [
+                PatchOperation::AddQuad(Quad::new(
+                    s.clone(),
+                    p.clone(),
+                    Literal::from(1),
+                    GraphName::DefaultGraph,
+                )),
+                PatchOperation::AddQuad(Quad::new(
+                    s.clone(),
+                    p.clone(),
+                    Literal::from(true),
+                    GraphName::DefaultGraph,
+                )),
+                PatchOperation::AddQuad(Quad::new(
+                    s,
+                    p,
+                    NamedNode::new_unchecked("http://example.com/o"),
+                    GraphName::DefaultGraph,
+                )),
+            ]
+        );
+        assert!(Patch::from_str(
+            "A <http://example.com/s> <http://example.com/p> \"1\"^^xsd:integer ."
+        )
+        .is_err());
+        assert!(
+            Patch::from_str("A <http://example.com/s> <http://example.com/p> trueish .").is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_patches() {
+        assert!(Patch::from_str("TX .\nTX .\nTC .\nTC .").is_err());
+        assert!(Patch::from_str("TC .").is_err());
+        assert!(Patch::from_str("TX .").is_err());
+        assert!(Patch::from_str("X .").is_err());
+        assert!(
+            Patch::from_str("A \"s\" <http://example.com/p> <http://example.com/o> .").is_err()
+        );
+        assert!(Patch::from_str("TX .\nTC .\nH id <uuid:0> .").is_err());
+    }
+
+    #[test]
+    fn test_committed_operations() {
+        let patch = Patch::from_str(
+            "TX .\nA <http://example.com/s> <http://example.com/p> \"1\" .\nTA .\nTX .\nA <http://example.com/s> <http://example.com/p> \"2\" .\nTC .\nD <http://example.com/s> <http://example.com/p> \"3\" .",
+        )
+        .unwrap();
+        let committed = patch.committed_operations();
+        assert_eq!(committed.len(), 2);
+        assert_eq!(
+            committed[0],
+            &PatchOperation::AddQuad(Quad::new(
+                NamedNode::new_unchecked("http://example.com/s"),
+                NamedNode::new_unchecked("http://example.com/p"),
+                Literal::new_simple_literal("2"),
+                GraphName::DefaultGraph,
+            ))
+        );
+    }
+}
@@ -1,9 +1,11 @@
 //! Implementation of [SPARQL 1.1 Query Results CSV and TSV Formats](https://www.w3.org/TR/sparql11-results-csv-tsv/)
 
 use crate::error::{ParseError, SyntaxError, SyntaxErrorKind};
+use crate::CsvTermTyping;
 use oxrdf::Variable;
 use oxrdf::{vocab::xsd, *};
 use std::io::{self, BufRead, Write};
+use std::mem::take;
 use std::str::FromStr;
 
 pub fn write_boolean_csv_result<W: Write>(mut sink: W, value: bool) -> io::Result<W> {
@@ -258,6 +260,172 @@ impl<R: BufRead> TsvSolutionsReader<R> {
     }
 }
 
+pub enum CsvQueryResultsReader<R: BufRead> {
+    Solutions {
+        variables: Vec<Variable>,
+        solutions: CsvSolutionsReader<R>,
+    },
+    Boolean(bool),
+}
+
+impl<R: BufRead> CsvQueryResultsReader<R> {
+    pub fn read(source: R, term_typing: CsvTermTyping) -> Result<Self, ParseError> {
+        let mut reader = CsvRecordReader {
+            source,
+            buffer: String::new(),
+        };
+
+        // We read the header
+        let header = reader
+            .read_record()?
+            .ok_or_else(|| SyntaxError::msg("Empty CSV results file"))?;
+        if let [value] = header.as_slice() {
+            if value.trim().eq_ignore_ascii_case("true") {
+                return Ok(Self::Boolean(true));
+            }
+            if value.trim().eq_ignore_ascii_case("false") {
+                return Ok(Self::Boolean(false));
+            }
+        }
+        let mut variables = Vec::new();
+        for v in header {
+            let v = v.trim();
+            let variable = Variable::new(v).map_err(|e| {
+                SyntaxError::msg(format!("Invalid variable declaration '{}': {}", v, e))
+            })?;
+            if variables.contains(&variable) {
+                return Err(SyntaxError::msg(format!(
+                    "The variable {} is declared twice",
+                    variable
+                ))
+                .into());
+            }
+            variables.push(variable);
+        }
+
+        Ok(Self::Solutions {
+            solutions: CsvSolutionsReader {
+                reader,
+                variables_count: variables.len(),
+                term_typing,
+            },
+            variables,
+        })
+    }
+}
+
+pub struct CsvSolutionsReader<R: BufRead> {
+    reader: CsvRecordReader<R>,
+    variables_count: usize,
+    term_typing: CsvTermTyping,
+}
+
+impl<R: BufRead> CsvSolutionsReader<R> {
+    pub fn read_next(&mut self) -> Result<Option<Vec<Option<Term>>>, ParseError> {
+        let record = if let Some(record) = self.reader.read_record()? {
+            record
+        } else {
+            return Ok(None);
+        };
+        if record.len() != self.variables_count {
+            return Err(SyntaxError::msg(format!(
+                "This CSV line has {} values but {} variables are declared",
+                record.len(),
+                self.variables_count
+            ))
+            .into());
+        }
+        Ok(Some(
+            record
+                .into_iter()
+                .map(|v| {
+                    if v.is_empty() {
+                        None
+                    } else {
+                        Some(parse_csv_term(v, self.term_typing))
+                    }
+                })
+                .collect(),
+        ))
+    }
+}
+
+fn parse_csv_term(value: String, term_typing: CsvTermTyping) -> Term {
+    if term_typing == CsvTermTyping::Infer {
+        if let Some(id) = value.strip_prefix("_:") {
+            if let Ok(node) = BlankNode::new(id) {
+                return node.into();
+            }
+        } else if value.contains(':') {
+            if let Ok(node) = NamedNode::new(value.as_str()) {
+                return node.into();
+            }
+        }
+    }
+    Literal::new_simple_literal(value).into()
+}
+
+/// Reads [RFC 4180](https://tools.ietf.org/html/rfc4180) records, values might be quoted and span multiple lines
+struct CsvRecordReader<R: BufRead> {
+    source: R,
+    buffer: String,
+}
+
+impl<R: BufRead> CsvRecordReader<R> {
+    fn read_record(&mut self) -> Result<Option<Vec<String>>, ParseError> {
+        let mut values = Vec::new();
+        let mut value = String::new();
+        let mut in_quotes = false;
+        loop {
+            self.buffer.clear();
+            if self.source.read_line(&mut self.buffer)? == 0 {
+                return if in_quotes {
+                    Err(
+                        SyntaxError::msg("Unterminated quoted value at the end of the CSV file")
+                            .into(),
+                    )
+                } else {
+                    Ok(None)
+                };
+            }
+            let mut chars = self.buffer.chars().peekable();
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                            value.push('"');
+                        } else {
+                            in_quotes = false;
+                        }
+                    } else {
+                        value.push(c);
+                    }
+                } else {
+                    match c {
+                        '"' if value.is_empty() => in_quotes = true,
+                        '"' => {
+                            return Err(SyntaxError::msg(
+                                "Unexpected quote in an unquoted CSV value",
+                            )
+                            .into())
+                        }
+                        ',' => values.push(take(&mut value)),
+                        '\r' if matches!(chars.peek(), Some('\n') | None) => (),
+                        '\n' => break,
+                        c => value.push(c),
+                    }
+                }
+            }
+            if !in_quotes {
+                // End of line or of the file
+                values.push(value);
+                return Ok(Some(values));
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -344,6 +512,92 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_csv_parsing() -> Result<(), ParseError> {
+        if let CsvQueryResultsReader::Solutions {
+            variables,
+            mut solutions,
+        } = CsvQueryResultsReader::read(
+            Cursor::new("x,literal\r\nhttp://example/x,String\r\nhttp://example/x,\"String-with-dquote\"\"\"\r\n_:b0,\"Multi\r\nline, value\"\r\n,Missing 'x'\r\n,\r\n"),
+            CsvTermTyping::Infer,
+        )? {
+            assert_eq!(
+                variables,
+                vec![
+                    Variable::new_unchecked("x"),
+                    Variable::new_unchecked("literal")
+                ]
+            );
+            let mut rows = Vec::new();
+            while let Some(row) = solutions.read_next()? {
+                rows.push(row);
+            }
+            assert_eq!(
+                rows,
+                vec![
+                    vec![
+                        Some(NamedNode::new_unchecked("http://example/x").into()),
+                        Some(Literal::new_simple_literal("String").into()),
+                    ],
+                    vec![
+                        Some(NamedNode::new_unchecked("http://example/x").into()),
+                        Some(Literal::new_simple_literal("String-with-dquote\"").into()),
+                    ],
+                    vec![
+                        Some(BlankNode::new_unchecked("b0").into()),
+                        Some(Literal::new_simple_literal("Multi\r\nline, value").into()),
+                    ],
+                    vec![
+                        None,
+                        Some(Literal::new_simple_literal("Missing 'x'").into()),
+                    ],
+                    vec![None, None],
+                ]
+            );
+        } else {
+            panic!("Solutions expected")
+        }
+        if let CsvQueryResultsReader::Solutions { mut solutions, .. } = CsvQueryResultsReader::read(
+            Cursor::new("x\nhttp://example/x\n_:b0"),
+            CsvTermTyping::Literals,
+        )? {
+            assert_eq!(
+                solutions.read_next()?,
+                Some(vec![Some(
+                    Literal::new_simple_literal("http://example/x").into()
+                )])
+            );
+            assert_eq!(
+                solutions.read_next()?,
+                Some(vec![Some(Literal::new_simple_literal("_:b0").into())])
+            );
+            assert_eq!(solutions.read_next()?, None);
+        } else {
+            panic!("Solutions expected")
+        }
+        assert!(matches!(
+            CsvQueryResultsReader::read(Cursor::new("true"), CsvTermTyping::Infer)?,
+            CsvQueryResultsReader::Boolean(true)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_csv() {
+        let bad_csvs = vec!["", "x,x", "?x", "x\n\"foo", "x\nfoo\"bar\"", "x,y\nfoo"];
+        for bad_csv in bad_csvs {
+            if let Ok(CsvQueryResultsReader::Solutions { mut solutions, .. }) =
+                CsvQueryResultsReader::read(Cursor::new(bad_csv), CsvTermTyping::Infer)
+            {
+                assert!(
+                    solutions.read_next().is_err(),
+                    "{:?} should be invalid",
+                    bad_csv
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_bad_tsv() {
         let mut bad_tsvs = vec![
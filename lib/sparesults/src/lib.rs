@@ -135,8 +135,12 @@ impl QueryResultsFormat {
 /// It currently supports the following formats:
 /// * [SPARQL Query Results XML Format](http://www.w3.org/TR/rdf-sparql-XMLres/) ([`QueryResultsFormat::Xml`](QueryResultsFormat::Xml)).
 /// * [SPARQL Query Results JSON Format](https://www.w3.org/TR/sparql11-results-json/) ([`QueryResultsFormat::Json`](QueryResultsFormat::Json)).
+/// * [SPARQL Query Results CSV Format](https://www.w3.org/TR/sparql11-results-csv-tsv/) ([`QueryResultsFormat::Csv`](QueryResultsFormat::Csv)).
 /// * [SPARQL Query Results TSV Format](https://www.w3.org/TR/sparql11-results-csv-tsv/) ([`QueryResultsFormat::Tsv`](QueryResultsFormat::Tsv)).
 ///
+/// The CSV format is lossy: it does not distinguish IRIs from literals and does not keep literals datatypes and language tags.
+/// See [`with_csv_term_typing`](QueryResultsParser::with_csv_term_typing) to choose how the CSV values are converted to RDF terms.
+///
 /// Example in JSON (the API is the same for XML and TSV):
 /// ```
 /// use sparesults::{QueryResultsFormat, QueryResultsParser, QueryResultsReader};
@@ -158,13 +162,46 @@ impl QueryResultsFormat {
 /// ```
 pub struct QueryResultsParser {
     format: QueryResultsFormat,
+    csv_term_typing: CsvTermTyping,
 }
 
 impl QueryResultsParser {
     /// Builds a parser for the given format.
     #[inline]
     pub fn from_format(format: QueryResultsFormat) -> Self {
-        Self { format }
+        Self {
+            format,
+            csv_term_typing: CsvTermTyping::default(),
+        }
+    }
+
+    /// Sets how the values of [CSV](QueryResultsFormat::Csv) results are converted to RDF terms.
+    ///
+    /// The default is [`CsvTermTyping::Infer`].
+    ///
+    /// ```
+    /// use sparesults::{CsvTermTyping, QueryResultsFormat, QueryResultsParser, QueryResultsReader};
+    /// use oxrdf::{Literal, NamedNode, Variable};
+    ///
+    /// let csv_parser = QueryResultsParser::from_format(QueryResultsFormat::Csv);
+    /// if let QueryResultsReader::Solutions(solutions) = csv_parser.read_results(b"foo,bar\r\nhttp://example.com,\"test, \"\"quoted\"\"\"".as_slice())? {
+    ///     for solution in solutions {
+    ///         assert_eq!(solution?.iter().collect::<Vec<_>>(), vec![(&Variable::new_unchecked("foo"), &NamedNode::new_unchecked("http://example.com").into()), (&Variable::new_unchecked("bar"), &Literal::from("test, \"quoted\"").into())]);
+    ///     }
+    /// }
+    ///
+    /// let csv_parser = QueryResultsParser::from_format(QueryResultsFormat::Csv).with_csv_term_typing(CsvTermTyping::Literals);
+    /// if let QueryResultsReader::Solutions(solutions) = csv_parser.read_results(b"foo\r\nhttp://example.com".as_slice())? {
+    ///     for solution in solutions {
+    ///         assert_eq!(solution?.iter().collect::<Vec<_>>(), vec![(&Variable::new_unchecked("foo"), &Literal::from("http://example.com").into())]);
+    ///     }
+    /// }
+    /// # Result::<(),sparesults::ParseError>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_csv_term_typing(mut self, term_typing: CsvTermTyping) -> Self {
+        self.csv_term_typing = term_typing;
+        self
     }
 
     /// Reads a result file.
@@ -212,7 +249,18 @@ impl QueryResultsParser {
                     solutions: SolutionsReaderKind::Json(solutions),
                 }),
             },
-            QueryResultsFormat::Csv => return Err(SyntaxError::msg("CSV SPARQL results syntax is lossy and can't be parsed to a proper RDF representation").into()),
+            QueryResultsFormat::Csv => {
+                match CsvQueryResultsReader::read(reader, self.csv_term_typing)? {
+                    CsvQueryResultsReader::Boolean(r) => QueryResultsReader::Boolean(r),
+                    CsvQueryResultsReader::Solutions {
+                        solutions,
+                        variables,
+                    } => QueryResultsReader::Solutions(SolutionsReader {
+                        variables: Rc::new(variables),
+                        solutions: SolutionsReaderKind::Csv(solutions),
+                    }),
+                }
+            }
             QueryResultsFormat::Tsv => match TsvQueryResultsReader::read(reader)? {
                 TsvQueryResultsReader::Boolean(r) => QueryResultsReader::Boolean(r),
                 TsvQueryResultsReader::Solutions {
@@ -227,6 +275,25 @@ impl QueryResultsParser {
     }
 }
 
+/// How the values of [CSV](QueryResultsFormat::Csv) results are converted to RDF terms.
+///
+/// The CSV results format does not keep the kind of the terms, so it has to be guessed.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+#[non_exhaustive]
+pub enum CsvTermTyping {
+    /// Values that are absolute IRIs are read as IRIs, values starting with `_:` as blank nodes and the other values as simple literals.
+    Infer,
+    /// All values are read as simple literals.
+    Literals,
+}
+
+impl Default for CsvTermTyping {
+    #[inline]
+    fn default() -> Self {
+        Self::Infer
+    }
+}
+
 /// The reader for a given read of a results file.
 ///
 /// It is either a read boolean ([`bool`]) or a streaming reader of a set of solutions ([`SolutionsReader`]).
@@ -283,6 +350,7 @@ pub struct SolutionsReader<R: BufRead> {
 enum SolutionsReaderKind<R: BufRead> {
     Xml(XmlSolutionsReader<R>),
     Json(JsonSolutionsReader<R>),
+    Csv(CsvSolutionsReader<R>),
     Tsv(TsvSolutionsReader<R>),
 }
 
@@ -314,6 +382,7 @@ impl<R: BufRead> Iterator for SolutionsReader<R> {
             match &mut self.solutions {
                 SolutionsReaderKind::Xml(reader) => reader.read_next(),
                 SolutionsReaderKind::Json(reader) => reader.read_next(),
+                SolutionsReaderKind::Csv(reader) => reader.read_next(),
                 SolutionsReaderKind::Tsv(reader) => reader.read_next(),
             }
             .transpose()?
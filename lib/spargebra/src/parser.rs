@@ -501,7 +501,7 @@ fn build_select(
                         }
                         v
                     }
-                    SelectionMember::Expression(expression, variable) => {
+                    SelectionMember::Expression(mut expression, variable) => {
                         if visible.contains(&variable) {
                             // We disallow to override an existing variable with an expression
                             return Err(
@@ -509,10 +509,19 @@ fn build_select(
                             );
                         }
                         if with_aggregate && !are_variables_bound(&expression, &visible) {
-                            // We validate projection variables if there is an aggregate
-                            return Err(
-                                "The SELECT contains an expression with a variable that is unbound",
-                            );
+                            if let Some(aggregates) = group_aggregates(&mut p) {
+                                lift_unbound_custom_aggregates(
+                                    &mut expression,
+                                    &mut visible,
+                                    aggregates,
+                                );
+                            }
+                            if !are_variables_bound(&expression, &visible) {
+                                // We validate projection variables if there is an aggregate
+                                return Err(
+                                    "The SELECT contains an expression with a variable that is unbound",
+                                );
+                            }
                         }
                         p = GraphPattern::Extend {
                             inner: Box::new(p),
@@ -618,6 +627,92 @@ fn are_variables_bound(expression: &Expression, variables: &HashSet<Variable>) -
     }
 }
 
+/// The aggregates of the grouping on which the `SELECT` expressions are evaluated
+fn group_aggregates(
+    pattern: &mut GraphPattern,
+) -> Option<&mut Vec<(Variable, AggregateExpression)>> {
+    match pattern {
+        GraphPattern::Group { aggregates, .. } => Some(aggregates),
+        GraphPattern::Filter { inner, .. } | GraphPattern::Extend { inner, .. } => {
+            group_aggregates(inner)
+        }
+        GraphPattern::Join { left, .. } => group_aggregates(left),
+        _ => None,
+    }
+}
+
+/// Turns the calls to custom functions with a single unbound argument into custom aggregates
+///
+/// It is their only valid interpretation in a `SELECT` expression evaluated on groups.
+fn lift_unbound_custom_aggregates(
+    expression: &mut Expression,
+    visible: &mut HashSet<Variable>,
+    aggregates: &mut Vec<(Variable, AggregateExpression)>,
+) {
+    match expression {
+        Expression::FunctionCall(Function::Custom(name), parameters)
+            if parameters.len() == 1 && !are_variables_bound(&parameters[0], visible) =>
+        {
+            let aggregate = AggregateExpression::Custom {
+                name: name.clone(),
+                expr: Box::new(parameters.remove(0)),
+                distinct: false,
+            };
+            let variable = aggregates
+                .iter()
+                .find_map(|(v, a)| if a == &aggregate { Some(v) } else { None })
+                .cloned()
+                .unwrap_or_else(|| {
+                    let variable = variable();
+                    aggregates.push((variable.clone(), aggregate));
+                    variable
+                });
+            visible.insert(variable.clone());
+            *expression = Expression::Variable(variable);
+        }
+        Expression::Or(a, b)
+        | Expression::And(a, b)
+        | Expression::Equal(a, b)
+        | Expression::SameTerm(a, b)
+        | Expression::Greater(a, b)
+        | Expression::GreaterOrEqual(a, b)
+        | Expression::Less(a, b)
+        | Expression::LessOrEqual(a, b)
+        | Expression::Add(a, b)
+        | Expression::Subtract(a, b)
+        | Expression::Multiply(a, b)
+        | Expression::Divide(a, b) => {
+            lift_unbound_custom_aggregates(a, visible, aggregates);
+            lift_unbound_custom_aggregates(b, visible, aggregates);
+        }
+        Expression::UnaryPlus(e) | Expression::UnaryMinus(e) | Expression::Not(e) => {
+            lift_unbound_custom_aggregates(e, visible, aggregates)
+        }
+        Expression::If(a, b, c) => {
+            lift_unbound_custom_aggregates(a, visible, aggregates);
+            lift_unbound_custom_aggregates(b, visible, aggregates);
+            lift_unbound_custom_aggregates(c, visible, aggregates);
+        }
+        Expression::In(a, b) => {
+            lift_unbound_custom_aggregates(a, visible, aggregates);
+            for b in b {
+                lift_unbound_custom_aggregates(b, visible, aggregates);
+            }
+        }
+        Expression::FunctionCall(_, parameters) => {
+            for parameter in parameters {
+                lift_unbound_custom_aggregates(parameter, visible, aggregates);
+            }
+        }
+        Expression::NamedNode(_)
+        | Expression::Literal(_)
+        | Expression::Variable(_)
+        | Expression::Bound(_)
+        | Expression::Coalesce(_)
+        | Expression::Exists(_) => (),
+    }
+}
+
 fn copy_graph(from: impl Into<GraphName>, to: impl Into<GraphNamePattern>) -> GraphUpdateOperation {
     let bgp = GraphPattern::Bgp {
         patterns: vec![TriplePattern::new(
@@ -2068,7 +2163,9 @@ parser! {
             name:iri() _ "(" _ e:Expression() _ ")" { AggregateExpression::Custom { name, expr: Box::new(e), distinct: false } }
 
         //[128]
-        rule iriOrFunction() -> Expression = i: iri() _ a: ArgList()? {
+        rule iriOrFunction() -> Expression =
+            name:iri() _ "(" _ i("DISTINCT") _ e:Expression() _ ")" {? state.new_aggregation(AggregateExpression::Custom { name, expr: Box::new(e), distinct: true }).map(|v| v.into()) } /
+            i: iri() _ a: ArgList()? {
             match a {
                 Some(a) => Expression::FunctionCall(Function::Custom(i), a),
                 None => i.into()
@@ -0,0 +1,291 @@
+//! Export of query solutions and quads to [Apache Arrow](https://arrow.apache.org/) record batches.
+//!
+//! It is available behind the `arrow` feature.
+//!
+//! Each RDF term is encoded using four nullable columns:
+//! * `{name}` the term value: the IRI, the blank node identifier, the literal lexical form or the N-Triples serialization of the quoted triple.
+//! * `{name}.kind` the term kind: `uri`, `bnode`, `literal` or `triple` (the names used by the [SPARQL JSON results format](https://www.w3.org/TR/sparql11-results-json/)).
+//! * `{name}.datatype` the literal datatype IRI.
+//! * `{name}.language` the literal language tag.
+//!
+//! `{name}` is the variable name for query solutions and `subject`, `predicate`, `object` and `graph_name` for quads.
+//! Unbound variables and the default graph are encoded with null values.
+//! The `.` character is not allowed in SPARQL variable names so the column names never conflict.
+//!
+//! Usage example:
+//! ```
+//! use oxigraph::store::Store;
+//! use oxigraph::model::*;
+//! use oxigraph::sparql::QueryResults;
+//!
+//! let store = Store::new()?;
+//! let ex = NamedNodeRef::new("http://example.com")?;
+//! store.insert(QuadRef::new(ex, ex, LiteralRef::new_simple_literal("foo"), GraphNameRef::DefaultGraph))?;
+//!
+//! if let QueryResults::Solutions(solutions) = store.query("SELECT ?s ?o WHERE { ?s ?p ?o }")? {
+//!     for batch in solutions.into_record_batches(1024) {
+//!         let batch = batch?;
+//!         assert_eq!(batch.num_rows(), 1);
+//!         assert_eq!(batch.num_columns(), 8);
+//!         assert_eq!(batch.schema().field(4).name(), "o");
+//!     }
+//! }
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+
+use crate::model::*;
+use crate::sparql::{EvaluationError, QuerySolutionIter, Variable};
+use arrow::array::{ArrayRef, DictionaryArray, StringArray};
+use arrow::datatypes::{DataType, Field, Int32Type, Int8Type, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use std::io;
+use std::mem::take;
+use std::sync::Arc;
+
+/// The default number of rows in each record batch.
+pub const DEFAULT_BATCH_SIZE: usize = 8192;
+
+/// Returns the Arrow schema of the solutions of a query with the given variables.
+///
+/// ```
+/// use oxigraph::io::arrow::solutions_schema;
+/// use oxigraph::sparql::Variable;
+///
+/// let schema = solutions_schema(&[Variable::new("s")?]);
+/// assert_eq!(schema.fields().iter().map(|f| f.name().as_str()).collect::<Vec<_>>(), ["s", "s.kind", "s.datatype", "s.language"]);
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+pub fn solutions_schema(variables: &[Variable]) -> Schema {
+    Schema::new(
+        variables
+            .iter()
+            .flat_map(|v| term_fields(v.as_str()))
+            .collect(),
+    )
+}
+
+/// Returns the Arrow schema of quads.
+pub fn quads_schema() -> Schema {
+    Schema::new(
+        ["subject", "predicate", "object", "graph_name"]
+            .iter()
+            .flat_map(|name| term_fields(name))
+            .collect(),
+    )
+}
+
+fn term_fields(name: &str) -> [Field; 4] {
+    [
+        Field::new(name, DataType::Utf8, true),
+        Field::new(
+            &format!("{}.kind", name),
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8)),
+            true,
+        ),
+        Field::new(
+            &format!("{}.datatype", name),
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+            true,
+        ),
+        Field::new(
+            &format!("{}.language", name),
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+            true,
+        ),
+    ]
+}
+
+/// An iterator over [`RecordBatch`] built from [`QuerySolutionIter`].
+///
+/// It is built using [`QuerySolutionIter::into_record_batches`].
+pub struct SolutionBatchIter {
+    solutions: QuerySolutionIter,
+    schema: SchemaRef,
+    batch_size: usize,
+    columns: Vec<TermColumnBuilder>,
+}
+
+impl SolutionBatchIter {
+    pub(crate) fn new(solutions: QuerySolutionIter, batch_size: usize) -> Self {
+        Self {
+            schema: Arc::new(solutions_schema(solutions.variables())),
+            columns: solutions
+                .variables()
+                .iter()
+                .map(|_| TermColumnBuilder::default())
+                .collect(),
+            solutions,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    /// The schema of the record batches.
+    #[inline]
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Iterator for SolutionBatchIter {
+    type Item = Result<RecordBatch, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<RecordBatch, EvaluationError>> {
+        let mut len = 0;
+        while len < self.batch_size {
+            match self.solutions.next() {
+                Some(Ok(solution)) => {
+                    for (i, column) in self.columns.iter_mut().enumerate() {
+                        column.append(solution.get(i).map(Term::as_ref));
+                    }
+                    len += 1;
+                }
+                Some(Err(error)) => return Some(Err(error)),
+                None => break,
+            }
+        }
+        if len == 0 {
+            return None;
+        }
+        Some(
+            build_batch(&self.schema, &mut self.columns)
+                .map_err(|e| EvaluationError::Io(arrow_to_io_error(e))),
+        )
+    }
+}
+
+/// An iterator over [`RecordBatch`] built from an iterator of quads.
+///
+/// It is built using [`Store::quads_record_batches`](crate::store::Store::quads_record_batches)
+/// or [`QuadBatchIter::new`].
+pub struct QuadBatchIter<I> {
+    quads: I,
+    schema: SchemaRef,
+    batch_size: usize,
+    columns: [TermColumnBuilder; 4],
+}
+
+impl<I> QuadBatchIter<I> {
+    /// Builds record batches of at most `batch_size` rows from an iterator of quads.
+    pub fn new(quads: I, batch_size: usize) -> Self {
+        Self {
+            quads,
+            schema: Arc::new(quads_schema()),
+            batch_size: batch_size.max(1),
+            columns: Default::default(),
+        }
+    }
+
+    /// The schema of the record batches.
+    #[inline]
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl<E: From<io::Error>, I: Iterator<Item = Result<Quad, E>>> Iterator for QuadBatchIter<I> {
+    type Item = Result<RecordBatch, E>;
+
+    fn next(&mut self) -> Option<Result<RecordBatch, E>> {
+        let mut len = 0;
+        while len < self.batch_size {
+            match self.quads.next() {
+                Some(Ok(quad)) => {
+                    let [subject, predicate, object, graph_name] = &mut self.columns;
+                    subject.append(Some(quad.subject.as_ref().into()));
+                    predicate.append(Some(quad.predicate.as_ref().into()));
+                    object.append(Some(quad.object.as_ref()));
+                    graph_name.append(match &quad.graph_name {
+                        GraphName::NamedNode(node) => Some(node.as_ref().into()),
+                        GraphName::BlankNode(node) => Some(node.as_ref().into()),
+                        GraphName::DefaultGraph => None,
+                    });
+                    len += 1;
+                }
+                Some(Err(error)) => return Some(Err(error)),
+                None => break,
+            }
+        }
+        if len == 0 {
+            return None;
+        }
+        Some(build_batch(&self.schema, &mut self.columns).map_err(|e| arrow_to_io_error(e).into()))
+    }
+}
+
+fn build_batch(
+    schema: &SchemaRef,
+    columns: &mut [TermColumnBuilder],
+) -> Result<RecordBatch, ArrowError> {
+    RecordBatch::try_new(
+        schema.clone(),
+        columns
+            .iter_mut()
+            .flat_map(TermColumnBuilder::finish)
+            .collect(),
+    )
+}
+
+fn arrow_to_io_error(error: ArrowError) -> io::Error {
+    match error {
+        ArrowError::IoError(message) => io::Error::new(io::ErrorKind::Other, message),
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
+/// Accumulates the values of the four columns encoding a term
+#[derive(Default)]
+struct TermColumnBuilder {
+    values: Vec<Option<String>>,
+    kinds: Vec<Option<&'static str>>,
+    datatypes: Vec<Option<String>>,
+    languages: Vec<Option<String>>,
+}
+
+impl TermColumnBuilder {
+    fn append(&mut self, term: Option<TermRef<'_>>) {
+        let (value, kind, datatype, language) = match term {
+            Some(TermRef::NamedNode(node)) => {
+                (Some(node.as_str().to_owned()), Some("uri"), None, None)
+            }
+            Some(TermRef::BlankNode(node)) => {
+                (Some(node.as_str().to_owned()), Some("bnode"), None, None)
+            }
+            Some(TermRef::Literal(literal)) => (
+                Some(literal.value().to_owned()),
+                Some("literal"),
+                Some(literal.datatype().as_str().to_owned()),
+                literal.language().map(ToOwned::to_owned),
+            ),
+            Some(TermRef::Triple(triple)) => (Some(triple.to_string()), Some("triple"), None, None),
+            None => (None, None, None, None),
+        };
+        self.values.push(value);
+        self.kinds.push(kind);
+        self.datatypes.push(datatype);
+        self.languages.push(language);
+    }
+
+    fn finish(&mut self) -> [ArrayRef; 4] {
+        [
+            Arc::new(take(&mut self.values).into_iter().collect::<StringArray>()),
+            Arc::new(
+                take(&mut self.kinds)
+                    .into_iter()
+                    .collect::<DictionaryArray<Int8Type>>(),
+            ),
+            Arc::new(
+                take(&mut self.datatypes)
+                    .iter()
+                    .map(Option::as_deref)
+                    .collect::<DictionaryArray<Int32Type>>(),
+            ),
+            Arc::new(
+                take(&mut self.languages)
+                    .iter()
+                    .map(Option::as_deref)
+                    .collect::<DictionaryArray<Int32Type>>(),
+            ),
+        ]
+    }
+}
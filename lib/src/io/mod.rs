@@ -1,8 +1,11 @@
 //! Utilities to read and write RDF graphs and datasets.
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
 mod error;
 mod format;
 pub mod read;
+mod turtle;
 pub mod write;
 
 pub use self::format::DatasetFormat;
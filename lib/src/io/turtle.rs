@@ -0,0 +1,642 @@
+//! Turtle and TriG serialization with prefixes, base IRI and pretty formatting.
+
+use crate::model::vocab::{rdf, xsd};
+use crate::model::*;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Writes terms using the Turtle syntax, abbreviating IRIs using the prefixes and the base IRI
+#[derive(Clone)]
+pub(super) struct TurtleFormatter {
+    prefixes: Vec<(String, String)>,
+    base_iri: Option<String>,
+}
+
+impl TurtleFormatter {
+    pub fn new(prefixes: Vec<(String, String)>, base_iri: Option<String>) -> io::Result<Self> {
+        for (prefix_name, _) in &prefixes {
+            if !is_valid_prefix_name(prefix_name) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("'{}' is not a valid Turtle prefix name", prefix_name),
+                ));
+            }
+        }
+        Ok(Self { prefixes, base_iri })
+    }
+
+    /// Writes the `@base` and `@prefix` declarations
+    pub fn write_header(&self, mut writer: impl Write) -> io::Result<()> {
+        if let Some(base_iri) = &self.base_iri {
+            writeln!(writer, "@base <{}> .", base_iri)?;
+        }
+        for (prefix_name, prefix_iri) in &self.prefixes {
+            writeln!(writer, "@prefix {}: <{}> .", prefix_name, prefix_iri)?;
+        }
+        if self.base_iri.is_some() || !self.prefixes.is_empty() {
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_triple(&self, output: &mut String, triple: TripleRef<'_>) {
+        self.write_subject(output, triple.subject);
+        output.push(' ');
+        self.write_predicate(output, triple.predicate);
+        output.push(' ');
+        self.write_term(output, triple.object);
+    }
+
+    fn write_subject(&self, output: &mut String, subject: SubjectRef<'_>) {
+        match subject {
+            SubjectRef::NamedNode(node) => self.write_iri(output, node),
+            SubjectRef::BlankNode(node) => output.push_str(&node.to_string()),
+            SubjectRef::Triple(triple) => self.write_quoted_triple(output, triple.as_ref()),
+        }
+    }
+
+    fn write_predicate(&self, output: &mut String, predicate: NamedNodeRef<'_>) {
+        if predicate == rdf::TYPE {
+            output.push('a');
+        } else {
+            self.write_iri(output, predicate);
+        }
+    }
+
+    pub fn write_term(&self, output: &mut String, term: TermRef<'_>) {
+        match term {
+            TermRef::NamedNode(node) => self.write_iri(output, node),
+            TermRef::BlankNode(node) => output.push_str(&node.to_string()),
+            TermRef::Literal(literal) => self.write_literal(output, literal),
+            TermRef::Triple(triple) => self.write_quoted_triple(output, triple.as_ref()),
+        }
+    }
+
+    pub fn write_graph_name(&self, output: &mut String, graph_name: GraphNameRef<'_>) {
+        match graph_name {
+            GraphNameRef::NamedNode(node) => self.write_iri(output, node),
+            GraphNameRef::BlankNode(node) => output.push_str(&node.to_string()),
+            GraphNameRef::DefaultGraph => (),
+        }
+    }
+
+    fn write_quoted_triple(&self, output: &mut String, triple: TripleRef<'_>) {
+        output.push_str("<< ");
+        self.write_triple(output, triple);
+        output.push_str(" >>");
+    }
+
+    fn write_iri(&self, output: &mut String, iri: NamedNodeRef<'_>) {
+        let iri = iri.as_str();
+        if let Some((prefix_name, local)) = self
+            .prefixes
+            .iter()
+            .filter_map(|(prefix_name, prefix_iri)| {
+                let local = iri.strip_prefix(prefix_iri.as_str())?;
+                is_valid_local_name(local).then(|| (prefix_name, local))
+            })
+            .min_by_key(|(_, local)| local.len())
+        {
+            output.push_str(prefix_name);
+            output.push(':');
+            output.push_str(local);
+            return;
+        }
+        output.push('<');
+        output.push_str(
+            self.base_iri
+                .as_deref()
+                .and_then(|base_iri| relative_iri(base_iri, iri))
+                .unwrap_or(iri),
+        );
+        output.push('>');
+    }
+
+    fn write_literal(&self, output: &mut String, literal: LiteralRef<'_>) {
+        let value = literal.value();
+        let datatype = literal.datatype();
+        let is_bare = match datatype.as_str() {
+            "http://www.w3.org/2001/XMLSchema#boolean" => value == "true" || value == "false",
+            "http://www.w3.org/2001/XMLSchema#integer" => is_turtle_integer(value),
+            "http://www.w3.org/2001/XMLSchema#decimal" => is_turtle_decimal(value),
+            "http://www.w3.org/2001/XMLSchema#double" => is_turtle_double(value),
+            _ => false,
+        };
+        if is_bare {
+            output.push_str(value);
+            return;
+        }
+        output.push('"');
+        for c in value.chars() {
+            match c {
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '"' => output.push_str("\\\""),
+                '\\' => output.push_str("\\\\"),
+                c => output.push(c),
+            }
+        }
+        output.push('"');
+        if let Some(language) = literal.language() {
+            output.push('@');
+            output.push_str(language);
+        } else if datatype != xsd::STRING {
+            output.push_str("^^");
+            self.write_iri(output, datatype);
+        }
+    }
+}
+
+/// Buffers quads and writes them grouped by graph, subject and predicate
+#[derive(Default)]
+pub(super) struct PrettyBuffer {
+    quads: Vec<Quad>,
+    seen: HashSet<Quad>,
+}
+
+impl PrettyBuffer {
+    pub fn push(&mut self, quad: QuadRef<'_>) {
+        let quad = quad.into_owned();
+        if self.seen.insert(quad.clone()) {
+            self.quads.push(quad);
+        }
+    }
+
+    pub fn write(&self, formatter: &TurtleFormatter, mut writer: impl Write) -> io::Result<()> {
+        let mut graphs = Vec::<GraphNameRef<'_>>::new();
+        let mut groups = HashMap::<GraphNameRef<'_>, SubjectGroups<'_>>::new();
+        let mut blank_nodes = HashMap::<BlankNodeRef<'_>, BlankNodeUsage<'_>>::new();
+        for quad in &self.quads {
+            let quad = quad.as_ref();
+            groups
+                .entry(quad.graph_name)
+                .or_insert_with(|| {
+                    graphs.push(quad.graph_name);
+                    SubjectGroups::default()
+                })
+                .push(quad.into());
+            match quad.subject {
+                SubjectRef::BlankNode(node) => {
+                    let usage = blank_nodes.entry(node).or_default();
+                    if !usage.subject_graphs.contains(&quad.graph_name) {
+                        usage.subject_graphs.push(quad.graph_name);
+                    }
+                }
+                SubjectRef::Triple(triple) => mark_not_inlinable(triple, &mut blank_nodes),
+                SubjectRef::NamedNode(_) => (),
+            }
+            match quad.object {
+                TermRef::BlankNode(node) => {
+                    let usage = blank_nodes.entry(node).or_default();
+                    usage.object_count += 1;
+                    usage.object_graph = Some(quad.graph_name);
+                }
+                TermRef::Triple(triple) => mark_not_inlinable(triple, &mut blank_nodes),
+                _ => (),
+            }
+            if let GraphNameRef::BlankNode(node) = quad.graph_name {
+                blank_nodes.entry(node).or_default().in_other_position = true;
+            }
+        }
+
+        let mut output = String::new();
+        let mut serializer = PrettySerializer {
+            formatter,
+            blank_nodes: &blank_nodes,
+            written: HashSet::new(),
+        };
+        let mut is_first_graph = true;
+        for graph_name in graphs {
+            if !is_first_graph {
+                output.push('\n');
+            }
+            is_first_graph = false;
+            let group = &groups[&graph_name];
+            if graph_name.is_default_graph() {
+                serializer.write_graph_content(&mut output, group, "");
+            } else {
+                formatter.write_graph_name(&mut output, graph_name);
+                output.push_str(" {\n");
+                serializer.write_graph_content(&mut output, group, "    ");
+                output.push_str("}\n");
+            }
+            writer.write_all(output.as_bytes())?;
+            output.clear();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct SubjectGroups<'a> {
+    subjects: Vec<SubjectRef<'a>>,
+    predicates: HashMap<SubjectRef<'a>, Vec<(NamedNodeRef<'a>, Vec<TermRef<'a>>)>>,
+}
+
+impl<'a> SubjectGroups<'a> {
+    fn push(&mut self, triple: TripleRef<'a>) {
+        let subjects = &mut self.subjects;
+        let predicates = self.predicates.entry(triple.subject).or_insert_with(|| {
+            subjects.push(triple.subject);
+            Vec::new()
+        });
+        if let Some((_, objects)) = predicates
+            .iter_mut()
+            .find(|(predicate, _)| *predicate == triple.predicate)
+        {
+            objects.push(triple.object);
+        } else {
+            predicates.push((triple.predicate, vec![triple.object]));
+        }
+    }
+}
+
+#[derive(Default)]
+struct BlankNodeUsage<'a> {
+    object_count: usize,
+    object_graph: Option<GraphNameRef<'a>>,
+    subject_graphs: Vec<GraphNameRef<'a>>,
+    in_other_position: bool,
+}
+
+impl BlankNodeUsage<'_> {
+    /// The blank node could be written as `[ ... ]` in the place where it is used as object
+    fn is_inlinable(&self) -> bool {
+        self.object_count == 1
+            && !self.in_other_position
+            && self
+                .subject_graphs
+                .iter()
+                .all(|g| Some(*g) == self.object_graph)
+    }
+
+    /// The blank node could be written as `[]` in subject position
+    fn is_anonymous_subject(&self) -> bool {
+        self.object_count == 0 && !self.in_other_position && self.subject_graphs.len() == 1
+    }
+}
+
+fn mark_not_inlinable<'a>(
+    triple: &'a Triple,
+    blank_nodes: &mut HashMap<BlankNodeRef<'a>, BlankNodeUsage<'a>>,
+) {
+    match &triple.subject {
+        Subject::BlankNode(node) => {
+            blank_nodes
+                .entry(node.as_ref())
+                .or_default()
+                .in_other_position = true
+        }
+        Subject::Triple(triple) => mark_not_inlinable(triple, blank_nodes),
+        Subject::NamedNode(_) => (),
+    }
+    match &triple.object {
+        Term::BlankNode(node) => {
+            blank_nodes
+                .entry(node.as_ref())
+                .or_default()
+                .in_other_position = true
+        }
+        Term::Triple(triple) => mark_not_inlinable(triple, blank_nodes),
+        _ => (),
+    }
+}
+
+struct PrettySerializer<'a, 'b> {
+    formatter: &'b TurtleFormatter,
+    blank_nodes: &'b HashMap<BlankNodeRef<'a>, BlankNodeUsage<'a>>,
+    written: HashSet<BlankNodeRef<'a>>,
+}
+
+impl<'a, 'b> PrettySerializer<'a, 'b> {
+    fn write_graph_content(
+        &mut self,
+        output: &mut String,
+        group: &SubjectGroups<'a>,
+        indent: &str,
+    ) {
+        for subject in &group.subjects {
+            if let SubjectRef::BlankNode(node) = subject {
+                if self.blank_nodes[node].is_inlinable() {
+                    continue; // Written where it is used
+                }
+            }
+            self.write_subject_block(output, group, *subject, indent);
+        }
+        // Blank nodes in reference cycles are never reached from a root
+        for subject in &group.subjects {
+            if let SubjectRef::BlankNode(node) = subject {
+                if !self.written.contains(node) {
+                    self.write_subject_block(output, group, *subject, indent);
+                }
+            }
+        }
+    }
+
+    fn write_subject_block(
+        &mut self,
+        output: &mut String,
+        group: &SubjectGroups<'a>,
+        subject: SubjectRef<'a>,
+        indent: &str,
+    ) {
+        output.push_str(indent);
+        if let SubjectRef::BlankNode(node) = subject {
+            self.written.insert(node);
+            if self.blank_nodes[&node].is_anonymous_subject() {
+                output.push_str("[]");
+            } else {
+                self.formatter.write_subject(output, subject);
+            }
+        } else {
+            self.formatter.write_subject(output, subject);
+        }
+        let predicate_indent = format!("{}    ", indent);
+        for (i, (predicate, objects)) in group.predicates[&subject].iter().enumerate() {
+            if i == 0 {
+                output.push(' ');
+            } else {
+                output.push_str(" ;\n");
+                output.push_str(&predicate_indent);
+            }
+            self.write_predicate_objects(output, group, *predicate, objects);
+        }
+        output.push_str(" .\n");
+    }
+
+    fn write_predicate_objects(
+        &mut self,
+        output: &mut String,
+        group: &SubjectGroups<'a>,
+        predicate: NamedNodeRef<'a>,
+        objects: &[TermRef<'a>],
+    ) {
+        self.formatter.write_predicate(output, predicate);
+        for (i, object) in objects.iter().enumerate() {
+            output.push_str(if i == 0 { " " } else { ", " });
+            self.write_object(output, group, *object);
+        }
+    }
+
+    fn write_object(
+        &mut self,
+        output: &mut String,
+        group: &SubjectGroups<'a>,
+        object: TermRef<'a>,
+    ) {
+        match object {
+            TermRef::NamedNode(node) if node == rdf::NIL => output.push_str("()"),
+            TermRef::BlankNode(node) if self.is_inlinable(node) => {
+                if let Some(items) = self.collection_items(group, node) {
+                    output.push('(');
+                    for item in items {
+                        output.push(' ');
+                        self.write_object(output, group, item);
+                    }
+                    output.push_str(" )");
+                    return;
+                }
+                self.written.insert(node);
+                if let Some(predicates) = group.predicates.get(&SubjectRef::BlankNode(node)) {
+                    output.push_str("[ ");
+                    for (i, (predicate, objects)) in predicates.iter().enumerate() {
+                        if i > 0 {
+                            output.push_str(" ; ");
+                        }
+                        self.write_predicate_objects(output, group, *predicate, objects);
+                    }
+                    output.push_str(" ]");
+                } else {
+                    output.push_str("[]");
+                }
+            }
+            _ => self.formatter.write_term(output, object),
+        }
+    }
+
+    fn is_inlinable(&self, node: BlankNodeRef<'a>) -> bool {
+        !self.written.contains(&node)
+            && self
+                .blank_nodes
+                .get(&node)
+                .map_or(false, BlankNodeUsage::is_inlinable)
+    }
+
+    /// Returns the elements of the RDF collection starting with this blank node, if it could be written as `( ... )`
+    fn collection_items(
+        &mut self,
+        group: &SubjectGroups<'a>,
+        head: BlankNodeRef<'a>,
+    ) -> Option<Vec<TermRef<'a>>> {
+        let mut items = Vec::new();
+        let mut nodes = Vec::new();
+        let mut current = head;
+        loop {
+            if !self.is_inlinable(current) || nodes.contains(&current) {
+                return None;
+            }
+            nodes.push(current);
+            let predicates = group.predicates.get(&SubjectRef::BlankNode(current))?;
+            if predicates.len() != 2 {
+                return None;
+            }
+            let mut first = None;
+            let mut rest = None;
+            for (predicate, objects) in predicates {
+                if objects.len() != 1 {
+                    return None;
+                }
+                if *predicate == rdf::FIRST {
+                    first = Some(objects[0]);
+                } else if *predicate == rdf::REST {
+                    rest = Some(objects[0]);
+                } else {
+                    return None;
+                }
+            }
+            items.push(first?);
+            match rest? {
+                TermRef::NamedNode(node) if node == rdf::NIL => break,
+                TermRef::BlankNode(node) => current = node,
+                _ => return None,
+            }
+        }
+        self.written.extend(nodes);
+        Some(items)
+    }
+}
+
+/// Checks if the string is a valid [PN_PREFIX](https://www.w3.org/TR/turtle/#grammar-production-PN_PREFIX)
+fn is_valid_prefix_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        None => true,
+        Some(c) if c.is_ascii_alphabetic() => {
+            !name.ends_with('.')
+                && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        }
+        Some(_) => false,
+    }
+}
+
+/// Checks if the string is a [PN_LOCAL](https://www.w3.org/TR/turtle/#grammar-production-PN_LOCAL) that does not require escaping
+fn is_valid_local_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        None => true,
+        Some(c) if c.is_ascii_alphanumeric() || c == '_' => {
+            !name.ends_with('.')
+                && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        }
+        Some(_) => false,
+    }
+}
+
+/// Returns a relative IRI that resolves to `iri` against `base_iri` if it is easy to build one
+fn relative_iri<'a>(base_iri: &str, iri: &'a str) -> Option<&'a str> {
+    if !base_iri.ends_with('/') {
+        return None;
+    }
+    let relative = iri.strip_prefix(base_iri)?;
+    let first_segment = relative.split(&['/', '?', '#'][..]).next().unwrap_or("");
+    let path = relative.split(&['?', '#'][..]).next().unwrap_or("");
+    if relative.starts_with('/')
+        || first_segment.contains(':')
+        || path
+            .split('/')
+            .any(|segment| segment == "." || segment == "..")
+    {
+        return None;
+    }
+    Some(relative)
+}
+
+fn is_turtle_integer(value: &str) -> bool {
+    let digits = value.strip_prefix(&['+', '-'][..]).unwrap_or(value);
+    !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit())
+}
+
+fn is_turtle_decimal(value: &str) -> bool {
+    let value = value.strip_prefix(&['+', '-'][..]).unwrap_or(value);
+    if let Some((before, after)) = value.split_once('.') {
+        before.bytes().all(|c| c.is_ascii_digit())
+            && !after.is_empty()
+            && after.bytes().all(|c| c.is_ascii_digit())
+    } else {
+        false
+    }
+}
+
+fn is_turtle_double(value: &str) -> bool {
+    let value = value.strip_prefix(&['+', '-'][..]).unwrap_or(value);
+    if let Some((mantissa, exponent)) = value.split_once(&['e', 'E'][..]) {
+        let exponent = exponent.strip_prefix(&['+', '-'][..]).unwrap_or(exponent);
+        let (before, after) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        !exponent.is_empty()
+            && exponent.bytes().all(|c| c.is_ascii_digit())
+            && !(before.is_empty() && after.is_empty())
+            && before.bytes().all(|c| c.is_ascii_digit())
+            && after.bytes().all(|c| c.is_ascii_digit())
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{DatasetFormat, DatasetSerializer};
+
+    #[test]
+    fn test_pretty_trig() -> io::Result<()> {
+        let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+        let (b, l1, l2, c) = (
+            BlankNode::default(),
+            BlankNode::default(),
+            BlankNode::default(),
+            BlankNode::default(),
+        );
+        let mut buffer = Vec::new();
+        let mut writer = DatasetSerializer::from_format(DatasetFormat::TriG)
+            .with_prefix("ex", "http://example.com/")
+            .unwrap()
+            .pretty()
+            .quad_writer(&mut buffer)?;
+        for quad in [
+            QuadRef::new(&ex("s"), &ex("p"), &b, GraphNameRef::DefaultGraph),
+            QuadRef::new(
+                &b,
+                &ex("q"),
+                LiteralRef::new_language_tagged_literal_unchecked("a", "en"),
+                GraphNameRef::DefaultGraph,
+            ),
+            QuadRef::new(&ex("s"), &ex("p"), &ex("o"), GraphNameRef::DefaultGraph),
+            QuadRef::new(&ex("s"), &ex("l"), &l1, GraphNameRef::DefaultGraph),
+            QuadRef::new(
+                &l1,
+                rdf::FIRST,
+                LiteralRef::new_typed_literal("1", xsd::INTEGER),
+                GraphNameRef::DefaultGraph,
+            ),
+            QuadRef::new(&l1, rdf::REST, &l2, GraphNameRef::DefaultGraph),
+            QuadRef::new(
+                &l2,
+                rdf::FIRST,
+                LiteralRef::new_typed_literal("x", &ex("dt")),
+                GraphNameRef::DefaultGraph,
+            ),
+            QuadRef::new(&l2, rdf::REST, rdf::NIL, GraphNameRef::DefaultGraph),
+            QuadRef::new(&ex("s"), &ex("p"), &ex("o"), &ex("g")),
+            QuadRef::new(&c, &ex("p"), &ex("o"), &ex("g")),
+            QuadRef::new(&ex("s"), &ex("p"), &ex("o"), GraphNameRef::DefaultGraph),
+        ] {
+            writer.write(quad)?;
+        }
+        writer.finish()?;
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "@prefix ex: <http://example.com/> .\n\nex:s ex:p [ ex:q \"a\"@en ], ex:o ;\n    ex:l ( 1 \"x\"^^ex:dt ) .\n\nex:g {\n    ex:s ex:p ex:o .\n    [] ex:p ex:o .\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_iri() {
+        assert_eq!(
+            relative_iri("http://example.com/", "http://example.com/foo/bar"),
+            Some("foo/bar")
+        );
+        assert_eq!(
+            relative_iri("http://example.com/", "http://example.com/#foo"),
+            Some("#foo")
+        );
+        assert_eq!(
+            relative_iri("http://example.com/a", "http://example.com/ab"),
+            None
+        );
+        assert_eq!(
+            relative_iri("http://example.com/", "http://example.com//a"),
+            None
+        );
+        assert_eq!(
+            relative_iri("http://example.com/", "http://example.com/a:b"),
+            None
+        );
+        assert_eq!(
+            relative_iri("http://example.com/", "http://example.com/../a"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_numeric_literals() {
+        assert!(is_turtle_integer("-12"));
+        assert!(!is_turtle_integer("1.2"));
+        assert!(is_turtle_decimal("+.2"));
+        assert!(!is_turtle_decimal("1."));
+        assert!(is_turtle_double("1.e2"));
+        assert!(is_turtle_double(".1E-2"));
+        assert!(!is_turtle_double("1e"));
+        assert!(!is_turtle_double("INF"));
+    }
+}
@@ -1,7 +1,9 @@
 //! Utilities to write RDF graphs and datasets.
 
+use crate::io::turtle::{PrettyBuffer, TurtleFormatter};
 use crate::io::{DatasetFormat, GraphFormat};
 use crate::model::*;
+use oxiri::{Iri, IriParseError};
 use rio_api::formatter::TriplesFormatter;
 use rio_api::model as rio;
 use rio_xml::RdfXmlFormatter;
@@ -30,21 +32,79 @@ use std::io::{self, Write};
 ///assert_eq!(buffer.as_slice(), "<http://example.com/s> <http://example.com/p> <http://example.com/o> .\n".as_bytes());
 /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
 /// ```
+///
+/// The Turtle output could be made more readable using prefixes, a base IRI and the [`pretty`](GraphSerializer::pretty) mode:
+/// ```
+/// use oxigraph::io::{GraphFormat, GraphSerializer};
+/// use oxigraph::model::*;
+///
+/// let mut buffer = Vec::new();
+/// let mut writer = GraphSerializer::from_format(GraphFormat::Turtle)
+///     .with_prefix("ex", "http://example.com/")?
+///     .pretty()
+///     .triple_writer(&mut buffer)?;
+/// let s = NamedNodeRef::new("http://example.com/s")?;
+/// let p = NamedNodeRef::new("http://example.com/p")?;
+/// writer.write(TripleRef::new(s, p, NamedNodeRef::new("http://example.com/o1")?))?;
+/// writer.write(TripleRef::new(s, p, NamedNodeRef::new("http://example.com/o2")?))?;
+/// writer.finish()?;
+///
+///assert_eq!(buffer.as_slice(), "@prefix ex: <http://example.com/> .\n\nex:s ex:p ex:o1, ex:o2 .\n".as_bytes());
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
 pub struct GraphSerializer {
     format: GraphFormat,
+    options: TurtleOptions,
 }
 
 impl GraphSerializer {
     /// Builds a serializer for the given format
     #[inline]
     pub fn from_format(format: GraphFormat) -> Self {
-        Self { format }
+        Self {
+            format,
+            options: TurtleOptions::default(),
+        }
+    }
+
+    /// Adds a prefix declaration used to abbreviate IRIs.
+    ///
+    /// It is only used by the Turtle format.
+    pub fn with_prefix(
+        mut self,
+        prefix_name: impl Into<String>,
+        prefix_iri: impl Into<String>,
+    ) -> Result<Self, IriParseError> {
+        self.options
+            .add_prefix(prefix_name.into(), prefix_iri.into())?;
+        Ok(self)
+    }
+
+    /// Sets the base IRI used to relativize IRIs.
+    ///
+    /// It is only used by the Turtle format.
+    pub fn with_base_iri(mut self, base_iri: impl Into<String>) -> Result<Self, IriParseError> {
+        self.options.base_iri = Some(Iri::parse(base_iri.into())?.into_inner());
+        Ok(self)
+    }
+
+    /// Groups the triples by subject and predicate, inlines blank nodes as `[ ... ]` and writes RDF collections as `( ... )`.
+    ///
+    /// The triples are buffered in memory and only written by [`TripleWriter::finish`].
+    /// It is only used by the Turtle format.
+    #[inline]
+    pub fn pretty(mut self) -> Self {
+        self.options.pretty = true;
+        self
     }
 
     /// Returns a [`TripleWriter`] allowing writing triples into the given [`Write`](std::io::Write) implementation
     pub fn triple_writer<W: Write>(&self, writer: W) -> io::Result<TripleWriter<W>> {
         Ok(TripleWriter {
             formatter: match self.format {
+                GraphFormat::Turtle if !self.options.is_empty() => {
+                    TripleWriterKind::Turtle(TurtleWriter::new(writer, &self.options)?)
+                }
                 GraphFormat::NTriples | GraphFormat::Turtle => TripleWriterKind::NTriples(writer),
                 GraphFormat::RdfXml => TripleWriterKind::RdfXml(RdfXmlFormatter::new(writer)?),
             },
@@ -80,6 +140,7 @@ pub struct TripleWriter<W: Write> {
 
 enum TripleWriterKind<W: Write> {
     NTriples(W),
+    Turtle(TurtleWriter<W>),
     RdfXml(RdfXmlFormatter<W>),
 }
 
@@ -91,6 +152,9 @@ impl<W: Write> TripleWriter<W> {
             TripleWriterKind::NTriples(writer) => {
                 writeln!(writer, "{} .", triple)?;
             }
+            TripleWriterKind::Turtle(writer) => {
+                writer.write(triple.in_graph(GraphNameRef::DefaultGraph), false)?
+            }
             TripleWriterKind::RdfXml(formatter) => formatter.format(&rio::Triple {
                 subject: match triple.subject {
                     SubjectRef::NamedNode(node) => rio::NamedNode { iri: node.as_str() }.into(),
@@ -144,6 +208,7 @@ impl<W: Write> TripleWriter<W> {
     pub fn finish(self) -> io::Result<()> {
         match self.formatter {
             TripleWriterKind::NTriples(mut writer) => writer.flush(),
+            TripleWriterKind::Turtle(writer) => writer.finish(),
             TripleWriterKind::RdfXml(formatter) => formatter.finish()?.flush(), //TODO: remove flush when the next version of Rio is going to be released
         }
     }
@@ -174,21 +239,58 @@ impl<W: Write> TripleWriter<W> {
 /// ```
 pub struct DatasetSerializer {
     format: DatasetFormat,
+    options: TurtleOptions,
 }
 
 impl DatasetSerializer {
     /// Builds a serializer for the given format
     #[inline]
     pub fn from_format(format: DatasetFormat) -> Self {
-        Self { format }
+        Self {
+            format,
+            options: TurtleOptions::default(),
+        }
+    }
+
+    /// Adds a prefix declaration used to abbreviate IRIs.
+    ///
+    /// It is only used by the TriG format.
+    pub fn with_prefix(
+        mut self,
+        prefix_name: impl Into<String>,
+        prefix_iri: impl Into<String>,
+    ) -> Result<Self, IriParseError> {
+        self.options
+            .add_prefix(prefix_name.into(), prefix_iri.into())?;
+        Ok(self)
+    }
+
+    /// Sets the base IRI used to relativize IRIs.
+    ///
+    /// It is only used by the TriG format.
+    pub fn with_base_iri(mut self, base_iri: impl Into<String>) -> Result<Self, IriParseError> {
+        self.options.base_iri = Some(Iri::parse(base_iri.into())?.into_inner());
+        Ok(self)
+    }
+
+    /// Groups the quads by graph, subject and predicate, inlines blank nodes as `[ ... ]` and writes RDF collections as `( ... )`.
+    ///
+    /// The quads are buffered in memory and only written by [`QuadWriter::finish`].
+    /// It is only used by the TriG format.
+    #[inline]
+    pub fn pretty(mut self) -> Self {
+        self.options.pretty = true;
+        self
     }
 
     /// Returns a [`QuadWriter`] allowing writing triples into the given [`Write`](std::io::Write) implementation
-    #[allow(clippy::unnecessary_wraps)]
     pub fn quad_writer<W: Write>(&self, writer: W) -> io::Result<QuadWriter<W>> {
         Ok(QuadWriter {
             formatter: match self.format {
                 DatasetFormat::NQuads => QuadWriterKind::NQuads(writer),
+                DatasetFormat::TriG if !self.options.is_empty() => {
+                    QuadWriterKind::PrettyTriG(TurtleWriter::new(writer, &self.options)?)
+                }
                 DatasetFormat::TriG => QuadWriterKind::TriG(writer),
             },
         })
@@ -225,6 +327,7 @@ pub struct QuadWriter<W: Write> {
 enum QuadWriterKind<W: Write> {
     NQuads(W),
     TriG(W),
+    PrettyTriG(TurtleWriter<W>),
 }
 
 impl<W: Write> QuadWriter<W> {
@@ -247,6 +350,7 @@ impl<W: Write> QuadWriter<W> {
                     writeln!(writer, "{} .", quad)?;
                 }
             }
+            QuadWriterKind::PrettyTriG(writer) => writer.write(quad, true)?,
         }
         Ok(())
     }
@@ -256,6 +360,81 @@ impl<W: Write> QuadWriter<W> {
     pub fn finish(self) -> io::Result<()> {
         match self.formatter {
             QuadWriterKind::NQuads(mut writer) | QuadWriterKind::TriG(mut writer) => writer.flush(),
+            QuadWriterKind::PrettyTriG(writer) => writer.finish(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TurtleOptions {
+    prefixes: Vec<(String, String)>,
+    base_iri: Option<String>,
+    pretty: bool,
+}
+
+impl TurtleOptions {
+    fn add_prefix(&mut self, prefix_name: String, prefix_iri: String) -> Result<(), IriParseError> {
+        let prefix_iri = Iri::parse(prefix_iri)?.into_inner();
+        if let Some(prefix) = self
+            .prefixes
+            .iter_mut()
+            .find(|(name, _)| *name == prefix_name)
+        {
+            prefix.1 = prefix_iri;
+        } else {
+            self.prefixes.push((prefix_name, prefix_iri));
+        }
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.prefixes.is_empty() && self.base_iri.is_none() && !self.pretty
+    }
+}
+
+/// Writes Turtle or TriG using the prefixes and the base IRI
+struct TurtleWriter<W: Write> {
+    writer: W,
+    formatter: TurtleFormatter,
+    buffer: Option<PrettyBuffer>,
+    output: String,
+}
+
+impl<W: Write> TurtleWriter<W> {
+    fn new(mut writer: W, options: &TurtleOptions) -> io::Result<Self> {
+        let formatter = TurtleFormatter::new(options.prefixes.clone(), options.base_iri.clone())?;
+        formatter.write_header(&mut writer)?;
+        Ok(Self {
+            writer,
+            formatter,
+            buffer: options.pretty.then(PrettyBuffer::default),
+            output: String::new(),
+        })
+    }
+
+    fn write(&mut self, quad: QuadRef<'_>, with_graph_name: bool) -> io::Result<()> {
+        if let Some(buffer) = &mut self.buffer {
+            buffer.push(quad);
+            return Ok(());
+        }
+        self.output.clear();
+        if with_graph_name && !quad.graph_name.is_default_graph() {
+            self.formatter
+                .write_graph_name(&mut self.output, quad.graph_name);
+            self.output.push_str(" { ");
+            self.formatter.write_triple(&mut self.output, quad.into());
+            self.output.push_str(" }\n");
+        } else {
+            self.formatter.write_triple(&mut self.output, quad.into());
+            self.output.push_str(" .\n");
+        }
+        self.writer.write_all(self.output.as_bytes())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        if let Some(buffer) = self.buffer {
+            buffer.write(&self.formatter, &mut self.writer)?;
         }
+        self.writer.flush()
     }
 }
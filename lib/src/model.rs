@@ -3,10 +3,10 @@
 use crate::xsd::*;
 use oxrdf::vocab::xsd;
 pub use oxrdf::{
-    dataset, graph, vocab, BlankNode, BlankNodeIdParseError, BlankNodeRef, Dataset, Graph,
-    GraphName, GraphNameRef, IriParseError, LanguageTagParseError, Literal, LiteralRef, NamedNode,
-    NamedNodeRef, NamedOrBlankNode, NamedOrBlankNodeRef, Quad, QuadRef, Subject, SubjectRef, Term,
-    TermParseError, TermRef, Triple, TripleRef,
+    canonicalization, dataset, diff, graph, patch, vocab, BlankNode, BlankNodeIdParseError,
+    BlankNodeRef, Dataset, Graph, GraphName, GraphNameRef, IriParseError, LanguageTagParseError,
+    Literal, LiteralRef, NamedNode, NamedNodeRef, NamedOrBlankNode, NamedOrBlankNodeRef, Quad,
+    QuadRef, Subject, SubjectRef, Term, TermParseError, TermRef, Triple, TripleRef,
 };
 
 impl From<Float> for Literal {
@@ -30,6 +30,20 @@ impl From<Decimal> for Literal {
     }
 }
 
+impl From<BigInteger> for Literal {
+    #[inline]
+    fn from(value: BigInteger) -> Self {
+        Self::new_typed_literal(value.to_string(), xsd::INTEGER)
+    }
+}
+
+impl From<BigDecimal> for Literal {
+    #[inline]
+    fn from(value: BigDecimal) -> Self {
+        Self::new_typed_literal(value.to_string(), xsd::DECIMAL)
+    }
+}
+
 impl From<DateTime> for Literal {
     #[inline]
     fn from(value: DateTime) -> Self {
@@ -7,10 +7,14 @@ use std::cell::RefCell;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::iter::empty;
+use std::rc::Rc;
 
 pub struct DatasetView {
     reader: StorageReader,
+    /// Strings shared with other views, e.g. the ones of a prepared query
+    shared: Rc<HashMap<StrHash, String>>,
     extra: RefCell<HashMap<StrHash, String>>,
+    keep_all_strings: bool,
     dataset: EncodedDatasetSpec,
 }
 
@@ -26,11 +30,39 @@ impl DatasetView {
         };
         Self {
             reader,
+            shared: Rc::default(),
             extra: RefCell::new(HashMap::default()),
+            keep_all_strings: false,
             dataset,
         }
     }
 
+    /// Keeps in memory the strings of all the encoded terms, even the ones already in the storage
+    pub fn keeping_all_strings(self) -> Self {
+        Self {
+            keep_all_strings: true,
+            ..self
+        }
+    }
+
+    /// Adds strings that are not in the storage like the ones returned by [`into_extra_strings`](Self::into_extra_strings)
+    ///
+    /// They are shared without copy, the strings inserted later are kept apart.
+    pub fn with_extra_strings(self, shared: Rc<HashMap<StrHash, String>>) -> Self {
+        Self { shared, ..self }
+    }
+
+    /// Returns the strings kept in memory by [`insert_str`](Self::insert_str) and the ones given to [`with_extra_strings`](Self::with_extra_strings)
+    pub fn into_extra_strings(self) -> Rc<HashMap<StrHash, String>> {
+        let extra = self.extra.into_inner();
+        if extra.is_empty() {
+            return self.shared;
+        }
+        let mut shared = Rc::try_unwrap(self.shared).unwrap_or_else(|shared| (*shared).clone());
+        shared.extend(extra);
+        Rc::new(shared)
+    }
+
     fn store_encoded_quads_for_pattern(
         &self,
         subject: Option<&EncodedTerm>,
@@ -160,8 +192,11 @@ impl DatasetView {
     }
 
     pub fn insert_str(&self, key: &StrHash, value: &str) {
+        if self.shared.contains_key(key) {
+            return;
+        }
         if let Entry::Vacant(e) = self.extra.borrow_mut().entry(*key) {
-            if !matches!(self.reader.contains_str(key), Ok(true)) {
+            if self.keep_all_strings || !matches!(self.reader.contains_str(key), Ok(true)) {
                 e.insert(value.to_owned());
             }
         }
@@ -170,7 +205,9 @@ impl DatasetView {
 
 impl StrLookup for DatasetView {
     fn get_str(&self, key: &StrHash) -> Result<Option<String>, StorageError> {
-        Ok(if let Some(value) = self.extra.borrow().get(key) {
+        Ok(if let Some(value) = self.shared.get(key) {
+            Some(value.clone())
+        } else if let Some(value) = self.extra.borrow().get(key) {
             Some(value.clone())
         } else {
             self.reader.get_str(key)?
@@ -178,7 +215,9 @@ impl StrLookup for DatasetView {
     }
 
     fn contains_str(&self, key: &StrHash) -> Result<bool, StorageError> {
-        Ok(self.extra.borrow().contains_key(key) || self.reader.contains_str(key)?)
+        Ok(self.shared.contains_key(key)
+            || self.extra.borrow().contains_key(key)
+            || self.reader.contains_str(key)?)
     }
 }
 
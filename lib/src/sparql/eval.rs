@@ -4,11 +4,14 @@ use crate::model::{NamedNode, Term, Triple};
 use crate::sparql::algebra::{Query, QueryDataset};
 use crate::sparql::dataset::DatasetView;
 use crate::sparql::error::EvaluationError;
+use crate::sparql::function::{CustomFunction, FunctionContext};
 use crate::sparql::model::*;
 use crate::sparql::plan::*;
 use crate::sparql::service::ServiceHandler;
+use crate::sparql::AggregateAccumulator;
 use crate::storage::numeric_encoder::*;
 use crate::storage::small_string::SmallString;
+use crate::xsd::date_time::TimezoneOffset;
 use crate::xsd::*;
 use digest::Digest;
 use md5::Md5;
@@ -20,12 +23,15 @@ use regex::{Regex, RegexBuilder};
 use sha1::Sha1;
 use sha2::{Sha256, Sha384, Sha512};
 use spargebra::algebra::GraphPattern;
+use spargebra::term::GroundTerm;
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::{Hash, Hasher};
 use std::iter::Iterator;
 use std::iter::{empty, once};
+use std::mem::take;
 use std::rc::Rc;
 use std::str;
 
@@ -39,7 +45,12 @@ pub struct SimpleEvaluator {
     base_iri: Option<Rc<Iri<String>>>,
     now: DateTime,
     service_handler: Rc<dyn ServiceHandler<Error = EvaluationError>>,
-    custom_functions: Rc<HashMap<NamedNode, Rc<dyn Fn(&[Term]) -> Option<Term>>>>,
+    custom_functions: Rc<HashMap<NamedNode, CustomFunction>>,
+    custom_aggregates: Rc<HashMap<NamedNode, Rc<dyn Fn() -> Box<dyn AggregateAccumulator>>>>,
+    parameters: Rc<Vec<(Variable, EncodedTerm)>>,
+    function_error: Rc<RefCell<Option<EvaluationError>>>,
+    service_batch_size: usize,
+    service_concurrency: usize,
 }
 
 impl SimpleEvaluator {
@@ -47,7 +58,8 @@ impl SimpleEvaluator {
         dataset: Rc<DatasetView>,
         base_iri: Option<Rc<Iri<String>>>,
         service_handler: Rc<dyn ServiceHandler<Error = EvaluationError>>,
-        custom_functions: Rc<HashMap<NamedNode, Rc<dyn Fn(&[Term]) -> Option<Term>>>>,
+        custom_functions: Rc<HashMap<NamedNode, CustomFunction>>,
+        custom_aggregates: Rc<HashMap<NamedNode, Rc<dyn Fn() -> Box<dyn AggregateAccumulator>>>>,
     ) -> Self {
         Self {
             dataset,
@@ -55,21 +67,42 @@ impl SimpleEvaluator {
             now: DateTime::now().unwrap(),
             service_handler,
             custom_functions,
+            custom_aggregates,
+            parameters: Rc::default(),
+            function_error: Rc::default(),
+            service_batch_size: 0,
+            service_concurrency: 1,
         }
     }
 
+    /// Sets the values bound by the [`PlanNode::Parameters`] nodes
+    pub fn with_parameters(mut self, parameters: Vec<(Variable, EncodedTerm)>) -> Self {
+        self.parameters = Rc::new(parameters);
+        self
+    }
+
+    /// Sets how [`PlanNode::ServiceJoin`] sends the left bindings to the services
+    ///
+    /// `batch_size` is the maximal number of bindings sent per request (`0` disables the bind join)
+    /// and `concurrency` the maximal number of requests sent at the same time.
+    pub fn with_service_batching(mut self, batch_size: usize, concurrency: usize) -> Self {
+        self.service_batch_size = batch_size;
+        self.service_concurrency = concurrency.max(1);
+        self
+    }
+
     pub fn evaluate_select_plan(
         &self,
         plan: &PlanNode,
         variables: Rc<Vec<Variable>>,
     ) -> QueryResults {
-        let iter = self.plan_evaluator(plan)(EncodedTuple::with_capacity(variables.len()));
+        let iter = self.evaluate_plan(plan, EncodedTuple::with_capacity(variables.len()));
         QueryResults::Solutions(decode_bindings(self.dataset.clone(), iter, variables))
     }
 
     pub fn evaluate_ask_plan(&self, plan: &PlanNode) -> Result<QueryResults, EvaluationError> {
         let from = EncodedTuple::with_capacity(plan.used_variables().len());
-        match self.plan_evaluator(plan)(from).next() {
+        match self.evaluate_plan(plan, from).next() {
             Some(Ok(_)) => Ok(QueryResults::Boolean(true)),
             Some(Err(error)) => Err(error),
             None => Ok(QueryResults::Boolean(false)),
@@ -85,7 +118,7 @@ impl SimpleEvaluator {
         QueryResults::Graph(QueryTripleIter {
             iter: Box::new(ConstructIterator {
                 eval: self.clone(),
-                iter: self.plan_evaluator(plan)(from),
+                iter: self.evaluate_plan(plan, from),
                 template,
                 buffered_results: Vec::default(),
                 bnodes: Vec::default(),
@@ -98,12 +131,20 @@ impl SimpleEvaluator {
         QueryResults::Graph(QueryTripleIter {
             iter: Box::new(DescribeIterator {
                 eval: self.clone(),
-                iter: self.plan_evaluator(plan)(from),
+                iter: self.evaluate_plan(plan, from),
                 quads: Box::new(empty()),
             }),
         })
     }
 
+    /// Evaluates a plan and returns the errors raised by the custom functions
+    pub fn evaluate_plan(&self, plan: &PlanNode, from: EncodedTuple) -> EncodedTuplesIterator {
+        Box::new(FunctionErrorIterator {
+            iter: self.plan_evaluator(plan)(from),
+            error: self.function_error.clone(),
+        })
+    }
+
     pub fn plan_evaluator(
         &self,
         node: &PlanNode,
@@ -334,6 +375,52 @@ impl SimpleEvaluator {
                     }))
                 })
             }
+            PlanNode::ServiceJoin { left, right } => {
+                if let PlanNode::Service {
+                    service_name: PatternValue::Constant(service_name),
+                    variables,
+                    graph_pattern,
+                    silent,
+                    ..
+                } = right.as_ref()
+                {
+                    if self.service_batch_size > 0 {
+                        let join_variables: Vec<_> = left
+                            .always_bound_variables()
+                            .intersection(&right.always_bound_variables())
+                            .copied()
+                            .collect();
+                        let left = self.plan_evaluator(left);
+                        let service_name = service_name.clone();
+                        let variables = variables.clone();
+                        let graph_pattern = graph_pattern.clone();
+                        let silent = *silent;
+                        let eval = self.clone();
+                        return Rc::new(move |from| {
+                            match eval.dataset.decode_named_node(&service_name) {
+                                Ok(service_name) => Box::new(ServiceJoinIterator {
+                                    eval: eval.clone(),
+                                    left_iter: left(from),
+                                    left_done: false,
+                                    service_name,
+                                    variables: variables.clone(),
+                                    join_variables: join_variables.clone(),
+                                    graph_pattern: graph_pattern.clone(),
+                                    silent,
+                                    buffered_results: VecDeque::new(),
+                                    unbatched: Vec::new(),
+                                }),
+                                Err(e) => Box::new(once(Err(e.into()))),
+                            }
+                        });
+                    }
+                }
+                // Bind join disabled: we evaluate the service only once
+                self.plan_evaluator(&PlanNode::HashJoin {
+                    left: left.clone(),
+                    right: right.clone(),
+                })
+            }
             PlanNode::AntiJoin { left, right } => {
                 let join_keys: Vec<_> = left
                     .always_bound_variables()
@@ -571,11 +658,7 @@ impl SimpleEvaluator {
                 let accumulator_builders: Vec<_> = aggregates
                     .iter()
                     .map(|(aggregate, _)| {
-                        Self::accumulator_builder(
-                            &self.dataset,
-                            &aggregate.function,
-                            aggregate.distinct,
-                        )
+                        self.accumulator_builder(&aggregate.function, aggregate.distinct)
                     })
                     .collect();
                 let accumulator_variables: Vec<_> =
@@ -633,7 +716,7 @@ impl SimpleEvaluator {
                                             result.set(*to_position, value.clone());
                                         }
                                     }
-                                    for (accumulator, variable) in
+                                    for (mut accumulator, variable) in
                                         accumulators.into_iter().zip(&accumulator_variables)
                                     {
                                         if let Some(value) = accumulator.state() {
@@ -646,6 +729,36 @@ impl SimpleEvaluator {
                     )
                 })
             }
+            PlanNode::Parameters {
+                child,
+                variables,
+                assigned,
+            } => {
+                let child = self.plan_evaluator(child);
+                let mut parameters = EncodedTuple::with_capacity(variables.len());
+                for (variable, value) in self.parameters.iter() {
+                    if let Some(position) = variables.iter().position(|v| v == variable) {
+                        if assigned.contains(&position) {
+                            // BIND would override the parameter value
+                            let message = format!(
+                                "The variable {} is assigned in the query and can't be given as a parameter",
+                                variable
+                            );
+                            return Rc::new(move |_| -> EncodedTuplesIterator {
+                                Box::new(once(Err(EvaluationError::msg(message.clone()))))
+                            });
+                        }
+                        parameters.set(position, value.clone());
+                    }
+                }
+                Rc::new(move |from| {
+                    if let Some(from) = from.combine_with(&parameters) {
+                        child(from)
+                    } else {
+                        Box::new(empty())
+                    }
+                })
+            }
         }
     }
 
@@ -658,17 +771,32 @@ impl SimpleEvaluator {
     ) -> Result<EncodedTuplesIterator, EvaluationError> {
         let service_name = get_pattern_value(service_name, from)
             .ok_or_else(|| EvaluationError::msg("The SERVICE name is not bound"))?;
-        if let QueryResults::Solutions(iter) = self.service_handler.handle(
-            self.dataset.decode_named_node(&service_name)?,
-            Query {
-                inner: spargebra::Query::Select {
-                    dataset: None,
-                    pattern: graph_pattern.clone(),
-                    base_iri: self.base_iri.as_ref().map(|iri| iri.as_ref().clone()),
-                },
-                dataset: QueryDataset::new(),
+        self.encode_service_results(
+            self.service_handler.handle(
+                self.dataset.decode_named_node(&service_name)?,
+                self.service_query(graph_pattern.clone()),
+            )?,
+            variables,
+        )
+    }
+
+    fn service_query(&self, pattern: GraphPattern) -> Query {
+        Query {
+            inner: spargebra::Query::Select {
+                dataset: None,
+                pattern,
+                base_iri: self.base_iri.as_ref().map(|iri| iri.as_ref().clone()),
             },
-        )? {
+            dataset: QueryDataset::new(),
+        }
+    }
+
+    fn encode_service_results(
+        &self,
+        results: QueryResults,
+        variables: Rc<Vec<Variable>>,
+    ) -> Result<EncodedTuplesIterator, EvaluationError> {
+        if let QueryResults::Solutions(iter) = results {
             Ok(encode_bindings(self.dataset.clone(), variables, iter))
         } else {
             Err(EvaluationError::msg(
@@ -678,10 +806,11 @@ impl SimpleEvaluator {
     }
 
     fn accumulator_builder(
-        dataset: &Rc<DatasetView>,
+        &self,
         function: &PlanAggregationFunction,
         distinct: bool,
     ) -> Box<dyn Fn() -> Box<dyn Accumulator>> {
+        let dataset = &self.dataset;
         match function {
             PlanAggregationFunction::Count => {
                 if distinct {
@@ -691,10 +820,15 @@ impl SimpleEvaluator {
                 }
             }
             PlanAggregationFunction::Sum => {
+                let dataset = dataset.clone();
                 if distinct {
-                    Box::new(|| Box::new(DistinctAccumulator::new(SumAccumulator::default())))
+                    Box::new(move || {
+                        Box::new(DistinctAccumulator::new(SumAccumulator::new(
+                            dataset.clone(),
+                        )))
+                    })
                 } else {
-                    Box::new(|| Box::new(SumAccumulator::default()))
+                    Box::new(move || Box::new(SumAccumulator::new(dataset.clone())))
                 }
             }
             PlanAggregationFunction::Min => {
@@ -706,10 +840,15 @@ impl SimpleEvaluator {
                 Box::new(move || Box::new(MaxAccumulator::new(dataset.clone())))
             } // DISTINCT does not make sense with max
             PlanAggregationFunction::Avg => {
+                let dataset = dataset.clone();
                 if distinct {
-                    Box::new(|| Box::new(DistinctAccumulator::new(AvgAccumulator::default())))
+                    Box::new(move || {
+                        Box::new(DistinctAccumulator::new(AvgAccumulator::new(
+                            dataset.clone(),
+                        )))
+                    })
                 } else {
-                    Box::new(|| Box::new(AvgAccumulator::default()))
+                    Box::new(move || Box::new(AvgAccumulator::new(dataset.clone())))
                 }
             }
             PlanAggregationFunction::Sample => Box::new(|| Box::new(SampleAccumulator::default())), // DISTINCT does not make sense with sample
@@ -732,6 +871,25 @@ impl SimpleEvaluator {
                     })
                 }
             }
+            PlanAggregationFunction::Custom(name) => {
+                let dataset = dataset.clone();
+                if let Some(factory) = self.custom_aggregates.get(name).cloned() {
+                    if distinct {
+                        Box::new(move || {
+                            Box::new(DistinctAccumulator::new(CustomAccumulator::new(
+                                dataset.clone(),
+                                factory(),
+                            )))
+                        })
+                    } else {
+                        Box::new(move || {
+                            Box::new(CustomAccumulator::new(dataset.clone(), factory()))
+                        })
+                    }
+                } else {
+                    Box::new(|| Box::new(FailingAccumulator))
+                }
+            }
         }
     }
 
@@ -1008,7 +1166,8 @@ impl SimpleEvaluator {
             PlanExpression::Equal(a, b) => {
                 let a = self.expression_evaluator(a);
                 let b = self.expression_evaluator(b);
-                Rc::new(move |tuple| equals(&a(tuple)?, &b(tuple)?).map(|v| v.into()))
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| equals(&dataset, &a(tuple)?, &b(tuple)?).map(|v| v.into()))
             }
             PlanExpression::Greater(a, b) => {
                 let a = self.expression_evaluator(a);
@@ -1060,12 +1219,29 @@ impl SimpleEvaluator {
             PlanExpression::Add(a, b) => {
                 let a = self.expression_evaluator(a);
                 let b = self.expression_evaluator(b);
-                Rc::new(
-                    move |tuple| match NumericBinaryOperands::new(a(tuple)?, b(tuple)?)? {
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    match NumericBinaryOperands::new(&dataset, a(tuple)?, b(tuple)?)? {
                         NumericBinaryOperands::Float(v1, v2) => Some((v1 + v2).into()),
                         NumericBinaryOperands::Double(v1, v2) => Some((v1 + v2).into()),
-                        NumericBinaryOperands::Integer(v1, v2) => Some(v1.checked_add(v2)?.into()),
-                        NumericBinaryOperands::Decimal(v1, v2) => Some(v1.checked_add(v2)?.into()),
+                        NumericBinaryOperands::Integer(v1, v2) => {
+                            Some(v1.checked_add(v2).map_or_else(
+                                || build_integer(&dataset, BigInteger::from(v1) + v2.into()),
+                                EncodedTerm::from,
+                            ))
+                        }
+                        NumericBinaryOperands::Decimal(v1, v2) => {
+                            Some(v1.checked_add(v2).map_or_else(
+                                || build_decimal(&dataset, BigDecimal::from(v1) + v2.into()),
+                                EncodedTerm::from,
+                            ))
+                        }
+                        NumericBinaryOperands::BigInteger(v1, v2) => {
+                            Some(build_integer(&dataset, v1 + v2))
+                        }
+                        NumericBinaryOperands::BigDecimal(v1, v2) => {
+                            Some(build_decimal(&dataset, v1 + v2))
+                        }
                         NumericBinaryOperands::Duration(v1, v2) => Some(v1.checked_add(v2)?.into()),
                         NumericBinaryOperands::YearMonthDuration(v1, v2) => {
                             Some(v1.checked_add(v2)?.into())
@@ -1098,90 +1274,123 @@ impl SimpleEvaluator {
                             Some(v1.checked_add_day_time_duration(v2)?.into())
                         }
                         _ => None,
-                    },
-                )
+                    }
+                })
             }
             PlanExpression::Subtract(a, b) => {
                 let a = self.expression_evaluator(a);
                 let b = self.expression_evaluator(b);
+                let dataset = self.dataset.clone();
                 Rc::new(move |tuple| {
-                    Some(match NumericBinaryOperands::new(a(tuple)?, b(tuple)?)? {
-                        NumericBinaryOperands::Float(v1, v2) => (v1 - v2).into(),
-                        NumericBinaryOperands::Double(v1, v2) => (v1 - v2).into(),
-                        NumericBinaryOperands::Integer(v1, v2) => v1.checked_sub(v2)?.into(),
-                        NumericBinaryOperands::Decimal(v1, v2) => v1.checked_sub(v2)?.into(),
-                        NumericBinaryOperands::DateTime(v1, v2) => v1.checked_sub(v2)?.into(),
-                        NumericBinaryOperands::Date(v1, v2) => v1.checked_sub(v2)?.into(),
-                        NumericBinaryOperands::Time(v1, v2) => v1.checked_sub(v2)?.into(),
-                        NumericBinaryOperands::Duration(v1, v2) => v1.checked_sub(v2)?.into(),
-                        NumericBinaryOperands::YearMonthDuration(v1, v2) => {
-                            v1.checked_sub(v2)?.into()
-                        }
-                        NumericBinaryOperands::DayTimeDuration(v1, v2) => {
-                            v1.checked_sub(v2)?.into()
-                        }
-                        NumericBinaryOperands::DateTimeDuration(v1, v2) => {
-                            v1.checked_sub_duration(v2)?.into()
-                        }
-                        NumericBinaryOperands::DateTimeYearMonthDuration(v1, v2) => {
-                            v1.checked_sub_year_month_duration(v2)?.into()
-                        }
-                        NumericBinaryOperands::DateTimeDayTimeDuration(v1, v2) => {
-                            v1.checked_sub_day_time_duration(v2)?.into()
-                        }
-                        NumericBinaryOperands::DateDuration(v1, v2) => {
-                            v1.checked_sub_duration(v2)?.into()
-                        }
-                        NumericBinaryOperands::DateYearMonthDuration(v1, v2) => {
-                            v1.checked_sub_year_month_duration(v2)?.into()
-                        }
-                        NumericBinaryOperands::DateDayTimeDuration(v1, v2) => {
-                            v1.checked_sub_day_time_duration(v2)?.into()
-                        }
-                        NumericBinaryOperands::TimeDuration(v1, v2) => {
-                            v1.checked_sub_duration(v2)?.into()
-                        }
-                        NumericBinaryOperands::TimeDayTimeDuration(v1, v2) => {
-                            v1.checked_sub_day_time_duration(v2)?.into()
-                        }
-                    })
+                    Some(
+                        match NumericBinaryOperands::new(&dataset, a(tuple)?, b(tuple)?)? {
+                            NumericBinaryOperands::Float(v1, v2) => (v1 - v2).into(),
+                            NumericBinaryOperands::Double(v1, v2) => (v1 - v2).into(),
+                            NumericBinaryOperands::Integer(v1, v2) => {
+                                v1.checked_sub(v2).map_or_else(
+                                    || build_integer(&dataset, BigInteger::from(v1) - v2.into()),
+                                    EncodedTerm::from,
+                                )
+                            }
+                            NumericBinaryOperands::Decimal(v1, v2) => {
+                                v1.checked_sub(v2).map_or_else(
+                                    || build_decimal(&dataset, BigDecimal::from(v1) - v2.into()),
+                                    EncodedTerm::from,
+                                )
+                            }
+                            NumericBinaryOperands::BigInteger(v1, v2) => {
+                                build_integer(&dataset, v1 - v2)
+                            }
+                            NumericBinaryOperands::BigDecimal(v1, v2) => {
+                                build_decimal(&dataset, v1 - v2)
+                            }
+                            NumericBinaryOperands::DateTime(v1, v2) => v1.checked_sub(v2)?.into(),
+                            NumericBinaryOperands::Date(v1, v2) => v1.checked_sub(v2)?.into(),
+                            NumericBinaryOperands::Time(v1, v2) => v1.checked_sub(v2)?.into(),
+                            NumericBinaryOperands::Duration(v1, v2) => v1.checked_sub(v2)?.into(),
+                            NumericBinaryOperands::YearMonthDuration(v1, v2) => {
+                                v1.checked_sub(v2)?.into()
+                            }
+                            NumericBinaryOperands::DayTimeDuration(v1, v2) => {
+                                v1.checked_sub(v2)?.into()
+                            }
+                            NumericBinaryOperands::DateTimeDuration(v1, v2) => {
+                                v1.checked_sub_duration(v2)?.into()
+                            }
+                            NumericBinaryOperands::DateTimeYearMonthDuration(v1, v2) => {
+                                v1.checked_sub_year_month_duration(v2)?.into()
+                            }
+                            NumericBinaryOperands::DateTimeDayTimeDuration(v1, v2) => {
+                                v1.checked_sub_day_time_duration(v2)?.into()
+                            }
+                            NumericBinaryOperands::DateDuration(v1, v2) => {
+                                v1.checked_sub_duration(v2)?.into()
+                            }
+                            NumericBinaryOperands::DateYearMonthDuration(v1, v2) => {
+                                v1.checked_sub_year_month_duration(v2)?.into()
+                            }
+                            NumericBinaryOperands::DateDayTimeDuration(v1, v2) => {
+                                v1.checked_sub_day_time_duration(v2)?.into()
+                            }
+                            NumericBinaryOperands::TimeDuration(v1, v2) => {
+                                v1.checked_sub_duration(v2)?.into()
+                            }
+                            NumericBinaryOperands::TimeDayTimeDuration(v1, v2) => {
+                                v1.checked_sub_day_time_duration(v2)?.into()
+                            }
+                        },
+                    )
                 })
             }
             PlanExpression::Multiply(a, b) => {
                 let a = self.expression_evaluator(a);
                 let b = self.expression_evaluator(b);
-                Rc::new(
-                    move |tuple| match NumericBinaryOperands::new(a(tuple)?, b(tuple)?)? {
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    match NumericBinaryOperands::new(&dataset, a(tuple)?, b(tuple)?)? {
                         NumericBinaryOperands::Float(v1, v2) => Some((v1 * v2).into()),
                         NumericBinaryOperands::Double(v1, v2) => Some((v1 * v2).into()),
-                        NumericBinaryOperands::Integer(v1, v2) => Some(v1.checked_mul(v2)?.into()),
-                        NumericBinaryOperands::Decimal(v1, v2) => Some(v1.checked_mul(v2)?.into()),
+                        NumericBinaryOperands::Integer(v1, v2) => {
+                            Some(v1.checked_mul(v2).map_or_else(
+                                || build_integer(&dataset, BigInteger::from(v1) * v2.into()),
+                                EncodedTerm::from,
+                            ))
+                        }
+                        // Decimal::checked_mul truncates, the exact product is computed instead
+                        NumericBinaryOperands::Decimal(v1, v2) => Some(build_decimal(
+                            &dataset,
+                            BigDecimal::from(v1) * BigDecimal::from(v2),
+                        )),
+                        NumericBinaryOperands::BigInteger(v1, v2) => {
+                            Some(build_integer(&dataset, v1 * v2))
+                        }
+                        NumericBinaryOperands::BigDecimal(v1, v2) => {
+                            Some(build_decimal(&dataset, v1 * v2))
+                        }
                         _ => None,
-                    },
-                )
+                    }
+                })
             }
             PlanExpression::Divide(a, b) => {
                 let a = self.expression_evaluator(a);
                 let b = self.expression_evaluator(b);
-                Rc::new(
-                    move |tuple| match NumericBinaryOperands::new(a(tuple)?, b(tuple)?)? {
-                        NumericBinaryOperands::Float(v1, v2) => Some((v1 / v2).into()),
-                        NumericBinaryOperands::Double(v1, v2) => Some((v1 / v2).into()),
-                        NumericBinaryOperands::Integer(v1, v2) => {
-                            Some(Decimal::from(v1).checked_div(v2)?.into())
-                        }
-                        NumericBinaryOperands::Decimal(v1, v2) => Some(v1.checked_div(v2)?.into()),
-                        _ => None,
-                    },
-                )
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    numeric_divide(
+                        &dataset,
+                        NumericBinaryOperands::new(&dataset, a(tuple)?, b(tuple)?)?,
+                    )
+                })
             }
             PlanExpression::UnaryPlus(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::FloatLiteral(value) => Some(value.into()),
                     EncodedTerm::DoubleLiteral(value) => Some(value.into()),
                     EncodedTerm::IntegerLiteral(value) => Some(value.into()),
                     EncodedTerm::DecimalLiteral(value) => Some(value.into()),
+                    value @ (EncodedTerm::BigIntegerLiteral { .. }
+                    | EncodedTerm::BigDecimalLiteral { .. }) => Some(value),
                     EncodedTerm::DurationLiteral(value) => Some(value.into()),
                     EncodedTerm::YearMonthDurationLiteral(value) => Some(value.into()),
                     EncodedTerm::DayTimeDurationLiteral(value) => Some(value.into()),
@@ -1190,11 +1399,21 @@ impl SimpleEvaluator {
             }
             PlanExpression::UnaryMinus(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::FloatLiteral(value) => Some((-value).into()),
                     EncodedTerm::DoubleLiteral(value) => Some((-value).into()),
-                    EncodedTerm::IntegerLiteral(value) => Some((-value).into()),
+                    EncodedTerm::IntegerLiteral(value) => Some(value.checked_neg().map_or_else(
+                        || build_integer(&dataset, -BigInteger::from(value)),
+                        EncodedTerm::from,
+                    )),
                     EncodedTerm::DecimalLiteral(value) => Some((-value).into()),
+                    value @ EncodedTerm::BigIntegerLiteral { .. } => {
+                        Some(build_integer(&dataset, -to_big_integer(&dataset, &value)?))
+                    }
+                    value @ EncodedTerm::BigDecimalLiteral { .. } => {
+                        Some(build_decimal(&dataset, -to_big_decimal(&dataset, &value)?))
+                    }
                     EncodedTerm::DurationLiteral(value) => Some((-value).into()),
                     EncodedTerm::YearMonthDurationLiteral(value) => Some((-value).into()),
                     EncodedTerm::DayTimeDurationLiteral(value) => Some((-value).into()),
@@ -1312,9 +1531,21 @@ impl SimpleEvaluator {
             PlanExpression::Rand => Rc::new(|_| Some(random::<f64>().into())),
             PlanExpression::Abs(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
-                    EncodedTerm::IntegerLiteral(value) => Some(value.checked_abs()?.into()),
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| match promote(e(tuple)?) {
+                    EncodedTerm::IntegerLiteral(value) => Some(value.checked_abs().map_or_else(
+                        || build_integer(&dataset, BigInteger::from(value).abs()),
+                        EncodedTerm::from,
+                    )),
                     EncodedTerm::DecimalLiteral(value) => Some(value.abs().into()),
+                    value @ EncodedTerm::BigIntegerLiteral { .. } => Some(build_integer(
+                        &dataset,
+                        to_big_integer(&dataset, &value)?.abs(),
+                    )),
+                    value @ EncodedTerm::BigDecimalLiteral { .. } => Some(build_decimal(
+                        &dataset,
+                        to_big_decimal(&dataset, &value)?.abs(),
+                    )),
                     EncodedTerm::FloatLiteral(value) => Some(value.abs().into()),
                     EncodedTerm::DoubleLiteral(value) => Some(value.abs().into()),
                     _ => None,
@@ -1322,9 +1553,15 @@ impl SimpleEvaluator {
             }
             PlanExpression::Ceil(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::IntegerLiteral(value) => Some(value.into()),
                     EncodedTerm::DecimalLiteral(value) => Some(value.ceil().into()),
+                    value @ EncodedTerm::BigIntegerLiteral { .. } => Some(value),
+                    value @ EncodedTerm::BigDecimalLiteral { .. } => Some(build_decimal(
+                        &dataset,
+                        to_big_decimal(&dataset, &value)?.ceil(),
+                    )),
                     EncodedTerm::FloatLiteral(value) => Some(value.ceil().into()),
                     EncodedTerm::DoubleLiteral(value) => Some(value.ceil().into()),
                     _ => None,
@@ -1332,9 +1569,15 @@ impl SimpleEvaluator {
             }
             PlanExpression::Floor(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::IntegerLiteral(value) => Some(value.into()),
                     EncodedTerm::DecimalLiteral(value) => Some(value.floor().into()),
+                    value @ EncodedTerm::BigIntegerLiteral { .. } => Some(value),
+                    value @ EncodedTerm::BigDecimalLiteral { .. } => Some(build_decimal(
+                        &dataset,
+                        to_big_decimal(&dataset, &value)?.floor(),
+                    )),
                     EncodedTerm::FloatLiteral(value) => Some(value.floor().into()),
                     EncodedTerm::DoubleLiteral(value) => Some(value.floor().into()),
                     _ => None,
@@ -1342,9 +1585,15 @@ impl SimpleEvaluator {
             }
             PlanExpression::Round(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::IntegerLiteral(value) => Some(value.into()),
                     EncodedTerm::DecimalLiteral(value) => Some(value.round().into()),
+                    value @ EncodedTerm::BigIntegerLiteral { .. } => Some(value),
+                    value @ EncodedTerm::BigDecimalLiteral { .. } => Some(build_decimal(
+                        &dataset,
+                        to_big_decimal(&dataset, &value)?.round(),
+                    )),
                     EncodedTerm::FloatLiteral(value) => Some(value.round().into()),
                     EncodedTerm::DoubleLiteral(value) => Some(value.round().into()),
                     _ => None,
@@ -1570,7 +1819,7 @@ impl SimpleEvaluator {
             }
             PlanExpression::Year(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::DateTimeLiteral(date_time) => Some(date_time.year().into()),
                     EncodedTerm::DateLiteral(date) => Some(date.year().into()),
                     EncodedTerm::GYearMonthLiteral(year_month) => Some(year_month.year().into()),
@@ -1580,7 +1829,7 @@ impl SimpleEvaluator {
             }
             PlanExpression::Month(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::DateTimeLiteral(date_time) => Some(date_time.month().into()),
                     EncodedTerm::DateLiteral(date) => Some(date.month().into()),
                     EncodedTerm::GYearMonthLiteral(year_month) => Some(year_month.month().into()),
@@ -1591,7 +1840,7 @@ impl SimpleEvaluator {
             }
             PlanExpression::Day(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::DateTimeLiteral(date_time) => Some(date_time.day().into()),
                     EncodedTerm::DateLiteral(date) => Some(date.day().into()),
                     EncodedTerm::GMonthDayLiteral(month_day) => Some(month_day.day().into()),
@@ -1601,7 +1850,7 @@ impl SimpleEvaluator {
             }
             PlanExpression::Hours(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::DateTimeLiteral(date_time) => Some(date_time.hour().into()),
                     EncodedTerm::TimeLiteral(time) => Some(time.hour().into()),
                     _ => None,
@@ -1609,7 +1858,7 @@ impl SimpleEvaluator {
             }
             PlanExpression::Minutes(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::DateTimeLiteral(date_time) => Some(date_time.minute().into()),
                     EncodedTerm::TimeLiteral(time) => Some(time.minute().into()),
                     _ => None,
@@ -1617,7 +1866,7 @@ impl SimpleEvaluator {
             }
             PlanExpression::Seconds(e) => {
                 let e = self.expression_evaluator(e);
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::DateTimeLiteral(date_time) => Some(date_time.second().into()),
                     EncodedTerm::TimeLiteral(time) => Some(time.second().into()),
                     _ => None,
@@ -1627,7 +1876,7 @@ impl SimpleEvaluator {
                 let e = self.expression_evaluator(e);
                 Rc::new(move |tuple| {
                     Some(
-                        match e(tuple)? {
+                        match promote(e(tuple)?) {
                             EncodedTerm::DateTimeLiteral(date_time) => date_time.timezone(),
                             EncodedTerm::TimeLiteral(time) => time.timezone(),
                             EncodedTerm::DateLiteral(date) => date.timezone(),
@@ -1646,7 +1895,7 @@ impl SimpleEvaluator {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
                 Rc::new(move |tuple| {
-                    let timezone_offset = match e(tuple)? {
+                    let timezone_offset = match promote(e(tuple)?) {
                         EncodedTerm::DateTimeLiteral(date_time) => date_time.timezone_offset(),
                         EncodedTerm::TimeLiteral(time) => time.timezone_offset(),
                         EncodedTerm::DateLiteral(date) => date.timezone_offset(),
@@ -1768,7 +2017,11 @@ impl SimpleEvaluator {
                             EncodedTerm::FloatLiteral(_)
                                 | EncodedTerm::DoubleLiteral(_)
                                 | EncodedTerm::IntegerLiteral(_)
+                                | EncodedTerm::BigIntegerLiteral { .. }
+                                | EncodedTerm::IntegerSubtypeLiteral { .. }
+                                | EncodedTerm::BigIntegerSubtypeLiteral { .. }
                                 | EncodedTerm::DecimalLiteral(_)
+                                | EncodedTerm::BigDecimalLiteral { .. }
                         )
                         .into(),
                     )
@@ -1849,12 +2102,14 @@ impl SimpleEvaluator {
             PlanExpression::BooleanCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::BooleanLiteral(value) => Some(value.into()),
                     EncodedTerm::FloatLiteral(value) => Some(value.to_bool().into()),
                     EncodedTerm::DoubleLiteral(value) => Some(value.to_bool().into()),
                     EncodedTerm::IntegerLiteral(value) => Some((value != 0).into()),
                     EncodedTerm::DecimalLiteral(value) => Some(value.to_bool().into()),
+                    EncodedTerm::BigIntegerLiteral { .. }
+                    | EncodedTerm::BigDecimalLiteral { .. } => Some(true.into()),
                     EncodedTerm::SmallStringLiteral(value) => parse_boolean_str(&value),
                     EncodedTerm::BigStringLiteral { value_id } => {
                         parse_boolean_str(&*dataset.get_str(&value_id).ok()??)
@@ -1865,11 +2120,15 @@ impl SimpleEvaluator {
             PlanExpression::DoubleCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::FloatLiteral(value) => Some(f64::from(value).into()),
                     EncodedTerm::DoubleLiteral(value) => Some(value.into()),
                     EncodedTerm::IntegerLiteral(value) => Some((value as f64).into()),
                     EncodedTerm::DecimalLiteral(value) => Some(value.to_double().into()),
+                    value @ (EncodedTerm::BigIntegerLiteral { .. }
+                    | EncodedTerm::BigDecimalLiteral { .. }) => {
+                        Some(to_big_decimal(&dataset, &value)?.to_double().into())
+                    }
                     EncodedTerm::BooleanLiteral(value) => {
                         Some(if value { 1_f64 } else { 0_f64 }.into())
                     }
@@ -1883,11 +2142,15 @@ impl SimpleEvaluator {
             PlanExpression::FloatCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::FloatLiteral(value) => Some(value.into()),
                     EncodedTerm::DoubleLiteral(value) => Some(value.to_f32().into()),
                     EncodedTerm::IntegerLiteral(value) => Some((value as f32).into()),
                     EncodedTerm::DecimalLiteral(value) => Some(value.to_float().into()),
+                    value @ (EncodedTerm::BigIntegerLiteral { .. }
+                    | EncodedTerm::BigDecimalLiteral { .. }) => {
+                        Some(to_big_decimal(&dataset, &value)?.to_float().into())
+                    }
                     EncodedTerm::BooleanLiteral(value) => {
                         Some(if value { 1_f32 } else { 0_f32 }.into())
                     }
@@ -1901,41 +2164,87 @@ impl SimpleEvaluator {
             PlanExpression::IntegerCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
-                Rc::new(move |tuple| match e(tuple)? {
-                    EncodedTerm::FloatLiteral(value) => Some(value.to_i64().into()),
-                    EncodedTerm::DoubleLiteral(value) => Some(value.to_i64().into()),
+                Rc::new(move |tuple| match promote(e(tuple)?) {
+                    EncodedTerm::FloatLiteral(value) => {
+                        Some(build_integer(&dataset, BigInteger::from_float(value)?))
+                    }
+                    EncodedTerm::DoubleLiteral(value) => {
+                        Some(build_integer(&dataset, BigInteger::from_double(value)?))
+                    }
                     EncodedTerm::IntegerLiteral(value) => Some(value.into()),
-                    EncodedTerm::DecimalLiteral(value) => Some(i64::try_from(value).ok()?.into()),
+                    EncodedTerm::DecimalLiteral(value) => Some(build_integer(
+                        &dataset,
+                        BigDecimal::from(value).to_integer(),
+                    )),
+                    value @ EncodedTerm::BigIntegerLiteral { .. } => Some(value),
+                    value @ EncodedTerm::BigDecimalLiteral { .. } => Some(build_integer(
+                        &dataset,
+                        to_big_decimal(&dataset, &value)?.to_integer(),
+                    )),
                     EncodedTerm::BooleanLiteral(value) => Some(if value { 1 } else { 0 }.into()),
-                    EncodedTerm::SmallStringLiteral(value) => parse_integer_str(&value),
-                    EncodedTerm::BigStringLiteral { value_id } => {
-                        parse_integer_str(&*dataset.get_str(&value_id).ok()??)
+                    EncodedTerm::SmallStringLiteral(value) => {
+                        Some(build_integer(&dataset, value.parse().ok()?))
                     }
+                    EncodedTerm::BigStringLiteral { value_id } => Some(build_integer(
+                        &dataset,
+                        dataset.get_str(&value_id).ok()??.parse().ok()?,
+                    )),
                     _ => None,
                 })
             }
             PlanExpression::DecimalCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
-                Rc::new(move |tuple| match e(tuple)? {
-                    EncodedTerm::FloatLiteral(value) => Some(Decimal::from_float(value).into()),
-                    EncodedTerm::DoubleLiteral(value) => Some(Decimal::from_double(value).into()),
+                Rc::new(move |tuple| match promote(e(tuple)?) {
+                    EncodedTerm::FloatLiteral(value) => {
+                        Some(build_decimal(&dataset, BigDecimal::from_float(value)?))
+                    }
+                    EncodedTerm::DoubleLiteral(value) => {
+                        Some(build_decimal(&dataset, BigDecimal::from_double(value)?))
+                    }
                     EncodedTerm::IntegerLiteral(value) => Some(Decimal::from(value).into()),
                     EncodedTerm::DecimalLiteral(value) => Some(value.into()),
+                    value @ (EncodedTerm::BigIntegerLiteral { .. }
+                    | EncodedTerm::BigDecimalLiteral { .. }) => {
+                        Some(build_decimal(&dataset, to_big_decimal(&dataset, &value)?))
+                    }
                     EncodedTerm::BooleanLiteral(value) => {
                         Some(Decimal::from(if value { 1 } else { 0 }).into())
                     }
-                    EncodedTerm::SmallStringLiteral(value) => parse_decimal_str(&value),
-                    EncodedTerm::BigStringLiteral { value_id } => {
-                        parse_decimal_str(&*dataset.get_str(&value_id).ok()??)
+                    EncodedTerm::SmallStringLiteral(value) => {
+                        Some(build_decimal(&dataset, value.parse().ok()?))
                     }
+                    EncodedTerm::BigStringLiteral { value_id } => Some(build_decimal(
+                        &dataset,
+                        dataset.get_str(&value_id).ok()??.parse().ok()?,
+                    )),
                     _ => None,
                 })
             }
+            PlanExpression::IntegerSubtypeCast(e, subtype) => {
+                let e = self.expression_evaluator(e);
+                let subtype = *subtype;
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    let value = match promote(e(tuple)?) {
+                        EncodedTerm::FloatLiteral(value) => BigInteger::from_float(value)?,
+                        EncodedTerm::DoubleLiteral(value) => BigInteger::from_double(value)?,
+                        EncodedTerm::BooleanLiteral(value) => {
+                            BigInteger::from(if value { 1 } else { 0 })
+                        }
+                        EncodedTerm::SmallStringLiteral(value) => value.parse().ok()?,
+                        EncodedTerm::BigStringLiteral { value_id } => {
+                            dataset.get_str(&value_id).ok()??.parse().ok()?
+                        }
+                        value => to_big_decimal(&dataset, &value)?.to_integer(),
+                    };
+                    build_integer_subtype(&dataset, value, subtype)
+                })
+            }
             PlanExpression::DateCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::DateLiteral(value) => Some(value.into()),
                     EncodedTerm::DateTimeLiteral(value) => Some(Date::try_from(value).ok()?.into()),
                     EncodedTerm::SmallStringLiteral(value) => parse_date_str(&value),
@@ -1948,7 +2257,7 @@ impl SimpleEvaluator {
             PlanExpression::TimeCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::TimeLiteral(value) => Some(value.into()),
                     EncodedTerm::DateTimeLiteral(value) => Some(Time::try_from(value).ok()?.into()),
                     EncodedTerm::SmallStringLiteral(value) => parse_time_str(&value),
@@ -1961,7 +2270,7 @@ impl SimpleEvaluator {
             PlanExpression::DateTimeCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
-                Rc::new(move |tuple| match e(tuple)? {
+                Rc::new(move |tuple| match promote(e(tuple)?) {
                     EncodedTerm::DateTimeLiteral(value) => Some(value.into()),
                     EncodedTerm::DateLiteral(value) => Some(DateTime::try_from(value).ok()?.into()),
                     EncodedTerm::SmallStringLiteral(value) => parse_date_time_str(&value),
@@ -1971,6 +2280,26 @@ impl SimpleEvaluator {
                     _ => None,
                 })
             }
+            PlanExpression::DateTimeStampCast(e) => {
+                let e = self.expression_evaluator(e);
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| match promote(e(tuple)?) {
+                    EncodedTerm::DateTimeLiteral(value) => {
+                        value.timezone_offset()?;
+                        Some(EncodedTerm::DateTimeStampLiteral(value))
+                    }
+                    EncodedTerm::DateLiteral(value) => {
+                        let value = DateTime::try_from(value).ok()?;
+                        value.timezone_offset()?;
+                        Some(EncodedTerm::DateTimeStampLiteral(value))
+                    }
+                    EncodedTerm::SmallStringLiteral(value) => parse_date_time_stamp_str(&value),
+                    EncodedTerm::BigStringLiteral { value_id } => {
+                        parse_date_time_stamp_str(&*dataset.get_str(&value_id).ok()??)
+                    }
+                    _ => None,
+                })
+            }
             PlanExpression::DurationCast(e) => {
                 let e = self.expression_evaluator(e);
                 let dataset = self.dataset.clone();
@@ -2019,6 +2348,100 @@ impl SimpleEvaluator {
                     _ => None,
                 })
             }
+            PlanExpression::Exp(e) => self.double_function(e, f64::exp),
+            PlanExpression::Exp10(e) => self.double_function(e, |v| 10_f64.powf(v)),
+            PlanExpression::Log(e) => self.double_function(e, f64::ln),
+            PlanExpression::Log10(e) => self.double_function(e, f64::log10),
+            PlanExpression::Sqrt(e) => self.double_function(e, f64::sqrt),
+            PlanExpression::Sin(e) => self.double_function(e, f64::sin),
+            PlanExpression::Cos(e) => self.double_function(e, f64::cos),
+            PlanExpression::Tan(e) => self.double_function(e, f64::tan),
+            PlanExpression::Asin(e) => self.double_function(e, f64::asin),
+            PlanExpression::Acos(e) => self.double_function(e, f64::acos),
+            PlanExpression::Atan(e) => self.double_function(e, f64::atan),
+            PlanExpression::Pow(a, b) => {
+                let a = self.expression_evaluator(a);
+                let b = self.expression_evaluator(b);
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    let a = to_double(&dataset, a(tuple)?)?;
+                    let b = to_double(&dataset, b(tuple)?)?;
+                    Some(a.powf(b).into())
+                })
+            }
+            PlanExpression::Atan2(a, b) => {
+                let a = self.expression_evaluator(a);
+                let b = self.expression_evaluator(b);
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    let a = to_double(&dataset, a(tuple)?)?;
+                    let b = to_double(&dataset, b(tuple)?)?;
+                    Some(a.atan2(b).into())
+                })
+            }
+            PlanExpression::StringJoin(l, separator) => {
+                let l: Vec<_> = l.iter().map(|e| self.expression_evaluator(e)).collect();
+                let separator = self.expression_evaluator(separator);
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    let separator = to_string(&dataset, &separator(tuple)?)?;
+                    let values = l
+                        .iter()
+                        .map(|e| to_string(&dataset, &e(tuple)?))
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(build_string_literal(&dataset, &values.join(&separator)))
+                })
+            }
+            PlanExpression::NormalizeSpace(e) => {
+                let e = self.expression_evaluator(e);
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    let (value, language) = to_string_and_language(&dataset, &e(tuple)?)?;
+                    let value = value
+                        .split(|c| matches!(c, ' ' | '\t' | '\r' | '\n'))
+                        .filter(|v| !v.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    Some(build_plain_literal(&dataset, &value, language))
+                })
+            }
+            PlanExpression::FormatNumber(value, picture) => {
+                let value = self.expression_evaluator(value);
+                let picture = self.expression_evaluator(picture);
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    let value = to_double(&dataset, value(tuple)?)?;
+                    let picture = to_simple_string(&dataset, &picture(tuple)?)?;
+                    Some(build_string_literal(
+                        &dataset,
+                        &format_number(value, &picture)?,
+                    ))
+                })
+            }
+            PlanExpression::AdjustDateTimeToTimezone(date_time, timezone) => {
+                let date_time = self.expression_evaluator(date_time);
+                let timezone = timezone.as_ref().map(|t| self.expression_evaluator(t));
+                Rc::new(move |tuple| {
+                    let date_time = if let EncodedTerm::DateTimeLiteral(date_time) =
+                        promote(date_time(tuple)?)
+                    {
+                        date_time
+                    } else {
+                        return None;
+                    };
+                    let timezone_offset = if let Some(timezone) = &timezone {
+                        if let EncodedTerm::DayTimeDurationLiteral(timezone) = timezone(tuple)? {
+                            TimezoneOffset::try_from(timezone).ok()?
+                        } else {
+                            return None;
+                        }
+                    } else {
+                        // We use UTC as implicit timezone
+                        TimezoneOffset::utc()
+                    };
+                    Some(date_time.adjust(Some(timezone_offset))?.into())
+                })
+            }
             PlanExpression::CustomFunction(function_name, args) => {
                 if let Some(function) = self.custom_functions.get(function_name).cloned() {
                     let args = args
@@ -2026,12 +2449,22 @@ impl SimpleEvaluator {
                         .map(|e| self.expression_evaluator(e))
                         .collect::<Vec<_>>();
                     let dataset = self.dataset.clone();
+                    let base_iri = self.base_iri.clone();
+                    let function_error = self.function_error.clone();
                     Rc::new(move |tuple| {
                         let args = args
                             .iter()
                             .map(|f| dataset.decode_term(&f(tuple)?).ok())
                             .collect::<Option<Vec<_>>>()?;
-                        Some(dataset.encode_term(&function(&args)?))
+                        let context = FunctionContext::new(&dataset, base_iri.as_deref());
+                        match function(&context, &args) {
+                            Ok(result) => Some(dataset.encode_term(&result?)),
+                            Err(error) => {
+                                // We keep the first error, it is returned by the FunctionErrorIterator
+                                function_error.borrow_mut().get_or_insert(error);
+                                None
+                            }
+                        }
                     })
                 } else {
                     Rc::new(|_| None)
@@ -2040,6 +2473,16 @@ impl SimpleEvaluator {
         }
     }
 
+    fn double_function(
+        &self,
+        arg: &PlanExpression,
+        function: fn(f64) -> f64,
+    ) -> Rc<dyn Fn(&EncodedTuple) -> Option<EncodedTerm>> {
+        let arg = self.expression_evaluator(arg);
+        let dataset = self.dataset.clone();
+        Rc::new(move |tuple| Some(function(to_double(&dataset, arg(tuple)?)?).into()))
+    }
+
     fn hash<H: Digest>(
         &self,
         arg: &PlanExpression,
@@ -2054,6 +2497,19 @@ impl SimpleEvaluator {
     }
 }
 
+fn to_double(dataset: &DatasetView, term: EncodedTerm) -> Option<f64> {
+    match promote(term) {
+        EncodedTerm::FloatLiteral(value) => Some(value.into()),
+        EncodedTerm::DoubleLiteral(value) => Some(value.into()),
+        EncodedTerm::IntegerLiteral(value) => Some(value as f64),
+        EncodedTerm::DecimalLiteral(value) => Some(value.to_double().into()),
+        value @ (EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. }) => {
+            Some(to_big_decimal(dataset, &value)?.to_double().into())
+        }
+        _ => None,
+    }
+}
+
 fn to_bool(term: &EncodedTerm) -> Option<bool> {
     match term {
         EncodedTerm::BooleanLiteral(value) => Some(*value),
@@ -2065,6 +2521,12 @@ fn to_bool(term: &EncodedTerm) -> Option<bool> {
         EncodedTerm::DoubleLiteral(value) => Some(*value != Double::default()),
         EncodedTerm::IntegerLiteral(value) => Some(*value != 0),
         EncodedTerm::DecimalLiteral(value) => Some(*value != Decimal::default()),
+        EncodedTerm::IntegerSubtypeLiteral { value, .. } => Some(*value != 0),
+        EncodedTerm::BigIntegerLiteral { .. }
+        | EncodedTerm::BigIntegerSubtypeLiteral { .. }
+        | EncodedTerm::BigDecimalLiteral { .. } => {
+            Some(true) // A big numeric literal can't be zero
+        }
         _ => None,
     }
 }
@@ -2084,7 +2546,10 @@ fn to_string_id(dataset: &DatasetView, term: &EncodedTerm) -> Option<SmallString
         EncodedTerm::BigStringLiteral { value_id }
         | EncodedTerm::BigSmallLangStringLiteral { value_id, .. }
         | EncodedTerm::BigBigLangStringLiteral { value_id, .. }
-        | EncodedTerm::BigTypedLiteral { value_id, .. } => Some((*value_id).into()),
+        | EncodedTerm::BigTypedLiteral { value_id, .. }
+        | EncodedTerm::BigIntegerLiteral { value_id }
+        | EncodedTerm::BigIntegerSubtypeLiteral { value_id, .. }
+        | EncodedTerm::BigDecimalLiteral { value_id } => Some((*value_id).into()),
         EncodedTerm::BooleanLiteral(value) => Some(build_string_id(
             dataset,
             if *value { "true" } else { "false" },
@@ -2092,8 +2557,13 @@ fn to_string_id(dataset: &DatasetView, term: &EncodedTerm) -> Option<SmallString
         EncodedTerm::FloatLiteral(value) => Some(build_string_id(dataset, &value.to_string())),
         EncodedTerm::DoubleLiteral(value) => Some(build_string_id(dataset, &value.to_string())),
         EncodedTerm::IntegerLiteral(value) => Some(build_string_id(dataset, &value.to_string())),
+        EncodedTerm::IntegerSubtypeLiteral { value, .. } => {
+            Some(build_string_id(dataset, &value.to_string()))
+        }
         EncodedTerm::DecimalLiteral(value) => Some(build_string_id(dataset, &value.to_string())),
-        EncodedTerm::DateTimeLiteral(value) => Some(build_string_id(dataset, &value.to_string())),
+        EncodedTerm::DateTimeLiteral(value) | EncodedTerm::DateTimeStampLiteral(value) => {
+            Some(build_string_id(dataset, &value.to_string()))
+        }
         EncodedTerm::TimeLiteral(value) => Some(build_string_id(dataset, &value.to_string())),
         EncodedTerm::DateLiteral(value) => Some(build_string_id(dataset, &value.to_string())),
         EncodedTerm::GYearMonthLiteral(value) => Some(build_string_id(dataset, &value.to_string())),
@@ -2242,6 +2712,129 @@ fn build_string_id(dataset: &DatasetView, value: &str) -> SmallStringOrId {
     }
 }
 
+fn build_integer(dataset: &DatasetView, value: BigInteger) -> EncodedTerm {
+    if let Ok(value) = i64::try_from(&value) {
+        value.into()
+    } else {
+        let value = value.to_string();
+        let value_id = StrHash::new(&value);
+        dataset.insert_str(&value_id, &value);
+        EncodedTerm::BigIntegerLiteral { value_id }
+    }
+}
+
+/// [op:numeric-divide](https://www.w3.org/TR/xpath-functions/#func-numeric-divide), used by `/` and `AVG`
+///
+/// Like for the other operators the decimal values are promoted to `BigDecimal` on overflow.
+fn numeric_divide(dataset: &DatasetView, operands: NumericBinaryOperands) -> Option<EncodedTerm> {
+    match operands {
+        NumericBinaryOperands::Float(v1, v2) => Some((v1 / v2).into()),
+        NumericBinaryOperands::Double(v1, v2) => Some((v1 / v2).into()),
+        NumericBinaryOperands::Integer(v1, v2) => {
+            if let Some(v) = Decimal::from(v1).checked_div(v2) {
+                Some(v.into())
+            } else {
+                Some(build_decimal(
+                    dataset,
+                    BigDecimal::from(v1).checked_div(v2)?,
+                ))
+            }
+        }
+        NumericBinaryOperands::Decimal(v1, v2) => {
+            if let Some(v) = v1.checked_div(v2) {
+                Some(v.into())
+            } else {
+                Some(build_decimal(
+                    dataset,
+                    BigDecimal::from(v1).checked_div(v2)?,
+                ))
+            }
+        }
+        NumericBinaryOperands::BigInteger(v1, v2) => Some(build_decimal(
+            dataset,
+            BigDecimal::from(v1).checked_div(v2)?,
+        )),
+        NumericBinaryOperands::BigDecimal(v1, v2) => {
+            Some(build_decimal(dataset, v1.checked_div(v2)?))
+        }
+        _ => None,
+    }
+}
+
+fn build_decimal(dataset: &DatasetView, value: BigDecimal) -> EncodedTerm {
+    if let Ok(value) = Decimal::try_from(&value) {
+        value.into()
+    } else {
+        let value = value.to_string();
+        let value_id = StrHash::new(&value);
+        dataset.insert_str(&value_id, &value);
+        EncodedTerm::BigDecimalLiteral { value_id }
+    }
+}
+
+fn to_big_integer(dataset: &DatasetView, term: &EncodedTerm) -> Option<BigInteger> {
+    match term {
+        EncodedTerm::IntegerLiteral(value) => Some((*value).into()),
+        EncodedTerm::BigIntegerLiteral { value_id } => {
+            dataset.get_str(value_id).ok()??.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn to_big_decimal(dataset: &DatasetView, term: &EncodedTerm) -> Option<BigDecimal> {
+    match term {
+        EncodedTerm::IntegerLiteral(_) | EncodedTerm::BigIntegerLiteral { .. } => {
+            Some(to_big_integer(dataset, term)?.into())
+        }
+        EncodedTerm::DecimalLiteral(value) => Some((*value).into()),
+        EncodedTerm::BigDecimalLiteral { value_id } => {
+            dataset.get_str(value_id).ok()??.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn build_integer_subtype(
+    dataset: &DatasetView,
+    value: BigInteger,
+    subtype: IntegerSubtype,
+) -> Option<EncodedTerm> {
+    if !subtype.contains(&value) {
+        return None;
+    }
+    Some(if let Ok(value) = i64::try_from(&value) {
+        EncodedTerm::IntegerSubtypeLiteral { value, subtype }
+    } else {
+        let value = value.to_string();
+        let value_id = StrHash::new(&value);
+        dataset.insert_str(&value_id, &value);
+        EncodedTerm::BigIntegerSubtypeLiteral { value_id, subtype }
+    })
+}
+
+/// Returns the term casted to the primitive datatype its datatype is derived from, if its datatype is not primitive
+///
+/// Following the XPath type promotion rules, operators and functions are evaluated on this primitive value.
+fn to_primitive(term: &EncodedTerm) -> Option<EncodedTerm> {
+    match term {
+        EncodedTerm::IntegerSubtypeLiteral { value, .. } => {
+            Some(EncodedTerm::IntegerLiteral(*value))
+        }
+        EncodedTerm::BigIntegerSubtypeLiteral { value_id, .. } => {
+            Some(EncodedTerm::BigIntegerLiteral {
+                value_id: *value_id,
+            })
+        }
+        EncodedTerm::DateTimeStampLiteral(value) => Some(EncodedTerm::DateTimeLiteral(*value)),
+        _ => None,
+    }
+}
+
+fn promote(term: EncodedTerm) -> EncodedTerm {
+    to_primitive(&term).unwrap_or(term)
+}
+
 fn build_language_id(dataset: &DatasetView, value: &EncodedTerm) -> Option<SmallStringOrId> {
     let mut language = to_simple_string(dataset, value)?;
     language.make_ascii_lowercase();
@@ -2342,7 +2935,13 @@ fn encode_bindings(
     clippy::cast_possible_truncation,
     clippy::cast_precision_loss
 )]
-fn equals(a: &EncodedTerm, b: &EncodedTerm) -> Option<bool> {
+fn equals(dataset: &DatasetView, a: &EncodedTerm, b: &EncodedTerm) -> Option<bool> {
+    if let Some(a) = to_primitive(a) {
+        return equals(dataset, &a, b);
+    }
+    if let Some(b) = to_primitive(b) {
+        return equals(dataset, a, &b);
+    }
     match a {
         EncodedTerm::DefaultGraph
         | EncodedTerm::NamedNode { .. }
@@ -2399,6 +2998,10 @@ fn equals(a: &EncodedTerm, b: &EncodedTerm) -> Option<bool> {
             EncodedTerm::DoubleLiteral(b) => Some(Double::from(*a) == *b),
             EncodedTerm::IntegerLiteral(b) => Some(*a == Float::from_i64(*b)),
             EncodedTerm::DecimalLiteral(b) => Some(*a == b.to_float()),
+            EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => Some(
+                partial_cmp_big_numerics(dataset, &EncodedTerm::FloatLiteral(*a), b)
+                    == Some(Ordering::Equal),
+            ),
             _ if b.is_unknown_typed_literal() => None,
             _ => Some(false),
         },
@@ -2407,6 +3010,10 @@ fn equals(a: &EncodedTerm, b: &EncodedTerm) -> Option<bool> {
             EncodedTerm::DoubleLiteral(b) => Some(a == b),
             EncodedTerm::IntegerLiteral(b) => Some(*a == Double::from_i64(*b)),
             EncodedTerm::DecimalLiteral(b) => Some(*a == b.to_double()),
+            EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => Some(
+                partial_cmp_big_numerics(dataset, &EncodedTerm::DoubleLiteral(*a), b)
+                    == Some(Ordering::Equal),
+            ),
             _ if b.is_unknown_typed_literal() => None,
             _ => Some(false),
         },
@@ -2415,6 +3022,22 @@ fn equals(a: &EncodedTerm, b: &EncodedTerm) -> Option<bool> {
             EncodedTerm::DoubleLiteral(b) => Some(Double::from_i64(*a) == *b),
             EncodedTerm::IntegerLiteral(b) => Some(a == b),
             EncodedTerm::DecimalLiteral(b) => Some(Decimal::from(*a) == *b),
+            EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => Some(
+                partial_cmp_big_numerics(dataset, &EncodedTerm::IntegerLiteral(*a), b)
+                    == Some(Ordering::Equal),
+            ),
+            _ if b.is_unknown_typed_literal() => None,
+            _ => Some(false),
+        },
+        EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => match b {
+            EncodedTerm::FloatLiteral(_)
+            | EncodedTerm::DoubleLiteral(_)
+            | EncodedTerm::IntegerLiteral(_)
+            | EncodedTerm::BigIntegerLiteral { .. }
+            | EncodedTerm::DecimalLiteral(_)
+            | EncodedTerm::BigDecimalLiteral { .. } => {
+                Some(partial_cmp_big_numerics(dataset, a, b) == Some(Ordering::Equal))
+            }
             _ if b.is_unknown_typed_literal() => None,
             _ => Some(false),
         },
@@ -2423,6 +3046,10 @@ fn equals(a: &EncodedTerm, b: &EncodedTerm) -> Option<bool> {
             EncodedTerm::DoubleLiteral(b) => Some(a.to_double() == *b),
             EncodedTerm::IntegerLiteral(b) => Some(*a == Decimal::from(*b)),
             EncodedTerm::DecimalLiteral(b) => Some(a == b),
+            EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => Some(
+                partial_cmp_big_numerics(dataset, &EncodedTerm::DecimalLiteral(*a), b)
+                    == Some(Ordering::Equal),
+            ),
             _ if b.is_unknown_typed_literal() => None,
             _ => Some(false),
         },
@@ -2490,9 +3117,9 @@ fn equals(a: &EncodedTerm, b: &EncodedTerm) -> Option<bool> {
         EncodedTerm::Triple(a) => {
             if let EncodedTerm::Triple(b) = b {
                 Some(
-                    equals(&a.subject, &b.subject)?
-                        && equals(&a.predicate, &b.predicate)?
-                        && equals(&a.object, &b.object)?,
+                    equals(dataset, &a.subject, &b.subject)?
+                        && equals(dataset, &a.predicate, &b.predicate)?
+                        && equals(dataset, &a.object, &b.object)?,
                 )
             } else {
                 Some(false)
@@ -2615,6 +3242,12 @@ fn partial_cmp_literals(
     a: &EncodedTerm,
     b: &EncodedTerm,
 ) -> Option<Ordering> {
+    if let Some(a) = to_primitive(a) {
+        return partial_cmp_literals(dataset, &a, b);
+    }
+    if let Some(b) = to_primitive(b) {
+        return partial_cmp_literals(dataset, a, &b);
+    }
     match a {
         EncodedTerm::SmallStringLiteral(a) => match b {
             EncodedTerm::SmallStringLiteral(b) => a.partial_cmp(b),
@@ -2687,6 +3320,9 @@ fn partial_cmp_literals(
             EncodedTerm::DoubleLiteral(b) => Double::from(*a).partial_cmp(b),
             EncodedTerm::IntegerLiteral(b) => a.partial_cmp(&Float::from_i64(*b)),
             EncodedTerm::DecimalLiteral(b) => a.partial_cmp(&b.to_float()),
+            EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => {
+                partial_cmp_big_numerics(dataset, &EncodedTerm::FloatLiteral(*a), b)
+            }
             _ => None,
         },
         EncodedTerm::DoubleLiteral(a) => match b {
@@ -2694,6 +3330,9 @@ fn partial_cmp_literals(
             EncodedTerm::DoubleLiteral(b) => a.partial_cmp(b),
             EncodedTerm::IntegerLiteral(b) => a.partial_cmp(&Double::from_i64(*b)),
             EncodedTerm::DecimalLiteral(b) => a.partial_cmp(&b.to_double()),
+            EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => {
+                partial_cmp_big_numerics(dataset, &EncodedTerm::DoubleLiteral(*a), b)
+            }
             _ => None,
         },
         EncodedTerm::IntegerLiteral(a) => match b {
@@ -2701,13 +3340,22 @@ fn partial_cmp_literals(
             EncodedTerm::DoubleLiteral(b) => Double::from_i64(*a).partial_cmp(b),
             EncodedTerm::IntegerLiteral(b) => a.partial_cmp(b),
             EncodedTerm::DecimalLiteral(b) => Decimal::from(*a).partial_cmp(b),
+            EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => {
+                partial_cmp_big_numerics(dataset, &EncodedTerm::IntegerLiteral(*a), b)
+            }
             _ => None,
         },
+        EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => {
+            partial_cmp_big_numerics(dataset, a, b)
+        }
         EncodedTerm::DecimalLiteral(a) => match b {
             EncodedTerm::FloatLiteral(b) => a.to_float().partial_cmp(b),
             EncodedTerm::DoubleLiteral(b) => a.to_double().partial_cmp(b),
             EncodedTerm::IntegerLiteral(b) => a.partial_cmp(&Decimal::from(*b)),
             EncodedTerm::DecimalLiteral(b) => a.partial_cmp(b),
+            EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. } => {
+                partial_cmp_big_numerics(dataset, &EncodedTerm::DecimalLiteral(*a), b)
+            }
             _ => None,
         },
         EncodedTerm::DateTimeLiteral(a) => {
@@ -2788,6 +3436,25 @@ fn partial_cmp_literals(
     }
 }
 
+/// Compares two numeric literals where at least one of them is an arbitrary-precision integer or decimal
+fn partial_cmp_big_numerics(
+    dataset: &DatasetView,
+    a: &EncodedTerm,
+    b: &EncodedTerm,
+) -> Option<Ordering> {
+    match (a, b) {
+        (EncodedTerm::FloatLiteral(a), b) => a.partial_cmp(&to_big_decimal(dataset, b)?.to_float()),
+        (a, EncodedTerm::FloatLiteral(b)) => to_big_decimal(dataset, a)?.to_float().partial_cmp(b),
+        (EncodedTerm::DoubleLiteral(a), b) => {
+            a.partial_cmp(&to_big_decimal(dataset, b)?.to_double())
+        }
+        (a, EncodedTerm::DoubleLiteral(b)) => {
+            to_big_decimal(dataset, a)?.to_double().partial_cmp(b)
+        }
+        (a, b) => to_big_decimal(dataset, a)?.partial_cmp(&to_big_decimal(dataset, b)?),
+    }
+}
+
 fn compare_str_ids(dataset: &DatasetView, a: &StrHash, b: &StrHash) -> Option<Ordering> {
     Some(dataset.get_str(a).ok()??.cmp(&dataset.get_str(b).ok()??))
 }
@@ -2825,9 +3492,20 @@ fn datatype(dataset: &DatasetView, value: &EncodedTerm) -> Option<EncodedTerm> {
         EncodedTerm::BooleanLiteral(..) => Some(encode_named_node(dataset, xsd::BOOLEAN)),
         EncodedTerm::FloatLiteral(..) => Some(encode_named_node(dataset, xsd::FLOAT)),
         EncodedTerm::DoubleLiteral(..) => Some(encode_named_node(dataset, xsd::DOUBLE)),
-        EncodedTerm::IntegerLiteral(..) => Some(encode_named_node(dataset, xsd::INTEGER)),
-        EncodedTerm::DecimalLiteral(..) => Some(encode_named_node(dataset, xsd::DECIMAL)),
+        EncodedTerm::IntegerLiteral(..) | EncodedTerm::BigIntegerLiteral { .. } => {
+            Some(encode_named_node(dataset, xsd::INTEGER))
+        }
+        EncodedTerm::IntegerSubtypeLiteral { subtype, .. }
+        | EncodedTerm::BigIntegerSubtypeLiteral { subtype, .. } => {
+            Some(encode_named_node(dataset, subtype.iri()))
+        }
+        EncodedTerm::DecimalLiteral(..) | EncodedTerm::BigDecimalLiteral { .. } => {
+            Some(encode_named_node(dataset, xsd::DECIMAL))
+        }
         EncodedTerm::DateTimeLiteral(..) => Some(encode_named_node(dataset, xsd::DATE_TIME)),
+        EncodedTerm::DateTimeStampLiteral(..) => {
+            Some(encode_named_node(dataset, xsd::DATE_TIME_STAMP))
+        }
         EncodedTerm::TimeLiteral(..) => Some(encode_named_node(dataset, xsd::TIME)),
         EncodedTerm::DateLiteral(..) => Some(encode_named_node(dataset, xsd::DATE)),
         EncodedTerm::GYearMonthLiteral(..) => Some(encode_named_node(dataset, xsd::G_YEAR_MONTH)),
@@ -2850,6 +3528,8 @@ enum NumericBinaryOperands {
     Double(Double, Double),
     Integer(i64, i64),
     Decimal(Decimal, Decimal),
+    BigInteger(BigInteger, BigInteger),
+    BigDecimal(BigDecimal, BigDecimal),
     Duration(Duration, Duration),
     YearMonthDuration(YearMonthDuration, YearMonthDuration),
     DayTimeDuration(DayTimeDuration, DayTimeDuration),
@@ -2868,8 +3548,8 @@ enum NumericBinaryOperands {
 
 impl NumericBinaryOperands {
     #[allow(clippy::cast_precision_loss)]
-    fn new(a: EncodedTerm, b: EncodedTerm) -> Option<Self> {
-        match (a, b) {
+    fn new(dataset: &DatasetView, a: EncodedTerm, b: EncodedTerm) -> Option<Self> {
+        match (promote(a), promote(b)) {
             (EncodedTerm::FloatLiteral(v1), EncodedTerm::FloatLiteral(v2)) => {
                 Some(Self::Float(v1, v2))
             }
@@ -2918,6 +3598,46 @@ impl NumericBinaryOperands {
             (EncodedTerm::DecimalLiteral(v1), EncodedTerm::DecimalLiteral(v2)) => {
                 Some(Self::Decimal(v1, v2))
             }
+            (
+                EncodedTerm::FloatLiteral(v1),
+                v2
+                @ (EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. }),
+            ) => Some(Self::Float(v1, to_big_decimal(dataset, &v2)?.to_float())),
+            (
+                EncodedTerm::DoubleLiteral(v1),
+                v2
+                @ (EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. }),
+            ) => Some(Self::Double(v1, to_big_decimal(dataset, &v2)?.to_double())),
+            (
+                v1
+                @ (EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. }),
+                EncodedTerm::FloatLiteral(v2),
+            ) => Some(Self::Float(to_big_decimal(dataset, &v1)?.to_float(), v2)),
+            (
+                v1
+                @ (EncodedTerm::BigIntegerLiteral { .. } | EncodedTerm::BigDecimalLiteral { .. }),
+                EncodedTerm::DoubleLiteral(v2),
+            ) => Some(Self::Double(to_big_decimal(dataset, &v1)?.to_double(), v2)),
+            (
+                v1 @ (EncodedTerm::IntegerLiteral(_) | EncodedTerm::BigIntegerLiteral { .. }),
+                v2 @ (EncodedTerm::IntegerLiteral(_) | EncodedTerm::BigIntegerLiteral { .. }),
+            ) => Some(Self::BigInteger(
+                to_big_integer(dataset, &v1)?,
+                to_big_integer(dataset, &v2)?,
+            )),
+            (
+                v1 @ (EncodedTerm::IntegerLiteral(_)
+                | EncodedTerm::BigIntegerLiteral { .. }
+                | EncodedTerm::DecimalLiteral(_)
+                | EncodedTerm::BigDecimalLiteral { .. }),
+                v2 @ (EncodedTerm::IntegerLiteral(_)
+                | EncodedTerm::BigIntegerLiteral { .. }
+                | EncodedTerm::DecimalLiteral(_)
+                | EncodedTerm::BigDecimalLiteral { .. }),
+            ) => Some(Self::BigDecimal(
+                to_big_decimal(dataset, &v1)?,
+                to_big_decimal(dataset, &v2)?,
+            )),
             (EncodedTerm::DurationLiteral(v1), EncodedTerm::DurationLiteral(v2)) => {
                 Some(Self::Duration(v1, v2))
             }
@@ -3155,6 +3875,163 @@ impl Iterator for HashJoinIterator {
     }
 }
 
+struct ServiceJoinIterator {
+    eval: SimpleEvaluator,
+    left_iter: EncodedTuplesIterator,
+    left_done: bool,
+    service_name: NamedNode,
+    variables: Rc<Vec<Variable>>,
+    join_variables: Vec<usize>,
+    graph_pattern: Rc<GraphPattern>,
+    silent: bool,
+    buffered_results: VecDeque<Result<EncodedTuple, EvaluationError>>,
+    unbatched: Vec<EncodedTuple>,
+}
+
+impl ServiceJoinIterator {
+    /// Reads the next batch of left tuples with their VALUES rows, the errors are directly buffered
+    ///
+    /// The tuples that can't be written in VALUES, like the ones with blank nodes, are kept aside in `unbatched`.
+    fn next_batch(&mut self) -> Vec<(EncodedTuple, Vec<Option<GroundTerm>>)> {
+        let mut batch = Vec::new();
+        while !self.left_done && batch.len() < self.eval.service_batch_size {
+            match self.left_iter.next() {
+                Some(Ok(tuple)) => {
+                    if let Some(row) = self.values_row(&tuple) {
+                        batch.push((tuple, row))
+                    } else {
+                        self.unbatched.push(tuple)
+                    }
+                }
+                Some(Err(error)) => self.buffered_results.push_back(Err(error)),
+                None => self.left_done = true,
+            }
+        }
+        batch
+    }
+
+    /// Returns the VALUES row of the tuple join variables or `None` if one of them is not a ground term
+    fn values_row(&self, tuple: &EncodedTuple) -> Option<Vec<Option<GroundTerm>>> {
+        self.join_variables
+            .iter()
+            .map(|v| {
+                if let Some(value) = tuple.get(*v) {
+                    let term = self.eval.dataset.decode_term(value).ok()?;
+                    Some(Some(GroundTerm::try_from(term).ok()?))
+                } else {
+                    Some(None)
+                }
+            })
+            .collect()
+    }
+
+    /// Builds the service query with the batch bindings as a VALUES clause
+    fn batch_query(&self, batch: &[(EncodedTuple, Vec<Option<GroundTerm>>)]) -> Query {
+        let mut bindings = Vec::with_capacity(batch.len());
+        let mut seen = HashSet::with_capacity(batch.len());
+        for (_, row) in batch {
+            if seen.insert(row) {
+                bindings.push(row.clone());
+            }
+        }
+        self.eval.service_query(GraphPattern::Join {
+            left: Box::new(GraphPattern::Values {
+                variables: self
+                    .join_variables
+                    .iter()
+                    .map(|v| self.variables[*v].clone())
+                    .collect(),
+                bindings,
+            }),
+            right: Box::new(self.graph_pattern.as_ref().clone()),
+        })
+    }
+
+    fn join_batch(
+        &mut self,
+        batch: Vec<EncodedTuple>,
+        results: Result<QueryResults, EvaluationError>,
+    ) {
+        let mut right = EncodedTupleSet::new(self.join_variables.clone());
+        let service_results = results.and_then(|results| {
+            for tuple in self
+                .eval
+                .encode_service_results(results, self.variables.clone())?
+            {
+                right.insert(tuple?);
+            }
+            Ok(())
+        });
+        if let Err(error) = service_results {
+            if self.silent {
+                self.buffered_results.extend(batch.into_iter().map(Ok));
+            } else {
+                self.buffered_results.push_back(Err(error));
+            }
+            return;
+        }
+        for left_tuple in batch {
+            for right_tuple in right.get(&left_tuple) {
+                if let Some(result_tuple) = left_tuple.combine_with(right_tuple) {
+                    self.buffered_results.push_back(Ok(result_tuple))
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for ServiceJoinIterator {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        loop {
+            if let Some(result) = self.buffered_results.pop_front() {
+                return Some(result);
+            }
+            if self.left_done {
+                if self.unbatched.is_empty() {
+                    return None;
+                }
+                // The tuples that could not be batched are joined with a single call without VALUES
+                let unbatched = take(&mut self.unbatched);
+                let results = self.eval.service_handler.handle(
+                    self.service_name.clone(),
+                    self.eval.service_query(self.graph_pattern.as_ref().clone()),
+                );
+                self.join_batch(unbatched, results);
+                continue;
+            }
+            let mut batches = Vec::new();
+            while batches.len() < self.eval.service_concurrency {
+                let batch = self.next_batch();
+                if batch.is_empty() {
+                    break;
+                }
+                batches.push(batch);
+            }
+            if batches.is_empty() {
+                continue;
+            }
+            let queries = batches
+                .iter()
+                .map(|batch| self.batch_query(batch))
+                .collect();
+            let results = self
+                .eval
+                .service_handler
+                .handle_all(self.service_name.clone(), queries);
+            if results.len() != batches.len() {
+                return Some(Err(EvaluationError::msg(
+                    "The service handler has not returned one result per query",
+                )));
+            }
+            for (batch, results) in batches.into_iter().zip(results) {
+                self.join_batch(batch.into_iter().map(|(tuple, _)| tuple).collect(), results);
+            }
+        }
+    }
+}
+
 struct LeftJoinIterator {
     right_evaluator: Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator>,
     left_iter: EncodedTuplesIterator,
@@ -3295,6 +4172,24 @@ impl Iterator for ConsecutiveDeduplication {
     }
 }
 
+/// Returns the errors raised by the custom functions during the evaluation of the inner iterator
+struct FunctionErrorIterator {
+    iter: EncodedTuplesIterator,
+    error: Rc<RefCell<Option<EvaluationError>>>,
+}
+
+impl Iterator for FunctionErrorIterator {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        let result = self.iter.next();
+        if let Some(error) = self.error.borrow_mut().take() {
+            return Some(Err(error));
+        }
+        result
+    }
+}
+
 struct ConstructIterator {
     eval: SimpleEvaluator,
     iter: EncodedTuplesIterator,
@@ -3577,7 +4472,7 @@ impl<
 trait Accumulator {
     fn add(&mut self, element: Option<EncodedTerm>);
 
-    fn state(&self) -> Option<EncodedTerm>;
+    fn state(&mut self) -> Option<EncodedTerm>;
 }
 
 #[derive(Default, Debug)]
@@ -3602,11 +4497,48 @@ impl<T: Accumulator> Accumulator for DistinctAccumulator<T> {
         }
     }
 
-    fn state(&self) -> Option<EncodedTerm> {
+    fn state(&mut self) -> Option<EncodedTerm> {
         self.inner.state()
     }
 }
 
+/// Wraps a user provided [`AggregateAccumulator`]
+struct CustomAccumulator {
+    dataset: Rc<DatasetView>,
+    inner: Box<dyn AggregateAccumulator>,
+}
+
+impl CustomAccumulator {
+    fn new(dataset: Rc<DatasetView>, inner: Box<dyn AggregateAccumulator>) -> Self {
+        Self { dataset, inner }
+    }
+}
+
+impl Accumulator for CustomAccumulator {
+    fn add(&mut self, element: Option<EncodedTerm>) {
+        // Errors are skipped like with COUNT
+        if let Some(term) = element.and_then(|e| self.dataset.decode_term(&e).ok()) {
+            self.inner.add(term);
+        }
+    }
+
+    fn state(&mut self) -> Option<EncodedTerm> {
+        let term = self.inner.finish()?;
+        Some(self.dataset.encode_term(&term))
+    }
+}
+
+/// Accumulator for the aggregates that could not be evaluated
+struct FailingAccumulator;
+
+impl Accumulator for FailingAccumulator {
+    fn add(&mut self, _element: Option<EncodedTerm>) {}
+
+    fn state(&mut self) -> Option<EncodedTerm> {
+        None
+    }
+}
+
 #[derive(Default, Debug)]
 struct CountAccumulator {
     count: i64,
@@ -3617,19 +4549,20 @@ impl Accumulator for CountAccumulator {
         self.count += 1;
     }
 
-    fn state(&self) -> Option<EncodedTerm> {
+    fn state(&mut self) -> Option<EncodedTerm> {
         Some(self.count.into())
     }
 }
 
-#[derive(Debug)]
 struct SumAccumulator {
+    dataset: Rc<DatasetView>,
     sum: Option<EncodedTerm>,
 }
 
-impl Default for SumAccumulator {
-    fn default() -> Self {
+impl SumAccumulator {
+    fn new(dataset: Rc<DatasetView>) -> Self {
         Self {
+            dataset,
             sum: Some(0.into()),
         }
     }
@@ -3638,14 +4571,27 @@ impl Default for SumAccumulator {
 impl Accumulator for SumAccumulator {
     fn add(&mut self, element: Option<EncodedTerm>) {
         if let Some(sum) = &self.sum {
-            if let Some(operands) = element.and_then(|e| NumericBinaryOperands::new(sum.clone(), e))
+            if let Some(operands) =
+                element.and_then(|e| NumericBinaryOperands::new(&self.dataset, sum.clone(), e))
             {
                 //TODO: unify with addition?
                 self.sum = match operands {
                     NumericBinaryOperands::Float(v1, v2) => Some((v1 + v2).into()),
                     NumericBinaryOperands::Double(v1, v2) => Some((v1 + v2).into()),
-                    NumericBinaryOperands::Integer(v1, v2) => v1.checked_add(v2).map(|v| v.into()),
-                    NumericBinaryOperands::Decimal(v1, v2) => v1.checked_add(v2).map(|v| v.into()),
+                    NumericBinaryOperands::Integer(v1, v2) => Some(v1.checked_add(v2).map_or_else(
+                        || build_integer(&self.dataset, BigInteger::from(v1) + v2.into()),
+                        EncodedTerm::from,
+                    )),
+                    NumericBinaryOperands::Decimal(v1, v2) => Some(v1.checked_add(v2).map_or_else(
+                        || build_decimal(&self.dataset, BigDecimal::from(v1) + v2.into()),
+                        EncodedTerm::from,
+                    )),
+                    NumericBinaryOperands::BigInteger(v1, v2) => {
+                        Some(build_integer(&self.dataset, v1 + v2))
+                    }
+                    NumericBinaryOperands::BigDecimal(v1, v2) => {
+                        Some(build_decimal(&self.dataset, v1 + v2))
+                    }
                     NumericBinaryOperands::Duration(v1, v2) => v1.checked_add(v2).map(|v| v.into()),
                     _ => None,
                 };
@@ -3655,40 +4601,44 @@ impl Accumulator for SumAccumulator {
         }
     }
 
-    fn state(&self) -> Option<EncodedTerm> {
+    fn state(&mut self) -> Option<EncodedTerm> {
         self.sum.clone()
     }
 }
 
-#[derive(Debug, Default)]
 struct AvgAccumulator {
+    dataset: Rc<DatasetView>,
     sum: SumAccumulator,
     count: CountAccumulator,
 }
 
+impl AvgAccumulator {
+    fn new(dataset: Rc<DatasetView>) -> Self {
+        Self {
+            sum: SumAccumulator::new(dataset.clone()),
+            count: CountAccumulator::default(),
+            dataset,
+        }
+    }
+}
+
 impl Accumulator for AvgAccumulator {
     fn add(&mut self, element: Option<EncodedTerm>) {
         self.sum.add(element.clone());
         self.count.add(element);
     }
 
-    fn state(&self) -> Option<EncodedTerm> {
+    fn state(&mut self) -> Option<EncodedTerm> {
         let sum = self.sum.state()?;
         let count = self.count.state()?;
         if count == EncodedTerm::from(0) {
             Some(0.into())
         } else {
-            //TODO: deduplicate?
             //TODO: duration?
-            match NumericBinaryOperands::new(sum, count)? {
-                NumericBinaryOperands::Float(v1, v2) => Some((v1 / v2).into()),
-                NumericBinaryOperands::Double(v1, v2) => Some((v1 / v2).into()),
-                NumericBinaryOperands::Integer(v1, v2) => {
-                    Decimal::from(v1).checked_div(v2).map(|v| v.into())
-                }
-                NumericBinaryOperands::Decimal(v1, v2) => v1.checked_div(v2).map(|v| v.into()),
-                _ => None,
-            }
+            numeric_divide(
+                &self.dataset,
+                NumericBinaryOperands::new(&self.dataset, sum, count)?,
+            )
         }
     }
 }
@@ -3716,7 +4666,7 @@ impl Accumulator for MinAccumulator {
         }
     }
 
-    fn state(&self) -> Option<EncodedTerm> {
+    fn state(&mut self) -> Option<EncodedTerm> {
         self.min.clone().and_then(|v| v)
     }
 }
@@ -3744,7 +4694,7 @@ impl Accumulator for MaxAccumulator {
         }
     }
 
-    fn state(&self) -> Option<EncodedTerm> {
+    fn state(&mut self) -> Option<EncodedTerm> {
         self.max.clone().and_then(|v| v)
     }
 }
@@ -3761,7 +4711,7 @@ impl Accumulator for SampleAccumulator {
         }
     }
 
-    fn state(&self) -> Option<EncodedTerm> {
+    fn state(&mut self) -> Option<EncodedTerm> {
         self.value.clone()
     }
 }
@@ -3804,13 +4754,89 @@ impl Accumulator for GroupConcatAccumulator {
         }
     }
 
-    fn state(&self) -> Option<EncodedTerm> {
+    fn state(&mut self) -> Option<EncodedTerm> {
         self.concat
             .as_ref()
             .map(|result| build_plain_literal(&self.dataset, result, self.language.and_then(|v| v)))
     }
 }
 
+/// Formats a number using a subset of the [fn:format-number](https://www.w3.org/TR/xpath-functions-31/#func-format-number) picture syntax
+///
+/// The `0` and `#` digits, the `.` decimal and `,` grouping separators, the `%` and `‰` markers
+/// and the `;` negative sub-picture separator are supported.
+fn format_number(value: f64, picture: &str) -> Option<String> {
+    let (picture, negative_picture) = match picture.split_once(';') {
+        Some((picture, negative_picture)) => (picture, Some(negative_picture)),
+        None => (picture, None),
+    };
+    let (picture, minus_sign) = if value.is_sign_negative() && value != 0. {
+        negative_picture.map_or((picture, "-"), |picture| (picture, ""))
+    } else {
+        (picture, "")
+    };
+    let is_active = |c: char| matches!(c, '0'..='9' | '#' | '.' | ',');
+    let start = picture.find(is_active)?;
+    let end = picture.rfind(is_active)? + 1;
+    let (prefix, mantissa, suffix) = (&picture[..start], &picture[start..end], &picture[end..]);
+    if value.is_nan() {
+        return Some("NaN".into());
+    }
+    let mut value = value.abs();
+    if prefix.contains('%') || suffix.contains('%') {
+        value *= 100.;
+    } else if prefix.contains('‰') || suffix.contains('‰') {
+        value *= 1000.;
+    }
+    if value.is_infinite() {
+        return Some(format!("{}{}Infinity{}", minus_sign, prefix, suffix));
+    }
+    let (integer_picture, fraction_picture) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if fraction_picture.contains(|c| c == '.' || c == ',') {
+        return None;
+    }
+    let min_integer_digits = integer_picture
+        .matches(|c: char| c.is_ascii_digit())
+        .count();
+    let grouping_size = integer_picture
+        .rfind(',')
+        .map(|i| integer_picture.len() - i - 1)
+        .filter(|size| *size > 0);
+    let min_fraction_digits = fraction_picture
+        .matches(|c: char| c.is_ascii_digit())
+        .count();
+    let max_fraction_digits = fraction_picture.len();
+
+    let formatted = format!("{:.*}", max_fraction_digits, value);
+    let (integer, mut fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+    while fraction.len() > min_fraction_digits && fraction.ends_with('0') {
+        fraction = &fraction[..fraction.len() - 1];
+    }
+    let mut integer = integer.trim_start_matches('0').to_owned();
+    while integer.len() < min_integer_digits {
+        integer.insert(0, '0');
+    }
+    if integer.is_empty() && fraction.is_empty() {
+        integer.push('0');
+    }
+
+    let mut result = String::with_capacity(formatted.len() + prefix.len() + suffix.len() + 1);
+    result.push_str(minus_sign);
+    result.push_str(prefix);
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && grouping_size.map_or(false, |size| (integer.len() - i) % size == 0) {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    if !fraction.is_empty() {
+        result.push('.');
+        result.push_str(fraction);
+    }
+    result.push_str(suffix);
+    Some(result)
+}
+
 fn generate_uuid(buffer: &mut String) {
     let mut uuid = random::<u128>().to_ne_bytes();
     uuid[6] = (uuid[6] & 0x0F) | 0x40;
@@ -3930,3 +4956,21 @@ fn uuid() {
         buffer
     );
 }
+
+#[test]
+fn format_number_picture() {
+    assert_eq!(
+        format_number(12345.6, "#,###.00").as_deref(),
+        Some("12,345.60")
+    );
+    assert_eq!(
+        format_number(1234.5678, "#,##0.00").as_deref(),
+        Some("1,234.57")
+    );
+    assert_eq!(format_number(0.14, "01%").as_deref(), Some("14%"));
+    assert_eq!(format_number(-6., "0").as_deref(), Some("-6"));
+    assert_eq!(format_number(-3., "0;(0)").as_deref(), Some("(3)"));
+    assert_eq!(format_number(0.5, "#.##").as_deref(), Some(".5"));
+    assert_eq!(format_number(0., "#").as_deref(), Some("0"));
+    assert_eq!(format_number(1., "foo"), None);
+}
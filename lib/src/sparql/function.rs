@@ -0,0 +1,66 @@
+use crate::model::{GraphNameRef, NamedNodeRef, Quad, SubjectRef, Term, TermRef};
+use crate::sparql::dataset::DatasetView;
+use crate::sparql::EvaluationError;
+use crate::storage::numeric_encoder::{Decoder, EncodedTerm};
+use oxiri::Iri;
+use std::iter::empty;
+use std::rc::Rc;
+
+/// The context given to the custom functions registered with [`QueryOptions::with_custom_function_with_context`](super::QueryOptions::with_custom_function_with_context).
+///
+/// It provides a read access to the queried dataset and to the query base IRI.
+pub struct FunctionContext<'a> {
+    dataset: &'a DatasetView,
+    base_iri: Option<&'a Iri<String>>,
+}
+
+impl<'a> FunctionContext<'a> {
+    pub(crate) fn new(dataset: &'a DatasetView, base_iri: Option<&'a Iri<String>>) -> Self {
+        Self { dataset, base_iri }
+    }
+
+    /// The base IRI of the query if set.
+    #[inline]
+    pub fn base_iri(&self) -> Option<&str> {
+        self.base_iri.map(|iri| iri.as_str())
+    }
+
+    /// Retrieves the quads of the queried dataset matching a pattern.
+    ///
+    /// If `graph_name` is `None` the quads of the default graph and of all the named graphs of the dataset are returned.
+    /// The dataset is the one specified by the query `FROM` and `FROM NAMED` clauses.
+    pub fn quads_for_pattern(
+        &self,
+        subject: Option<SubjectRef<'_>>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<TermRef<'_>>,
+        graph_name: Option<GraphNameRef<'_>>,
+    ) -> impl Iterator<Item = Result<Quad, EvaluationError>> + 'a {
+        let subject = subject.map(EncodedTerm::from);
+        let predicate = predicate.map(EncodedTerm::from);
+        let object = object.map(EncodedTerm::from);
+        let dataset = self.dataset;
+        let default_graph = if graph_name.is_none() {
+            dataset.encoded_quads_for_pattern(
+                subject.as_ref(),
+                predicate.as_ref(),
+                object.as_ref(),
+                Some(&EncodedTerm::DefaultGraph),
+            )
+        } else {
+            Box::new(empty())
+        };
+        default_graph
+            .chain(dataset.encoded_quads_for_pattern(
+                subject.as_ref(),
+                predicate.as_ref(),
+                object.as_ref(),
+                graph_name.map(EncodedTerm::from).as_ref(),
+            ))
+            .map(move |quad| Ok(dataset.decode_quad(&quad?)?))
+    }
+}
+
+/// A custom function as stored in [`QueryOptions`](super::QueryOptions)
+pub(crate) type CustomFunction =
+    Rc<dyn Fn(&FunctionContext<'_>, &[Term]) -> Result<Option<Term>, EvaluationError>>;
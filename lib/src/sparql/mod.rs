@@ -6,6 +6,7 @@ mod algebra;
 mod dataset;
 mod error;
 mod eval;
+mod function;
 mod http;
 mod model;
 mod plan;
@@ -14,23 +15,32 @@ mod service;
 mod update;
 
 use crate::model::{NamedNode, Term};
+use crate::sparql::algebra::QueryDataset;
 pub use crate::sparql::algebra::{Query, Update};
 use crate::sparql::dataset::DatasetView;
 pub use crate::sparql::error::{EvaluationError, QueryError};
 use crate::sparql::eval::SimpleEvaluator;
+use crate::sparql::function::CustomFunction;
+pub use crate::sparql::function::FunctionContext;
 pub use crate::sparql::model::{QueryResults, QuerySolution, QuerySolutionIter, QueryTripleIter};
+use crate::sparql::plan::{PlanNode, TripleTemplate};
 use crate::sparql::plan_builder::PlanBuilder;
-pub use crate::sparql::service::ServiceHandler;
 use crate::sparql::service::{EmptyServiceHandler, ErrorConversionServiceHandler};
+pub use crate::sparql::service::{ServiceHandler, StoreServiceHandler};
 pub(crate) use crate::sparql::update::evaluate_update;
+use crate::storage::numeric_encoder::StrHash;
 use crate::storage::StorageReader;
+use oxiri::Iri;
 pub use oxrdf::{Variable, VariableNameParseError};
 pub use sparesults::QueryResultsFormat;
 pub use spargebra::ParseError;
 use std::collections::HashMap;
+use std::error::Error;
 use std::rc::Rc;
 use std::time::Duration;
 
+const DEFAULT_SERVICE_BATCH_SIZE: usize = 100;
+
 #[allow(clippy::needless_pass_by_value)]
 pub(crate) fn evaluate_query(
     reader: StorageReader,
@@ -39,31 +49,46 @@ pub(crate) fn evaluate_query(
 ) -> Result<QueryResults, EvaluationError> {
     let query = query.try_into().map_err(std::convert::Into::into)?;
     let dataset = DatasetView::new(reader, &query.dataset);
+    let (service_batch_size, service_concurrency) = options.service_batching();
     match query.inner {
         spargebra::Query::Select {
             pattern, base_iri, ..
         } => {
-            let (plan, variables) =
-                PlanBuilder::build(&dataset, &pattern, true, &options.custom_functions)?;
+            let (plan, variables) = PlanBuilder::build(
+                &dataset,
+                &pattern,
+                true,
+                &options.custom_functions,
+                &options.custom_aggregates,
+            )?;
             Ok(SimpleEvaluator::new(
                 Rc::new(dataset),
                 base_iri.map(Rc::new),
                 options.service_handler(),
                 Rc::new(options.custom_functions),
+                Rc::new(options.custom_aggregates),
             )
+            .with_service_batching(service_batch_size, service_concurrency)
             .evaluate_select_plan(&plan, Rc::new(variables)))
         }
         spargebra::Query::Ask {
             pattern, base_iri, ..
         } => {
-            let (plan, _) =
-                PlanBuilder::build(&dataset, &pattern, false, &options.custom_functions)?;
+            let (plan, _) = PlanBuilder::build(
+                &dataset,
+                &pattern,
+                false,
+                &options.custom_functions,
+                &options.custom_aggregates,
+            )?;
             SimpleEvaluator::new(
                 Rc::new(dataset),
                 base_iri.map(Rc::new),
                 options.service_handler(),
                 Rc::new(options.custom_functions),
+                Rc::new(options.custom_aggregates),
             )
+            .with_service_batching(service_batch_size, service_concurrency)
             .evaluate_ask_plan(&plan)
         }
         spargebra::Query::Construct {
@@ -72,38 +97,233 @@ pub(crate) fn evaluate_query(
             base_iri,
             ..
         } => {
-            let (plan, variables) =
-                PlanBuilder::build(&dataset, &pattern, false, &options.custom_functions)?;
+            let (plan, variables) = PlanBuilder::build(
+                &dataset,
+                &pattern,
+                false,
+                &options.custom_functions,
+                &options.custom_aggregates,
+            )?;
             let construct = PlanBuilder::build_graph_template(
                 &dataset,
                 &template,
                 variables,
                 &options.custom_functions,
+                &options.custom_aggregates,
             );
             Ok(SimpleEvaluator::new(
                 Rc::new(dataset),
                 base_iri.map(Rc::new),
                 options.service_handler(),
                 Rc::new(options.custom_functions),
+                Rc::new(options.custom_aggregates),
             )
+            .with_service_batching(service_batch_size, service_concurrency)
             .evaluate_construct_plan(&plan, construct))
         }
         spargebra::Query::Describe {
             pattern, base_iri, ..
         } => {
-            let (plan, _) =
-                PlanBuilder::build(&dataset, &pattern, false, &options.custom_functions)?;
+            let (plan, _) = PlanBuilder::build(
+                &dataset,
+                &pattern,
+                false,
+                &options.custom_functions,
+                &options.custom_aggregates,
+            )?;
             Ok(SimpleEvaluator::new(
                 Rc::new(dataset),
                 base_iri.map(Rc::new),
                 options.service_handler(),
                 Rc::new(options.custom_functions),
+                Rc::new(options.custom_aggregates),
             )
+            .with_service_batching(service_batch_size, service_concurrency)
             .evaluate_describe_plan(&plan))
         }
     }
 }
 
+/// A [SPARQL query](https://www.w3.org/TR/sparql11-query/) already parsed and planned.
+///
+/// It allows to execute the same query multiple times without parsing and planning it again each time,
+/// binding some variables to different values at each execution in a way similar to a `VALUES` clause.
+///
+/// It is built using [`Store::prepare_query`](crate::store::Store::prepare_query)
+/// and executed with [`Store::query_prepared`](crate::store::Store::query_prepared).
+///
+/// Usage example:
+/// ```
+/// use oxigraph::store::Store;
+/// use oxigraph::model::*;
+/// use oxigraph::sparql::{QueryResults, Variable};
+///
+/// let store = Store::new()?;
+/// let ex = NamedNodeRef::new("http://example.com")?;
+/// store.insert(QuadRef::new(ex, ex, LiteralRef::new_simple_literal("foo"), GraphNameRef::DefaultGraph))?;
+///
+/// let query = store.prepare_query("SELECT ?o WHERE { ?s ?p ?o }")?;
+/// if let QueryResults::Solutions(mut solutions) = store.query_prepared(&query, [(Variable::new("s")?, ex.into_owned().into())])? {
+///     assert_eq!(solutions.next().unwrap()?.get("o"), Some(&Literal::from("foo").into()));
+/// }
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+pub struct PreparedQuery {
+    plan: PreparedPlan,
+    dataset: QueryDataset,
+    base_iri: Option<Rc<Iri<String>>>,
+    strings: Rc<HashMap<StrHash, String>>,
+    service_handler: Rc<dyn ServiceHandler<Error = EvaluationError>>,
+    custom_functions: Rc<HashMap<NamedNode, CustomFunction>>,
+    custom_aggregates: Rc<HashMap<NamedNode, Rc<dyn Fn() -> Box<dyn AggregateAccumulator>>>>,
+    service_batch_size: usize,
+    service_concurrency: usize,
+}
+
+enum PreparedPlan {
+    Select {
+        plan: PlanNode,
+        variables: Rc<Vec<Variable>>,
+    },
+    Ask {
+        plan: PlanNode,
+    },
+    Construct {
+        plan: PlanNode,
+        template: Vec<TripleTemplate>,
+    },
+    Describe {
+        plan: PlanNode,
+    },
+}
+
+pub(crate) fn prepare_query(
+    reader: StorageReader,
+    query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    options: QueryOptions,
+) -> Result<PreparedQuery, EvaluationError> {
+    let query = query.try_into().map_err(std::convert::Into::into)?;
+    // We keep all the strings to be able to execute the query on later snapshots or other stores
+    let dataset = DatasetView::new(reader, &query.dataset).keeping_all_strings();
+    let (plan, base_iri) = match &query.inner {
+        spargebra::Query::Select {
+            pattern, base_iri, ..
+        } => {
+            let (plan, variables) = PlanBuilder::build_with_parameters(
+                &dataset,
+                pattern,
+                true,
+                &options.custom_functions,
+                &options.custom_aggregates,
+                true,
+            )?;
+            (
+                PreparedPlan::Select {
+                    plan,
+                    variables: Rc::new(variables),
+                },
+                base_iri,
+            )
+        }
+        spargebra::Query::Ask {
+            pattern, base_iri, ..
+        } => {
+            let (plan, _) = PlanBuilder::build_with_parameters(
+                &dataset,
+                pattern,
+                false,
+                &options.custom_functions,
+                &options.custom_aggregates,
+                true,
+            )?;
+            (PreparedPlan::Ask { plan }, base_iri)
+        }
+        spargebra::Query::Construct {
+            template,
+            pattern,
+            base_iri,
+            ..
+        } => {
+            let (plan, variables) = PlanBuilder::build_with_parameters(
+                &dataset,
+                pattern,
+                false,
+                &options.custom_functions,
+                &options.custom_aggregates,
+                true,
+            )?;
+            let template = PlanBuilder::build_graph_template(
+                &dataset,
+                template,
+                variables,
+                &options.custom_functions,
+                &options.custom_aggregates,
+            );
+            (PreparedPlan::Construct { plan, template }, base_iri)
+        }
+        spargebra::Query::Describe {
+            pattern, base_iri, ..
+        } => {
+            let (plan, _) = PlanBuilder::build_with_parameters(
+                &dataset,
+                pattern,
+                false,
+                &options.custom_functions,
+                &options.custom_aggregates,
+                true,
+            )?;
+            (PreparedPlan::Describe { plan }, base_iri)
+        }
+    };
+    let (service_batch_size, service_concurrency) = options.service_batching();
+    Ok(PreparedQuery {
+        plan,
+        base_iri: base_iri.clone().map(Rc::new),
+        strings: dataset.into_extra_strings(),
+        service_handler: options.service_handler(),
+        custom_functions: Rc::new(options.custom_functions),
+        custom_aggregates: Rc::new(options.custom_aggregates),
+        service_batch_size,
+        service_concurrency,
+        dataset: query.dataset,
+    })
+}
+
+pub(crate) fn evaluate_prepared_query(
+    reader: StorageReader,
+    query: &PreparedQuery,
+    bindings: impl IntoIterator<Item = (Variable, Term)>,
+) -> Result<QueryResults, EvaluationError> {
+    let dataset =
+        DatasetView::new(reader, &query.dataset).with_extra_strings(query.strings.clone());
+    let parameters = bindings
+        .into_iter()
+        .map(|(variable, value)| {
+            let value = dataset.encode_term(&value);
+            (variable, value)
+        })
+        .collect();
+    let evaluator = SimpleEvaluator::new(
+        Rc::new(dataset),
+        query.base_iri.clone(),
+        query.service_handler.clone(),
+        query.custom_functions.clone(),
+        query.custom_aggregates.clone(),
+    )
+    .with_parameters(parameters)
+    .with_service_batching(query.service_batch_size, query.service_concurrency);
+    match &query.plan {
+        PreparedPlan::Select { plan, variables } => {
+            Ok(evaluator.evaluate_select_plan(plan, variables.clone()))
+        }
+        PreparedPlan::Ask { plan } => evaluator.evaluate_ask_plan(plan),
+        PreparedPlan::Construct { plan, template } => {
+            Ok(evaluator.evaluate_construct_plan(plan, template.clone()))
+        }
+        PreparedPlan::Describe { plan } => Ok(evaluator.evaluate_describe_plan(plan)),
+    }
+}
+
 /// Options for SPARQL query evaluation.
 ///
 ///
@@ -125,9 +345,12 @@ pub(crate) fn evaluate_query(
 #[derive(Clone, Default)]
 pub struct QueryOptions {
     service_handler: Option<Rc<dyn ServiceHandler<Error = EvaluationError>>>,
-    custom_functions: HashMap<NamedNode, Rc<dyn Fn(&[Term]) -> Option<Term>>>,
+    custom_functions: HashMap<NamedNode, CustomFunction>,
+    custom_aggregates: HashMap<NamedNode, Rc<dyn Fn() -> Box<dyn AggregateAccumulator>>>,
     http_timeout: Option<Duration>,
     http_redirection_limit: usize,
+    service_batch_size: Option<usize>,
+    service_concurrency: usize,
 }
 
 impl QueryOptions {
@@ -169,6 +392,31 @@ impl QueryOptions {
         self
     }
 
+    /// Sets the maximal number of bindings sent at once to a `SERVICE` when it is joined with the rest of the query.
+    ///
+    /// When a `SERVICE` with a constant IRI shares variables with the rest of the query,
+    /// the bindings already computed for these variables are sent to the service in a `VALUES` clause
+    /// instead of fetching all the service results (bind join).
+    ///
+    /// By default this value is `100`. `0` disables the bind join.
+    #[inline]
+    #[must_use]
+    pub fn with_service_batch_size(mut self, batch_size: usize) -> Self {
+        self.service_batch_size = Some(batch_size);
+        self
+    }
+
+    /// Sets the maximal number of bind join requests sent at the same time to a `SERVICE`.
+    ///
+    /// The requests are sent using [`ServiceHandler::handle_all`].
+    /// By default this value is `1`.
+    #[inline]
+    #[must_use]
+    pub fn with_service_concurrency(mut self, concurrency: usize) -> Self {
+        self.service_concurrency = concurrency;
+        self
+    }
+
     /// Adds a custom SPARQL evaluation function.
     ///
     /// Example with a function serializing terms to N-Triples:
@@ -197,10 +445,121 @@ impl QueryOptions {
         name: NamedNode,
         evaluator: impl Fn(&[Term]) -> Option<Term> + 'static,
     ) -> Self {
-        self.custom_functions.insert(name, Rc::new(evaluator));
+        self.custom_functions.insert(
+            name,
+            Rc::new(move |_: &FunctionContext<'_>, args: &[Term]| Ok(evaluator(args))),
+        );
+        self
+    }
+
+    /// Adds a custom SPARQL evaluation function with access to the evaluation context.
+    ///
+    /// The [`FunctionContext`] gives a read access to the queried dataset and to the query base IRI.
+    /// If the function returns `Ok(None)` the function call evaluates to an unbound value like with [`with_custom_function`](Self::with_custom_function).
+    /// If the function returns an error the query evaluation fails with this error.
+    ///
+    /// Example with a function returning the `rdfs:label` of a resource:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::model::vocab::rdfs;
+    /// use oxigraph::sparql::{EvaluationError, QueryOptions, QueryResults};
+    ///
+    /// let store = Store::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, rdfs::LABEL, LiteralRef::new_simple_literal("Example"), GraphNameRef::DefaultGraph))?;
+    ///
+    /// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+    ///     "SELECT (<http://example.com/label>(<http://example.com>) AS ?label) WHERE {}",
+    ///     QueryOptions::default().with_custom_function_with_context(
+    ///         NamedNode::new("http://example.com/label")?,
+    ///         |context, args| -> Result<_, EvaluationError> {
+    ///             let subject = match args.get(0) {
+    ///                 Some(Term::NamedNode(subject)) => subject,
+    ///                 _ => return Ok(None),
+    ///             };
+    ///             let label = context.quads_for_pattern(Some(subject.as_ref().into()), Some(rdfs::LABEL), None, None).next();
+    ///             Ok(label.transpose()?.map(|quad| quad.object))
+    ///         }
+    ///     )
+    /// )? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("label"), Some(&Literal::from("Example").into()));
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn with_custom_function_with_context<E: Error + Send + Sync + 'static>(
+        mut self,
+        name: NamedNode,
+        evaluator: impl Fn(&FunctionContext<'_>, &[Term]) -> Result<Option<Term>, E> + 'static,
+    ) -> Self {
+        self.custom_functions.insert(
+            name,
+            Rc::new(move |context: &FunctionContext<'_>, args: &[Term]| {
+                evaluator(context, args).map_err(EvaluationError::wrap)
+            }),
+        );
+        self
+    }
+
+    /// Adds a custom SPARQL aggregate function.
+    ///
+    /// The `accumulator` function is called to build a new [`AggregateAccumulator`] for each group.
+    ///
+    /// The calls in the `SELECT` expressions, in `ORDER BY` and in `HAVING` if there is a `GROUP BY` are aggregations, the calls in the `WHERE` clause are not.
+    ///
+    /// Example with an aggregate returning the length of the longest string representation of the aggregated terms:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::{AggregateAccumulator, QueryOptions, QueryResults};
+    ///
+    /// #[derive(Default)]
+    /// struct MaxLength(usize);
+    ///
+    /// impl AggregateAccumulator for MaxLength {
+    ///     fn add(&mut self, term: Term) {
+    ///         self.0 = self.0.max(term.to_string().len());
+    ///     }
+    ///
+    ///     fn finish(&mut self) -> Option<Term> {
+    ///         Some(Literal::from(self.0 as i64).into())
+    ///     }
+    /// }
+    ///
+    /// let store = Store::new()?;
+    ///
+    /// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+    ///     "SELECT (<http://example.com/maxLength>(?v) AS ?l) WHERE { VALUES ?v { 1 \"foo\" } }",
+    ///     QueryOptions::default().with_custom_aggregate(
+    ///         NamedNode::new("http://example.com/maxLength")?,
+    ///         || Box::new(MaxLength::default())
+    ///     )
+    /// )? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("l"), Some(&Literal::from(47).into()));
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn with_custom_aggregate(
+        mut self,
+        name: NamedNode,
+        accumulator: impl Fn() -> Box<dyn AggregateAccumulator> + 'static,
+    ) -> Self {
+        self.custom_aggregates.insert(name, Rc::new(accumulator));
         self
     }
 
+    fn service_batching(&self) -> (usize, usize) {
+        (
+            self.service_batch_size
+                .unwrap_or(DEFAULT_SERVICE_BATCH_SIZE),
+            self.service_concurrency.max(1),
+        )
+    }
+
     fn service_handler(&self) -> Rc<dyn ServiceHandler<Error = EvaluationError>> {
         self.service_handler.clone().unwrap_or_else(|| {
             if cfg!(feature = "http_client") {
@@ -215,6 +574,17 @@ impl QueryOptions {
     }
 }
 
+/// The state of a custom aggregate function during the evaluation of a group.
+///
+/// See [`QueryOptions::with_custom_aggregate`] for an example.
+pub trait AggregateAccumulator {
+    /// Adds a term to the aggregated values. Unbound values and evaluation errors are skipped.
+    fn add(&mut self, term: Term);
+
+    /// Returns the aggregate value or `None` if it is unbound.
+    fn finish(&mut self) -> Option<Term>;
+}
+
 /// Options for SPARQL update evaluation.
 #[derive(Clone, Default)]
 pub struct UpdateOptions {
@@ -1,3 +1,5 @@
+#[cfg(feature = "arrow")]
+use crate::io::arrow::SolutionBatchIter;
 use crate::io::GraphFormat;
 use crate::io::GraphSerializer;
 use crate::model::*;
@@ -192,6 +194,16 @@ impl QuerySolutionIter {
     pub fn variables(&self) -> &[Variable] {
         &*self.variables
     }
+
+    /// Converts the solutions into [Apache Arrow](https://arrow.apache.org/) record batches of at most `batch_size` rows.
+    ///
+    /// See the [`arrow`](crate::io::arrow) module for the columns encoding.
+    /// It is available behind the `arrow` feature.
+    #[cfg(feature = "arrow")]
+    #[inline]
+    pub fn into_record_batches(self, batch_size: usize) -> SolutionBatchIter {
+        SolutionBatchIter::new(self, batch_size)
+    }
 }
 
 impl<R: BufRead + 'static> From<SolutionsReader<R>> for QuerySolutionIter {
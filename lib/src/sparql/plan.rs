@@ -1,5 +1,6 @@
 use crate::model::NamedNode;
 use crate::storage::numeric_encoder::EncodedTerm;
+use crate::xsd::IntegerSubtype;
 use oxrdf::Variable;
 use spargebra::algebra::GraphPattern;
 use std::cmp::max;
@@ -41,6 +42,11 @@ pub enum PlanNode {
         left: Box<Self>,
         right: Box<Self>,
     },
+    /// Sends batches of left results as VALUES to the right service (bind join)
+    ServiceJoin {
+        left: Box<Self>,
+        right: Box<Self>,
+    },
     /// Streams left and materializes right anti join
     AntiJoin {
         left: Box<Self>,
@@ -93,6 +99,12 @@ pub enum PlanNode {
         key_mapping: Rc<Vec<(usize, usize)>>, // aggregate key pairs of (variable key in child, variable key in output)
         aggregates: Rc<Vec<(PlanAggregation, usize)>>,
     },
+    /// Binds the parameters given when executing a prepared query
+    Parameters {
+        child: Box<Self>,
+        variables: Rc<Vec<Variable>>, // the variables in scope, by key
+        assigned: Rc<Vec<usize>>, // the keys of the variables assigned by BIND that can't be parameters
+    },
 }
 
 impl PlanNode {
@@ -162,6 +174,7 @@ impl PlanNode {
             }
             PlanNode::HashJoin { left, right }
             | PlanNode::ForLoopJoin { left, right, .. }
+            | PlanNode::ServiceJoin { left, right }
             | PlanNode::AntiJoin { left, right }
             | PlanNode::LeftJoin { left, right, .. } => {
                 left.lookup_used_variables(callback);
@@ -180,7 +193,8 @@ impl PlanNode {
             | PlanNode::HashDeduplicate { child }
             | PlanNode::Reduced { child }
             | PlanNode::Skip { child, .. }
-            | PlanNode::Limit { child, .. } => child.lookup_used_variables(callback),
+            | PlanNode::Limit { child, .. }
+            | PlanNode::Parameters { child, .. } => child.lookup_used_variables(callback),
             PlanNode::Service {
                 child,
                 service_name,
@@ -300,7 +314,9 @@ impl PlanNode {
                     }
                 }
             }
-            PlanNode::HashJoin { left, right } | PlanNode::ForLoopJoin { left, right, .. } => {
+            PlanNode::HashJoin { left, right }
+            | PlanNode::ForLoopJoin { left, right, .. }
+            | PlanNode::ServiceJoin { left, right } => {
                 left.lookup_always_bound_variables(callback);
                 right.lookup_always_bound_variables(callback);
             }
@@ -322,7 +338,8 @@ impl PlanNode {
             | PlanNode::HashDeduplicate { child }
             | PlanNode::Reduced { child }
             | PlanNode::Skip { child, .. }
-            | PlanNode::Limit { child, .. } => child.lookup_always_bound_variables(callback),
+            | PlanNode::Limit { child, .. }
+            | PlanNode::Parameters { child, .. } => child.lookup_always_bound_variables(callback),
             PlanNode::Service { child, silent, .. } => {
                 if *silent {
                     // none, might return a null tuple
@@ -448,13 +465,33 @@ pub enum PlanExpression {
     FloatCast(Box<Self>),
     DecimalCast(Box<Self>),
     IntegerCast(Box<Self>),
+    IntegerSubtypeCast(Box<Self>, IntegerSubtype),
     DateCast(Box<Self>),
     TimeCast(Box<Self>),
     DateTimeCast(Box<Self>),
+    DateTimeStampCast(Box<Self>),
     DurationCast(Box<Self>),
     YearMonthDurationCast(Box<Self>),
     DayTimeDurationCast(Box<Self>),
     StringCast(Box<Self>),
+    Exp(Box<Self>),
+    Exp10(Box<Self>),
+    Log(Box<Self>),
+    Log10(Box<Self>),
+    Pow(Box<Self>, Box<Self>),
+    Sqrt(Box<Self>),
+    Sin(Box<Self>),
+    Cos(Box<Self>),
+    Tan(Box<Self>),
+    Asin(Box<Self>),
+    Acos(Box<Self>),
+    Atan(Box<Self>),
+    Atan2(Box<Self>, Box<Self>),
+    /// The values and the separator
+    StringJoin(Vec<Self>, Box<Self>),
+    NormalizeSpace(Box<Self>),
+    FormatNumber(Box<Self>, Box<Self>),
+    AdjustDateTimeToTimezone(Box<Self>, Option<Box<Self>>),
     CustomFunction(NamedNode, Vec<Self>),
 }
 
@@ -512,13 +549,28 @@ impl PlanExpression {
             | Self::FloatCast(e)
             | Self::DecimalCast(e)
             | Self::IntegerCast(e)
+            | Self::IntegerSubtypeCast(e, _)
             | Self::DateCast(e)
             | Self::TimeCast(e)
             | Self::DateTimeCast(e)
+            | Self::DateTimeStampCast(e)
             | Self::DurationCast(e)
             | Self::YearMonthDurationCast(e)
             | Self::DayTimeDurationCast(e)
-            | Self::StringCast(e) => e.lookup_used_variables(callback),
+            | Self::StringCast(e)
+            | Self::Exp(e)
+            | Self::Exp10(e)
+            | Self::Log(e)
+            | Self::Log10(e)
+            | Self::Sqrt(e)
+            | Self::Sin(e)
+            | Self::Cos(e)
+            | Self::Tan(e)
+            | Self::Asin(e)
+            | Self::Acos(e)
+            | Self::Atan(e)
+            | Self::NormalizeSpace(e)
+            | Self::AdjustDateTimeToTimezone(e, None) => e.lookup_used_variables(callback),
             Self::Or(a, b)
             | Self::And(a, b)
             | Self::Equal(a, b)
@@ -540,7 +592,11 @@ impl PlanExpression {
             | Self::StrDt(a, b)
             | Self::SameTerm(a, b)
             | Self::SubStr(a, b, None)
-            | Self::Regex(a, b, None) => {
+            | Self::Regex(a, b, None)
+            | Self::Pow(a, b)
+            | Self::Atan2(a, b)
+            | Self::FormatNumber(a, b)
+            | Self::AdjustDateTimeToTimezone(a, Some(b)) => {
                 a.lookup_used_variables(callback);
                 b.lookup_used_variables(callback);
             }
@@ -564,6 +620,12 @@ impl PlanExpression {
                     e.lookup_used_variables(callback);
                 }
             }
+            Self::StringJoin(es, separator) => {
+                for e in es {
+                    e.lookup_used_variables(callback);
+                }
+                separator.lookup_used_variables(callback);
+            }
             Self::Exists(e) => {
                 e.lookup_used_variables(callback);
             }
@@ -587,6 +649,7 @@ pub enum PlanAggregationFunction {
     Avg,
     Sample,
     GroupConcat { separator: Rc<String> },
+    Custom(NamedNode),
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
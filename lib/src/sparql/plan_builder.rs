@@ -1,20 +1,28 @@
-use crate::model::Term as OxTerm;
 use crate::sparql::dataset::DatasetView;
 use crate::sparql::error::EvaluationError;
+use crate::sparql::function::CustomFunction;
 use crate::sparql::plan::*;
+use crate::sparql::AggregateAccumulator;
 use crate::storage::numeric_encoder::{EncodedTerm, EncodedTriple};
+use crate::xsd::IntegerSubtype;
 use oxrdf::vocab::xsd;
-use oxrdf::TermRef;
+use oxrdf::{LiteralRef, TermRef};
 use rand::random;
 use spargebra::algebra::*;
 use spargebra::term::*;
 use std::collections::{BTreeSet, HashMap, HashSet};
-use std::mem::swap;
+use std::mem::{replace, swap};
+use std::ptr;
 use std::rc::Rc;
 
+const MATH_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions/math#";
+const FN_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions#";
+
 pub struct PlanBuilder<'a> {
     dataset: &'a DatasetView,
-    custom_functions: &'a HashMap<NamedNode, Rc<dyn Fn(&[OxTerm]) -> Option<OxTerm>>>,
+    custom_functions: &'a HashMap<NamedNode, CustomFunction>,
+    custom_aggregates: &'a HashMap<NamedNode, Rc<dyn Fn() -> Box<dyn AggregateAccumulator>>>,
+    parameters_scope: Option<*const GraphPattern>,
 }
 
 impl<'a> PlanBuilder<'a> {
@@ -22,12 +30,48 @@ impl<'a> PlanBuilder<'a> {
         dataset: &'a DatasetView,
         pattern: &GraphPattern,
         is_cardinality_meaningful: bool,
-        custom_functions: &'a HashMap<NamedNode, Rc<dyn Fn(&[OxTerm]) -> Option<OxTerm>>>,
+        custom_functions: &'a HashMap<NamedNode, CustomFunction>,
+        custom_aggregates: &'a HashMap<NamedNode, Rc<dyn Fn() -> Box<dyn AggregateAccumulator>>>,
+    ) -> Result<(PlanNode, Vec<Variable>), EvaluationError> {
+        Self::build_with_parameters(
+            dataset,
+            pattern,
+            is_cardinality_meaningful,
+            custom_functions,
+            custom_aggregates,
+            false,
+        )
+    }
+
+    /// Builds a plan and, if `with_parameters` is set, adds to it a [`PlanNode::Parameters`] node
+    /// binding the query parameters in the scope of the query `WHERE` clause
+    pub fn build_with_parameters(
+        dataset: &'a DatasetView,
+        pattern: &GraphPattern,
+        is_cardinality_meaningful: bool,
+        custom_functions: &'a HashMap<NamedNode, CustomFunction>,
+        custom_aggregates: &'a HashMap<NamedNode, Rc<dyn Fn() -> Box<dyn AggregateAccumulator>>>,
+        with_parameters: bool,
     ) -> Result<(PlanNode, Vec<Variable>), EvaluationError> {
+        let lifted_pattern;
+        let pattern = if custom_aggregates.is_empty() {
+            pattern
+        } else {
+            let mut pattern = pattern.clone();
+            lift_custom_aggregates(&mut pattern, &|name| custom_aggregates.contains_key(name));
+            lifted_pattern = pattern;
+            &lifted_pattern
+        };
         let mut variables = Vec::default();
         let plan = PlanBuilder {
             dataset,
             custom_functions,
+            custom_aggregates,
+            parameters_scope: if with_parameters {
+                Some(parameters_scope(pattern) as *const _)
+            } else {
+                None
+            },
         }
         .build_for_graph_pattern(
             pattern,
@@ -50,11 +94,14 @@ impl<'a> PlanBuilder<'a> {
         dataset: &'a DatasetView,
         template: &[TriplePattern],
         mut variables: Vec<Variable>,
-        custom_functions: &'a HashMap<NamedNode, Rc<dyn Fn(&[OxTerm]) -> Option<OxTerm>>>,
+        custom_functions: &'a HashMap<NamedNode, CustomFunction>,
+        custom_aggregates: &'a HashMap<NamedNode, Rc<dyn Fn() -> Box<dyn AggregateAccumulator>>>,
     ) -> Vec<TripleTemplate> {
         PlanBuilder {
             dataset,
             custom_functions,
+            custom_aggregates,
+            parameters_scope: None,
         }
         .build_for_graph_template(template, &mut variables)
     }
@@ -65,6 +112,25 @@ impl<'a> PlanBuilder<'a> {
         variables: &mut Vec<Variable>,
         graph_name: &PatternValue,
     ) -> Result<PlanNode, EvaluationError> {
+        if self
+            .parameters_scope
+            .map_or(false, |scope| ptr::eq(scope, pattern))
+        {
+            self.parameters_scope = None;
+            let child = self.build_for_graph_pattern(pattern, variables, graph_name)?;
+            let mut assigned = Vec::new();
+            lookup_assigned_variables(pattern, &mut |variable| {
+                let key = variable_key(variables, variable);
+                if !assigned.contains(&key) {
+                    assigned.push(key);
+                }
+            });
+            return Ok(PlanNode::Parameters {
+                child: Box::new(child),
+                variables: Rc::new(variables.clone()),
+                assigned: Rc::new(assigned),
+            });
+        }
         Ok(match pattern {
             GraphPattern::Bgp { patterns } => sort_bgp(patterns)
                 .iter()
@@ -705,6 +771,22 @@ impl<'a> PlanBuilder<'a> {
                             graph_name,
                             "dateTime",
                         )?
+                    } else if name.as_ref() == xsd::DATE_TIME_STAMP {
+                        self.build_cast(
+                            parameters,
+                            PlanExpression::DateTimeStampCast,
+                            variables,
+                            graph_name,
+                            "dateTimeStamp",
+                        )?
+                    } else if let Some(subtype) = IntegerSubtype::from_iri(name.as_str()) {
+                        self.build_cast(
+                            parameters,
+                            |e| PlanExpression::IntegerSubtypeCast(e, subtype),
+                            variables,
+                            graph_name,
+                            subtype.name(),
+                        )?
                     } else if name.as_ref() == xsd::DURATION {
                         self.build_cast(
                             parameters,
@@ -737,6 +819,10 @@ impl<'a> PlanBuilder<'a> {
                             graph_name,
                             "string",
                         )?
+                    } else if let Some(function) =
+                        self.build_for_extension_function(name, parameters, variables, graph_name)?
+                    {
+                        function
                     } else {
                         return Err(EvaluationError::msg(format!(
                             "Not supported custom function {}",
@@ -787,6 +873,86 @@ impl<'a> PlanBuilder<'a> {
         }
     }
 
+    /// Builds the functions of the [XPath and XQuery Functions and Operators](https://www.w3.org/TR/xpath-functions/) library that are not SPARQL built-ins
+    fn build_for_extension_function(
+        &mut self,
+        name: &NamedNode,
+        parameters: &[Expression],
+        variables: &mut Vec<Variable>,
+        graph_name: &PatternValue,
+    ) -> Result<Option<PlanExpression>, EvaluationError> {
+        let (local_name, min_arity, max_arity) =
+            if let Some(local_name) = name.as_str().strip_prefix(MATH_NAMESPACE) {
+                match local_name {
+                    "pi" => (local_name, 0, 0),
+                    "exp" | "exp10" | "log" | "log10" | "sqrt" | "sin" | "cos" | "tan" | "asin"
+                    | "acos" | "atan" => (local_name, 1, 1),
+                    "pow" | "atan2" => (local_name, 2, 2),
+                    _ => return Ok(None),
+                }
+            } else if let Some(local_name) = name.as_str().strip_prefix(FN_NAMESPACE) {
+                match local_name {
+                    "normalize-space" => (local_name, 1, 1),
+                    "format-number" => (local_name, 2, 2),
+                    "adjust-dateTime-to-timezone" => (local_name, 1, 2),
+                    "string-join" => (local_name, 1, usize::MAX),
+                    _ => return Ok(None),
+                }
+            } else {
+                return Ok(None);
+            };
+        if parameters.len() < min_arity || parameters.len() > max_arity {
+            return Err(EvaluationError::msg(format!(
+                "The function {} does not take {} parameters",
+                name,
+                parameters.len()
+            )));
+        }
+        let mut parameters = parameters
+            .iter()
+            .map(|p| {
+                Ok(Box::new(
+                    self.build_for_expression(p, variables, graph_name)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, EvaluationError>>()?
+            .into_iter();
+        let mut next = || parameters.next().unwrap();
+        Ok(Some(match local_name {
+            "pi" => PlanExpression::Constant(std::f64::consts::PI.into()),
+            "exp" => PlanExpression::Exp(next()),
+            "exp10" => PlanExpression::Exp10(next()),
+            "log" => PlanExpression::Log(next()),
+            "log10" => PlanExpression::Log10(next()),
+            "pow" => PlanExpression::Pow(next(), next()),
+            "sqrt" => PlanExpression::Sqrt(next()),
+            "sin" => PlanExpression::Sin(next()),
+            "cos" => PlanExpression::Cos(next()),
+            "tan" => PlanExpression::Tan(next()),
+            "asin" => PlanExpression::Asin(next()),
+            "acos" => PlanExpression::Acos(next()),
+            "atan" => PlanExpression::Atan(next()),
+            "atan2" => PlanExpression::Atan2(next(), next()),
+            "normalize-space" => PlanExpression::NormalizeSpace(next()),
+            "format-number" => PlanExpression::FormatNumber(next(), next()),
+            "adjust-dateTime-to-timezone" => {
+                PlanExpression::AdjustDateTimeToTimezone(next(), parameters.next())
+            }
+            "string-join" => {
+                // SPARQL has no sequences: the values are the parameters but the last one that is the separator.
+                // With a single parameter it is the only value and the separator is empty like in fn:string-join($arg1).
+                let mut values = parameters.map(|p| *p).collect::<Vec<_>>();
+                let separator = if values.len() == 1 {
+                    PlanExpression::Constant(self.build_term(LiteralRef::new_simple_literal("")))
+                } else {
+                    *values.pop().unwrap()
+                };
+                PlanExpression::StringJoin(values, Box::new(separator))
+            }
+            _ => return Ok(None),
+        }))
+    }
+
     fn expression_list(
         &mut self,
         l: &[Expression],
@@ -942,9 +1108,24 @@ impl<'a> PlanBuilder<'a> {
                 parameter: Some(self.build_for_expression(expr, variables, graph_name)?),
                 distinct: *distinct,
             }),
-            AggregateExpression::Custom { .. } => Err(EvaluationError::msg(
-                "Custom aggregation functions are not supported yet",
-            )),
+            AggregateExpression::Custom {
+                name,
+                expr,
+                distinct,
+            } => {
+                if self.custom_aggregates.contains_key(name) {
+                    Ok(PlanAggregation {
+                        function: PlanAggregationFunction::Custom(name.clone()),
+                        parameter: Some(self.build_for_expression(expr, variables, graph_name)?),
+                        distinct: *distinct,
+                    })
+                } else {
+                    Err(EvaluationError::msg(format!(
+                        "Not supported custom aggregate function: {}",
+                        name
+                    )))
+                }
+            }
         }
     }
 
@@ -1086,7 +1267,9 @@ impl<'a> PlanBuilder<'a> {
                     self.add_left_join_problematic_variables(child, set);
                 }
             }
-            PlanNode::HashJoin { left, right } | PlanNode::ForLoopJoin { left, right } => {
+            PlanNode::HashJoin { left, right }
+            | PlanNode::ForLoopJoin { left, right }
+            | PlanNode::ServiceJoin { left, right } => {
                 self.add_left_join_problematic_variables(&*left, set);
                 self.add_left_join_problematic_variables(&*right, set);
             }
@@ -1115,7 +1298,8 @@ impl<'a> PlanBuilder<'a> {
             | PlanNode::HashDeduplicate { child }
             | PlanNode::Reduced { child }
             | PlanNode::Skip { child, .. }
-            | PlanNode::Limit { child, .. } => {
+            | PlanNode::Limit { child, .. }
+            | PlanNode::Parameters { child, .. } => {
                 self.add_left_join_problematic_variables(&*child, set)
             }
             PlanNode::Service { child, silent, .. } => {
@@ -1151,6 +1335,17 @@ impl<'a> PlanBuilder<'a> {
     }
 
     fn new_join(mut left: PlanNode, mut right: PlanNode) -> PlanNode {
+        if Self::is_bind_joinable_service(&left) && !Self::is_bind_joinable_service(&right) {
+            swap(&mut left, &mut right);
+        }
+        if Self::is_bind_joinable_service(&right) && Self::has_some_common_variables(&left, &right)
+        {
+            // We send the left bindings to the service to avoid fetching all its results
+            return PlanNode::ServiceJoin {
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
         if Self::is_fit_for_for_loop_join(&left)
             && Self::is_fit_for_for_loop_join(&right)
             && Self::has_some_common_variables(&left, &right)
@@ -1176,6 +1371,16 @@ impl<'a> PlanBuilder<'a> {
         }
     }
 
+    fn is_bind_joinable_service(node: &PlanNode) -> bool {
+        matches!(
+            node,
+            PlanNode::Service {
+                service_name: PatternValue::Constant(_),
+                ..
+            }
+        )
+    }
+
     fn has_some_common_variables(left: &PlanNode, right: &PlanNode) -> bool {
         left.always_bound_variables()
             .intersection(&right.always_bound_variables())
@@ -1200,13 +1405,15 @@ impl<'a> PlanBuilder<'a> {
             PlanNode::AntiJoin { .. }
             | PlanNode::LeftJoin { .. }
             | PlanNode::Service { .. }
+            | PlanNode::ServiceJoin { .. }
             | PlanNode::Sort { .. }
             | PlanNode::HashDeduplicate { .. }
             | PlanNode::Reduced { .. }
             | PlanNode::Skip { .. }
             | PlanNode::Limit { .. }
             | PlanNode::Project { .. }
-            | PlanNode::Aggregate { .. } => false,
+            | PlanNode::Aggregate { .. }
+            | PlanNode::Parameters { .. } => false,
         }
     }
 
@@ -1244,6 +1451,19 @@ impl<'a> PlanBuilder<'a> {
                     }
                 }
             }
+            PlanNode::ServiceJoin { left, right } => {
+                if filter_variables.iter().all(|v| left.is_variable_bound(*v)) {
+                    PlanNode::ServiceJoin {
+                        left: Box::new(Self::push_filter(left, filter)),
+                        right,
+                    }
+                } else {
+                    PlanNode::Filter {
+                        child: Box::new(PlanNode::ServiceJoin { left, right }),
+                        expression: filter,
+                    }
+                }
+            }
             PlanNode::ForLoopJoin { left, right } => {
                 if filter_variables.iter().all(|v| left.is_variable_bound(*v)) {
                     PlanNode::ForLoopJoin {
@@ -1334,6 +1554,252 @@ impl<'a> PlanBuilder<'a> {
     }
 }
 
+/// Returns the pattern in which the query parameters should be bound, i.e. the query `WHERE` clause
+///
+/// It is the input of the outer projection, or the input of the grouping if the query is an aggregation.
+fn parameters_scope(pattern: &GraphPattern) -> &GraphPattern {
+    let mut pattern = pattern;
+    while let GraphPattern::Slice { inner, .. }
+    | GraphPattern::Distinct { inner }
+    | GraphPattern::Reduced { inner } = pattern
+    {
+        pattern = inner;
+    }
+    let scope = if let GraphPattern::Project { inner, .. } = pattern {
+        inner
+    } else {
+        return pattern;
+    };
+    pattern = scope;
+    while let GraphPattern::OrderBy { inner, .. }
+    | GraphPattern::Filter { inner, .. }
+    | GraphPattern::Extend { inner, .. } = pattern
+    {
+        pattern = inner;
+    }
+    if let GraphPattern::Group { inner, .. } = pattern {
+        inner
+    } else {
+        scope
+    }
+}
+
+/// Calls `callback` on the variables assigned by a `BIND` or a `SELECT` expression in the scope of the pattern
+///
+/// Sub-queries and `SERVICE` calls have their own scope and are not visited.
+fn lookup_assigned_variables<'a>(
+    pattern: &'a GraphPattern,
+    callback: &mut impl FnMut(&'a Variable),
+) {
+    match pattern {
+        GraphPattern::Join { left, right }
+        | GraphPattern::LeftJoin { left, right, .. }
+        | GraphPattern::Union { left, right }
+        | GraphPattern::Minus { left, right } => {
+            lookup_assigned_variables(left, callback);
+            lookup_assigned_variables(right, callback);
+        }
+        GraphPattern::Extend {
+            inner, variable, ..
+        } => {
+            callback(variable);
+            lookup_assigned_variables(inner, callback);
+        }
+        GraphPattern::Filter { inner, .. }
+        | GraphPattern::Graph { inner, .. }
+        | GraphPattern::OrderBy { inner, .. }
+        | GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner }
+        | GraphPattern::Slice { inner, .. }
+        | GraphPattern::Group { inner, .. } => lookup_assigned_variables(inner, callback),
+        GraphPattern::Project { .. }
+        | GraphPattern::Service { .. }
+        | GraphPattern::Bgp { .. }
+        | GraphPattern::Path { .. }
+        | GraphPattern::Values { .. } => (),
+    }
+}
+
+/// Turns the calls to custom aggregate functions in the `SELECT`, `HAVING` and `ORDER BY` clauses into aggregations
+///
+/// Without the `DISTINCT` keyword or an unbound argument the parser is not able to make the difference between custom functions and custom aggregates.
+fn lift_custom_aggregates(pattern: &mut GraphPattern, is_aggregate: &impl Fn(&NamedNode) -> bool) {
+    match pattern {
+        GraphPattern::Join { left, right }
+        | GraphPattern::LeftJoin { left, right, .. }
+        | GraphPattern::Union { left, right }
+        | GraphPattern::Minus { left, right } => {
+            lift_custom_aggregates(left, is_aggregate);
+            lift_custom_aggregates(right, is_aggregate);
+        }
+        GraphPattern::Filter { inner, .. }
+        | GraphPattern::Graph { inner, .. }
+        | GraphPattern::Extend { inner, .. }
+        | GraphPattern::OrderBy { inner, .. }
+        | GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner }
+        | GraphPattern::Slice { inner, .. }
+        | GraphPattern::Group { inner, .. }
+        | GraphPattern::Service { inner, .. } => lift_custom_aggregates(inner, is_aggregate),
+        GraphPattern::Project { inner, variables } => {
+            lift_custom_aggregates(inner, is_aggregate);
+            lift_projection_custom_aggregates(inner, variables, is_aggregate);
+        }
+        GraphPattern::Bgp { .. } | GraphPattern::Path { .. } | GraphPattern::Values { .. } => (),
+    }
+}
+
+/// Lifts the custom aggregates from the nodes built by the parser above the `WHERE` clause
+///
+/// They are the `ORDER BY`, the `SELECT` expressions, whose variables are always projected, and the `HAVING` filter on a grouping.
+/// The lifting stops at the first node that might come from the `WHERE` clause like a `BIND` of a not projected variable.
+fn lift_projection_custom_aggregates(
+    pattern: &mut GraphPattern,
+    projection: &[Variable],
+    is_aggregate: &impl Fn(&NamedNode) -> bool,
+) {
+    let mut aggregates = Vec::new();
+    let mut lowest_depth_with_aggregate = None;
+    let mut depth = 0;
+    let mut current = &mut *pattern;
+    loop {
+        let aggregates_count = aggregates.len();
+        match current {
+            GraphPattern::OrderBy { expression, .. } => {
+                for expression in expression {
+                    let (OrderExpression::Asc(expression) | OrderExpression::Desc(expression)) =
+                        expression;
+                    lift_expression_custom_aggregates(expression, is_aggregate, &mut aggregates);
+                }
+            }
+            GraphPattern::Extend {
+                expression,
+                variable,
+                ..
+            } if projection.contains(variable) => {
+                lift_expression_custom_aggregates(expression, is_aggregate, &mut aggregates)
+            }
+            GraphPattern::Filter { expr, inner }
+                if matches!(**inner, GraphPattern::Group { .. }) =>
+            {
+                lift_expression_custom_aggregates(expr, is_aggregate, &mut aggregates)
+            }
+            GraphPattern::Group {
+                aggregates: group_aggregates,
+                ..
+            } => {
+                group_aggregates.extend(aggregates);
+                return;
+            }
+            _ => break,
+        }
+        if aggregates.len() > aggregates_count {
+            lowest_depth_with_aggregate = Some(depth);
+        }
+        current = if let Some(inner) = projection_modifier_inner(current) {
+            inner
+        } else {
+            break;
+        };
+        depth += 1;
+    }
+    // There is no GROUP BY: we add an implicit group just below the lowest aggregate call
+    if let Some(lowest_depth_with_aggregate) = lowest_depth_with_aggregate {
+        let mut current = pattern;
+        for _ in 0..lowest_depth_with_aggregate {
+            if let Some(inner) = projection_modifier_inner(current) {
+                current = inner;
+            } else {
+                return;
+            }
+        }
+        if let Some(inner) = projection_modifier_inner(current) {
+            let group_inner = replace(
+                inner,
+                GraphPattern::Bgp {
+                    patterns: Vec::new(),
+                },
+            );
+            *inner = GraphPattern::Group {
+                inner: Box::new(group_inner),
+                variables: Vec::new(),
+                aggregates,
+            };
+        }
+    }
+}
+
+fn projection_modifier_inner(pattern: &mut GraphPattern) -> Option<&mut GraphPattern> {
+    match pattern {
+        GraphPattern::OrderBy { inner, .. }
+        | GraphPattern::Extend { inner, .. }
+        | GraphPattern::Filter { inner, .. } => Some(inner.as_mut()),
+        _ => None,
+    }
+}
+
+fn lift_expression_custom_aggregates(
+    expression: &mut Expression,
+    is_aggregate: &impl Fn(&NamedNode) -> bool,
+    aggregates: &mut Vec<(Variable, AggregateExpression)>,
+) {
+    match expression {
+        Expression::FunctionCall(Function::Custom(name), parameters)
+            if parameters.len() == 1 && is_aggregate(name) =>
+        {
+            let variable = Variable::new_unchecked(format!("{:x}", random::<u128>()));
+            aggregates.push((
+                variable.clone(),
+                AggregateExpression::Custom {
+                    name: name.clone(),
+                    expr: Box::new(parameters.remove(0)),
+                    distinct: false,
+                },
+            ));
+            *expression = Expression::Variable(variable);
+        }
+        Expression::Or(a, b)
+        | Expression::And(a, b)
+        | Expression::Equal(a, b)
+        | Expression::SameTerm(a, b)
+        | Expression::Greater(a, b)
+        | Expression::GreaterOrEqual(a, b)
+        | Expression::Less(a, b)
+        | Expression::LessOrEqual(a, b)
+        | Expression::Add(a, b)
+        | Expression::Subtract(a, b)
+        | Expression::Multiply(a, b)
+        | Expression::Divide(a, b) => {
+            lift_expression_custom_aggregates(a, is_aggregate, aggregates);
+            lift_expression_custom_aggregates(b, is_aggregate, aggregates);
+        }
+        Expression::UnaryPlus(e) | Expression::UnaryMinus(e) | Expression::Not(e) => {
+            lift_expression_custom_aggregates(e, is_aggregate, aggregates)
+        }
+        Expression::If(a, b, c) => {
+            lift_expression_custom_aggregates(a, is_aggregate, aggregates);
+            lift_expression_custom_aggregates(b, is_aggregate, aggregates);
+            lift_expression_custom_aggregates(c, is_aggregate, aggregates);
+        }
+        Expression::In(a, b) => {
+            lift_expression_custom_aggregates(a, is_aggregate, aggregates);
+            for b in b {
+                lift_expression_custom_aggregates(b, is_aggregate, aggregates);
+            }
+        }
+        Expression::Coalesce(parameters) | Expression::FunctionCall(_, parameters) => {
+            for parameter in parameters {
+                lift_expression_custom_aggregates(parameter, is_aggregate, aggregates);
+            }
+        }
+        Expression::NamedNode(_)
+        | Expression::Literal(_)
+        | Expression::Variable(_)
+        | Expression::Bound(_)
+        | Expression::Exists(_) => (),
+    }
+}
+
 fn variable_key(variables: &mut Vec<Variable>, variable: &Variable) -> usize {
     match slice_key(variables, variable) {
         Some(key) => key,
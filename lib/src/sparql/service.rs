@@ -1,11 +1,17 @@
-use crate::model::NamedNode;
+use crate::model::{Dataset, NamedNode};
 use crate::sparql::algebra::Query;
 use crate::sparql::error::EvaluationError;
 use crate::sparql::http::Client;
 use crate::sparql::model::QueryResults;
-use crate::sparql::QueryResultsFormat;
+use crate::sparql::{QueryOptions, QueryResultsFormat};
+use crate::storage::StorageError;
+use crate::store::Store;
+use std::collections::HashMap;
 use std::error::Error;
-use std::io::BufReader;
+use std::io::{self, BufReader, Cursor, Read};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 
 /// Handler for [SPARQL 1.1 Federated Query](https://www.w3.org/TR/sparql11-federated-query/) SERVICE.
@@ -54,6 +60,24 @@ pub trait ServiceHandler {
 
     /// Evaluates a [`Query`] against a given service identified by a [`NamedNode`](crate::model::NamedNode).
     fn handle(&self, service_name: NamedNode, query: Query) -> Result<QueryResults, Self::Error>;
+
+    /// Evaluates multiple [`Query`] against a given service identified by a [`NamedNode`](crate::model::NamedNode).
+    ///
+    /// It is used to send the batches of bindings of a bind join if more than one concurrent request is allowed
+    /// (see [`QueryOptions::with_service_concurrency`](super::QueryOptions::with_service_concurrency)).
+    /// The results must be returned in the same order as the queries.
+    ///
+    /// By default the queries are evaluated one after the other using [`handle`](Self::handle).
+    fn handle_all(
+        &self,
+        service_name: NamedNode,
+        queries: Vec<Query>,
+    ) -> Vec<Result<QueryResults, Self::Error>> {
+        queries
+            .into_iter()
+            .map(|query| self.handle(service_name.clone(), query))
+            .collect()
+    }
 }
 
 pub struct EmptyServiceHandler;
@@ -90,16 +114,28 @@ impl<S: ServiceHandler> ServiceHandler for ErrorConversionServiceHandler<S> {
             .handle(service_name, query)
             .map_err(EvaluationError::wrap)
     }
+
+    fn handle_all(
+        &self,
+        service_name: NamedNode,
+        queries: Vec<Query>,
+    ) -> Vec<Result<QueryResults, EvaluationError>> {
+        self.handler
+            .handle_all(service_name, queries)
+            .into_iter()
+            .map(|result| result.map_err(EvaluationError::wrap))
+            .collect()
+    }
 }
 
 pub struct SimpleServiceHandler {
-    client: Client,
+    client: Arc<Client>,
 }
 
 impl SimpleServiceHandler {
     pub fn new(http_timeout: Option<Duration>, http_redirection_limit: usize) -> Self {
         Self {
-            client: Client::new(http_timeout, http_redirection_limit),
+            client: Arc::new(Client::new(http_timeout, http_redirection_limit)),
         }
     }
 }
@@ -116,7 +152,7 @@ impl ServiceHandler for SimpleServiceHandler {
             service_name.as_str(),
             query.to_string().into_bytes(),
             "application/sparql-query",
-            "application/sparql-results+json, application/sparql-results+xml",
+            ACCEPTED_RESULTS_FORMATS,
         )?;
         let format = QueryResultsFormat::from_media_type(&content_type).ok_or_else(|| {
             EvaluationError::msg(format!(
@@ -126,4 +162,166 @@ impl ServiceHandler for SimpleServiceHandler {
         })?;
         Ok(QueryResults::read(BufReader::new(body), format)?)
     }
+
+    fn handle_all(
+        &self,
+        service_name: NamedNode,
+        queries: Vec<Query>,
+    ) -> Vec<Result<QueryResults, EvaluationError>> {
+        if queries.len() <= 1 {
+            return queries
+                .into_iter()
+                .map(|query| self.handle(service_name.clone(), query))
+                .collect();
+        }
+        // The requests are done in parallel threads and the responses are read in memory
+        let threads = queries
+            .into_iter()
+            .map(|query| {
+                let client = self.client.clone();
+                let url = service_name.as_str().to_owned();
+                let payload = query.to_string().into_bytes();
+                thread::spawn(move || -> io::Result<(String, Vec<u8>)> {
+                    let (content_type, mut body) = client.post(
+                        &url,
+                        payload,
+                        "application/sparql-query",
+                        ACCEPTED_RESULTS_FORMATS,
+                    )?;
+                    let mut buffer = Vec::new();
+                    body.read_to_end(&mut buffer)?;
+                    Ok((content_type, buffer))
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .map(|thread| {
+                let (content_type, body) = thread.join().map_err(|_| {
+                    EvaluationError::msg("The thread executing the service request panicked")
+                })??;
+                let format =
+                    QueryResultsFormat::from_media_type(&content_type).ok_or_else(|| {
+                        EvaluationError::msg(format!(
+                            "Unsupported Content-Type returned by {}: {}",
+                            service_name, content_type
+                        ))
+                    })?;
+                Ok(QueryResults::read(Cursor::new(body), format)?)
+            })
+            .collect()
+    }
+}
+
+const ACCEPTED_RESULTS_FORMATS: &str = "application/sparql-results+json, application/sparql-results+xml, text/tab-separated-values;q=0.8, text/csv;q=0.5";
+
+/// A [`ServiceHandler`] evaluating the `SERVICE` calls against other local [`Store`]s.
+///
+/// The queries are evaluated in-process without any HTTP request.
+/// The `SERVICE` calls to IRIs that are not registered are given to a fallback handler,
+/// by default the one used by [`QueryOptions`] (HTTP requests if the `http_client` feature is enabled).
+///
+/// ```
+/// use oxigraph::store::Store;
+/// use oxigraph::model::*;
+/// use oxigraph::sparql::{QueryOptions, QueryResults, StoreServiceHandler};
+///
+/// let vocabulary = Store::new()?;
+/// let ex = NamedNodeRef::new("http://example.com")?;
+/// vocabulary.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+///
+/// let store = Store::new()?;
+/// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+///     "SELECT ?s WHERE { SERVICE <http://example.com/vocabulary> { ?s ?p ?o } }",
+///     QueryOptions::default().with_service_handler(
+///         StoreServiceHandler::new().with_store(NamedNode::new("http://example.com/vocabulary")?, vocabulary)
+///     )
+/// )? {
+///     assert_eq!(solutions.next().unwrap()?.get("s"), Some(&ex.into()));
+/// }
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Clone)]
+pub struct StoreServiceHandler {
+    stores: HashMap<NamedNode, Store>,
+    fallback: Rc<dyn ServiceHandler<Error = EvaluationError>>,
+}
+
+impl StoreServiceHandler {
+    /// Builds a handler without any registered store.
+    pub fn new() -> Self {
+        Self {
+            stores: HashMap::new(),
+            fallback: QueryOptions::default().service_handler(),
+        }
+    }
+
+    /// Registers a [`Store`] as the service identified by `name`.
+    #[must_use]
+    pub fn with_store(mut self, name: NamedNode, store: Store) -> Self {
+        self.stores.insert(name, store);
+        self
+    }
+
+    /// Registers a [`Dataset`] as the service identified by `name`.
+    ///
+    /// The dataset is copied into an in-memory [`Store`].
+    pub fn with_dataset(
+        mut self,
+        name: NamedNode,
+        dataset: &Dataset,
+    ) -> Result<Self, StorageError> {
+        let store = Store::new()?;
+        store.extend(dataset.iter())?;
+        self.stores.insert(name, store);
+        Ok(self)
+    }
+
+    /// Sets the [`ServiceHandler`] used for the services that are not registered.
+    #[must_use]
+    pub fn with_fallback(mut self, fallback: impl ServiceHandler + 'static) -> Self {
+        self.fallback = Rc::new(ErrorConversionServiceHandler::wrap(fallback));
+        self
+    }
+}
+
+impl Default for StoreServiceHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServiceHandler for StoreServiceHandler {
+    type Error = EvaluationError;
+
+    fn handle(
+        &self,
+        service_name: NamedNode,
+        query: Query,
+    ) -> Result<QueryResults, EvaluationError> {
+        if let Some(store) = self.stores.get(&service_name) {
+            // The services might themselves call the other services
+            store.query_opt(
+                query,
+                QueryOptions::default().with_service_handler(self.clone()),
+            )
+        } else {
+            self.fallback.handle(service_name, query)
+        }
+    }
+
+    fn handle_all(
+        &self,
+        service_name: NamedNode,
+        queries: Vec<Query>,
+    ) -> Vec<Result<QueryResults, EvaluationError>> {
+        if self.stores.contains_key(&service_name) {
+            queries
+                .into_iter()
+                .map(|query| self.handle(service_name.clone(), query))
+                .collect()
+        } else {
+            self.fallback.handle_all(service_name, queries)
+        }
+    }
 }
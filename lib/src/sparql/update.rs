@@ -123,15 +123,20 @@ impl<'a, 'b: 'a> SimpleUpdateEvaluator<'a, 'b> {
             algebra,
             false,
             &self.options.query_options.custom_functions,
+            &self.options.query_options.custom_aggregates,
         )?;
+        let (service_batch_size, service_concurrency) =
+            self.options.query_options.service_batching();
         let evaluator = SimpleEvaluator::new(
             dataset.clone(),
             self.base_iri.clone(),
             self.options.query_options.service_handler(),
             Rc::new(self.options.query_options.custom_functions.clone()),
-        );
+            Rc::new(self.options.query_options.custom_aggregates.clone()),
+        )
+        .with_service_batching(service_batch_size, service_concurrency);
         let mut bnodes = HashMap::new();
-        for tuple in evaluator.plan_evaluator(&plan)(EncodedTuple::with_capacity(variables.len())) {
+        for tuple in evaluator.evaluate_plan(&plan, EncodedTuple::with_capacity(variables.len())) {
             let tuple = tuple?;
             for quad in delete {
                 if let Some(quad) =
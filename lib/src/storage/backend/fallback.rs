@@ -233,6 +233,17 @@ impl Transaction<'_> {
         })))
     }
 
+    pub fn get_for_update(
+        &self,
+        column_family: &ColumnFamily,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok((*self.0)
+            .borrow()
+            .get(column_family)
+            .and_then(|cf| cf.get(key).cloned()))
+    }
+
     pub fn contains_key_for_update(
         &self,
         column_family: &ColumnFamily,
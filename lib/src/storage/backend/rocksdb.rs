@@ -3,6 +3,7 @@
 #![allow(unsafe_code, trivial_casts)]
 
 use crate::storage::error::StorageError;
+use crate::storage::StorageOptions;
 use crate::store::CorruptionError;
 use lazy_static::lazy_static;
 use libc::{self, c_char, c_void, free};
@@ -138,16 +139,23 @@ impl Db {
             temp_dir()
         }
         .join(format!("oxigraph-rocksdb-{}", random::<u128>()));
-        Ok(Self(Arc::new(Self::do_open(path, column_families, true)?)))
+        Ok(Self(Arc::new(Self::do_open(
+            path,
+            column_families,
+            &StorageOptions::default(),
+            true,
+        )?)))
     }
 
     pub fn open(
         path: &Path,
         column_families: Vec<ColumnFamilyDefinition>,
+        options: &StorageOptions,
     ) -> Result<Self, StorageError> {
         Ok(Self(Arc::new(Self::do_open(
             path.to_owned(),
             column_families,
+            options,
             false,
         )?)))
     }
@@ -155,6 +163,7 @@ impl Db {
     fn do_open(
         path: PathBuf,
         mut column_families: Vec<ColumnFamilyDefinition>,
+        storage_options: &StorageOptions,
         in_memory: bool,
     ) -> Result<DbHandler, StorageError> {
         let c_path = path_to_cstring(&path)?;
@@ -181,14 +190,27 @@ impl Db {
                     )
                     .into());
                 }
+                let max_open_files = available_fd - 48;
                 rocksdb_options_set_max_open_files(
                     options,
-                    (available_fd - 48).try_into().unwrap(),
+                    storage_options
+                        .max_open_files
+                        .map_or(max_open_files, |m| min(m.into(), max_open_files))
+                        .try_into()
+                        .unwrap(),
                 );
+            } else if let Some(max_open_files) = storage_options.max_open_files {
+                rocksdb_options_set_max_open_files(options, max_open_files.try_into().unwrap());
+            }
+            if let Some(write_buffer_size) = storage_options.write_buffer_size {
+                rocksdb_options_set_write_buffer_size(options, write_buffer_size);
             }
             rocksdb_options_set_info_log_level(options, 2); // We only log warnings
             rocksdb_options_set_max_log_file_size(options, 1024 * 1024); // Only 1MB log size
             rocksdb_options_set_recycle_log_file_num(options, 10); // We do not keep more than 10 log files
+            if storage_options.statistics {
+                rocksdb_options_enable_statistics(options); // Used by the block cache metrics
+            }
             rocksdb_options_set_compression(
                 options,
                 if in_memory {
@@ -217,6 +239,15 @@ impl Db {
                 block_based_table_options,
                 16,
             );
+            if let Some(block_cache_size) = storage_options.block_cache_size {
+                let block_cache = rocksdb_cache_create_lru(block_cache_size);
+                assert!(
+                    !block_cache.is_null(),
+                    "rocksdb_cache_create_lru returned null"
+                );
+                rocksdb_block_based_options_set_block_cache(block_based_table_options, block_cache);
+                rocksdb_cache_destroy(block_cache); // The table options keep their own reference
+            }
             rocksdb_options_set_block_based_table_factory(options, block_based_table_options);
 
             let transactiondb_options = rocksdb_transactiondb_options_create();
@@ -600,6 +631,39 @@ impl Db {
         }
         Ok(())
     }
+
+    /// Returns the value of a RocksDB integer property like `rocksdb.estimate-num-keys` for a column family
+    pub fn int_property(&self, column_family: &ColumnFamily, name: &str) -> Option<u64> {
+        let name = CString::new(name).ok()?;
+        let mut value = 0;
+        if unsafe {
+            rocksdb_transactiondb_property_int_cf(
+                self.0.db,
+                column_family.0,
+                name.as_ptr(),
+                &mut value,
+            )
+        } == 0
+        {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of block cache hits and misses since the database has been opened
+    pub fn block_cache_counts(&self) -> (u64, u64) {
+        let mut hits = 0;
+        let mut misses = 0;
+        unsafe {
+            rocksdb_options_statistics_get_block_cache_counts(
+                self.0.options,
+                &mut hits,
+                &mut misses,
+            );
+        }
+        (hits, misses)
+    }
 }
 
 // It is fine to not keep a lifetime: there is no way to use this type without the database being still in scope.
@@ -8,7 +8,7 @@ use std::mem::size_of;
 use std::rc::Rc;
 
 #[cfg(not(target_arch = "wasm32"))]
-pub const LATEST_STORAGE_VERSION: u64 = 1;
+pub const LATEST_STORAGE_VERSION: u64 = 3;
 pub const WRITTEN_TERM_MAX_SIZE: usize = size_of::<u8>() + 2 * size_of::<StrHash>();
 
 // Encoded term type blocks
@@ -31,6 +31,8 @@ const TYPE_BIG_SMALL_LANG_STRING_LITERAL: u8 = 22;
 const TYPE_BIG_BIG_LANG_STRING_LITERAL: u8 = 23;
 const TYPE_SMALL_TYPED_LITERAL: u8 = 24;
 const TYPE_BIG_TYPED_LITERAL: u8 = 25;
+const TYPE_INTEGER_SUBTYPE_LITERAL: u8 = 26;
+const TYPE_BIG_INTEGER_SUBTYPE_LITERAL: u8 = 27;
 const TYPE_BOOLEAN_LITERAL_TRUE: u8 = 28;
 const TYPE_BOOLEAN_LITERAL_FALSE: u8 = 29;
 const TYPE_FLOAT_LITERAL: u8 = 30;
@@ -48,6 +50,9 @@ const TYPE_G_MONTH_LITERAL: u8 = 41;
 const TYPE_DURATION_LITERAL: u8 = 42;
 const TYPE_YEAR_MONTH_DURATION_LITERAL: u8 = 43;
 const TYPE_DAY_TIME_DURATION_LITERAL: u8 = 44;
+const TYPE_BIG_INTEGER_LITERAL: u8 = 45;
+const TYPE_BIG_DECIMAL_LITERAL: u8 = 46;
+const TYPE_DATE_TIME_STAMP_LITERAL: u8 = 47;
 const TYPE_TRIPLE: u8 = 48;
 
 #[derive(Clone, Copy)]
@@ -331,11 +336,45 @@ impl<R: Read> TermReader for R {
                 self.read_exact(&mut buffer)?;
                 Ok(EncodedTerm::IntegerLiteral(i64::from_be_bytes(buffer)))
             }
+            TYPE_BIG_INTEGER_LITERAL => {
+                let mut buffer = [0; 16];
+                self.read_exact(&mut buffer)?;
+                Ok(EncodedTerm::BigIntegerLiteral {
+                    value_id: StrHash::from_be_bytes(buffer),
+                })
+            }
+            TYPE_INTEGER_SUBTYPE_LITERAL => {
+                let mut subtype_buffer = [0; 1];
+                self.read_exact(&mut subtype_buffer)?;
+                let mut value_buffer = [0; 8];
+                self.read_exact(&mut value_buffer)?;
+                Ok(EncodedTerm::IntegerSubtypeLiteral {
+                    subtype: integer_subtype_from_byte(subtype_buffer[0])?,
+                    value: i64::from_be_bytes(value_buffer),
+                })
+            }
+            TYPE_BIG_INTEGER_SUBTYPE_LITERAL => {
+                let mut subtype_buffer = [0; 1];
+                self.read_exact(&mut subtype_buffer)?;
+                let mut value_buffer = [0; 16];
+                self.read_exact(&mut value_buffer)?;
+                Ok(EncodedTerm::BigIntegerSubtypeLiteral {
+                    subtype: integer_subtype_from_byte(subtype_buffer[0])?,
+                    value_id: StrHash::from_be_bytes(value_buffer),
+                })
+            }
             TYPE_DECIMAL_LITERAL => {
                 let mut buffer = [0; 16];
                 self.read_exact(&mut buffer)?;
                 Ok(EncodedTerm::DecimalLiteral(Decimal::from_be_bytes(buffer)))
             }
+            TYPE_BIG_DECIMAL_LITERAL => {
+                let mut buffer = [0; 16];
+                self.read_exact(&mut buffer)?;
+                Ok(EncodedTerm::BigDecimalLiteral {
+                    value_id: StrHash::from_be_bytes(buffer),
+                })
+            }
             TYPE_DATE_TIME_LITERAL => {
                 let mut buffer = [0; 18];
                 self.read_exact(&mut buffer)?;
@@ -343,6 +382,13 @@ impl<R: Read> TermReader for R {
                     buffer,
                 )))
             }
+            TYPE_DATE_TIME_STAMP_LITERAL => {
+                let mut buffer = [0; 18];
+                self.read_exact(&mut buffer)?;
+                Ok(EncodedTerm::DateTimeStampLiteral(DateTime::from_be_bytes(
+                    buffer,
+                )))
+            }
             TYPE_TIME_LITERAL => {
                 let mut buffer = [0; 18];
                 self.read_exact(&mut buffer)?;
@@ -413,6 +459,45 @@ impl<R: Read> TermReader for R {
     }
 }
 
+fn integer_subtype_from_byte(byte: u8) -> Result<IntegerSubtype, StorageError> {
+    Ok(match byte {
+        0 => IntegerSubtype::NonPositiveInteger,
+        1 => IntegerSubtype::NegativeInteger,
+        2 => IntegerSubtype::Long,
+        3 => IntegerSubtype::Int,
+        4 => IntegerSubtype::Short,
+        5 => IntegerSubtype::Byte,
+        6 => IntegerSubtype::NonNegativeInteger,
+        7 => IntegerSubtype::UnsignedLong,
+        8 => IntegerSubtype::UnsignedInt,
+        9 => IntegerSubtype::UnsignedShort,
+        10 => IntegerSubtype::UnsignedByte,
+        11 => IntegerSubtype::PositiveInteger,
+        _ => {
+            return Err(
+                CorruptionError::msg("the term buffer has an invalid integer subtype").into(),
+            )
+        }
+    })
+}
+
+fn integer_subtype_to_byte(subtype: IntegerSubtype) -> u8 {
+    match subtype {
+        IntegerSubtype::NonPositiveInteger => 0,
+        IntegerSubtype::NegativeInteger => 1,
+        IntegerSubtype::Long => 2,
+        IntegerSubtype::Int => 3,
+        IntegerSubtype::Short => 4,
+        IntegerSubtype::Byte => 5,
+        IntegerSubtype::NonNegativeInteger => 6,
+        IntegerSubtype::UnsignedLong => 7,
+        IntegerSubtype::UnsignedInt => 8,
+        IntegerSubtype::UnsignedShort => 9,
+        IntegerSubtype::UnsignedByte => 10,
+        IntegerSubtype::PositiveInteger => 11,
+    }
+}
+
 pub fn write_spog_quad(sink: &mut Vec<u8>, quad: &EncodedQuad) {
     write_term(sink, &quad.subject);
     write_term(sink, &quad.predicate);
@@ -585,14 +670,36 @@ pub fn write_term(sink: &mut Vec<u8>, term: &EncodedTerm) {
             sink.push(TYPE_INTEGER_LITERAL);
             sink.extend_from_slice(&value.to_be_bytes())
         }
+        EncodedTerm::BigIntegerLiteral { value_id } => {
+            sink.push(TYPE_BIG_INTEGER_LITERAL);
+            sink.extend_from_slice(&value_id.to_be_bytes());
+        }
+        EncodedTerm::IntegerSubtypeLiteral { value, subtype } => {
+            sink.push(TYPE_INTEGER_SUBTYPE_LITERAL);
+            sink.push(integer_subtype_to_byte(*subtype));
+            sink.extend_from_slice(&value.to_be_bytes())
+        }
+        EncodedTerm::BigIntegerSubtypeLiteral { value_id, subtype } => {
+            sink.push(TYPE_BIG_INTEGER_SUBTYPE_LITERAL);
+            sink.push(integer_subtype_to_byte(*subtype));
+            sink.extend_from_slice(&value_id.to_be_bytes());
+        }
         EncodedTerm::DecimalLiteral(value) => {
             sink.push(TYPE_DECIMAL_LITERAL);
             sink.extend_from_slice(&value.to_be_bytes())
         }
+        EncodedTerm::BigDecimalLiteral { value_id } => {
+            sink.push(TYPE_BIG_DECIMAL_LITERAL);
+            sink.extend_from_slice(&value_id.to_be_bytes());
+        }
         EncodedTerm::DateTimeLiteral(value) => {
             sink.push(TYPE_DATE_TIME_LITERAL);
             sink.extend_from_slice(&value.to_be_bytes())
         }
+        EncodedTerm::DateTimeStampLiteral(value) => {
+            sink.push(TYPE_DATE_TIME_STAMP_LITERAL);
+            sink.extend_from_slice(&value.to_be_bytes())
+        }
         EncodedTerm::TimeLiteral(value) => {
             sink.push(TYPE_TIME_LITERAL);
             sink.extend_from_slice(&value.to_be_bytes())
@@ -718,7 +825,18 @@ mod tests {
             )
             .into(),
             Literal::new_typed_literal("-1.32", xsd::DECIMAL).into(),
+            Literal::new_typed_literal("123456789012345678901234567890", xsd::INTEGER).into(),
+            Literal::new_typed_literal(
+                "-123456789012345678901234567890.000000000000000000001",
+                xsd::DECIMAL,
+            )
+            .into(),
+            Literal::new_typed_literal("42", xsd::INT).into(),
+            Literal::new_typed_literal("18446744073709551615", xsd::UNSIGNED_LONG).into(),
+            Literal::new_typed_literal("300", xsd::BYTE).into(),
             Literal::new_typed_literal("2020-01-01T01:01:01Z", xsd::DATE_TIME).into(),
+            Literal::new_typed_literal("2020-01-01T01:01:01Z", xsd::DATE_TIME_STAMP).into(),
+            Literal::new_typed_literal("2020-01-01T01:01:01", xsd::DATE_TIME_STAMP).into(),
             Literal::new_typed_literal("2020-01-01", xsd::DATE).into(),
             Literal::new_typed_literal("01:01:01Z", xsd::TIME).into(),
             Literal::new_typed_literal("2020-01", xsd::G_YEAR_MONTH).into(),
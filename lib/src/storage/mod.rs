@@ -14,9 +14,9 @@ use crate::storage::numeric_encoder::{
 };
 use backend::{ColumnFamily, ColumnFamilyDefinition, Db, Iter};
 use std::cmp::{max, min};
-use std::collections::VecDeque;
 #[cfg(not(target_arch = "wasm32"))]
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::collections::{HashSet, VecDeque};
 use std::error::Error;
 #[cfg(not(target_arch = "wasm32"))]
 use std::mem::take;
@@ -27,6 +27,8 @@ use std::sync::Arc;
 #[cfg(not(target_arch = "wasm32"))]
 use std::thread::spawn;
 use std::thread::JoinHandle;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
 use sysinfo::{System, SystemExt};
 
 mod backend;
@@ -46,10 +48,13 @@ const DSPO_CF: &str = "dspo";
 const DPOS_CF: &str = "dpos";
 const DOSP_CF: &str = "dosp";
 const GRAPHS_CF: &str = "graphs";
+const GRAPH_VERSIONS_CF: &str = "graph_versions";
 const DEFAULT_CF: &str = "default";
 #[cfg(not(target_arch = "wasm32"))]
 const DEFAULT_BULK_LOAD_BATCH_SIZE: usize = 1_000_000;
 const MAX_BULK_LOAD_BATCH_SIZE: usize = 100_000_000;
+#[cfg(not(target_arch = "wasm32"))]
+const MIGRATION_BATCH_SIZE: usize = 10_000;
 
 /// Low level storage primitives
 #[derive(Clone)]
@@ -67,6 +72,18 @@ pub struct Storage {
     dpos_cf: ColumnFamily,
     dosp_cf: ColumnFamily,
     graphs_cf: ColumnFamily,
+    graph_versions_cf: ColumnFamily,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn contains_unknown_typed_literal(term: &EncodedTerm) -> bool {
+    match term {
+        EncodedTerm::Triple(triple) => {
+            contains_unknown_typed_literal(&triple.subject)
+                || contains_unknown_typed_literal(&triple.object)
+        }
+        term => term.is_unknown_typed_literal(),
+    }
 }
 
 impl Storage {
@@ -75,8 +92,8 @@ impl Storage {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn open(path: &Path) -> Result<Self, StorageError> {
-        Self::setup(Db::open(path, Self::column_families())?)
+    pub fn open(path: &Path, options: &StorageOptions) -> Result<Self, StorageError> {
+        Self::setup(Db::open(path, Self::column_families(), options)?)
     }
 
     fn column_families() -> Vec<ColumnFamilyDefinition> {
@@ -147,6 +164,12 @@ impl Storage {
                 min_prefix_size: 17, // named or blank node start
                 unordered_writes: false,
             },
+            ColumnFamilyDefinition {
+                name: GRAPH_VERSIONS_CF,
+                use_iter: false,
+                min_prefix_size: 0,
+                unordered_writes: false,
+            },
         ]
     }
 
@@ -164,6 +187,7 @@ impl Storage {
             dpos_cf: db.column_family(DPOS_CF).unwrap(),
             dosp_cf: db.column_family(DOSP_CF).unwrap(),
             graphs_cf: db.column_family(GRAPHS_CF).unwrap(),
+            graph_versions_cf: db.column_family(GRAPH_VERSIONS_CF).unwrap(),
             db,
         };
         #[cfg(not(target_arch = "wasm32"))]
@@ -197,6 +221,18 @@ impl Storage {
             version = 1;
             self.update_version(version)?;
         }
+        if version == 1 {
+            // We migrate to v2: the xsd:integer and xsd:decimal literals that were overflowing are now natively encoded
+            self.reencode_unknown_typed_literals()?;
+            version = 2;
+            self.update_version(version)?;
+        }
+        if version == 2 {
+            // We migrate to v3: xsd:integer subtypes and xsd:dateTimeStamp get their own encodings
+            self.reencode_unknown_typed_literals()?;
+            version = 3;
+            self.update_version(version)?;
+        }
 
         match version {
             _ if version < LATEST_STORAGE_VERSION => Err(CorruptionError::msg(format!(
@@ -225,6 +261,49 @@ impl Storage {
         )
     }
 
+    /// Rewrites the quads containing typed literals that now get a native encoding
+    ///
+    /// The quads are rewritten in transactions of at most `MIGRATION_BATCH_SIZE` quads to bound the memory usage.
+    /// If the migration is interrupted, the quads already rewritten are not outdated anymore and are skipped when it is resumed.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reencode_unknown_typed_literals(&self) -> Result<(), StorageError> {
+        let reader = self.snapshot();
+        let mut outdated = Vec::with_capacity(MIGRATION_BATCH_SIZE);
+        for quad in reader.quads() {
+            let quad = quad?;
+            if [&quad.subject, &quad.object]
+                .into_iter()
+                .any(contains_unknown_typed_literal)
+                && EncodedQuad::from(reader.decode_quad(&quad)?.as_ref()) != quad
+            {
+                outdated.push(quad);
+                if outdated.len() >= MIGRATION_BATCH_SIZE {
+                    self.reencode_quads(&reader, &outdated)?;
+                    outdated.clear();
+                }
+            }
+        }
+        self.reencode_quads(&reader, &outdated)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reencode_quads(
+        &self,
+        reader: &StorageReader,
+        quads: &[EncodedQuad],
+    ) -> Result<(), StorageError> {
+        if quads.is_empty() {
+            return Ok(());
+        }
+        self.transaction(|mut writer| {
+            for quad in quads {
+                writer.remove_encoded(quad)?;
+                writer.insert(reader.decode_quad(quad)?.as_ref())?;
+            }
+            Ok::<_, StorageError>(())
+        })
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     fn update_version(&self, version: u64) -> Result<(), StorageError> {
         self.db
@@ -248,6 +327,7 @@ impl Storage {
                 buffer: Vec::new(),
                 transaction,
                 storage: self,
+                modified_graphs: HashSet::new(),
             })
         })
     }
@@ -286,6 +366,136 @@ impl Storage {
     pub fn backup(&self, target_directory: &Path) -> Result<(), StorageError> {
         self.db.backup(target_directory)
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn metrics(&self) -> StorageMetrics {
+        let (block_cache_hits, block_cache_misses) = self.db.block_cache_counts();
+        let mut metrics = StorageMetrics {
+            estimated_quads: self
+                .db
+                .int_property(&self.dspo_cf, "rocksdb.estimate-num-keys")
+                .unwrap_or(0)
+                + self
+                    .db
+                    .int_property(&self.gspo_cf, "rocksdb.estimate-num-keys")
+                    .unwrap_or(0),
+            block_cache_hits,
+            block_cache_misses,
+            ..StorageMetrics::default()
+        };
+        for (name, column_family) in [
+            (DEFAULT_CF, &self.default_cf),
+            (ID2STR_CF, &self.id2str_cf),
+            (SPOG_CF, &self.spog_cf),
+            (POSG_CF, &self.posg_cf),
+            (OSPG_CF, &self.ospg_cf),
+            (GSPO_CF, &self.gspo_cf),
+            (GPOS_CF, &self.gpos_cf),
+            (GOSP_CF, &self.gosp_cf),
+            (DSPO_CF, &self.dspo_cf),
+            (DPOS_CF, &self.dpos_cf),
+            (DOSP_CF, &self.dosp_cf),
+            (GRAPHS_CF, &self.graphs_cf),
+            (GRAPH_VERSIONS_CF, &self.graph_versions_cf),
+        ] {
+            metrics.compaction_pending |= self
+                .db
+                .int_property(column_family, "rocksdb.compaction-pending")
+                .unwrap_or(0)
+                > 0;
+            metrics.estimated_pending_compaction_bytes += self
+                .db
+                .int_property(column_family, "rocksdb.estimate-pending-compaction-bytes")
+                .unwrap_or(0);
+            metrics.sst_files_size.insert(
+                name.to_owned(),
+                self.db
+                    .int_property(column_family, "rocksdb.total-sst-files-size")
+                    .unwrap_or(0),
+            );
+        }
+        metrics
+    }
+}
+
+/// Low-level metrics about the storage of a [`Store`](crate::store::Store), useful for monitoring.
+///
+/// Returned by [`Store::storage_metrics`](crate::store::Store::storage_metrics).
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct StorageMetrics {
+    /// An estimation of the number of quads, cheaper to compute than [`Store::len`](crate::store::Store::len).
+    pub estimated_quads: u64,
+    /// The number of RocksDB block cache hits since the store has been opened.
+    ///
+    /// It is only counted if the statistics are enabled with [`StorageOptions::with_statistics`].
+    pub block_cache_hits: u64,
+    /// The number of RocksDB block cache misses since the store has been opened.
+    ///
+    /// It is only counted if the statistics are enabled with [`StorageOptions::with_statistics`].
+    pub block_cache_misses: u64,
+    /// If RocksDB has at least one compaction pending.
+    pub compaction_pending: bool,
+    /// An estimation of the number of bytes the pending RocksDB compactions have to rewrite.
+    pub estimated_pending_compaction_bytes: u64,
+    /// The total size in bytes of the SST files of each RocksDB column family.
+    pub sst_files_size: HashMap<String, u64>,
+}
+
+/// Options of the RocksDB storage of a [`Store`](crate::store::Store).
+///
+/// Used by [`Store::open_with_options`](crate::store::Store::open_with_options).
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Default)]
+pub struct StorageOptions {
+    block_cache_size: Option<usize>,
+    max_open_files: Option<u32>,
+    write_buffer_size: Option<usize>,
+    statistics: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StorageOptions {
+    /// Sets the size in bytes of the cache of uncompressed data blocks.
+    ///
+    /// By default the RocksDB default size is used.
+    #[inline]
+    #[must_use]
+    pub fn with_block_cache_size(mut self, size: usize) -> Self {
+        self.block_cache_size = Some(size);
+        self
+    }
+
+    /// Sets the maximal number of files kept open.
+    ///
+    /// By default all the file descriptors allowed to the process are used except 48 of them.
+    #[inline]
+    #[must_use]
+    pub fn with_max_open_files(mut self, max_open_files: u32) -> Self {
+        self.max_open_files = Some(max_open_files);
+        self
+    }
+
+    /// Sets the size in bytes of the in-memory write buffer of each column family before it is written to disk.
+    ///
+    /// By default the RocksDB default size is used.
+    #[inline]
+    #[must_use]
+    pub fn with_write_buffer_size(mut self, size: usize) -> Self {
+        self.write_buffer_size = Some(size);
+        self
+    }
+
+    /// Enables the collection of the RocksDB statistics like the block cache hits and misses.
+    ///
+    /// It is disabled by default because it slows down the reads and the writes.
+    /// If it is not enabled the block cache counts of [`StorageMetrics`] are always 0.
+    #[inline]
+    #[must_use]
+    pub fn with_statistics(mut self) -> Self {
+        self.statistics = true;
+        self
+    }
 }
 
 pub struct StorageReader {
@@ -559,6 +769,19 @@ impl StorageReader {
             .contains_key(&self.storage.graphs_cf, &encode_term(graph_name))
     }
 
+    /// Returns the version number and the last modification time (in seconds since the Unix epoch) of a graph
+    ///
+    /// `None` is returned if the graph has never been modified.
+    pub fn graph_version(
+        &self,
+        graph_name: &EncodedTerm,
+    ) -> Result<Option<(u64, u64)>, StorageError> {
+        Ok(self
+            .reader
+            .get(&self.storage.graph_versions_cf, &encode_term(graph_name))?
+            .map(|value| decode_graph_version(&value)))
+    }
+
     fn spog_quads(&self, prefix: &[u8]) -> DecodingQuadIterator {
         self.inner_quads(&self.storage.spog_cf, prefix, QuadEncoding::Spog)
     }
@@ -838,6 +1061,7 @@ pub struct StorageWriter<'a> {
     buffer: Vec<u8>,
     transaction: Transaction<'a>,
     storage: &'a Storage,
+    modified_graphs: HashSet<EncodedTerm>,
 }
 
 impl<'a> StorageWriter<'a> {
@@ -875,6 +1099,7 @@ impl<'a> StorageWriter<'a> {
                 self.insert_term(quad.subject.into(), &encoded.subject)?;
                 self.insert_term(quad.predicate.into(), &encoded.predicate)?;
                 self.insert_term(quad.object, &encoded.object)?;
+                self.update_graph_version(&encoded.graph_name)?;
                 true
             }
         } else {
@@ -927,6 +1152,7 @@ impl<'a> StorageWriter<'a> {
                         .insert_empty(&self.storage.graphs_cf, &self.buffer)?;
                     self.insert_graph_name(quad.graph_name, &encoded.graph_name)?;
                 }
+                self.update_graph_version(&encoded.graph_name)?;
                 true
             }
         };
@@ -950,11 +1176,48 @@ impl<'a> StorageWriter<'a> {
             self.transaction
                 .insert_empty(&self.storage.graphs_cf, &self.buffer)?;
             self.insert_term(graph_name.into(), &encoded_graph_name)?;
+            self.update_graph_version(&encoded_graph_name)?;
             true
         };
         Ok(result)
     }
 
+    /// Returns the version number and the last modification time of a graph
+    ///
+    /// The version key is read with `get_for_update` so that the transaction conflicts with any concurrent writer of the same graph.
+    pub fn graph_version_for_update(
+        &self,
+        graph_name: &EncodedTerm,
+    ) -> Result<Option<(u64, u64)>, StorageError> {
+        Ok(self
+            .transaction
+            .get_for_update(&self.storage.graph_versions_cf, &encode_term(graph_name))?
+            .map(|value| decode_graph_version(&value)))
+    }
+
+    /// Increments the graph version the first time the graph is modified in the transaction
+    ///
+    /// The version key is read with `get_for_update` so that two transactions writing the same graph never commit the same version.
+    /// As a consequence, all the transactions modifying a given graph (e.g. the default graph) are serialized:
+    /// one of two concurrent writers of the same graph waits for the other one or fails with a conflict and is retried by [`Storage::transaction`].
+    /// Writers of distinct graphs are not affected and the lock is only taken once per graph and transaction.
+    fn update_graph_version(&mut self, graph_name: &EncodedTerm) -> Result<(), StorageError> {
+        if self.modified_graphs.contains(graph_name) {
+            return Ok(());
+        }
+        self.modified_graphs.insert(graph_name.clone());
+        let key = encode_term(graph_name);
+        let version = self
+            .transaction
+            .get_for_update(&self.storage.graph_versions_cf, &key)?
+            .map_or(0, |value| decode_graph_version(&value).0);
+        self.transaction.insert(
+            &self.storage.graph_versions_cf,
+            &key,
+            &encode_graph_version(version + 1, now_timestamp()),
+        )
+    }
+
     fn insert_term(
         &mut self,
         term: TermRef<'_>,
@@ -1025,6 +1288,7 @@ impl<'a> StorageWriter<'a> {
                 write_osp_quad(&mut self.buffer, quad);
                 self.transaction
                     .remove(&self.storage.dosp_cf, &self.buffer)?;
+                self.update_graph_version(&quad.graph_name)?;
                 true
             } else {
                 false
@@ -1063,6 +1327,7 @@ impl<'a> StorageWriter<'a> {
                 write_gosp_quad(&mut self.buffer, quad);
                 self.transaction
                     .remove(&self.storage.gosp_cf, &self.buffer)?;
+                self.update_graph_version(&quad.graph_name)?;
                 true
             } else {
                 false
@@ -1131,6 +1396,7 @@ impl<'a> StorageWriter<'a> {
             write_term(&mut self.buffer, graph_name);
             self.transaction
                 .remove(&self.storage.graphs_cf, &self.buffer)?;
+            self.update_graph_version(graph_name)?;
             true
         } else {
             false
@@ -1156,6 +1422,38 @@ impl<'a> StorageWriter<'a> {
     }
 }
 
+fn encode_graph_version(version: u64, modified: u64) -> [u8; 16] {
+    let mut value = [0; 16];
+    value[..8].copy_from_slice(&version.to_be_bytes());
+    value[8..].copy_from_slice(&modified.to_be_bytes());
+    value
+}
+
+fn decode_graph_version(value: &[u8]) -> (u64, u64) {
+    let mut version = [0; 8];
+    let mut modified = [0; 8];
+    if value.len() == 16 {
+        version.copy_from_slice(&value[..8]);
+        modified.copy_from_slice(&value[8..]);
+    }
+    (u64::from_be_bytes(version), u64::from_be_bytes(modified))
+}
+
+/// The current time in seconds since the Unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// The current time in seconds since the Unix epoch
+#[cfg(target_arch = "wasm32")]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn now_timestamp() -> u64 {
+    (js_sys::Date::now() / 1000.) as u64
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub struct StorageBulkLoader {
     storage: Storage,
@@ -1354,6 +1652,10 @@ impl FileBulkLoader {
 
     fn save(&mut self) -> Result<(), StorageError> {
         let mut to_load = Vec::new();
+        let mut modified_graphs = self.graphs.iter().cloned().collect::<Vec<_>>();
+        if !self.triples.is_empty() {
+            modified_graphs.push(EncodedTerm::DefaultGraph);
+        }
 
         // id2str
         if !self.id2str.is_empty() {
@@ -1473,7 +1775,23 @@ impl FileBulkLoader {
             self.quads.clear();
         }
 
-        self.storage.db.insert_stt_files(&to_load)
+        self.storage.db.insert_stt_files(&to_load)?;
+
+        // The bulk load is not transactional: concurrent loads might only increment once the version
+        for graph_name in modified_graphs {
+            let key = encode_term(&graph_name);
+            let version = self
+                .storage
+                .db
+                .get(&self.storage.graph_versions_cf, &key)?
+                .map_or(0, |value| decode_graph_version(&value).0);
+            self.storage.db.insert(
+                &self.storage.graph_versions_cf,
+                &key,
+                &encode_graph_version(version + 1, now_timestamp()),
+            )?;
+        }
+        Ok(())
     }
 
     fn insert_term(
@@ -10,6 +10,7 @@ use std::hash::Hash;
 use std::hash::Hasher;
 use std::rc::Rc;
 use std::str;
+use std::str::FromStr;
 
 #[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
 #[repr(transparent)]
@@ -84,8 +85,23 @@ pub enum EncodedTerm {
     FloatLiteral(Float),
     DoubleLiteral(Double),
     IntegerLiteral(i64),
+    BigIntegerLiteral {
+        value_id: StrHash,
+    },
+    IntegerSubtypeLiteral {
+        value: i64,
+        subtype: IntegerSubtype,
+    },
+    BigIntegerSubtypeLiteral {
+        value_id: StrHash,
+        subtype: IntegerSubtype,
+    },
     DecimalLiteral(Decimal),
+    BigDecimalLiteral {
+        value_id: StrHash,
+    },
     DateTimeLiteral(DateTime),
+    DateTimeStampLiteral(DateTime),
     TimeLiteral(Time),
     DateLiteral(Date),
     GYearMonthLiteral(GYearMonth),
@@ -186,8 +202,47 @@ impl PartialEq for EncodedTerm {
             (Self::FloatLiteral(a), Self::FloatLiteral(b)) => a == b,
             (Self::DoubleLiteral(a), Self::DoubleLiteral(b)) => a == b,
             (Self::IntegerLiteral(a), Self::IntegerLiteral(b)) => a == b,
+            (
+                Self::BigIntegerLiteral {
+                    value_id: value_id_a,
+                },
+                Self::BigIntegerLiteral {
+                    value_id: value_id_b,
+                },
+            ) => value_id_a == value_id_b,
+            (
+                Self::IntegerSubtypeLiteral {
+                    value: value_a,
+                    subtype: subtype_a,
+                },
+                Self::IntegerSubtypeLiteral {
+                    value: value_b,
+                    subtype: subtype_b,
+                },
+            ) => value_a == value_b && subtype_a == subtype_b,
+            (
+                Self::BigIntegerSubtypeLiteral {
+                    value_id: value_id_a,
+                    subtype: subtype_a,
+                },
+                Self::BigIntegerSubtypeLiteral {
+                    value_id: value_id_b,
+                    subtype: subtype_b,
+                },
+            ) => value_id_a == value_id_b && subtype_a == subtype_b,
             (Self::DecimalLiteral(a), Self::DecimalLiteral(b)) => a == b,
+            (
+                Self::BigDecimalLiteral {
+                    value_id: value_id_a,
+                },
+                Self::BigDecimalLiteral {
+                    value_id: value_id_b,
+                },
+            ) => value_id_a == value_id_b,
             (Self::DateTimeLiteral(a), Self::DateTimeLiteral(b)) => a.is_identical_with(b),
+            (Self::DateTimeStampLiteral(a), Self::DateTimeStampLiteral(b)) => {
+                a.is_identical_with(b)
+            }
             (Self::TimeLiteral(a), Self::TimeLiteral(b)) => a.is_identical_with(b),
             (Self::DateLiteral(a), Self::DateLiteral(b)) => a.is_identical_with(b),
             (Self::GYearMonthLiteral(a), Self::GYearMonthLiteral(b)) => a.is_identical_with(b),
@@ -250,8 +305,19 @@ impl Hash for EncodedTerm {
             Self::FloatLiteral(value) => value.hash(state),
             Self::DoubleLiteral(value) => value.hash(state),
             Self::IntegerLiteral(value) => value.hash(state),
+            Self::BigIntegerLiteral { value_id } => value_id.hash(state),
+            Self::IntegerSubtypeLiteral { value, subtype } => {
+                value.hash(state);
+                subtype.hash(state);
+            }
+            Self::BigIntegerSubtypeLiteral { value_id, subtype } => {
+                value_id.hash(state);
+                subtype.hash(state);
+            }
             Self::DecimalLiteral(value) => value.hash(state),
+            Self::BigDecimalLiteral { value_id } => value_id.hash(state),
             Self::DateTimeLiteral(value) => value.hash(state),
+            Self::DateTimeStampLiteral(value) => value.hash(state),
             Self::TimeLiteral(value) => value.hash(state),
             Self::DateLiteral(value) => value.hash(state),
             Self::GYearMonthLiteral(value) => value.hash(state),
@@ -296,8 +362,13 @@ impl EncodedTerm {
                 | Self::FloatLiteral(_)
                 | Self::DoubleLiteral(_)
                 | Self::IntegerLiteral(_)
+                | Self::BigIntegerLiteral { .. }
+                | Self::IntegerSubtypeLiteral { .. }
+                | Self::BigIntegerSubtypeLiteral { .. }
                 | Self::DecimalLiteral(_)
+                | Self::BigDecimalLiteral { .. }
                 | Self::DateTimeLiteral(_)
+                | Self::DateTimeStampLiteral(_)
                 | Self::TimeLiteral(_)
                 | Self::DateLiteral(_)
                 | Self::GYearMonthLiteral(_)
@@ -496,22 +567,10 @@ impl From<LiteralRef<'_>> for EncodedTerm {
             }
             "http://www.w3.org/2001/XMLSchema#float" => parse_float_str(value),
             "http://www.w3.org/2001/XMLSchema#double" => parse_double_str(value),
-            "http://www.w3.org/2001/XMLSchema#integer"
-            | "http://www.w3.org/2001/XMLSchema#byte"
-            | "http://www.w3.org/2001/XMLSchema#short"
-            | "http://www.w3.org/2001/XMLSchema#int"
-            | "http://www.w3.org/2001/XMLSchema#long"
-            | "http://www.w3.org/2001/XMLSchema#unsignedByte"
-            | "http://www.w3.org/2001/XMLSchema#unsignedShort"
-            | "http://www.w3.org/2001/XMLSchema#unsignedInt"
-            | "http://www.w3.org/2001/XMLSchema#unsignedLong"
-            | "http://www.w3.org/2001/XMLSchema#positiveInteger"
-            | "http://www.w3.org/2001/XMLSchema#negativeInteger"
-            | "http://www.w3.org/2001/XMLSchema#nonPositiveInteger"
-            | "http://www.w3.org/2001/XMLSchema#nonNegativeInteger" => parse_integer_str(value),
+            "http://www.w3.org/2001/XMLSchema#integer" => parse_integer_str(value),
             "http://www.w3.org/2001/XMLSchema#decimal" => parse_decimal_str(value),
-            "http://www.w3.org/2001/XMLSchema#dateTime"
-            | "http://www.w3.org/2001/XMLSchema#dateTimeStamp" => parse_date_time_str(value),
+            "http://www.w3.org/2001/XMLSchema#dateTime" => parse_date_time_str(value),
+            "http://www.w3.org/2001/XMLSchema#dateTimeStamp" => parse_date_time_stamp_str(value),
             "http://www.w3.org/2001/XMLSchema#time" => parse_time_str(value),
             "http://www.w3.org/2001/XMLSchema#date" => parse_date_str(value),
             "http://www.w3.org/2001/XMLSchema#gYearMonth" => parse_g_year_month_str(value),
@@ -526,7 +585,8 @@ impl From<LiteralRef<'_>> for EncodedTerm {
             "http://www.w3.org/2001/XMLSchema#dayTimeDuration" => {
                 parse_day_time_duration_str(value)
             }
-            _ => None,
+            _ => IntegerSubtype::from_iri(datatype)
+                .and_then(|subtype| parse_integer_subtype_str(value, subtype)),
         };
         match native_encoding {
             Some(term) => term,
@@ -712,14 +772,37 @@ pub fn insert_term<F: FnMut(&StrHash, &str) -> Result<(), StorageError>>(
                 insert_str(value_id, literal.value())?;
                 insert_str(datatype_id, literal.datatype().as_str())
             }
+            EncodedTerm::BigIntegerLiteral { value_id } => {
+                if let Ok(value) = BigInteger::from_str(literal.value()) {
+                    insert_str(value_id, &value.to_string())
+                } else {
+                    unreachable!("Invalid term encoding {:?} for {}", encoded, term)
+                }
+            }
+            EncodedTerm::BigIntegerSubtypeLiteral { value_id, .. } => {
+                if let Ok(value) = BigInteger::from_str(literal.value()) {
+                    insert_str(value_id, &value.to_string())
+                } else {
+                    unreachable!("Invalid term encoding {:?} for {}", encoded, term)
+                }
+            }
+            EncodedTerm::BigDecimalLiteral { value_id } => {
+                if let Ok(value) = BigDecimal::from_str(literal.value()) {
+                    insert_str(value_id, &value.to_string())
+                } else {
+                    unreachable!("Invalid term encoding {:?} for {}", encoded, term)
+                }
+            }
             EncodedTerm::SmallStringLiteral(..)
             | EncodedTerm::SmallSmallLangStringLiteral { .. }
             | EncodedTerm::BooleanLiteral(..)
             | EncodedTerm::FloatLiteral(..)
             | EncodedTerm::DoubleLiteral(..)
             | EncodedTerm::IntegerLiteral(..)
+            | EncodedTerm::IntegerSubtypeLiteral { .. }
             | EncodedTerm::DecimalLiteral(..)
             | EncodedTerm::DateTimeLiteral(..)
+            | EncodedTerm::DateTimeStampLiteral(..)
             | EncodedTerm::TimeLiteral(..)
             | EncodedTerm::DateLiteral(..)
             | EncodedTerm::GYearMonthLiteral(..)
@@ -764,18 +847,77 @@ pub fn parse_double_str(value: &str) -> Option<EncodedTerm> {
     value.parse().map(EncodedTerm::DoubleLiteral).ok()
 }
 
+/// Parses an `xsd:integer` using the inline encoding if possible.
+///
+/// If a `BigIntegerLiteral` is returned, its canonical lexical form must be inserted in the string store.
+/// The integer subtypes must be parsed with [`parse_integer_subtype_str`] to keep their datatype.
 pub fn parse_integer_str(value: &str) -> Option<EncodedTerm> {
-    value.parse().map(EncodedTerm::IntegerLiteral).ok()
+    if let Ok(value) = value.parse() {
+        return Some(EncodedTerm::IntegerLiteral(value));
+    }
+    let value = BigInteger::from_str(value).ok()?;
+    Some(if let Ok(value) = i64::try_from(&value) {
+        EncodedTerm::IntegerLiteral(value)
+    } else {
+        EncodedTerm::BigIntegerLiteral {
+            value_id: StrHash::new(&value.to_string()),
+        }
+    })
 }
 
+/// Parses an integer subtype value, returning `None` if the value is not in the datatype range.
+///
+/// If a `BigIntegerSubtypeLiteral` is returned, its canonical lexical form must be inserted in the string store.
+pub fn parse_integer_subtype_str(value: &str, subtype: IntegerSubtype) -> Option<EncodedTerm> {
+    if let Ok(value) = value.parse() {
+        return subtype
+            .contains_i64(value)
+            .then(|| EncodedTerm::IntegerSubtypeLiteral { value, subtype });
+    }
+    let value = BigInteger::from_str(value).ok()?;
+    if !subtype.contains(&value) {
+        return None;
+    }
+    Some(if let Ok(value) = i64::try_from(&value) {
+        EncodedTerm::IntegerSubtypeLiteral { value, subtype }
+    } else {
+        EncodedTerm::BigIntegerSubtypeLiteral {
+            value_id: StrHash::new(&value.to_string()),
+            subtype,
+        }
+    })
+}
+
+/// Parses a decimal using the inline encoding if possible.
+///
+/// If a `BigDecimalLiteral` is returned, its canonical lexical form must be inserted in the string store.
 pub fn parse_decimal_str(value: &str) -> Option<EncodedTerm> {
-    value.parse().map(EncodedTerm::DecimalLiteral).ok()
+    if let Ok(value) = value.parse() {
+        return Some(EncodedTerm::DecimalLiteral(value));
+    }
+    let value = BigDecimal::from_str(value).ok()?;
+    Some(if let Ok(value) = Decimal::try_from(&value) {
+        EncodedTerm::DecimalLiteral(value)
+    } else {
+        EncodedTerm::BigDecimalLiteral {
+            value_id: StrHash::new(&value.to_string()),
+        }
+    })
 }
 
 pub fn parse_date_time_str(value: &str) -> Option<EncodedTerm> {
     value.parse().map(EncodedTerm::DateTimeLiteral).ok()
 }
 
+/// Parses a `xsd:dateTimeStamp`, i.e. a `xsd:dateTime` with a mandatory timezone
+pub fn parse_date_time_stamp_str(value: &str) -> Option<EncodedTerm> {
+    let value: DateTime = value.parse().ok()?;
+    value
+        .timezone_offset()
+        .is_some()
+        .then(|| EncodedTerm::DateTimeStampLiteral(value))
+}
+
 pub fn parse_time_str(value: &str) -> Option<EncodedTerm> {
     value.parse().map(EncodedTerm::TimeLiteral).ok()
 }
@@ -967,8 +1109,29 @@ impl<S: StrLookup> Decoder for S {
             EncodedTerm::FloatLiteral(value) => Ok(Literal::from(*value).into()),
             EncodedTerm::DoubleLiteral(value) => Ok(Literal::from(*value).into()),
             EncodedTerm::IntegerLiteral(value) => Ok(Literal::from(*value).into()),
+            EncodedTerm::BigIntegerLiteral { value_id } => Ok(Literal::new_typed_literal(
+                get_required_str(self, value_id)?,
+                vocab::xsd::INTEGER,
+            )
+            .into()),
+            EncodedTerm::IntegerSubtypeLiteral { value, subtype } => {
+                Ok(Literal::new_typed_literal(value.to_string(), subtype.iri()).into())
+            }
+            EncodedTerm::BigIntegerSubtypeLiteral { value_id, subtype } => Ok(
+                Literal::new_typed_literal(get_required_str(self, value_id)?, subtype.iri()).into(),
+            ),
             EncodedTerm::DecimalLiteral(value) => Ok(Literal::from(*value).into()),
+            EncodedTerm::BigDecimalLiteral { value_id } => Ok(Literal::new_typed_literal(
+                get_required_str(self, value_id)?,
+                vocab::xsd::DECIMAL,
+            )
+            .into()),
             EncodedTerm::DateTimeLiteral(value) => Ok(Literal::from(*value).into()),
+            EncodedTerm::DateTimeStampLiteral(value) => Ok(Literal::new_typed_literal(
+                value.to_string(),
+                vocab::xsd::DATE_TIME_STAMP,
+            )
+            .into()),
             EncodedTerm::DateLiteral(value) => Ok(Literal::from(*value).into()),
             EncodedTerm::TimeLiteral(value) => Ok(Literal::from(*value).into()),
             EncodedTerm::GYearMonthLiteral(value) => Ok(Literal::from(*value).into()),
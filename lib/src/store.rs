@@ -23,14 +23,18 @@
 //! };
 //! # Result::<_, Box<dyn std::error::Error>>::Ok(())
 //! ```
+#[cfg(feature = "arrow")]
+use crate::io::arrow::{QuadBatchIter, DEFAULT_BATCH_SIZE};
 use crate::io::read::ParseError;
 use crate::io::{
     DatasetFormat, DatasetParser, DatasetSerializer, GraphFormat, GraphParser, GraphSerializer,
 };
+use crate::model::patch::{Patch, PatchOperation};
+use crate::model::vocab::rdf;
 use crate::model::*;
 use crate::sparql::{
-    evaluate_query, evaluate_update, EvaluationError, Query, QueryOptions, QueryResults, Update,
-    UpdateOptions,
+    evaluate_prepared_query, evaluate_query, evaluate_update, prepare_query, EvaluationError,
+    PreparedQuery, Query, QueryOptions, QueryResults, Update, UpdateOptions, Variable,
 };
 use crate::storage::numeric_encoder::{Decoder, EncodedQuad, EncodedTerm};
 #[cfg(not(target_arch = "wasm32"))]
@@ -39,10 +43,20 @@ use crate::storage::{
     ChainedDecodingQuadIterator, DecodingGraphIterator, Storage, StorageReader, StorageWriter,
 };
 pub use crate::storage::{CorruptionError, LoaderError, SerializerError, StorageError};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::storage::{StorageMetrics, StorageOptions};
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parquet")]
+use parquet::errors::ParquetError;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+#[cfg(feature = "parquet")]
+use std::io;
 use std::io::{BufRead, Write};
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{fmt, str};
 
 /// An on-disk [RDF dataset](https://www.w3.org/TR/rdf11-concepts/#dfn-rdf-dataset).
@@ -53,6 +67,11 @@ use std::{fmt, str};
 /// been "committed" (i.e. no partial writes) and the exposed state does not change for the complete duration
 /// of a read operation (e.g. a SPARQL query) or a read/write operation (e.g. a SPARQL update).
 ///
+/// Each write increments the [version](Store::graph_version) of the graphs it modifies.
+/// Hence, all the writers of the same graph (SPARQL updates, [transactions](Store::transaction), bulk loads...)
+/// are serialized: a write waits for or conflicts with the concurrent writes of the same graph, conflicting transactions being retried.
+/// Writes to distinct graphs are still done concurrently.
+///
 /// Usage example:
 /// ```
 /// use oxigraph::store::Store;
@@ -97,8 +116,32 @@ impl Store {
     /// Opens a [`Store`] and creates it if it does not exist yet.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        Self::open_with_options(path, StorageOptions::default())
+    }
+
+    /// Opens a [`Store`] with some tuning of its RocksDB storage and creates it if it does not exist yet.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::{StorageOptions, Store};
+    ///
+    /// # let dir = std::env::temp_dir().join("oxigraph-open-with-options-doctest");
+    /// let store = Store::open_with_options(
+    ///     &dir,
+    ///     StorageOptions::default().with_block_cache_size(64 * 1024 * 1024),
+    /// )?;
+    /// assert!(store.is_empty()?);
+    /// # drop(store);
+    /// # std::fs::remove_dir_all(dir)?;
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_with_options(
+        path: impl AsRef<Path>,
+        options: StorageOptions,
+    ) -> Result<Self, StorageError> {
         Ok(Self {
-            storage: Storage::open(path.as_ref())?,
+            storage: Storage::open(path.as_ref(), &options)?,
         })
     }
 
@@ -157,6 +200,59 @@ impl Store {
         evaluate_query(self.storage.snapshot(), query, options)
     }
 
+    /// Parses and plans a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) in order to execute it multiple times with [`query_prepared`](Self::query_prepared).
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::{QueryResults, Variable};
+    ///
+    /// let store = Store::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    ///
+    /// let query = store.prepare_query("ASK { ?s ?p ?o }")?;
+    /// if let QueryResults::Boolean(result) = store.query_prepared(&query, [(Variable::new("s")?, ex.into_owned().into())])? {
+    ///     assert!(result);
+    /// }
+    /// if let QueryResults::Boolean(result) = store.query_prepared(&query, [(Variable::new("s")?, Literal::from(1).into())])? {
+    ///     assert!(!result);
+    /// }
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn prepare_query(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        self.prepare_query_opt(query, QueryOptions::default())
+    }
+
+    /// Parses and plans a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options in order to execute it multiple times with [`query_prepared`](Self::query_prepared).
+    ///
+    /// The options are used by all the executions of the prepared query.
+    pub fn prepare_query_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        prepare_query(self.storage.snapshot(), query, options)
+    }
+
+    /// Executes a query prepared with [`prepare_query`](Self::prepare_query).
+    ///
+    /// The given variables are bound to the given values in the scope of the query `WHERE` clause,
+    /// like if a `VALUES` clause with a single row was appended to it.
+    /// Bindings of variables that are not in this scope are ignored.
+    /// Variables assigned in the scope by `BIND` or by a `SELECT` expression can't be bound: the evaluation fails with an error.
+    pub fn query_prepared(
+        &self,
+        query: &PreparedQuery,
+        bindings: impl IntoIterator<Item = (Variable, Term)>,
+    ) -> Result<QueryResults, EvaluationError> {
+        evaluate_prepared_query(self.storage.snapshot(), query, bindings)
+    }
+
     /// Retrieves quads with a filter on each quad component
     ///
     /// Usage example:
@@ -279,6 +375,65 @@ impl Store {
         self.storage.snapshot().is_empty()
     }
 
+    /// Computes [VoID](https://www.w3.org/TR/void/) statistics about the store content.
+    ///
+    /// The statistics are computed for the whole dataset, for the default graph and for each named graph.
+    ///
+    /// Warning: this function executes a full scan and keeps in memory the distinct subjects and objects.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::model::vocab::rdf;
+    ///
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// let store = Store::new()?;
+    /// store.insert(QuadRef::new(ex, rdf::TYPE, ex, GraphNameRef::DefaultGraph))?;
+    /// store.insert(QuadRef::new(ex, ex, ex, ex))?;
+    ///
+    /// let statistics = store.statistics()?;
+    /// assert_eq!(statistics.dataset.triples, 2);
+    /// assert_eq!(statistics.dataset.distinct_subjects, 1);
+    /// assert_eq!(statistics.default_graph.classes.get(&ex.into_owned()), Some(&1));
+    /// let graph = &statistics.named_graphs[&NamedOrBlankNode::from(ex.into_owned())];
+    /// assert_eq!(graph.properties.get(&ex.into_owned()), Some(&1));
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn statistics(&self) -> Result<StoreStatistics, StorageError> {
+        let reader = self.storage.snapshot();
+        let mut dataset = GraphStatisticsBuilder::default();
+        let mut graphs = HashMap::<_, GraphStatisticsBuilder>::new();
+        let rdf_type = EncodedTerm::from(rdf::TYPE);
+        for graph_name in reader.named_graphs() {
+            graphs.entry(graph_name?).or_default();
+        }
+        for quad in reader.quads_for_pattern(None, None, None, None) {
+            let quad = quad?;
+            dataset.add(&quad, &rdf_type);
+            graphs
+                .entry(quad.graph_name.clone())
+                .or_default()
+                .add(&quad, &rdf_type);
+        }
+        let default_graph = graphs
+            .remove(&EncodedTerm::DefaultGraph)
+            .unwrap_or_default();
+        Ok(StoreStatistics {
+            dataset: dataset.build(&reader)?,
+            default_graph: default_graph.build(&reader)?,
+            named_graphs: graphs
+                .into_iter()
+                .map(|(graph_name, graph)| {
+                    Ok((
+                        reader.decode_named_or_blank_node(&graph_name)?,
+                        graph.build(&reader)?,
+                    ))
+                })
+                .collect::<Result<_, StorageError>>()?,
+        })
+    }
+
     /// Executes a transaction.
     ///
     /// Transactions ensure the "repeatable read" isolation level: the store only exposes changes that have
@@ -511,6 +666,30 @@ impl Store {
         self.transaction(move |mut t| t.remove(quad))
     }
 
+    /// Applies atomically the committed operations of an [RDF Patch](crate::model::patch).
+    ///
+    /// Operations of aborted transactions are ignored and so are the prefix operations.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::model::patch::Patch;
+    /// use std::str::FromStr;
+    ///
+    /// let patch = Patch::from_str("TX .\nA <http://example.com> <http://example.com> <http://example.com> .\nTC .")?;
+    ///
+    /// let store = Store::new()?;
+    /// store.apply_patch(&patch)?;
+    ///
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// assert!(store.contains(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?);
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn apply_patch(&self, patch: &Patch) -> Result<(), StorageError> {
+        self.transaction(|mut t| t.apply_patch(patch))
+    }
+
     /// Dumps a store graph into a file.
     ///    
     /// Usage example:
@@ -543,6 +722,77 @@ impl Store {
         Ok(())
     }
 
+    /// Retrieves the quads matching a pattern as [Apache Arrow](https://arrow.apache.org/) record batches of at most `batch_size` rows.
+    ///
+    /// See the [`arrow`](crate::io::arrow) module for the columns encoding.
+    /// It is available behind the `arrow` feature.
+    ///
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    ///
+    /// let store = Store::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    ///
+    /// for batch in store.quads_record_batches(None, None, None, None, 1024) {
+    ///     assert_eq!(batch?.num_rows(), 1);
+    /// }
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[cfg(feature = "arrow")]
+    pub fn quads_record_batches(
+        &self,
+        subject: Option<SubjectRef<'_>>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<TermRef<'_>>,
+        graph_name: Option<GraphNameRef<'_>>,
+        batch_size: usize,
+    ) -> QuadBatchIter<QuadIter> {
+        QuadBatchIter::new(
+            self.quads_for_pattern(subject, predicate, object, graph_name),
+            batch_size,
+        )
+    }
+
+    /// Dumps the quads matching a pattern into an [Apache Parquet](https://parquet.apache.org/) file.
+    ///
+    /// See the [`arrow`](crate::io::arrow) module for the columns encoding.
+    /// It is available behind the `parquet` feature.
+    ///
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    ///
+    /// let store = Store::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    ///
+    /// let mut buffer = Vec::new();
+    /// store.dump_parquet(&mut buffer, None, None, None, None)?;
+    /// assert!(buffer.starts_with(b"PAR1"));
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[cfg(feature = "parquet")]
+    pub fn dump_parquet(
+        &self,
+        writer: impl Write,
+        subject: Option<SubjectRef<'_>>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<TermRef<'_>>,
+        graph_name: Option<GraphNameRef<'_>>,
+    ) -> Result<(), SerializerError> {
+        let batches =
+            self.quads_record_batches(subject, predicate, object, graph_name, DEFAULT_BATCH_SIZE);
+        let mut writer =
+            ArrowWriter::try_new(writer, batches.schema(), None).map_err(parquet_to_io_error)?;
+        for batch in batches {
+            writer.write(&batch?).map_err(parquet_to_io_error)?;
+        }
+        writer.close().map_err(parquet_to_io_error)?;
+        Ok(())
+    }
+
     /// Dumps the store into a file.
     ///    
     /// ```
@@ -615,6 +865,38 @@ impl Store {
         self.storage.snapshot().contains_named_graph(&graph_name)
     }
 
+    /// Returns the current version of a graph of the store.
+    ///
+    /// The version is incremented by each transaction modifying the graph and is returned with the time of the last modification.
+    /// `None` is returned if the graph has never been modified.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::{NamedNode, QuadRef};
+    ///
+    /// let ex = NamedNode::new("http://example.com")?;
+    /// let store = Store::new()?;
+    /// assert!(store.graph_version(&ex)?.is_none());
+    /// store.insert(QuadRef::new(&ex, &ex, &ex, &ex))?;
+    /// assert_eq!(store.graph_version(&ex)?.unwrap().version, 1);
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn graph_version<'a>(
+        &self,
+        graph_name: impl Into<GraphNameRef<'a>>,
+    ) -> Result<Option<GraphVersion>, StorageError> {
+        let graph_name = EncodedTerm::from(graph_name.into());
+        Ok(self
+            .storage
+            .snapshot()
+            .graph_version(&graph_name)?
+            .map(|(version, last_modified)| GraphVersion {
+                version,
+                last_modified: UNIX_EPOCH + Duration::from_secs(last_modified),
+            }))
+    }
+
     /// Inserts a graph into this store.
     ///
     /// Returns `true` if the graph was not already in the store.
@@ -753,6 +1035,22 @@ impl Store {
         self.storage.backup(target_directory.as_ref())
     }
 
+    /// Returns low-level metrics about the storage, useful for monitoring.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    ///
+    /// let store = Store::new()?;
+    /// let metrics = store.storage_metrics();
+    /// println!("About {} quads", metrics.estimated_quads);
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn storage_metrics(&self) -> StorageMetrics {
+        self.storage.metrics()
+    }
+
     /// Creates a bulk loader allowing to load at lot of data quickly into the store.
     ///
     /// Usage example:
@@ -1111,6 +1409,24 @@ impl<'a> Transaction<'a> {
         self.writer.remove(quad.into())
     }
 
+    /// Applies the committed operations of an [RDF Patch](crate::model::patch) inside of this transaction.
+    ///
+    /// Operations of aborted transactions are ignored and so are the prefix operations.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), StorageError> {
+        for operation in patch.committed_operations() {
+            match operation {
+                PatchOperation::AddQuad(quad) => {
+                    self.writer.insert(quad.as_ref())?;
+                }
+                PatchOperation::DeleteQuad(quad) => {
+                    self.writer.remove(quad.as_ref())?;
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
     /// Returns all the store named graphs.
     pub fn named_graphs(&self) -> GraphNameIter {
         let reader = self.writer.reader();
@@ -1130,6 +1446,42 @@ impl<'a> Transaction<'a> {
             .contains_named_graph(&EncodedTerm::from(graph_name.into()))
     }
 
+    /// Returns the current version of a graph.
+    ///
+    /// The version is locked until the end of the transaction:
+    /// if another transaction modifies the graph concurrently, one of the two transactions is retried.
+    /// It allows to check a version and to write the graph atomically (e.g. to implement HTTP `If-Match`).
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::{NamedNodeRef, QuadRef};
+    ///
+    /// let ex = NamedNodeRef::new_unchecked("http://example.com");
+    /// let store = Store::new()?;
+    /// store.insert(QuadRef::new(ex, ex, ex, ex))?;
+    /// store.transaction(|mut transaction| {
+    ///     if transaction.graph_version(ex)?.map_or(false, |v| v.version == 1) {
+    ///         transaction.clear_graph(ex)?;
+    ///     }
+    ///     Result::<_, oxigraph::store::StorageError>::Ok(())
+    /// })?;
+    /// assert_eq!(store.graph_version(ex)?.unwrap().version, 2);
+    /// # Result::<_, oxigraph::store::StorageError>::Ok(())
+    /// ```
+    pub fn graph_version<'b>(
+        &self,
+        graph_name: impl Into<GraphNameRef<'b>>,
+    ) -> Result<Option<GraphVersion>, StorageError> {
+        Ok(self
+            .writer
+            .graph_version_for_update(&EncodedTerm::from(graph_name.into()))?
+            .map(|(version, last_modified)| GraphVersion {
+                version,
+                last_modified: UNIX_EPOCH + Duration::from_secs(last_modified),
+            }))
+    }
+
     /// Inserts a graph into this store.
     ///
     /// Returns `true` if the graph was not already in the store.
@@ -1227,6 +1579,14 @@ impl<'a> Transaction<'a> {
     }
 }
 
+#[cfg(feature = "parquet")]
+fn parquet_to_io_error(error: ParquetError) -> io::Error {
+    match error {
+        ParquetError::External(error) => io::Error::new(io::ErrorKind::Other, error),
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
 /// An iterator returning the quads contained in a [`Store`].
 pub struct QuadIter {
     iter: ChainedDecodingQuadIterator,
@@ -1266,6 +1626,90 @@ impl Iterator for GraphNameIter {
     }
 }
 
+/// The version of a graph of a [`Store`].
+///
+/// Returned by [`Store::graph_version`].
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub struct GraphVersion {
+    /// A number incremented by each transaction modifying the graph.
+    pub version: u64,
+    /// The time of the last modification of the graph, with a precision of one second.
+    pub last_modified: SystemTime,
+}
+
+/// [VoID](https://www.w3.org/TR/void/) statistics about the content of a [`Store`].
+///
+/// Returned by [`Store::statistics`].
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct StoreStatistics {
+    /// The statistics of the whole dataset, i.e. of all the quads of the store.
+    pub dataset: GraphStatistics,
+    /// The statistics of the default graph.
+    pub default_graph: GraphStatistics,
+    /// The statistics of each named graph.
+    pub named_graphs: HashMap<NamedOrBlankNode, GraphStatistics>,
+}
+
+/// [VoID](https://www.w3.org/TR/void/) statistics about a graph or a dataset.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct GraphStatistics {
+    /// The number of triples (`void:triples`).
+    pub triples: usize,
+    /// The number of distinct subjects (`void:distinctSubjects`).
+    pub distinct_subjects: usize,
+    /// The number of distinct objects (`void:distinctObjects`).
+    pub distinct_objects: usize,
+    /// The number of triples for each predicate (`void:propertyPartition`).
+    pub properties: HashMap<NamedNode, usize>,
+    /// The number of distinct instances of each class, i.e. of `rdf:type` subjects (`void:classPartition`).
+    pub classes: HashMap<NamedNode, usize>,
+}
+
+#[derive(Default)]
+struct GraphStatisticsBuilder {
+    triples: usize,
+    subjects: HashSet<EncodedTerm>,
+    objects: HashSet<EncodedTerm>,
+    properties: HashMap<EncodedTerm, usize>,
+    classes: HashMap<EncodedTerm, HashSet<EncodedTerm>>,
+}
+
+impl GraphStatisticsBuilder {
+    fn add(&mut self, quad: &EncodedQuad, rdf_type: &EncodedTerm) {
+        self.triples += 1;
+        self.subjects.insert(quad.subject.clone());
+        self.objects.insert(quad.object.clone());
+        *self.properties.entry(quad.predicate.clone()).or_default() += 1;
+        if quad.predicate == *rdf_type {
+            self.classes
+                .entry(quad.object.clone())
+                .or_default()
+                .insert(quad.subject.clone());
+        }
+    }
+
+    fn build(self, reader: &StorageReader) -> Result<GraphStatistics, StorageError> {
+        let mut classes = HashMap::with_capacity(self.classes.len());
+        for (class, instances) in self.classes {
+            // Only IRIs are classes of class partitions
+            if let Term::NamedNode(class) = reader.decode_term(&class)? {
+                classes.insert(class, instances.len());
+            }
+        }
+        Ok(GraphStatistics {
+            triples: self.triples,
+            distinct_subjects: self.subjects.len(),
+            distinct_objects: self.objects.len(),
+            properties: self
+                .properties
+                .into_iter()
+                .map(|(property, count)| Ok((reader.decode_named_node(&property)?, count)))
+                .collect::<Result<_, StorageError>>()?,
+            classes,
+        })
+    }
+}
+
 /// A bulk loader allowing to load at lot of data quickly into the store.
 ///
 /// Warning: The operations provided here are not atomic.
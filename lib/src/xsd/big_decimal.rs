@@ -0,0 +1,484 @@
+use crate::xsd::decimal::{
+    ParseDecimalError, PARSE_OVERFLOW, PARSE_UNEXPECTED_CHAR, PARSE_UNEXPECTED_END,
+};
+use crate::xsd::{BigInteger, Decimal, Double, Float};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Write;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// Number of digits kept after "." in addition to the ones of the operands in division results.
+const DIVISION_EXTRA_DIGITS: u32 = 36;
+
+/// [XML Schema `decimal` datatype](https://www.w3.org/TR/xmlschema11-2/#decimal) implementation with arbitrary precision.
+///
+/// Addition, subtraction and multiplication are exact.
+/// Division results are truncated to `DIVISION_EXTRA_DIGITS` (36) more digits after "." than the operand with the most of them.
+///
+/// Values that are representable by [`Decimal`] are usually handled as [`Decimal`] and this type only used for the other ones.
+#[derive(Eq, PartialEq, Debug, Clone, Hash, Default)]
+pub struct BigDecimal {
+    value: BigInt, // the decimal is value / 10^scale
+    scale: u32,    // always normalized: value is not a multiple of 10 if scale > 0
+}
+
+impl BigDecimal {
+    /// Constructs the decimal i / 10^n
+    #[inline]
+    pub fn new(i: impl Into<BigInteger>, n: u32) -> Self {
+        Self::normalized(i.into().as_big_int().clone(), n)
+    }
+
+    fn normalized(mut value: BigInt, mut scale: u32) -> Self {
+        if value.is_zero() {
+            return Self::default();
+        }
+        let ten = BigInt::from(10);
+        while scale > 0 {
+            let (quotient, remainder) = value.div_rem(&ten);
+            if !remainder.is_zero() {
+                break;
+            }
+            value = quotient;
+            scale -= 1;
+        }
+        Self { value, scale }
+    }
+
+    /// Returns the value multiplied by 10^scale with scale >= self.scale
+    fn scaled_value(&self, scale: u32) -> BigInt {
+        &self.value * pow10(scale - self.scale)
+    }
+
+    /// [op:numeric-divide](https://www.w3.org/TR/xpath-functions/#func-numeric-divide)
+    ///
+    /// The result is truncated to `max(self.scale, rhs.scale) + DIVISION_EXTRA_DIGITS` digits after ".".
+    pub fn checked_div(&self, rhs: impl Into<Self>) -> Option<Self> {
+        let rhs = rhs.into();
+        if rhs.value.is_zero() {
+            return None;
+        }
+        let scale = self.scale.max(rhs.scale) + DIVISION_EXTRA_DIGITS;
+        Some(Self::normalized(
+            &self.value * pow10(scale - self.scale + rhs.scale) / &rhs.value,
+            scale,
+        ))
+    }
+
+    /// [op:numeric-mod](https://www.w3.org/TR/xpath-functions/#func-numeric-mod)
+    pub fn checked_rem(&self, rhs: impl Into<Self>) -> Option<Self> {
+        let rhs = rhs.into();
+        if rhs.value.is_zero() {
+            return None;
+        }
+        let scale = self.scale.max(rhs.scale);
+        Some(Self::normalized(
+            self.scaled_value(scale) % rhs.scaled_value(scale),
+            scale,
+        ))
+    }
+
+    /// [fn:abs](https://www.w3.org/TR/xpath-functions/#func-abs)
+    #[inline]
+    pub fn abs(&self) -> Self {
+        Self {
+            value: self.value.abs(),
+            scale: self.scale,
+        }
+    }
+
+    /// [fn:round](https://www.w3.org/TR/xpath-functions/#func-round)
+    pub fn round(&self) -> Self {
+        if self.scale == 0 {
+            return self.clone();
+        }
+        let divisor = pow10(self.scale);
+        let half: BigInt = &divisor / 2;
+        Self::normalized((&self.value + half).div_floor(&divisor), 0)
+    }
+
+    /// [fn:ceiling](https://www.w3.org/TR/xpath-functions/#func-ceiling)
+    pub fn ceil(&self) -> Self {
+        if self.scale == 0 {
+            return self.clone();
+        }
+        Self::normalized(-(-&self.value).div_floor(&pow10(self.scale)), 0)
+    }
+
+    /// [fn:floor](https://www.w3.org/TR/xpath-functions/#func-floor)
+    pub fn floor(&self) -> Self {
+        if self.scale == 0 {
+            return self.clone();
+        }
+        Self::normalized(self.value.div_floor(&pow10(self.scale)), 0)
+    }
+
+    #[inline]
+    pub fn is_negative(&self) -> bool {
+        self.value.is_negative()
+    }
+
+    #[inline]
+    pub fn is_positive(&self) -> bool {
+        self.value.is_positive()
+    }
+
+    /// Returns the integer part of the decimal i.e. it truncates the decimal toward zero.
+    #[inline]
+    pub fn to_integer(&self) -> BigInteger {
+        (&self.value / pow10(self.scale)).into()
+    }
+
+    /// Creates a `BigDecimal` from a `Float`.
+    ///
+    /// Returns `None` if the float is `NaN` or infinite.
+    pub fn from_float(v: Float) -> Option<Self> {
+        let v = f32::from(v);
+        if !v.is_finite() {
+            return None;
+        }
+        // The Display implementation of f32 never uses the scientific notation
+        v.to_string().parse().ok()
+    }
+
+    /// Creates a `BigDecimal` from a `Double`.
+    ///
+    /// Returns `None` if the double is `NaN` or infinite.
+    pub fn from_double(v: Double) -> Option<Self> {
+        let v = f64::from(v);
+        if !v.is_finite() {
+            return None;
+        }
+        // The Display implementation of f64 never uses the scientific notation
+        v.to_string().parse().ok()
+    }
+
+    /// Creates a `Float` from a `BigDecimal` without taking care of precision
+    #[inline]
+    pub fn to_float(&self) -> Float {
+        self.to_double().to_f32().into()
+    }
+
+    /// Creates a `Double` from a `BigDecimal` without taking care of precision
+    #[inline]
+    pub fn to_double(&self) -> Double {
+        self.to_string().parse().unwrap_or_else(|_| f64::NAN.into())
+    }
+
+    /// Creates a `bool` from a `BigDecimal` according to xsd:boolean cast constraints
+    #[inline]
+    pub fn to_bool(&self) -> bool {
+        !self.value.is_zero()
+    }
+}
+
+fn pow10(exponent: u32) -> BigInt {
+    BigInt::from(10).pow(exponent)
+}
+
+impl From<i32> for BigDecimal {
+    #[inline]
+    fn from(value: i32) -> Self {
+        Self {
+            value: value.into(),
+            scale: 0,
+        }
+    }
+}
+
+impl From<i64> for BigDecimal {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Self {
+            value: value.into(),
+            scale: 0,
+        }
+    }
+}
+
+impl From<BigInteger> for BigDecimal {
+    #[inline]
+    fn from(value: BigInteger) -> Self {
+        Self {
+            value: value.as_big_int().clone(),
+            scale: 0,
+        }
+    }
+}
+
+impl From<Decimal> for BigDecimal {
+    #[inline]
+    fn from(value: Decimal) -> Self {
+        Self::normalized(value.to_scaled_i128().into(), Decimal::SCALE)
+    }
+}
+
+impl TryFrom<&BigDecimal> for Decimal {
+    type Error = DecimalPrecisionError;
+
+    /// Converts the decimal to a [`Decimal`] if it is possible without loss of precision.
+    fn try_from(value: &BigDecimal) -> Result<Self, DecimalPrecisionError> {
+        if value.scale > Decimal::SCALE {
+            return Err(DecimalPrecisionError);
+        }
+        Ok(Decimal::from_scaled_i128(
+            value
+                .scaled_value(Decimal::SCALE)
+                .to_i128()
+                .ok_or(DecimalPrecisionError)?,
+        ))
+    }
+}
+
+impl PartialOrd for BigDecimal {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigDecimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.scaled_value(scale).cmp(&other.scaled_value(scale))
+    }
+}
+
+impl FromStr for BigDecimal {
+    type Err = ParseDecimalError;
+
+    /// Parses decimals lexical mapping
+    fn from_str(input: &str) -> Result<Self, ParseDecimalError> {
+        // (\+|-)?([0-9]+(\.[0-9]*)?|\.[0-9]+)
+        let unsigned = input.strip_prefix(&['+', '-'][..]).unwrap_or(input);
+        let (before_dot, after_dot) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if before_dot.is_empty() && after_dot.is_empty() {
+            return Err(PARSE_UNEXPECTED_END);
+        }
+        if !before_dot
+            .bytes()
+            .chain(after_dot.bytes())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(PARSE_UNEXPECTED_CHAR);
+        }
+        let mut digits = String::with_capacity(before_dot.len() + after_dot.len() + 1);
+        if input.starts_with('-') {
+            digits.push('-');
+        }
+        digits.push_str(before_dot);
+        digits.push_str(after_dot);
+        Ok(Self::normalized(
+            BigInt::from_str(&digits).map_err(|_| PARSE_UNEXPECTED_CHAR)?,
+            after_dot.len().try_into().map_err(|_| PARSE_OVERFLOW)?,
+        ))
+    }
+}
+
+impl fmt::Display for BigDecimal {
+    /// Formats the decimal following its canonical representation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.value.abs().to_string();
+        if self.value.is_negative() {
+            f.write_char('-')?;
+        }
+        let scale = usize::try_from(self.scale).map_err(|_| fmt::Error)?;
+        if digits.len() > scale {
+            let (before_dot, after_dot) = digits.split_at(digits.len() - scale);
+            f.write_str(before_dot)?;
+            if !after_dot.is_empty() {
+                f.write_char('.')?;
+                f.write_str(after_dot)?;
+            }
+        } else {
+            f.write_str("0.")?;
+            for _ in digits.len()..scale {
+                f.write_char('0')?;
+            }
+            f.write_str(&digits)?;
+        }
+        Ok(())
+    }
+}
+
+impl Neg for BigDecimal {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        Self {
+            value: self.value.neg(),
+            scale: self.scale,
+        }
+    }
+}
+
+impl Add for BigDecimal {
+    type Output = Self;
+
+    /// [op:numeric-add](https://www.w3.org/TR/xpath-functions/#func-numeric-add)
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        let scale = self.scale.max(rhs.scale);
+        Self::normalized(self.scaled_value(scale) + rhs.scaled_value(scale), scale)
+    }
+}
+
+impl Sub for BigDecimal {
+    type Output = Self;
+
+    /// [op:numeric-subtract](https://www.w3.org/TR/xpath-functions/#func-numeric-subtract)
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        let scale = self.scale.max(rhs.scale);
+        Self::normalized(self.scaled_value(scale) - rhs.scaled_value(scale), scale)
+    }
+}
+
+impl Mul for BigDecimal {
+    type Output = Self;
+
+    /// [op:numeric-multiply](https://www.w3.org/TR/xpath-functions/#func-numeric-multiply)
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        Self::normalized(self.value * rhs.value, self.scale + rhs.scale)
+    }
+}
+
+/// An error when a decimal value can't be converted without loss of precision.
+#[derive(Debug, Clone, Copy)]
+pub struct DecimalPrecisionError;
+
+impl fmt::Display for DecimalPrecisionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Value can't be represented without loss of precision")
+    }
+}
+
+impl std::error::Error for DecimalPrecisionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(d("210").to_string(), "210");
+        assert_eq!(d("1000").to_string(), "1000");
+        assert_eq!(d("-1.23").to_string(), "-1.23");
+        assert_eq!(d("+100000.00").to_string(), "100000");
+        assert_eq!(d("0.1220").to_string(), "0.122");
+        assert_eq!(d(".12200").to_string(), "0.122");
+        assert_eq!(d("-.005").to_string(), "-0.005");
+        assert_eq!(d("12.").to_string(), "12");
+        assert_eq!(d("-0.0").to_string(), "0");
+        assert_eq!(
+            d("123456789012345678901234567890.123456789012345678901234567890").to_string(),
+            "123456789012345678901234567890.12345678901234567890123456789"
+        );
+        assert!(BigDecimal::from_str("").is_err());
+        assert!(BigDecimal::from_str(".").is_err());
+        assert!(BigDecimal::from_str("-").is_err());
+        assert!(BigDecimal::from_str("1.2.3").is_err());
+        assert!(BigDecimal::from_str("1e3").is_err());
+    }
+
+    #[test]
+    fn cmp() {
+        assert!(d("1.1") > d("1.09999999999999999999999"));
+        assert!(d("-1.1") < d("-1.09999999999999999999999"));
+        assert_eq!(d("10.0"), d("10"));
+    }
+
+    #[test]
+    fn add() {
+        assert_eq!(
+            d("0.1000000000000000000000001") + d("0.9"),
+            d("1.0000000000000000000000001")
+        );
+        assert_eq!(d("1.5") + d("-1.5"), BigDecimal::default());
+    }
+
+    #[test]
+    fn sub() {
+        assert_eq!(
+            d("1") - d("0.0000000000000000000001"),
+            d("0.9999999999999999999999")
+        );
+    }
+
+    #[test]
+    fn mul() {
+        assert_eq!(
+            d("0.00000000000000000001") * d("0.00000000000000000001"),
+            d("0.0000000000000000000000000000000000000001")
+        );
+        assert_eq!(
+            d("99999999999999999999.5") * d("2"),
+            d("199999999999999999999")
+        );
+    }
+
+    #[test]
+    fn div() {
+        assert_eq!(d("1").checked_div(d("8")), Some(d("0.125")));
+        assert_eq!(d("10").checked_div(d("100")), Some(d("0.1")));
+        assert_eq!(
+            d("1").checked_div(d("3")),
+            Some(d("0.333333333333333333333333333333333333"))
+        );
+        assert_eq!(d("1").checked_div(d("0")), None);
+        assert_eq!(d("7.5").checked_rem(d("2")), Some(d("1.5")));
+        assert_eq!(d("-7.5").checked_rem(d("2")), Some(d("-1.5")));
+    }
+
+    #[test]
+    fn round() {
+        assert_eq!(d("2.5").round(), d("3"));
+        assert_eq!(d("2.4999").round(), d("2"));
+        assert_eq!(d("-2.5").round(), d("-2"));
+        assert_eq!(d("-2.51").round(), d("-3"));
+    }
+
+    #[test]
+    fn ceil() {
+        assert_eq!(d("10.5").ceil(), d("11"));
+        assert_eq!(d("-10.5").ceil(), d("-10"));
+        assert_eq!(d("10").ceil(), d("10"));
+    }
+
+    #[test]
+    fn floor() {
+        assert_eq!(d("10.5").floor(), d("10"));
+        assert_eq!(d("-10.5").floor(), d("-11"));
+        assert_eq!(d("-10").floor(), d("-10"));
+    }
+
+    #[test]
+    fn decimal_conversion() {
+        let value = Decimal::from_str("-1.000000000000000001").unwrap();
+        assert_eq!(BigDecimal::from(value), d("-1.000000000000000001"));
+        assert_eq!(Decimal::try_from(&BigDecimal::from(value)).unwrap(), value);
+        assert!(Decimal::try_from(&d("0.0000000000000000001")).is_err());
+        assert!(Decimal::try_from(&d("1000000000000000000000000")).is_err());
+    }
+
+    #[test]
+    fn double_conversion() {
+        assert_eq!(BigDecimal::from_double(0.5.into()), Some(d("0.5")));
+        assert_eq!(
+            BigDecimal::from_double(1e30.into()),
+            Some(d("1000000000000000000000000000000"))
+        );
+        assert_eq!(BigDecimal::from_double(f64::NAN.into()), None);
+        assert_eq!(f64::from(d("0.25").to_double()), 0.25);
+    }
+}
@@ -0,0 +1,318 @@
+use crate::xsd::decimal::{ParseDecimalError, PARSE_UNEXPECTED_CHAR, PARSE_UNEXPECTED_END};
+use crate::xsd::{BigDecimal, Decimal, Double, Float};
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// [XML Schema `integer` datatype](https://www.w3.org/TR/xmlschema11-2/#integer) implementation with arbitrary precision.
+///
+/// Values fitting in an `i64` are usually handled directly as `i64` and this type only used when they overflow.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Hash, Default)]
+pub struct BigInteger {
+    value: BigInt,
+}
+
+impl BigInteger {
+    /// [op:numeric-integer-divide](https://www.w3.org/TR/xpath-functions/#func-numeric-integer-divide)
+    #[inline]
+    pub fn checked_div(&self, rhs: impl Into<Self>) -> Option<Self> {
+        let rhs = rhs.into();
+        if rhs.value.is_zero() {
+            return None;
+        }
+        Some(Self {
+            value: &self.value / rhs.value,
+        })
+    }
+
+    /// [op:numeric-mod](https://www.w3.org/TR/xpath-functions/#func-numeric-mod)
+    #[inline]
+    pub fn checked_rem(&self, rhs: impl Into<Self>) -> Option<Self> {
+        let rhs = rhs.into();
+        if rhs.value.is_zero() {
+            return None;
+        }
+        Some(Self {
+            value: &self.value % rhs.value,
+        })
+    }
+
+    /// [fn:abs](https://www.w3.org/TR/xpath-functions/#func-abs)
+    #[inline]
+    pub fn abs(&self) -> Self {
+        Self {
+            value: self.value.abs(),
+        }
+    }
+
+    #[inline]
+    pub fn is_negative(&self) -> bool {
+        self.value.is_negative()
+    }
+
+    #[inline]
+    pub fn is_positive(&self) -> bool {
+        self.value.is_positive()
+    }
+
+    /// Creates a `BigInteger` from a `Float` by truncating the fractional part.
+    ///
+    /// Returns `None` if the float is `NaN` or infinite.
+    #[inline]
+    pub fn from_float(v: Float) -> Option<Self> {
+        Self::from_double(v.into())
+    }
+
+    /// Creates a `BigInteger` from a `Double` by truncating the fractional part.
+    ///
+    /// Returns `None` if the double is `NaN` or infinite.
+    #[inline]
+    pub fn from_double(v: Double) -> Option<Self> {
+        Some(BigDecimal::from_double(v)?.to_integer())
+    }
+
+    /// Creates a `Float` from a `BigInteger` without taking care of precision
+    #[inline]
+    pub fn to_float(&self) -> Float {
+        self.to_double().to_f32().into()
+    }
+
+    /// Creates a `Double` from a `BigInteger` without taking care of precision
+    #[inline]
+    pub fn to_double(&self) -> Double {
+        self.value.to_f64().unwrap_or(f64::NAN).into()
+    }
+
+    /// Creates a `bool` from a `BigInteger` according to xsd:boolean cast constraints
+    #[inline]
+    pub fn to_bool(&self) -> bool {
+        !self.value.is_zero()
+    }
+
+    #[inline]
+    pub(super) fn as_big_int(&self) -> &BigInt {
+        &self.value
+    }
+}
+
+impl From<i32> for BigInteger {
+    #[inline]
+    fn from(value: i32) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+impl From<i64> for BigInteger {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+impl From<BigInt> for BigInteger {
+    #[inline]
+    fn from(value: BigInt) -> Self {
+        Self { value }
+    }
+}
+
+impl TryFrom<BigInteger> for i64 {
+    type Error = IntegerOverflowError;
+
+    #[inline]
+    fn try_from(value: BigInteger) -> Result<Self, IntegerOverflowError> {
+        value.value.to_i64().ok_or(IntegerOverflowError)
+    }
+}
+
+impl TryFrom<&BigInteger> for i64 {
+    type Error = IntegerOverflowError;
+
+    #[inline]
+    fn try_from(value: &BigInteger) -> Result<Self, IntegerOverflowError> {
+        value.value.to_i64().ok_or(IntegerOverflowError)
+    }
+}
+
+impl TryFrom<&BigInteger> for i128 {
+    type Error = IntegerOverflowError;
+
+    #[inline]
+    fn try_from(value: &BigInteger) -> Result<Self, IntegerOverflowError> {
+        value.value.to_i128().ok_or(IntegerOverflowError)
+    }
+}
+
+impl TryFrom<&BigInteger> for Decimal {
+    type Error = IntegerOverflowError;
+
+    #[inline]
+    fn try_from(value: &BigInteger) -> Result<Self, IntegerOverflowError> {
+        Decimal::try_from(value.value.to_i128().ok_or(IntegerOverflowError)?)
+            .map_err(|_| IntegerOverflowError)
+    }
+}
+
+impl FromStr for BigInteger {
+    type Err = ParseDecimalError;
+
+    /// Parses integers lexical mapping
+    fn from_str(input: &str) -> Result<Self, ParseDecimalError> {
+        // (\+|-)?[0-9]+
+        let digits = input.strip_prefix(&['+', '-'][..]).unwrap_or(input);
+        if digits.is_empty() {
+            return Err(PARSE_UNEXPECTED_END);
+        }
+        if !digits.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(PARSE_UNEXPECTED_CHAR);
+        }
+        let value = BigInt::from_str(digits).map_err(|_| PARSE_UNEXPECTED_CHAR)?;
+        Ok(Self {
+            value: if input.starts_with('-') {
+                -value
+            } else {
+                value
+            },
+        })
+    }
+}
+
+impl fmt::Display for BigInteger {
+    /// Formats the integer following its canonical representation.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl Neg for BigInteger {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        Self {
+            value: self.value.neg(),
+        }
+    }
+}
+
+impl Add for BigInteger {
+    type Output = Self;
+
+    /// [op:numeric-add](https://www.w3.org/TR/xpath-functions/#func-numeric-add)
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            value: self.value + rhs.value,
+        }
+    }
+}
+
+impl Sub for BigInteger {
+    type Output = Self;
+
+    /// [op:numeric-subtract](https://www.w3.org/TR/xpath-functions/#func-numeric-subtract)
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            value: self.value - rhs.value,
+        }
+    }
+}
+
+impl Mul for BigInteger {
+    type Output = Self;
+
+    /// [op:numeric-multiply](https://www.w3.org/TR/xpath-functions/#func-numeric-multiply)
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            value: self.value * rhs.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IntegerOverflowError;
+
+impl fmt::Display for IntegerOverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Value overflow")
+    }
+}
+
+impl std::error::Error for IntegerOverflowError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        assert_eq!(BigInteger::from_str("0").unwrap().to_string(), "0");
+        assert_eq!(BigInteger::from_str("-0").unwrap().to_string(), "0");
+        assert_eq!(BigInteger::from_str("+0012").unwrap().to_string(), "12");
+        assert_eq!(
+            BigInteger::from_str("-123456789012345678901234567890")
+                .unwrap()
+                .to_string(),
+            "-123456789012345678901234567890"
+        );
+        assert!(BigInteger::from_str("").is_err());
+        assert!(BigInteger::from_str("+").is_err());
+        assert!(BigInteger::from_str("1_000").is_err());
+        assert!(BigInteger::from_str("1.0").is_err());
+        assert!(BigInteger::from_str(" 1").is_err());
+    }
+
+    #[test]
+    fn add() {
+        assert_eq!(
+            BigInteger::from(i64::MAX) + 1.into(),
+            BigInteger::from_str("9223372036854775808").unwrap()
+        );
+        assert_eq!(
+            BigInteger::from(i64::MIN) + i64::MAX.into(),
+            BigInteger::from(-1)
+        );
+    }
+
+    #[test]
+    fn mul() {
+        assert_eq!(
+            (BigInteger::from(i64::MAX) * i64::MAX.into()).to_string(),
+            "85070591730234615847396907784232501249"
+        );
+    }
+
+    #[test]
+    fn div() {
+        assert_eq!(BigInteger::from(7).checked_div(2), Some(3.into()));
+        assert_eq!(BigInteger::from(-7).checked_div(2), Some((-3).into()));
+        assert_eq!(BigInteger::from(7).checked_div(0), None);
+        assert_eq!(BigInteger::from(-7).checked_rem(2), Some((-1).into()));
+    }
+
+    #[test]
+    fn to_i64() {
+        assert_eq!(i64::try_from(BigInteger::from(i64::MIN)).unwrap(), i64::MIN);
+        assert!(i64::try_from(BigInteger::from(i64::MAX) + 1.into()).is_err());
+    }
+
+    #[test]
+    fn from_double() {
+        assert_eq!(
+            BigInteger::from_double(1e20.into()).unwrap().to_string(),
+            "100000000000000000000"
+        );
+        assert_eq!(BigInteger::from_double((-2.7).into()).unwrap(), (-2).into());
+        assert!(BigInteger::from_double(f64::NAN.into()).is_none());
+        assert!(BigInteger::from_double(f64::INFINITY.into()).is_none());
+    }
+}
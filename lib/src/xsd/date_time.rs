@@ -170,6 +170,13 @@ impl DateTime {
         }
     }
 
+    /// [fn:adjust-dateTime-to-timezone](https://www.w3.org/TR/xpath-functions/#func-adjust-dateTime-to-timezone)
+    pub fn adjust(&self, timezone_offset: Option<TimezoneOffset>) -> Option<Self> {
+        Some(Self {
+            timestamp: self.timestamp.adjust(timezone_offset)?,
+        })
+    }
+
     pub fn is_identical_with(&self, other: &Self) -> bool {
         self.timestamp.is_identical_with(&other.timestamp)
     }
@@ -1053,6 +1060,23 @@ impl From<i16> for TimezoneOffset {
     }
 }
 
+impl TryFrom<DayTimeDuration> for TimezoneOffset {
+    type Error = DateTimeError;
+
+    fn try_from(value: DayTimeDuration) -> Result<Self, DateTimeError> {
+        let result = i16::try_from(value.all_seconds().as_i128() / 60)
+            .ok()
+            .map(Self::new)
+            .filter(|offset| offset.offset.abs() <= 14 * 60);
+        match result {
+            Some(result) if DayTimeDuration::from(result) == value => Ok(result),
+            _ => Err(DateTimeError {
+                kind: DateTimeErrorKind::InvalidTimezone(value),
+            }),
+        }
+    }
+}
+
 impl From<TimezoneOffset> for DayTimeDuration {
     fn from(value: TimezoneOffset) -> Self {
         Self::new(i32::from(value.offset) * 60)
@@ -1318,6 +1342,28 @@ impl Timestamp {
         }
     }
 
+    fn adjust(&self, timezone_offset: Option<TimezoneOffset>) -> Option<Self> {
+        Some(match (self.timezone_offset, timezone_offset) {
+            // The point in time does not change
+            (Some(_), Some(to_timezone)) => Self {
+                value: self.value,
+                timezone_offset: Some(to_timezone),
+            },
+            // The local time does not change
+            (Some(from_timezone), None) => Self {
+                value: self
+                    .value
+                    .checked_add(i64::from(from_timezone.offset) * 60)?,
+                timezone_offset: None,
+            },
+            (None, Some(to_timezone)) => Self {
+                value: self.value.checked_sub(i64::from(to_timezone.offset) * 60)?,
+                timezone_offset: Some(to_timezone),
+            },
+            (None, None) => *self,
+        })
+    }
+
     fn checked_sub_seconds(&self, seconds: Decimal) -> Option<Self> {
         Some(Self {
             value: self.value.checked_sub(seconds)?,
@@ -1513,6 +1559,7 @@ pub struct DateTimeError {
 #[derive(Debug, Clone)]
 enum DateTimeErrorKind {
     InvalidDayOfMonth { day: u8, month: u8 },
+    InvalidTimezone(DayTimeDuration),
     Overflow,
     SystemTime(SystemTimeError),
 }
@@ -1523,6 +1570,9 @@ impl fmt::Display for DateTimeError {
             DateTimeErrorKind::InvalidDayOfMonth { day, month } => {
                 write!(f, "{} is not a valid day of {}", day, month)
             }
+            DateTimeErrorKind::InvalidTimezone(timezone) => {
+                write!(f, "{} is not a valid timezone offset", timezone)
+            }
             DateTimeErrorKind::Overflow => write!(f, "Overflow during date time normalization"),
             DateTimeErrorKind::SystemTime(error) => error.fmt(f),
         }
@@ -2070,6 +2120,38 @@ mod tests {
         assert_eq!(Time::from_str("13:20:00").unwrap().timezone(), None);
     }
 
+    #[test]
+    fn adjust() {
+        let offset =
+            |tz: &str| TimezoneOffset::try_from(DayTimeDuration::from_str(tz).unwrap()).unwrap();
+        assert_eq!(
+            DateTime::from_str("2002-03-07T10:00:00-05:00")
+                .unwrap()
+                .adjust(Some(offset("-PT10H")))
+                .unwrap()
+                .to_string(),
+            "2002-03-07T05:00:00-10:00"
+        );
+        assert_eq!(
+            DateTime::from_str("2002-03-07T10:00:00")
+                .unwrap()
+                .adjust(Some(offset("-PT10H")))
+                .unwrap()
+                .to_string(),
+            "2002-03-07T10:00:00-10:00"
+        );
+        assert_eq!(
+            DateTime::from_str("2002-03-07T10:00:00-07:00")
+                .unwrap()
+                .adjust(None)
+                .unwrap()
+                .to_string(),
+            "2002-03-07T10:00:00"
+        );
+        assert!(TimezoneOffset::try_from(DayTimeDuration::from_str("PT15H").unwrap()).is_err());
+        assert!(TimezoneOffset::try_from(DayTimeDuration::from_str("PT1M30S").unwrap()).is_err());
+    }
+
     #[test]
     fn sub() {
         assert_eq!(
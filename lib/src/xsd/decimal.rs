@@ -19,6 +19,9 @@ pub struct Decimal {
 }
 
 impl Decimal {
+    /// Number of digits stored after "."
+    pub(super) const SCALE: u32 = DECIMAL_PART_DIGITS as u32;
+
     /// Constructs the decimal i / 10^n
     #[allow(clippy::cast_possible_truncation)]
     pub fn new(i: i128, n: u32) -> Result<Self, DecimalOverflowError> {
@@ -189,6 +192,18 @@ impl Decimal {
         self.value != 0
     }
 
+    /// Returns the fix point encoding of the decimal i.e. the decimal multiplied by 10^18
+    #[inline]
+    pub(super) const fn to_scaled_i128(self) -> i128 {
+        self.value
+    }
+
+    /// Constructs the decimal i / 10^18
+    #[inline]
+    pub(super) const fn from_scaled_i128(value: i128) -> Self {
+        Self { value }
+    }
+
     #[inline]
     pub(super) const fn as_i128(&self) -> i128 {
         self.value / DECIMAL_PART_POW
@@ -392,16 +407,16 @@ enum ParseDecimalErrorKind {
     UnexpectedEnd,
 }
 
-const PARSE_OVERFLOW: ParseDecimalError = ParseDecimalError {
+pub(super) const PARSE_OVERFLOW: ParseDecimalError = ParseDecimalError {
     kind: ParseDecimalErrorKind::Overflow,
 };
 const PARSE_UNDERFLOW: ParseDecimalError = ParseDecimalError {
     kind: ParseDecimalErrorKind::Underflow,
 };
-const PARSE_UNEXPECTED_CHAR: ParseDecimalError = ParseDecimalError {
+pub(super) const PARSE_UNEXPECTED_CHAR: ParseDecimalError = ParseDecimalError {
     kind: ParseDecimalErrorKind::UnexpectedChar,
 };
-const PARSE_UNEXPECTED_END: ParseDecimalError = ParseDecimalError {
+pub(super) const PARSE_UNEXPECTED_END: ParseDecimalError = ParseDecimalError {
     kind: ParseDecimalErrorKind::UnexpectedEnd,
 };
 
@@ -0,0 +1,147 @@
+use crate::model::vocab::xsd;
+use crate::model::NamedNodeRef;
+use crate::xsd::BigInteger;
+
+/// The [XML Schema datatypes derived from `integer`](https://www.w3.org/TR/xmlschema11-2/#built-in-datatypes).
+///
+/// Values of these datatypes are integers with some extra range constraints.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum IntegerSubtype {
+    NonPositiveInteger,
+    NegativeInteger,
+    Long,
+    Int,
+    Short,
+    Byte,
+    NonNegativeInteger,
+    UnsignedLong,
+    UnsignedInt,
+    UnsignedShort,
+    UnsignedByte,
+    PositiveInteger,
+}
+
+impl IntegerSubtype {
+    pub fn from_iri(iri: &str) -> Option<Self> {
+        Some(match iri {
+            "http://www.w3.org/2001/XMLSchema#nonPositiveInteger" => Self::NonPositiveInteger,
+            "http://www.w3.org/2001/XMLSchema#negativeInteger" => Self::NegativeInteger,
+            "http://www.w3.org/2001/XMLSchema#long" => Self::Long,
+            "http://www.w3.org/2001/XMLSchema#int" => Self::Int,
+            "http://www.w3.org/2001/XMLSchema#short" => Self::Short,
+            "http://www.w3.org/2001/XMLSchema#byte" => Self::Byte,
+            "http://www.w3.org/2001/XMLSchema#nonNegativeInteger" => Self::NonNegativeInteger,
+            "http://www.w3.org/2001/XMLSchema#unsignedLong" => Self::UnsignedLong,
+            "http://www.w3.org/2001/XMLSchema#unsignedInt" => Self::UnsignedInt,
+            "http://www.w3.org/2001/XMLSchema#unsignedShort" => Self::UnsignedShort,
+            "http://www.w3.org/2001/XMLSchema#unsignedByte" => Self::UnsignedByte,
+            "http://www.w3.org/2001/XMLSchema#positiveInteger" => Self::PositiveInteger,
+            _ => return None,
+        })
+    }
+
+    pub fn iri(self) -> NamedNodeRef<'static> {
+        match self {
+            Self::NonPositiveInteger => xsd::NON_POSITIVE_INTEGER,
+            Self::NegativeInteger => xsd::NEGATIVE_INTEGER,
+            Self::Long => xsd::LONG,
+            Self::Int => xsd::INT,
+            Self::Short => xsd::SHORT,
+            Self::Byte => xsd::BYTE,
+            Self::NonNegativeInteger => xsd::NON_NEGATIVE_INTEGER,
+            Self::UnsignedLong => xsd::UNSIGNED_LONG,
+            Self::UnsignedInt => xsd::UNSIGNED_INT,
+            Self::UnsignedShort => xsd::UNSIGNED_SHORT,
+            Self::UnsignedByte => xsd::UNSIGNED_BYTE,
+            Self::PositiveInteger => xsd::POSITIVE_INTEGER,
+        }
+    }
+
+    /// The datatype local name in the XML Schema namespace
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::NonPositiveInteger => "nonPositiveInteger",
+            Self::NegativeInteger => "negativeInteger",
+            Self::Long => "long",
+            Self::Int => "int",
+            Self::Short => "short",
+            Self::Byte => "byte",
+            Self::NonNegativeInteger => "nonNegativeInteger",
+            Self::UnsignedLong => "unsignedLong",
+            Self::UnsignedInt => "unsignedInt",
+            Self::UnsignedShort => "unsignedShort",
+            Self::UnsignedByte => "unsignedByte",
+            Self::PositiveInteger => "positiveInteger",
+        }
+    }
+
+    /// The inclusive bounds of the datatype value space, `None` meaning unbounded
+    fn bounds(self) -> (Option<i128>, Option<i128>) {
+        match self {
+            Self::NonPositiveInteger => (None, Some(0)),
+            Self::NegativeInteger => (None, Some(-1)),
+            Self::Long => (Some(i64::MIN.into()), Some(i64::MAX.into())),
+            Self::Int => (Some(i32::MIN.into()), Some(i32::MAX.into())),
+            Self::Short => (Some(i16::MIN.into()), Some(i16::MAX.into())),
+            Self::Byte => (Some(i8::MIN.into()), Some(i8::MAX.into())),
+            Self::NonNegativeInteger => (Some(0), None),
+            Self::UnsignedLong => (Some(0), Some(u64::MAX.into())),
+            Self::UnsignedInt => (Some(0), Some(u32::MAX.into())),
+            Self::UnsignedShort => (Some(0), Some(u16::MAX.into())),
+            Self::UnsignedByte => (Some(0), Some(u8::MAX.into())),
+            Self::PositiveInteger => (Some(1), None),
+        }
+    }
+
+    /// Checks if the value is in the datatype value space
+    pub fn contains(self, value: &BigInteger) -> bool {
+        let (min, max) = self.bounds();
+        if let Ok(value) = i128::try_from(value) {
+            min.map_or(true, |min| min <= value) && max.map_or(true, |max| value <= max)
+        } else if value.is_negative() {
+            min.is_none()
+        } else {
+            max.is_none()
+        }
+    }
+
+    /// Checks if the value is in the datatype value space
+    pub fn contains_i64(self, value: i64) -> bool {
+        let (min, max) = self.bounds();
+        let value = i128::from(value);
+        min.map_or(true, |min| min <= value) && max.map_or(true, |max| value <= max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn from_iri() {
+        assert_eq!(
+            IntegerSubtype::from_iri(xsd::UNSIGNED_SHORT.as_str()),
+            Some(IntegerSubtype::UnsignedShort)
+        );
+        assert_eq!(IntegerSubtype::from_iri(xsd::INTEGER.as_str()), None);
+        assert_eq!(IntegerSubtype::Int.iri(), xsd::INT);
+    }
+
+    #[test]
+    fn contains() {
+        assert!(IntegerSubtype::Byte.contains_i64(-128));
+        assert!(!IntegerSubtype::Byte.contains_i64(128));
+        assert!(!IntegerSubtype::UnsignedInt.contains_i64(-1));
+        assert!(IntegerSubtype::NonPositiveInteger.contains_i64(0));
+        assert!(!IntegerSubtype::PositiveInteger.contains_i64(0));
+        assert!(IntegerSubtype::UnsignedLong
+            .contains(&BigInteger::from_str("18446744073709551615").unwrap()));
+        assert!(!IntegerSubtype::UnsignedLong
+            .contains(&BigInteger::from_str("18446744073709551616").unwrap()));
+        assert!(IntegerSubtype::NegativeInteger
+            .contains(&BigInteger::from_str("-1000000000000000000000000000000000000000").unwrap()));
+        assert!(!IntegerSubtype::Long
+            .contains(&BigInteger::from_str("-1000000000000000000000000000000000000000").unwrap()));
+    }
+}
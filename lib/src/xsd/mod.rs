@@ -1,13 +1,19 @@
+mod big_decimal;
+mod big_integer;
 pub mod date_time;
 pub mod decimal;
 mod double;
 mod duration;
 mod float;
+mod integer_subtype;
 mod parser;
 
+pub use self::big_decimal::{BigDecimal, DecimalPrecisionError};
+pub use self::big_integer::{BigInteger, IntegerOverflowError};
 pub use self::date_time::{Date, DateTime, GDay, GMonth, GMonthDay, GYear, GYearMonth, Time};
 pub use self::decimal::Decimal;
 pub use self::double::Double;
 pub use self::duration::{DayTimeDuration, Duration, YearMonthDuration};
 pub use self::float::Float;
+pub use self::integer_subtype::IntegerSubtype;
 pub use self::parser::XsdParseError;
@@ -1,8 +1,13 @@
 use oxigraph::io::{DatasetFormat, GraphFormat};
 use oxigraph::model::vocab::{rdf, xsd};
 use oxigraph::model::*;
+use oxigraph::sparql::{
+    AggregateAccumulator, EvaluationError, Query, QueryOptions, QueryResults, ServiceHandler,
+    Variable,
+};
 use oxigraph::store::Store;
 use rand::random;
+use std::cell::RefCell;
 use std::env::temp_dir;
 use std::error::Error;
 use std::fs::{create_dir, remove_dir_all, File};
@@ -10,6 +15,8 @@ use std::io::{Cursor, Write};
 use std::iter::once;
 use std::path::PathBuf;
 use std::process::Command;
+use std::rc::Rc;
+use std::thread::spawn;
 
 const DATA: &str = r#"
 @prefix schema: <http://schema.org/> .
@@ -246,6 +253,438 @@ fn test_snapshot_isolation_iterator() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_apply_patch() -> Result<(), Box<dyn Error>> {
+    let store = Store::new()?;
+    store.load_dataset(Cursor::new(GRAPH_DATA), DatasetFormat::TriG, None)?;
+    let mut new = Dataset::new();
+    for quad in store.iter() {
+        new.insert(&quad?);
+    }
+    let old = new.clone();
+    let graph_name =
+        NamedNodeRef::new_unchecked("http://www.wikidata.org/wiki/Special:EntityData/Q90");
+    new.remove(QuadRef::new(
+        NamedNodeRef::new_unchecked("http://www.wikidata.org/entity/Q90"),
+        NamedNodeRef::new_unchecked("http://schema.org/postalCode"),
+        LiteralRef::new_simple_literal("75001"),
+        graph_name,
+    ));
+    new.insert(QuadRef::new(
+        NamedNodeRef::new_unchecked("http://www.wikidata.org/entity/Q90"),
+        NamedNodeRef::new_unchecked("http://schema.org/postalCode"),
+        LiteralRef::new_simple_literal("75002"),
+        graph_name,
+    ));
+    let patch = old.diff(&new).to_patch();
+    assert_eq!(patch.committed_operations().len(), 2);
+    store.apply_patch(&patch)?;
+    store.validate()?;
+    let mut result = Dataset::new();
+    for quad in store.iter() {
+        result.insert(&quad?);
+    }
+    assert_eq!(result, new);
+    Ok(())
+}
+
+#[test]
+fn test_graph_version() -> Result<(), Box<dyn Error>> {
+    let ex = NamedNodeRef::new_unchecked("http://example.com");
+    let store = Store::new()?;
+    assert!(store.graph_version(ex)?.is_none());
+    store.transaction(|mut t| {
+        t.insert(QuadRef::new(ex, ex, ex, ex))?;
+        t.insert(QuadRef::new(ex, ex, rdf::TYPE, ex))
+    })?;
+    assert_eq!(store.graph_version(ex)?.unwrap().version, 1);
+    assert!(store.graph_version(GraphNameRef::DefaultGraph)?.is_none());
+    assert!(!store.insert(QuadRef::new(ex, ex, ex, ex))?);
+    assert_eq!(store.graph_version(ex)?.unwrap().version, 1);
+    store.remove(QuadRef::new(ex, ex, ex, ex))?;
+    assert_eq!(store.graph_version(ex)?.unwrap().version, 2);
+    store
+        .bulk_loader()
+        .load_quads(once(Quad::new(ex, ex, ex, ex)))?;
+    assert_eq!(store.graph_version(ex)?.unwrap().version, 3);
+    Ok(())
+}
+
+#[test]
+fn test_transaction_graph_version_conflict() -> Result<(), Box<dyn Error>> {
+    let ex = NamedNodeRef::new_unchecked("http://example.com");
+    let store = Store::new()?;
+    store.insert(QuadRef::new(ex, ex, ex, ex))?;
+    let threads = (0..4)
+        .map(|i| {
+            let store = store.clone();
+            spawn(move || {
+                store.transaction(|mut t| {
+                    if t.graph_version(ex)?.unwrap().version != 1 {
+                        return Ok(false);
+                    }
+                    t.insert(QuadRef::new(ex, ex, Literal::from(i).as_ref(), ex))
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut written = 0;
+    for thread in threads {
+        if thread.join().unwrap()? {
+            written += 1;
+        }
+    }
+    assert_eq!(written, 1);
+    assert_eq!(store.graph_version(ex)?.unwrap().version, 2);
+    assert_eq!(store.len()?, 2);
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_writes_to_the_same_graph() -> Result<(), Box<dyn Error>> {
+    let ex = NamedNodeRef::new_unchecked("http://example.com");
+    let store = Store::new()?;
+    let threads = (0..4)
+        .map(|i| {
+            let store = store.clone();
+            spawn(move || {
+                for j in 0..10 {
+                    store.insert(QuadRef::new(
+                        ex,
+                        ex,
+                        Literal::from(i * 10 + j).as_ref(),
+                        GraphNameRef::DefaultGraph,
+                    ))?;
+                }
+                store.update(&format!(
+                    "INSERT DATA {{ <http://example.com> <http://example.com> \"update {}\" }}",
+                    i
+                ))?;
+                Result::<_, Box<dyn Error + Send + Sync>>::Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap().map_err(|e| e.to_string())?;
+    }
+    assert_eq!(store.len()?, 44);
+    assert_eq!(
+        store
+            .graph_version(GraphNameRef::DefaultGraph)?
+            .unwrap()
+            .version,
+        44
+    );
+    Ok(())
+}
+
+#[test]
+fn test_prepared_query() -> Result<(), Box<dyn Error>> {
+    let store = Store::new()?;
+    store.load_dataset(Cursor::new(GRAPH_DATA), DatasetFormat::TriG, None)?;
+    let postal_code = Variable::new("postalCode")?;
+    let query = store.prepare_query(
+        "SELECT ?postalCode WHERE { GRAPH ?g { ?s <http://schema.org/postalCode> ?postalCode } }",
+    )?;
+    let execute = |bindings: Vec<(Variable, Term)>| -> Result<Vec<Term>, Box<dyn Error>> {
+        if let QueryResults::Solutions(solutions) = store.query_prepared(&query, bindings)? {
+            Ok(solutions
+                .map(|s| Ok(s?.get(&postal_code).cloned().unwrap()))
+                .collect::<Result<_, EvaluationError>>()?)
+        } else {
+            Err("SELECT query should return solutions".into())
+        }
+    };
+    let paris = Variable::new("s")?;
+    // the binding is applied even if ?s is not projected
+    assert_eq!(
+        execute(vec![(
+            paris.clone(),
+            NamedNode::new("http://www.wikidata.org/entity/Q90")?.into()
+        )])?,
+        vec![Literal::new_simple_literal("75001").into()]
+    );
+    // the bound value does not exist in the store
+    assert!(execute(vec![(
+        paris,
+        NamedNode::new("http://example.com/not-in-store")?.into()
+    )])?
+    .is_empty());
+    // the binding of a projected variable is returned
+    assert_eq!(
+        execute(vec![(
+            postal_code.clone(),
+            Literal::new_simple_literal("75001").into()
+        )])?,
+        vec![Literal::new_simple_literal("75001").into()]
+    );
+
+    let count = store.prepare_query(
+        "SELECT (COUNT(*) AS ?c) WHERE { GRAPH ?g { ?s <http://schema.org/postalCode> ?p } }",
+    )?;
+    if let QueryResults::Solutions(mut solutions) = store.query_prepared(
+        &count,
+        [(
+            Variable::new("p")?,
+            Literal::new_simple_literal("75002").into(),
+        )],
+    )? {
+        assert_eq!(
+            solutions.next().unwrap()?.get("c"),
+            Some(&Literal::from(0).into())
+        );
+    }
+
+    // a variable assigned by BIND can't be a parameter
+    let bind = store.prepare_query("SELECT ?x WHERE { BIND(1 AS ?x) }")?;
+    if let QueryResults::Solutions(mut solutions) =
+        store.query_prepared(&bind, [(Variable::new("x")?, Literal::from(2).into())])?
+    {
+        assert!(solutions.next().unwrap().is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_custom_aggregate() -> Result<(), Box<dyn Error>> {
+    #[derive(Default)]
+    struct Median(Vec<i64>);
+
+    impl AggregateAccumulator for Median {
+        fn add(&mut self, term: Term) {
+            if let Term::Literal(literal) = term {
+                if let Ok(value) = literal.value().parse() {
+                    self.0.push(value);
+                }
+            }
+        }
+
+        fn finish(&mut self) -> Option<Term> {
+            self.0.sort_unstable();
+            Some(Literal::from(*self.0.get(self.0.len() / 2)?).into())
+        }
+    }
+
+    let store = Store::new()?;
+    let options = QueryOptions::default()
+        .with_custom_aggregate(NamedNode::new("http://example.com/median")?, || {
+            Box::new(Median::default())
+        });
+    let evaluate = |query: &str| -> Result<Vec<Option<Term>>, Box<dyn Error>> {
+        if let QueryResults::Solutions(solutions) = store.query_opt(query, options.clone())? {
+            Ok(solutions
+                .map(|s| Ok(s?.get("m").cloned()))
+                .collect::<Result<_, EvaluationError>>()?)
+        } else {
+            Err("SELECT query should return solutions".into())
+        }
+    };
+    assert_eq!(
+        evaluate(
+            "SELECT (<http://example.com/median>(?v) AS ?m) WHERE { VALUES ?v { 1 5 2 5 5 } }"
+        )?,
+        vec![Some(Literal::from(5).into())]
+    );
+    assert_eq!(
+        evaluate("SELECT (<http://example.com/median>(DISTINCT ?v) AS ?m) WHERE { VALUES ?v { 1 5 2 5 5 } }")?,
+        vec![Some(Literal::from(2).into())]
+    );
+    assert_eq!(
+        evaluate("SELECT ?k (<http://example.com/median>(?v) AS ?m) WHERE { VALUES (?k ?v) { (1 1) (1 3) (1 2) (2 4) } } GROUP BY ?k ORDER BY ?k")?,
+        vec![Some(Literal::from(2).into()), Some(Literal::from(4).into())]
+    );
+    assert_eq!(
+        evaluate("SELECT ?m WHERE { VALUES ?v { 1 } } GROUP BY ?v HAVING(<http://example.com/median>(?v) > 1)")?,
+        Vec::new()
+    );
+    // the BIND in the WHERE clause are not aggregations
+    assert!(store
+        .query_opt(
+            "SELECT ?x WHERE { VALUES (?x ?v) { (1 1) (2 2) } BIND(<http://example.com/median>(?v) AS ?m) }",
+            options.clone()
+        )
+        .is_err());
+    // unknown custom aggregates are rejected
+    assert!(store
+        .query("SELECT (<http://example.com/other>(DISTINCT ?v) AS ?m) WHERE {}")
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_custom_function_with_context() -> Result<(), Box<dyn Error>> {
+    let store = Store::new()?;
+    store.load_dataset(Cursor::new(GRAPH_DATA), DatasetFormat::TriG, None)?;
+    let options = QueryOptions::default()
+        .with_custom_function_with_context(
+            NamedNode::new("http://example.com/postalCode")?,
+            |context, args| -> Result<_, EvaluationError> {
+                let subject = match args.get(0) {
+                    Some(Term::NamedNode(subject)) => subject.as_ref(),
+                    _ => return Ok(None),
+                };
+                let postal_code = NamedNodeRef::new_unchecked("http://schema.org/postalCode");
+                Ok(context
+                    .quads_for_pattern(Some(subject.into()), Some(postal_code), None, None)
+                    .next()
+                    .transpose()?
+                    .map(|quad| quad.object))
+            },
+        )
+        .with_custom_function_with_context(
+            NamedNode::new("http://example.com/base")?,
+            |context, _| -> Result<_, EvaluationError> {
+                Ok(context.base_iri().map(|iri| Literal::from(iri).into()))
+            },
+        )
+        .with_custom_function_with_context(NamedNode::new("http://example.com/fail")?, |_, _| {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "fail"))
+        });
+    let evaluate = |query: &str| -> Result<Option<Term>, Box<dyn Error>> {
+        if let QueryResults::Solutions(mut solutions) = store.query_opt(query, options.clone())? {
+            Ok(solutions.next().unwrap()?.get("r").cloned())
+        } else {
+            Err("SELECT query should return solutions".into())
+        }
+    };
+    assert_eq!(
+        evaluate("SELECT (<http://example.com/postalCode>(<http://www.wikidata.org/entity/Q90>) AS ?r) WHERE {}")?,
+        Some(Literal::new_simple_literal("75001").into())
+    );
+    assert_eq!(
+        evaluate("SELECT (<http://example.com/postalCode>(<http://example.com/not-in-store>) AS ?r) WHERE {}")?,
+        None
+    );
+    assert_eq!(
+        evaluate("BASE <http://example.com/> SELECT (<http://example.com/base>() AS ?r) WHERE {}")?,
+        Some(Literal::new_simple_literal("http://example.com/").into())
+    );
+    assert!(evaluate("SELECT (<http://example.com/fail>() AS ?r) WHERE {}").is_err());
+    Ok(())
+}
+
+struct RecordingServiceHandler {
+    store: Store,
+    queries: Rc<RefCell<Vec<Query>>>,
+    failing: bool,
+}
+
+impl ServiceHandler for RecordingServiceHandler {
+    type Error = EvaluationError;
+
+    fn handle(&self, _: NamedNode, query: Query) -> Result<QueryResults, EvaluationError> {
+        self.queries.borrow_mut().push(query.clone());
+        if self.failing {
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "service failure").into());
+        }
+        self.store.query(query)
+    }
+}
+
+#[test]
+fn test_service_bind_join() -> Result<(), Box<dyn Error>> {
+    let store = Store::new()?;
+    let service_store = Store::new()?;
+    let class = NamedNode::new("http://example.com/C")?;
+    let predicate = NamedNode::new("http://example.com/p")?;
+    for i in 0..5 {
+        let subject = NamedNode::new(format!("http://example.com/s{}", i))?;
+        store.insert(&Quad::new(
+            subject.clone(),
+            rdf::TYPE,
+            class.clone(),
+            GraphName::DefaultGraph,
+        ))?;
+        service_store.insert(&Quad::new(
+            subject,
+            predicate.clone(),
+            Literal::from(i),
+            GraphName::DefaultGraph,
+        ))?;
+    }
+    // Only in the service, must not be returned by the join
+    service_store.insert(&Quad::new(
+        NamedNode::new("http://example.com/other")?,
+        predicate,
+        Literal::from(10),
+        GraphName::DefaultGraph,
+    ))?;
+
+    let query = "SELECT ?s ?o WHERE { ?s a <http://example.com/C> SERVICE <http://example.com/service> { ?s <http://example.com/p> ?o } }";
+    let evaluate_with = |query: &str,
+                         options: QueryOptions,
+                         failing: bool|
+     -> Result<(Vec<Query>, usize), Box<dyn Error>> {
+        let queries = Rc::new(RefCell::new(Vec::new()));
+        let options = options.with_service_handler(RecordingServiceHandler {
+            store: service_store.clone(),
+            queries: queries.clone(),
+            failing,
+        });
+        let count = if let QueryResults::Solutions(solutions) = store.query_opt(query, options)? {
+            solutions.collect::<Result<Vec<_>, _>>()?.len()
+        } else {
+            return Err("SELECT query should return solutions".into());
+        };
+        let queries = queries.borrow().clone();
+        Ok((queries, count))
+    };
+    let evaluate = |options: QueryOptions| evaluate_with(query, options, false);
+
+    let (queries, count) = evaluate(QueryOptions::default().with_service_batch_size(2))?;
+    assert_eq!(count, 5);
+    assert_eq!(queries.len(), 3);
+    assert!(queries.iter().all(|q| q.to_string().contains("VALUES")));
+
+    let (queries, count) = evaluate(
+        QueryOptions::default()
+            .with_service_batch_size(2)
+            .with_service_concurrency(2),
+    )?;
+    assert_eq!(count, 5);
+    assert_eq!(queries.len(), 3);
+
+    let (queries, count) = evaluate(QueryOptions::default().with_service_batch_size(0))?;
+    assert_eq!(count, 5);
+    assert_eq!(queries.len(), 1);
+    assert!(!queries[0].to_string().contains("VALUES"));
+
+    // With SILENT the left tuples are returned unchanged if a batched call fails
+    let silent_query = "SELECT ?s ?o WHERE { ?s a <http://example.com/C> SERVICE SILENT <http://example.com/service> { ?s <http://example.com/p> ?o } }";
+    let (queries, count) = evaluate_with(
+        silent_query,
+        QueryOptions::default().with_service_batch_size(2),
+        true,
+    )?;
+    assert_eq!(count, 5);
+    assert_eq!(queries.len(), 3);
+    assert!(evaluate_with(
+        query,
+        QueryOptions::default().with_service_batch_size(2),
+        true
+    )
+    .is_err());
+
+    // Blank nodes can't be sent in VALUES: their tuples are joined with a single call without VALUES
+    store.insert(&Quad::new(
+        BlankNode::default(),
+        rdf::TYPE,
+        class,
+        GraphName::DefaultGraph,
+    ))?;
+    let (queries, count) = evaluate(QueryOptions::default().with_service_batch_size(2))?;
+    assert_eq!(count, 5);
+    assert_eq!(queries.len(), 4);
+    assert_eq!(
+        queries
+            .iter()
+            .filter(|q| !q.to_string().contains("VALUES"))
+            .count(),
+        1
+    );
+    Ok(())
+}
+
 #[test]
 fn test_bulk_load_on_existing_delete_overrides_the_delete() -> Result<(), Box<dyn Error>> {
     let quad = QuadRef::new(
@@ -129,6 +129,16 @@ void rocksdb_transactiondb_create_checkpoint_with_status(
     delete checkpoint;
 }
 
+int rocksdb_transactiondb_property_int_cf(
+        rocksdb_transactiondb_t* db, rocksdb_column_family_handle_t* column_family,
+        const char* propname, uint64_t* out_val) {
+    if (db->rep->GetIntProperty(column_family->rep, Slice(propname), out_val)) {
+        return 0;
+    } else {
+        return -1;
+    }
+}
+
 
 void rocksdb_transaction_commit_with_status(rocksdb_transaction_t* txn, rocksdb_status_t* statusptr) {
     SaveStatus(statusptr, txn->rep->Commit());
@@ -214,4 +224,16 @@ rocksdb_readoptions_t* rocksdb_readoptions_create_copy(rocksdb_readoptions_t* op
     return new rocksdb_readoptions_t(*options);
 }
 
+
+void rocksdb_options_statistics_get_block_cache_counts(
+        rocksdb_options_t* opt, uint64_t* hits, uint64_t* misses) {
+    if (opt->rep.statistics) {
+        *hits = opt->rep.statistics->getTickerCount(ROCKSDB_NAMESPACE::BLOCK_CACHE_HIT);
+        *misses = opt->rep.statistics->getTickerCount(ROCKSDB_NAMESPACE::BLOCK_CACHE_MISS);
+    } else {
+        *hits = 0;
+        *misses = 0;
+    }
+}
+
 }
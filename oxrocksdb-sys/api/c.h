@@ -99,6 +99,10 @@ extern ROCKSDB_LIBRARY_API void rocksdb_transactiondb_ingest_external_files_with
 extern ROCKSDB_LIBRARY_API void rocksdb_transactiondb_create_checkpoint_with_status(
         rocksdb_transactiondb_t* db, const char* checkpoint_dir, rocksdb_status_t* statusptr);
 
+extern ROCKSDB_LIBRARY_API int rocksdb_transactiondb_property_int_cf(
+        rocksdb_transactiondb_t* db, rocksdb_column_family_handle_t* column_family,
+        const char* propname, uint64_t* out_val);
+
 
 extern ROCKSDB_LIBRARY_API void rocksdb_transaction_commit_with_status(
         rocksdb_transaction_t* txn, rocksdb_status_t* statusptr);
@@ -143,6 +147,10 @@ extern ROCKSDB_LIBRARY_API void rocksdb_iter_get_status(
 extern ROCKSDB_LIBRARY_API rocksdb_readoptions_t* rocksdb_readoptions_create_copy(
         rocksdb_readoptions_t*);
 
+
+extern ROCKSDB_LIBRARY_API void rocksdb_options_statistics_get_block_cache_counts(
+        rocksdb_options_t* opt, uint64_t* hits, uint64_t* misses);
+
 #ifdef __cplusplus
 }
 #endif
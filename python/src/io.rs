@@ -1,20 +1,30 @@
 #![allow(clippy::needless_option_as_deref)]
 
-use crate::model::{PyQuad, PyTriple};
+use crate::model::{PyBlankNode, PyQuad, PyTriple};
+use arrow::datatypes::SchemaRef;
+use arrow::pyarrow::PyArrowConvert;
+use arrow::record_batch::RecordBatch;
 use oxigraph::io::read::{ParseError, QuadReader, TripleReader};
 use oxigraph::io::{
     DatasetFormat, DatasetParser, DatasetSerializer, GraphFormat, GraphParser, GraphSerializer,
 };
+use oxigraph::model::canonicalization::{
+    CanonicalizationHashAlgorithm, CanonicalizedDataset, Canonicalizer,
+};
+use oxigraph::model::{GraphName, Quad, Triple};
 use pyo3::exceptions::{PyIOError, PySyntaxError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyDict};
 use pyo3::wrap_pyfunction;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufReader, BufWriter, Read, Write};
 
 pub fn add_to_module(module: &PyModule) -> PyResult<()> {
     module.add_wrapped(wrap_pyfunction!(parse))?;
-    module.add_wrapped(wrap_pyfunction!(serialize))
+    module.add_wrapped(wrap_pyfunction!(serialize))?;
+    module.add_wrapped(wrap_pyfunction!(canonicalize))?;
+    module.add_class::<PyCanonicalizedDataset>()
 }
 
 /// Parses RDF graph and dataset serialization formats.
@@ -104,19 +114,36 @@ pub fn parse(
 /// :type output: io.RawIOBase or io.BufferedIOBase or str
 /// :param mime_type: the MIME type of the RDF serialization.
 /// :type mime_type: str
-/// :raises ValueError: if the MIME type is not supported.
+/// :param prefixes: the prefixes to use to abbreviate IRIs in Turtle and TriG, as a dict from prefix names to prefix IRIs. If it or ``base_iri`` is set, the output is grouped by subject and predicate and blank nodes and RDF collections are written inline.
+/// :type prefixes: dict(str, str) or None, optional
+/// :param base_iri: the base IRI used to relativize IRIs in Turtle and TriG.
+/// :type base_iri: str or None, optional
+/// :raises ValueError: if the MIME type is not supported or if a prefix or the base IRI is invalid.
 /// :raises TypeError: if a triple is given during a quad format serialization or reverse.
 ///
 /// >>> output = io.BytesIO()
 /// >>> serialize([Triple(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1'))], output, "text/turtle")
 /// >>> output.getvalue()
 /// b'<http://example.com> <http://example.com/p> "1" .\n'
+///
+/// >>> output = io.BytesIO()
+/// >>> serialize([Triple(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1'))], output, "text/turtle", prefixes={'ex': 'http://example.com/'})
+/// >>> output.getvalue()
+/// b'@prefix ex: <http://example.com/> .\n\n<http://example.com> ex:p "1" .\n'
 #[pyfunction]
-#[pyo3(text_signature = "(input, output, /, mime_type, *, base_iri = None)")]
-pub fn serialize(input: &PyAny, output: PyObject, mime_type: &str, py: Python<'_>) -> PyResult<()> {
+#[pyo3(text_signature = "(input, output, /, mime_type, *, prefixes = None, base_iri = None)")]
+#[args(input, output, mime_type, "*", prefixes = "None", base_iri = "None")]
+pub fn serialize(
+    input: &PyAny,
+    output: PyObject,
+    mime_type: &str,
+    prefixes: Option<&PyDict>,
+    base_iri: Option<&str>,
+    py: Python<'_>,
+) -> PyResult<()> {
     let output = PyFileLike::create(output, py).map_err(map_io_err)?;
     if let Some(graph_format) = GraphFormat::from_media_type(mime_type) {
-        let mut writer = GraphSerializer::from_format(graph_format)
+        let mut writer = graph_serializer(graph_format, prefixes, base_iri)?
             .triple_writer(output)
             .map_err(map_io_err)?;
         for i in input.iter()? {
@@ -127,7 +154,7 @@ pub fn serialize(input: &PyAny, output: PyObject, mime_type: &str, py: Python<'_
         writer.finish().map_err(map_io_err)?;
         Ok(())
     } else if let Some(dataset_format) = DatasetFormat::from_media_type(mime_type) {
-        let mut writer = DatasetSerializer::from_format(dataset_format)
+        let mut writer = dataset_serializer(dataset_format, prefixes, base_iri)?
             .quad_writer(output)
             .map_err(map_io_err)?;
         for i in input.iter()? {
@@ -145,6 +172,161 @@ pub fn serialize(input: &PyAny, output: PyObject, mime_type: &str, py: Python<'_
     }
 }
 
+pub(crate) fn graph_serializer(
+    format: GraphFormat,
+    prefixes: Option<&PyDict>,
+    base_iri: Option<&str>,
+) -> PyResult<GraphSerializer> {
+    let mut serializer = GraphSerializer::from_format(format);
+    if let Some(prefixes) = prefixes {
+        for (prefix_name, prefix_iri) in prefixes {
+            serializer = serializer
+                .with_prefix(
+                    prefix_name.extract::<String>()?,
+                    prefix_iri.extract::<String>()?,
+                )
+                .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        }
+    }
+    if let Some(base_iri) = base_iri {
+        serializer = serializer
+            .with_base_iri(base_iri)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    }
+    Ok(if prefixes.is_some() || base_iri.is_some() {
+        serializer.pretty()
+    } else {
+        serializer
+    })
+}
+
+pub(crate) fn dataset_serializer(
+    format: DatasetFormat,
+    prefixes: Option<&PyDict>,
+    base_iri: Option<&str>,
+) -> PyResult<DatasetSerializer> {
+    let mut serializer = DatasetSerializer::from_format(format);
+    if let Some(prefixes) = prefixes {
+        for (prefix_name, prefix_iri) in prefixes {
+            serializer = serializer
+                .with_prefix(
+                    prefix_name.extract::<String>()?,
+                    prefix_iri.extract::<String>()?,
+                )
+                .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        }
+    }
+    if let Some(base_iri) = base_iri {
+        serializer = serializer
+            .with_base_iri(base_iri)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    }
+    Ok(if prefixes.is_some() || base_iri.is_some() {
+        serializer.pretty()
+    } else {
+        serializer
+    })
+}
+
+/// Canonicalizes an RDF graph or dataset using the `RDFC-1.0 <https://www.w3.org/TR/rdf-canon/>`_ algorithm.
+///
+/// Triples are considered to be in the default graph.
+///
+/// :param input: the RDF triples and quads to canonicalize.
+/// :type input: iter(Triple) or iter(Quad)
+/// :param hash_algorithm: the hash function to use, ``sha256`` (the default) or ``sha384``.
+/// :type hash_algorithm: str or None, optional
+/// :param work_limit: the maximal number of steps allowed to the algorithm to protect against "poison" graphs.
+/// :type work_limit: int or None, optional
+/// :return: the canonicalized dataset.
+/// :rtype: CanonicalizedDataset
+/// :raises ValueError: if the hash algorithm is not supported or if the work limit is exceeded.
+/// :raises TypeError: if an element of the input is not a triple or a quad.
+///
+/// >>> canonicalize([Triple(BlankNode('b'), NamedNode('http://example.com/p'), Literal('1'))]).n_quads
+/// '_:c14n0 <http://example.com/p> "1" .\n'
+#[pyfunction]
+#[pyo3(text_signature = "(input, /, *, hash_algorithm = None, work_limit = None)")]
+pub fn canonicalize(
+    input: &PyAny,
+    hash_algorithm: Option<&str>,
+    work_limit: Option<usize>,
+    py: Python<'_>,
+) -> PyResult<PyCanonicalizedDataset> {
+    let mut canonicalizer = Canonicalizer::new();
+    if let Some(hash_algorithm) = hash_algorithm {
+        canonicalizer = canonicalizer.with_hash_algorithm(
+            CanonicalizationHashAlgorithm::from_name(hash_algorithm).ok_or_else(|| {
+                PyValueError::new_err(format!("Not supported hash algorithm: {}", hash_algorithm))
+            })?,
+        );
+    }
+    if let Some(work_limit) = work_limit {
+        canonicalizer = canonicalizer.with_work_limit(work_limit);
+    }
+    let mut quads = Vec::new();
+    for i in input.iter()? {
+        let i = i?;
+        quads.push(if let Ok(quad) = i.downcast::<PyCell<PyQuad>>() {
+            Quad::from(quad.borrow().clone())
+        } else {
+            Triple::from(i.downcast::<PyCell<PyTriple>>()?.borrow().clone())
+                .in_graph(GraphName::DefaultGraph)
+        });
+    }
+    Ok(PyCanonicalizedDataset {
+        inner: py
+            .allow_threads(|| canonicalizer.canonicalize_quads(quads))
+            .map_err(|e| PyValueError::new_err(e.to_string()))?,
+    })
+}
+
+/// The result of the :py:func:`canonicalize` function.
+#[pyclass(name = "CanonicalizedDataset", module = "oxigraph")]
+pub struct PyCanonicalizedDataset {
+    inner: CanonicalizedDataset,
+}
+
+#[pymethods]
+impl PyCanonicalizedDataset {
+    /// :return: the canonical N-Quads serialization of the dataset.
+    /// :rtype: str
+    #[getter]
+    fn n_quads(&self) -> &str {
+        self.inner.n_quads()
+    }
+
+    /// :return: the relabeled quads in the canonical N-Quads order.
+    /// :rtype: list(Quad)
+    #[getter]
+    fn quads(&self) -> Vec<PyQuad> {
+        self.inner
+            .quads()
+            .iter()
+            .cloned()
+            .map(PyQuad::from)
+            .collect()
+    }
+
+    /// :return: the mapping from the input blank nodes to the canonical ones.
+    /// :rtype: dict(BlankNode, BlankNode)
+    #[getter]
+    fn issued_identifiers(&self) -> HashMap<PyBlankNode, PyBlankNode> {
+        self.inner
+            .issued_identifiers()
+            .iter()
+            .map(|(k, v)| (k.clone().into(), v.clone().into()))
+            .collect()
+    }
+
+    /// :return: the hexadecimal hash of the canonical N-Quads serialization.
+    /// :rtype: str
+    #[getter]
+    fn hash(&self) -> &str {
+        self.inner.hash()
+    }
+}
+
 #[pyclass(name = "TripleReader", module = "oxigraph")]
 pub struct PyTripleReader {
     inner: TripleReader<BufReader<PyFileLike>>,
@@ -260,6 +442,23 @@ fn to_io_err(error: impl Into<PyErr>) -> io::Error {
     io::Error::new(io::ErrorKind::Other, error.into())
 }
 
+/// Builds a ``pyarrow.Table`` from Arrow record batches
+pub(crate) fn record_batches_to_pyarrow(
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let batches = batches
+        .iter()
+        .map(|batch| batch.to_pyarrow(py))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(py
+        .import("pyarrow")?
+        .getattr("Table")?
+        .call_method1("from_batches", (batches, schema.to_pyarrow(py)?))?
+        .into())
+}
+
 pub(crate) fn map_io_err(error: io::Error) -> PyErr {
     if error.get_ref().map_or(false, |s| s.is::<PyErr>()) {
         *error.into_inner().unwrap().downcast().unwrap()
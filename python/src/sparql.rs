@@ -1,11 +1,15 @@
-use crate::io::{allow_threads_unsafe, map_io_err, map_parse_error};
+use crate::io::{allow_threads_unsafe, map_io_err, map_parse_error, record_batches_to_pyarrow};
 use crate::map_storage_error;
 use crate::model::*;
+use oxigraph::io::arrow::DEFAULT_BATCH_SIZE;
 use oxigraph::model::Term;
 use oxigraph::sparql::*;
 use pyo3::exceptions::{PyRuntimeError, PySyntaxError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::{Py, PyRef};
+use std::iter::empty;
+use std::mem::replace;
+use std::rc::Rc;
 use std::vec::IntoIter;
 
 pub fn parse_query(
@@ -190,6 +194,41 @@ impl PyQuerySolutions {
             .map_err(map_evaluation_error)?
             .map(move |inner| PyQuerySolution { inner }))
     }
+
+    /// Converts the remaining solutions into a `pyarrow.Table <https://arrow.apache.org/docs/python/generated/pyarrow.Table.html>`_.
+    ///
+    /// Each variable is encoded using four columns:
+    /// ``{variable}`` with the term value (IRI, blank node identifier or literal lexical form),
+    /// ``{variable}.kind`` with the term kind (``uri``, ``bnode``, ``literal`` or ``triple``),
+    /// ``{variable}.datatype`` with the literal datatype IRI and ``{variable}.language`` with the literal language tag.
+    /// Unbound variables are encoded with null values.
+    ///
+    /// It requires the `pyarrow <https://arrow.apache.org/docs/python/>`_ package.
+    ///
+    /// :param batch_size: the maximal number of rows of each record batch of the table.
+    /// :type batch_size: int or None, optional
+    /// :return: the solutions table.
+    /// :rtype: pyarrow.Table
+    /// :raises IOError: if an I/O error happens during the query evaluation.
+    ///
+    /// >>> store = Store()
+    /// >>> store.add(Quad(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1')))
+    /// >>> table = store.query('SELECT ?s ?o WHERE { ?s ?p ?o }').to_arrow()
+    /// >>> table.column_names
+    /// ['s', 's.kind', 's.datatype', 's.language', 'o', 'o.kind', 'o.datatype', 'o.language']
+    /// >>> table.column('o').to_pylist()
+    /// ['1']
+    #[pyo3(text_signature = "($self, /, *, batch_size = None)")]
+    #[args("*", batch_size = "None")]
+    fn to_arrow(&mut self, batch_size: Option<usize>, py: Python<'_>) -> PyResult<PyObject> {
+        let variables = Rc::new(self.inner.variables().to_vec());
+        let batches = replace(&mut self.inner, QuerySolutionIter::new(variables, empty()))
+            .into_record_batches(batch_size.unwrap_or(DEFAULT_BATCH_SIZE));
+        let schema = batches.schema();
+        let batches = allow_threads_unsafe(|| batches.collect::<Result<Vec<_>, _>>())
+            .map_err(map_evaluation_error)?;
+        record_batches_to_pyarrow(schema, batches, py)
+    }
 }
 
 /// An iterator of :py:class:`Triple` returned by a SPARQL ``CONSTRUCT`` or ``DESCRIBE`` query
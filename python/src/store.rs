@@ -1,14 +1,19 @@
 #![allow(clippy::needless_option_as_deref)]
 
-use crate::io::{allow_threads_unsafe, map_io_err, map_parse_error, PyFileLike};
+use crate::io::{
+    allow_threads_unsafe, dataset_serializer, graph_serializer, map_io_err, map_parse_error,
+    record_batches_to_pyarrow, PyFileLike,
+};
 use crate::model::*;
 use crate::sparql::*;
+use oxigraph::io::arrow::DEFAULT_BATCH_SIZE;
 use oxigraph::io::{DatasetFormat, GraphFormat};
 use oxigraph::model::{GraphName, GraphNameRef};
 use oxigraph::sparql::Update;
-use oxigraph::store::{self, LoaderError, SerializerError, StorageError, Store};
+use oxigraph::store::{self, LoaderError, StorageError, Store};
 use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::{Py, PyRef};
 
 /// RDF store.
@@ -131,6 +136,72 @@ impl PyStore {
         })
     }
 
+    /// Looks for the quads matching a given pattern and returns them as a `pyarrow.Table <https://arrow.apache.org/docs/python/generated/pyarrow.Table.html>`_.
+    ///
+    /// Each quad component is encoded using four columns:
+    /// ``{component}`` with the term value (IRI, blank node identifier or literal lexical form),
+    /// ``{component}.kind`` with the term kind (``uri``, ``bnode``, ``literal`` or ``triple``),
+    /// ``{component}.datatype`` with the literal datatype IRI and ``{component}.language`` with the literal language tag.
+    /// The components are ``subject``, ``predicate``, ``object`` and ``graph_name``.
+    /// The default graph is encoded with null values.
+    ///
+    /// It requires the `pyarrow <https://arrow.apache.org/docs/python/>`_ package.
+    /// The table could be written to a Parquet file using ``pyarrow.parquet.write_table``.
+    ///
+    /// :param subject: the quad subject or :py:const:`None` to match everything.
+    /// :type subject: NamedNode or BlankNode or None
+    /// :param predicate: the quad predicate or :py:const:`None` to match everything.
+    /// :type predicate: NamedNode or None
+    /// :param object: the quad object or :py:const:`None` to match everything.
+    /// :type object: NamedNode or BlankNode or Literal or None
+    /// :param graph_name: the quad graph name. To match only the default graph, use :py:class:`DefaultGraph`. To match everything use :py:const:`None`.
+    /// :type graph_name: NamedNode or BlankNode or DefaultGraph or None
+    /// :param batch_size: the maximal number of rows of each record batch of the table.
+    /// :type batch_size: int or None, optional
+    /// :return: the quads table.
+    /// :rtype: pyarrow.Table
+    /// :raises IOError: if an I/O error happens during the quads lookup.
+    ///
+    /// >>> store = Store()
+    /// >>> store.add(Quad(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1'), NamedNode('http://example.com/g')))
+    /// >>> store.quads_to_arrow(None, None, None, None).column('graph_name').to_pylist()
+    /// ['http://example.com/g']
+    #[pyo3(
+        text_signature = "($self, subject, predicate, object, graph_name = None, *, batch_size = None)"
+    )]
+    #[args(
+        subject,
+        predicate,
+        object,
+        graph_name = "None",
+        "*",
+        batch_size = "None"
+    )]
+    fn quads_to_arrow(
+        &self,
+        subject: &PyAny,
+        predicate: &PyAny,
+        object: &PyAny,
+        graph_name: Option<&PyAny>,
+        batch_size: Option<usize>,
+        py: Python<'_>,
+    ) -> PyResult<PyObject> {
+        let (subject, predicate, object, graph_name) =
+            extract_quads_pattern(subject, predicate, object, graph_name)?;
+        let batches = self.inner.quads_record_batches(
+            subject.as_ref().map(|p| p.into()),
+            predicate.as_ref().map(|p| p.into()),
+            object.as_ref().map(|p| p.into()),
+            graph_name.as_ref().map(|p| p.into()),
+            batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+        );
+        let schema = batches.schema();
+        let batches = py
+            .allow_threads(|| batches.collect::<Result<Vec<_>, _>>())
+            .map_err(map_storage_error)?;
+        record_batches_to_pyarrow(schema, batches, py)
+    }
+
     /// Executes a `SPARQL 1.1 query <https://www.w3.org/TR/sparql11-query/>`_.
     ///
     /// :param query: the query to execute.
@@ -423,7 +494,11 @@ impl PyStore {
     /// :type mime_type: str
     /// :param from_graph: if a triple based format is requested, the store graph from which dump the triples. By default, the default graph is used.
     /// :type from_graph: NamedNode or BlankNode or DefaultGraph or None, optional
-    /// :raises ValueError: if the MIME type is not supported or the `from_graph` parameter is given with a quad syntax.
+    /// :param prefixes: the prefixes to use to abbreviate IRIs in Turtle and TriG, as a dict from prefix names to prefix IRIs. If it or ``base_iri`` is set, the output is grouped by subject and predicate and blank nodes and RDF collections are written inline.
+    /// :type prefixes: dict(str, str) or None, optional
+    /// :param base_iri: the base IRI used to relativize IRIs in Turtle and TriG.
+    /// :type base_iri: str or None, optional
+    /// :raises ValueError: if the MIME type is not supported, the `from_graph` parameter is given with a quad syntax or if a prefix or the base IRI is invalid.
     /// :raises IOError: if an I/O error happens during a quad lookup
     ///
     /// >>> store = Store()
@@ -432,13 +507,29 @@ impl PyStore {
     /// >>> store.dump(output, "text/turtle", from_graph=NamedNode("http://example.com/g"))
     /// >>> output.getvalue()
     /// b'<http://example.com> <http://example.com/p> "1" .\n'
-    #[pyo3(text_signature = "($self, output, /, mime_type, *, from_graph = None)")]
-    #[args(output, mime_type, "*", from_graph = "None")]
+    ///
+    /// >>> output = io.BytesIO()
+    /// >>> store.dump(output, "application/trig", prefixes={'ex': 'http://example.com/'})
+    /// >>> output.getvalue()
+    /// b'@prefix ex: <http://example.com/> .\n\n<http://example.com/g> {\n    <http://example.com> ex:p "1" .\n}\n'
+    #[pyo3(
+        text_signature = "($self, output, /, mime_type, *, from_graph = None, prefixes = None, base_iri = None)"
+    )]
+    #[args(
+        output,
+        mime_type,
+        "*",
+        from_graph = "None",
+        prefixes = "None",
+        base_iri = "None"
+    )]
     fn dump(
         &self,
         output: PyObject,
         mime_type: &str,
         from_graph: Option<&PyAny>,
+        prefixes: Option<&PyDict>,
+        base_iri: Option<&str>,
         py: Python<'_>,
     ) -> PyResult<()> {
         let from_graph_name = if let Some(graph_name) = from_graph {
@@ -447,31 +538,48 @@ impl PyStore {
             None
         };
         let output = PyFileLike::create(output, py).map_err(map_io_err)?;
-        py.allow_threads(|| {
-            if let Some(graph_format) = GraphFormat::from_media_type(mime_type) {
-                self.inner
-                    .dump_graph(
-                        output,
-                        graph_format,
-                        &from_graph_name.unwrap_or(GraphName::DefaultGraph),
-                    )
-                    .map_err(map_serializer_error)
-            } else if let Some(dataset_format) = DatasetFormat::from_media_type(mime_type) {
-                if from_graph_name.is_some() {
-                    return Err(PyValueError::new_err(
-                        "The target graph name parameter is not available for dataset formats",
-                    ));
+        if let Some(graph_format) = GraphFormat::from_media_type(mime_type) {
+            let serializer = graph_serializer(graph_format, prefixes, base_iri)?;
+            py.allow_threads(|| {
+                let mut writer = serializer.triple_writer(output).map_err(map_io_err)?;
+                for quad in self.inner.quads_for_pattern(
+                    None,
+                    None,
+                    None,
+                    Some(
+                        from_graph_name
+                            .as_ref()
+                            .map_or(GraphNameRef::DefaultGraph, GraphName::as_ref),
+                    ),
+                ) {
+                    writer
+                        .write(quad.map_err(map_storage_error)?.as_ref())
+                        .map_err(map_io_err)?;
                 }
-                self.inner
-                    .dump_dataset(output, dataset_format)
-                    .map_err(map_serializer_error)
-            } else {
-                Err(PyValueError::new_err(format!(
-                    "Not supported MIME type: {}",
-                    mime_type
-                )))
+                writer.finish().map_err(map_io_err)
+            })
+        } else if let Some(dataset_format) = DatasetFormat::from_media_type(mime_type) {
+            if from_graph_name.is_some() {
+                return Err(PyValueError::new_err(
+                    "The target graph name parameter is not available for dataset formats",
+                ));
             }
-        })
+            let serializer = dataset_serializer(dataset_format, prefixes, base_iri)?;
+            py.allow_threads(|| {
+                let mut writer = serializer.quad_writer(output).map_err(map_io_err)?;
+                for quad in self.inner.iter() {
+                    writer
+                        .write(&quad.map_err(map_storage_error)?)
+                        .map_err(map_io_err)?;
+                }
+                writer.finish().map_err(map_io_err)
+            })
+        } else {
+            Err(PyValueError::new_err(format!(
+                "Not supported MIME type: {}",
+                mime_type
+            )))
+        }
     }
 
     /// Returns an iterator over all the store named graphs.
@@ -747,10 +855,3 @@ pub(crate) fn map_loader_error(error: LoaderError) -> PyErr {
         LoaderError::Parsing(error) => map_parse_error(error),
     }
 }
-
-pub(crate) fn map_serializer_error(error: SerializerError) -> PyErr {
-    match error {
-        SerializerError::Storage(error) => map_storage_error(error),
-        SerializerError::Io(error) => PyIOError::new_err(error.to_string()),
-    }
-}
@@ -217,5 +217,44 @@ class TestVariable(unittest.TestCase):
         self.assertNotEqual(Variable("foo"), Variable("bar"))
 
 
+
+class TestCanonicalize(unittest.TestCase):
+    def test_isomorphic(self):
+        p = NamedNode("http://example.com/p")
+        x = BlankNode()
+        y = BlankNode()
+        result1 = canonicalize([Triple(x, p, y)])
+        result2 = canonicalize([Quad(y, p, x, DefaultGraph())])
+        self.assertEqual(result1.n_quads, result2.n_quads)
+        self.assertEqual(result1.hash, result2.hash)
+        self.assertEqual(
+            result1.n_quads, "_:c14n1 <http://example.com/p> _:c14n0 .\n"
+        )
+        self.assertEqual(
+            result1.issued_identifiers,
+            {x: BlankNode("c14n1"), y: BlankNode("c14n0")},
+        )
+        self.assertEqual(
+            result1.quads, [Quad(BlankNode("c14n1"), p, BlankNode("c14n0"))]
+        )
+
+    def test_hash_algorithm(self):
+        triple = Triple(
+            BlankNode(), NamedNode("http://example.com/p"), Literal("o")
+        )
+        self.assertEqual(
+            len(canonicalize([triple], hash_algorithm="sha384").hash), 96
+        )
+        with self.assertRaises(ValueError):
+            canonicalize([triple], hash_algorithm="md5")
+
+    def test_work_limit(self):
+        p = NamedNode("http://example.com/p")
+        nodes = [BlankNode() for _ in range(6)]
+        triples = [Triple(s, p, o) for s in nodes for o in nodes if s != o]
+        with self.assertRaises(ValueError):
+            canonicalize(triples, work_limit=100)
+
+
 if __name__ == "__main__":
     unittest.main()
@@ -10,6 +10,7 @@ bar = NamedNode("http://bar")
 baz = NamedNode("http://baz")
 triple = Triple(foo, foo, foo)
 graph = NamedNode("http://graph")
+xsd_integer = NamedNode("http://www.w3.org/2001/XMLSchema#integer")
 
 
 class TestStore(unittest.TestCase):
@@ -152,6 +153,30 @@ class TestStore(unittest.TestCase):
         )
         self.assertEqual(len(list(results)), 2)
 
+    def test_select_query_to_arrow(self):
+        store = Store()
+        store.add(Quad(foo, bar, Literal("baz", language="en")))
+        table = store.query("SELECT ?s ?o ?u WHERE { ?s ?p ?o }").to_arrow(batch_size=1)
+        self.assertEqual(
+            table.column_names[:4], ["s", "s.kind", "s.datatype", "s.language"]
+        )
+        self.assertEqual(table.column("s").to_pylist(), ["http://foo"])
+        self.assertEqual(table.column("s.kind").to_pylist(), ["uri"])
+        self.assertEqual(table.column("o").to_pylist(), ["baz"])
+        self.assertEqual(table.column("o.language").to_pylist(), ["en"])
+        self.assertEqual(table.column("u").to_pylist(), [None])
+
+    def test_quads_to_arrow(self):
+        store = Store()
+        store.add(Quad(foo, bar, baz, graph))
+        store.add(Quad(foo, bar, Literal("1", datatype=xsd_integer)))
+        table = store.quads_to_arrow(None, None, None, DefaultGraph())
+        self.assertEqual(table.num_rows, 1)
+        self.assertEqual(table.column("object.datatype").to_pylist(), [xsd_integer.value])
+        self.assertEqual(table.column("graph_name").to_pylist(), [None])
+        table = store.quads_to_arrow(None, None, None, batch_size=1)
+        self.assertEqual(table.num_rows, 2)
+
     def test_update_insert_data(self):
         store = Store()
         store.update('INSERT DATA { <http://foo> <http://foo> <http://foo> }')
@@ -258,6 +283,22 @@ class TestStore(unittest.TestCase):
             b"<http://foo> <http://bar> <http://baz> <http://graph> .\n",
         )
 
+    def test_dump_pretty_turtle(self):
+        store = Store()
+        store.add(Quad(foo, bar, baz, graph))
+        output = BytesIO()
+        store.dump(
+            output,
+            "text/turtle",
+            from_graph=graph,
+            prefixes={"ex": "http://"},
+            base_iri="http://example.com/",
+        )
+        self.assertEqual(
+            output.getvalue(),
+            b"@base <http://example.com/> .\n@prefix ex: <http://> .\n\nex:foo ex:bar ex:baz .\n",
+        )
+
     def test_dump_file(self):
         with NamedTemporaryFile(delete=False) as fp:
             file_name = fp.name
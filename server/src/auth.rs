@@ -0,0 +1,70 @@
+//! HTTP Basic authentication of the clients.
+
+use crate::datasets::dataset_path;
+use crate::{error, is_write_request};
+use oxhttp::model::{HeaderName, Request, Response, Status};
+use std::collections::HashMap;
+
+/// The users allowed to use the server, authentication is disabled if there is none
+#[derive(Default)]
+pub struct AuthOptions {
+    /// The passwords by user name
+    pub users: HashMap<String, String>,
+    /// If the queries and the reads of the Graph Store Protocol are allowed without authentication
+    pub anonymous_read: bool,
+}
+
+/// Checks the `Authorization` header before calling `handler`
+///
+/// The administration API and `/metrics` always require authentication, the `/health/` probes never do.
+pub fn handle_auth(
+    request: &mut Request,
+    auth: &AuthOptions,
+    handler: impl FnOnce(&mut Request) -> Response,
+) -> Response {
+    if auth.users.is_empty() || request.url().path().starts_with("/health/") {
+        return handler(request);
+    }
+    let path = dataset_path(request.url().path()).1;
+    let is_anonymous_allowed = auth.anonymous_read
+        && !path.starts_with("/datasets")
+        && request.url().path() != "/metrics"
+        && !is_write_request(path, request.method().as_ref());
+    if is_anonymous_allowed || is_authenticated(request, auth) {
+        handler(request)
+    } else {
+        let mut response = error(Status::UNAUTHORIZED, "Authentication required");
+        response
+            .append_header(
+                HeaderName::WWW_AUTHENTICATE,
+                "Basic realm=\"Oxigraph\", charset=\"UTF-8\"",
+            )
+            .unwrap();
+        response
+    }
+}
+
+fn is_authenticated(request: &Request, auth: &AuthOptions) -> bool {
+    let credentials = request
+        .header(&HeaderName::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|credentials| base64::decode(credentials.trim()).ok())
+        .and_then(|credentials| String::from_utf8(credentials).ok());
+    if let Some((user, password)) = credentials.as_deref().and_then(|c| c.split_once(':')) {
+        auth.users
+            .get(user)
+            .map_or(false, |expected| constant_time_eq(expected, password))
+    } else {
+        false
+    }
+}
+
+/// Compares the passwords without leaking through timing how many of their first bytes are equal
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
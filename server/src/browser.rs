@@ -0,0 +1,379 @@
+//! Server-rendered HTML browser of the dataset content, served under `/browse`.
+//!
+//! It does not load any external asset so it works without Internet access.
+
+use crate::{bad_request, error, html, internal_server_error, url_query};
+use oxhttp::model::{Request, Response, Status};
+use oxigraph::model::{GraphName, GraphNameRef, NamedNode, NamedOrBlankNode, Quad, Subject, Term};
+use oxigraph::store::{StorageError, Store};
+use std::iter::once;
+use std::str::FromStr;
+use url::form_urlencoded;
+
+/// Number of quads or graphs displayed on each page
+const PAGE_SIZE: usize = 100;
+/// Maximal page number
+const MAX_PAGE: usize = 1_000_000;
+/// Maximal number of quads counted in each graph
+const COUNT_LIMIT: usize = 10_000;
+
+/// Serves the `/browse` pages, `path` is relative to the dataset and `prefix` is the path of the dataset
+pub fn handle_browser_request(
+    request: &Request,
+    store: &Store,
+    prefix: &str,
+    path: &str,
+) -> Response {
+    let result = match path {
+        "/browse" | "/browse/" => {
+            let mut page = 0;
+            for (k, v) in form_urlencoded::parse(url_query(request)) {
+                match k.as_ref() {
+                    "page" => match parse_page(&v) {
+                        Ok(p) => page = p,
+                        Err(response) => return response,
+                    },
+                    _ => return bad_request(format!("Unexpected parameter: {}", k)),
+                }
+            }
+            graphs_page(store, prefix, page)
+        }
+        "/browse/resource" => {
+            let mut iri = None;
+            for (k, v) in form_urlencoded::parse(url_query(request)) {
+                match k.as_ref() {
+                    "iri" => iri = Some(v.into_owned()),
+                    _ => return bad_request(format!("Unexpected parameter: {}", k)),
+                }
+            }
+            let iri = match iri.map(NamedNode::new) {
+                Some(Ok(iri)) => iri,
+                Some(Err(e)) => return bad_request(e),
+                None => return bad_request("The iri parameter is required"),
+            };
+            resource_page(store, prefix, &iri)
+        }
+        "/browse/quads" => {
+            let pattern = match QuadPattern::from_query(url_query(request)) {
+                Ok(pattern) => pattern,
+                Err(response) => return response,
+            };
+            quads_page(store, prefix, &pattern)
+        }
+        _ => {
+            return error(
+                Status::NOT_FOUND,
+                format!("{} is not a page of the browser", path),
+            )
+        }
+    };
+    result.unwrap_or_else(internal_server_error)
+}
+
+/// Lists the default graph and the named graphs with their number of quads
+///
+/// The graphs are paginated and the quads are only counted up to [`COUNT_LIMIT`] to bound the cost of the page.
+fn graphs_page(store: &Store, prefix: &str, page: usize) -> Result<Response, StorageError> {
+    let mut graphs = once(Ok(GraphName::DefaultGraph))
+        .chain(store.named_graphs().map(|graph| {
+            Ok(match graph? {
+                NamedOrBlankNode::NamedNode(graph) => graph.into(),
+                NamedOrBlankNode::BlankNode(graph) => graph.into(),
+            })
+        }))
+        .skip(page * PAGE_SIZE) // The pages are bounded by parse_page
+        .take(PAGE_SIZE + 1)
+        .collect::<Result<Vec<_>, StorageError>>()?;
+    let has_more = graphs.len() > PAGE_SIZE;
+    graphs.truncate(PAGE_SIZE);
+    let mut body = navigation(prefix);
+    body.push_str("<table>\n<thead><tr><th>Graph</th><th>Quads</th></tr></thead>\n<tbody>\n");
+    for graph in &graphs {
+        let mut count = 0;
+        for quad in store
+            .quads_for_pattern(None, None, None, Some(graph.as_ref()))
+            .take(COUNT_LIMIT + 1)
+        {
+            quad?;
+            count += 1;
+        }
+        let pattern = QuadPattern {
+            graph_name: Some(graph.clone()),
+            ..QuadPattern::default()
+        };
+        body.push_str(&format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td></tr>\n",
+            html::escape(&pattern.url(prefix, 0)),
+            match graph {
+                GraphName::DefaultGraph => "Default graph".into(),
+                graph => html::escape(&graph.to_string()),
+            },
+            if count > COUNT_LIMIT {
+                format!("more than {}", COUNT_LIMIT)
+            } else {
+                count.to_string()
+            }
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n<p>");
+    if page > 0 {
+        body.push_str(&format!(
+            "<a href=\"{}/browse{}\">Previous</a> ",
+            html::escape(prefix),
+            graphs_page_query(page - 1)
+        ));
+    }
+    if has_more {
+        body.push_str(&format!(
+            "<a href=\"{}/browse{}\">Next</a>",
+            html::escape(prefix),
+            graphs_page_query(page + 1)
+        ));
+    }
+    body.push_str("</p>");
+    Ok(html::page("Graphs", &body))
+}
+
+fn graphs_page_query(page: usize) -> String {
+    if page > 0 {
+        format!("?page={}", page)
+    } else {
+        String::new()
+    }
+}
+
+/// Shows the quads in which the resource is the subject and the ones in which it is the object
+fn resource_page(store: &Store, prefix: &str, iri: &NamedNode) -> Result<Response, StorageError> {
+    let mut body = navigation(prefix);
+    for (title, pattern) in [
+        (
+            "Outgoing",
+            QuadPattern {
+                subject: Some(iri.clone().into()),
+                ..QuadPattern::default()
+            },
+        ),
+        (
+            "Incoming",
+            QuadPattern {
+                object: Some(iri.clone().into()),
+                ..QuadPattern::default()
+            },
+        ),
+    ] {
+        body.push_str(&format!("<h2>{}</h2>\n", title));
+        let (quads, has_more) = pattern.page(store, 0)?;
+        body.push_str(&quads_table(&quads, prefix));
+        if has_more {
+            body.push_str(&format!(
+                "<p><a href=\"{}\">More</a></p>\n",
+                html::escape(&pattern.url(prefix, 1))
+            ));
+        }
+    }
+    Ok(html::page(iri.as_str(), &body))
+}
+
+/// Pages through the quads matching a pattern
+fn quads_page(
+    store: &Store,
+    prefix: &str,
+    pattern: &QuadPattern,
+) -> Result<Response, StorageError> {
+    let (quads, has_more) = pattern.page(store, pattern.page)?;
+    let mut body = navigation(prefix);
+    body.push_str(&format!(
+        "<form action=\"{}/browse/quads\">\n{}{}{}{}<button type=\"submit\">Search</button>\n</form>\n<p><small>The terms are written in N-Triples, the graph could also be <code>default</code>.</small></p>\n",
+        html::escape(prefix),
+        pattern_input("subject", pattern.subject.as_ref().map(Subject::to_string)),
+        pattern_input("predicate", pattern.predicate.as_ref().map(NamedNode::to_string)),
+        pattern_input("object", pattern.object.as_ref().map(Term::to_string)),
+        pattern_input("graph", pattern.graph_name.as_ref().map(|graph| match graph {
+            GraphName::DefaultGraph => "default".into(),
+            graph => graph.to_string(),
+        }))
+    ));
+    body.push_str(&quads_table(&quads, prefix));
+    body.push_str("<p>");
+    if pattern.page > 0 {
+        body.push_str(&format!(
+            "<a href=\"{}\">Previous</a> ",
+            html::escape(&pattern.url(prefix, pattern.page - 1))
+        ));
+    }
+    if has_more {
+        body.push_str(&format!(
+            "<a href=\"{}\">Next</a>",
+            html::escape(&pattern.url(prefix, pattern.page + 1))
+        ));
+    }
+    body.push_str("</p>");
+    Ok(html::page(
+        &format!("Quads, page {}", pattern.page + 1),
+        &body,
+    ))
+}
+
+fn navigation(prefix: &str) -> String {
+    format!(
+        "<nav><a href=\"{0}/browse\">Graphs</a> <a href=\"{0}/browse/quads\">Quads</a> <a href=\"{0}/\">SPARQL</a></nav>\n",
+        html::escape(prefix)
+    )
+}
+
+fn pattern_input(name: &str, value: Option<String>) -> String {
+    format!(
+        "<label>{0} <input name=\"{0}\" value=\"{1}\"></label>\n",
+        name,
+        html::escape(&value.unwrap_or_default())
+    )
+}
+
+fn quads_table(quads: &[Quad], prefix: &str) -> String {
+    let link = |iri: &str| resource_url(prefix, iri);
+    let mut table = String::from(
+        "<table>\n<thead><tr><th>Subject</th><th>Predicate</th><th>Object</th><th>Graph</th></tr></thead>\n<tbody>\n",
+    );
+    for quad in quads {
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            html::term(quad.subject.as_ref().into(), link),
+            html::term(quad.predicate.as_ref().into(), link),
+            html::term(quad.object.as_ref(), link),
+            match quad.graph_name.as_ref() {
+                GraphNameRef::NamedNode(graph) => html::term(graph.into(), link),
+                GraphNameRef::BlankNode(graph) => html::term(graph.into(), link),
+                GraphNameRef::DefaultGraph => "<small>default</small>".into(),
+            }
+        ));
+    }
+    table.push_str("</tbody>\n</table>\n");
+    table
+}
+
+fn resource_url(prefix: &str, iri: &str) -> String {
+    format!(
+        "{}/browse/resource?{}",
+        prefix,
+        form_urlencoded::Serializer::new(String::new())
+            .append_pair("iri", iri)
+            .finish()
+    )
+}
+
+#[derive(Default)]
+struct QuadPattern {
+    subject: Option<Subject>,
+    predicate: Option<NamedNode>,
+    object: Option<Term>,
+    graph_name: Option<GraphName>,
+    page: usize,
+}
+
+impl QuadPattern {
+    /// Parses the `subject`, `predicate`, `object`, `graph` and `page` URL query parameters
+    ///
+    /// The empty parameters are ignored to support the forms.
+    fn from_query(query: &[u8]) -> Result<Self, Response> {
+        let mut pattern = Self::default();
+        for (k, v) in form_urlencoded::parse(query) {
+            let v = v.trim();
+            if v.is_empty() {
+                continue;
+            }
+            match k.as_ref() {
+                "subject" => {
+                    pattern.subject = Some(match parse_term(v)? {
+                        Term::NamedNode(node) => node.into(),
+                        Term::BlankNode(node) => node.into(),
+                        Term::Triple(triple) => triple.into(),
+                        Term::Literal(_) => {
+                            return Err(bad_request("The subject can't be a literal"))
+                        }
+                    })
+                }
+                "predicate" => {
+                    pattern.predicate = Some(match parse_term(v)? {
+                        Term::NamedNode(node) => node,
+                        _ => return Err(bad_request("The predicate must be an IRI")),
+                    })
+                }
+                "object" => pattern.object = Some(parse_term(v)?),
+                "graph" => {
+                    pattern.graph_name = Some(if v == "default" {
+                        GraphName::DefaultGraph
+                    } else {
+                        match parse_term(v)? {
+                            Term::NamedNode(node) => node.into(),
+                            Term::BlankNode(node) => node.into(),
+                            _ => {
+                                return Err(bad_request(
+                                    "The graph must be an IRI, a blank node or default",
+                                ))
+                            }
+                        }
+                    })
+                }
+                "page" => pattern.page = parse_page(v)?,
+                _ => return Err(bad_request(format!("Unexpected parameter: {}", k))),
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// The URL of the given page of the quads matching the pattern
+    fn url(&self, prefix: &str, page: usize) -> String {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        if let Some(subject) = &self.subject {
+            query.append_pair("subject", &subject.to_string());
+        }
+        if let Some(predicate) = &self.predicate {
+            query.append_pair("predicate", &predicate.to_string());
+        }
+        if let Some(object) = &self.object {
+            query.append_pair("object", &object.to_string());
+        }
+        match &self.graph_name {
+            Some(GraphName::DefaultGraph) => {
+                query.append_pair("graph", "default");
+            }
+            Some(graph) => {
+                query.append_pair("graph", &graph.to_string());
+            }
+            None => (),
+        }
+        if page > 0 {
+            query.append_pair("page", &page.to_string());
+        }
+        format!("{}/browse/quads?{}", prefix, query.finish())
+    }
+
+    /// Returns the quads of the page and if there are more quads after it
+    fn page(&self, store: &Store, page: usize) -> Result<(Vec<Quad>, bool), StorageError> {
+        let mut quads = store
+            .quads_for_pattern(
+                self.subject.as_ref().map(Subject::as_ref),
+                self.predicate.as_ref().map(NamedNode::as_ref),
+                self.object.as_ref().map(Term::as_ref),
+                self.graph_name.as_ref().map(GraphName::as_ref),
+            )
+            .skip(page * PAGE_SIZE) // The pages are bounded by parse_page
+            .take(PAGE_SIZE + 1)
+            .collect::<Result<Vec<_>, _>>()?;
+        let has_more = quads.len() > PAGE_SIZE;
+        quads.truncate(PAGE_SIZE);
+        Ok((quads, has_more))
+    }
+}
+
+/// Parses a page number, the pages are bounded for their offset to never overflow
+fn parse_page(value: &str) -> Result<usize, Response> {
+    match value.parse::<usize>() {
+        Ok(page) if page <= MAX_PAGE => Ok(page),
+        _ => Err(bad_request(format!("Invalid page number: {}", value))),
+    }
+}
+
+fn parse_term(value: &str) -> Result<Term, Response> {
+    Term::from_str(value).map_err(|e| bad_request(format!("Invalid term {}: {}", value, e)))
+}
@@ -0,0 +1,228 @@
+//! The server configuration file.
+//!
+//! It is written in [TOML](https://toml.io/). The command line options override its values.
+
+use serde::Deserialize;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Host and port to listen to
+    pub bind: Option<String>,
+    /// Directory in which the default store is persisted
+    pub location: Option<PathBuf>,
+    /// If the default graph of the queries is the union of all the graphs by default
+    pub union_default_graph: bool,
+    /// Number of query plans kept in cache by each server thread
+    pub query_plan_cache_size: Option<usize>,
+    /// If the `/datasets` administration API is enabled
+    pub admin_api: bool,
+    /// The directory in which the datasets created with the administration API are persisted
+    pub datasets_directory: Option<PathBuf>,
+    pub rocksdb: RocksDbConfig,
+    pub limits: LimitsConfig,
+    pub timeouts: TimeoutsConfig,
+    pub cors: CorsConfig,
+    pub tls: TlsConfig,
+    pub auth: AuthConfig,
+    pub log: LogConfig,
+    pub linked_data: LinkedDataConfig,
+    /// The named datasets
+    #[serde(rename = "dataset")]
+    pub datasets: Vec<DatasetConfig>,
+}
+
+/// The `[rocksdb]` table, tuning the storage of the default store and of the named datasets
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RocksDbConfig {
+    pub block_cache_size: Option<usize>,
+    pub max_open_files: Option<u32>,
+    pub write_buffer_size: Option<usize>,
+}
+
+/// The `[limits]` table
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Maximal size in bytes of the SPARQL queries and updates sent in request bodies
+    pub max_sparql_body_size: Option<u64>,
+    /// Maximal size in bytes of the RDF Patch documents sent to `/store`
+    pub max_patch_body_size: Option<u64>,
+    /// Maximal size in bytes of the RDF documents sent to `/store` with `PUT` or `POST`
+    pub max_store_body_size: Option<u64>,
+}
+
+/// The `[timeouts]` table, in seconds
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutsConfig {
+    /// Maximal duration of the HTTP requests, including the sending of the responses
+    pub http: Option<f64>,
+}
+
+/// The `[cors]` table
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    pub origins: Vec<String>,
+    pub methods: Option<Vec<String>>,
+    pub headers: Option<Vec<String>>,
+    pub allow_credentials: bool,
+}
+
+/// The `[tls]` table
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    pub client_ca: Option<PathBuf>,
+}
+
+/// The `[auth]` table
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// The users allowed to use the server with HTTP Basic authentication, authentication is disabled if empty
+    pub users: Vec<UserConfig>,
+    /// If the queries and the reads of the Graph Store Protocol are allowed without authentication
+    pub anonymous_read: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserConfig {
+    pub name: String,
+    pub password: String,
+}
+
+/// The `[log]` table
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub access_log: Option<PathBuf>,
+    pub slow_query_log: Option<PathBuf>,
+    /// Time in seconds above which a query is written to the slow query log
+    pub slow_query_threshold: Option<f64>,
+}
+
+/// The `[linked_data]` table
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinkedDataConfig {
+    /// The graphs in which the descriptions are looked for: `default`, `union` or the IRI of a named graph
+    pub graph: Option<String>,
+    #[serde(rename = "mapping")]
+    pub mappings: Vec<LinkedDataMappingConfig>,
+}
+
+/// A namespace dereferenced by the server, from a `[[linked_data.mapping]]` table
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinkedDataMappingConfig {
+    /// The IRI prefix of the resources
+    pub namespace: String,
+    /// The server path prefix to which the namespace is mapped
+    pub path: String,
+    /// If set, the requests to `path` are redirected with `303 See Other` to this path prefix serving the descriptions
+    pub document_path: Option<String>,
+}
+
+/// A named dataset, from a `[[dataset]]` table
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatasetConfig {
+    pub name: String,
+    /// The directory in which the dataset is persisted, it is kept in memory if not set
+    pub location: Option<PathBuf>,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        toml::from_str(&fs::read_to_string(path)?).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid configuration file {}: {}", path.display(), e),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config: Config = toml::from_str(
+            r#"
+            bind = "0.0.0.0:7878"
+            location = "data"
+            union_default_graph = true
+
+            [rocksdb]
+            block_cache_size = 1073741824
+
+            [limits]
+            max_sparql_body_size = 4096
+            max_patch_body_size = 8192
+            max_store_body_size = 16384
+
+            [timeouts]
+            http = 0.5
+
+            [cors]
+            origins = ["https://example.com"]
+
+            [auth]
+            users = [{ name = "admin", password = "secret" }]
+            anonymous_read = true
+
+            [log]
+            slow_query_threshold = 2
+
+            [linked_data]
+            graph = "union"
+
+            [[linked_data.mapping]]
+            namespace = "http://example.com/id/"
+            path = "/id/"
+            document_path = "/doc/"
+
+            [[dataset]]
+            name = "vocabularies"
+            read_only = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.bind.as_deref(), Some("0.0.0.0:7878"));
+        assert_eq!(config.location, Some(PathBuf::from("data")));
+        assert!(config.union_default_graph);
+        assert_eq!(config.rocksdb.block_cache_size, Some(1_073_741_824));
+        assert_eq!(config.limits.max_sparql_body_size, Some(4096));
+        assert_eq!(config.limits.max_patch_body_size, Some(8192));
+        assert_eq!(config.limits.max_store_body_size, Some(16384));
+        assert_eq!(config.timeouts.http, Some(0.5));
+        assert_eq!(config.cors.origins, ["https://example.com"]);
+        assert!(config.cors.methods.is_none());
+        assert_eq!(config.auth.users[0].name, "admin");
+        assert!(config.auth.anonymous_read);
+        assert_eq!(config.log.slow_query_threshold, Some(2.));
+        assert_eq!(config.linked_data.graph.as_deref(), Some("union"));
+        assert_eq!(config.linked_data.mappings[0].path, "/id/");
+        assert_eq!(
+            config.linked_data.mappings[0].document_path.as_deref(),
+            Some("/doc/")
+        );
+        assert_eq!(config.datasets[0].name, "vocabularies");
+        assert!(config.datasets[0].read_only);
+
+        assert!(toml::from_str::<Config>("unknown = 1").is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Named datasets served under the `/{name}/` path prefixes and their administration API.
+
+use crate::logs::JsonObject;
+use crate::{bad_request, error, internal_server_error};
+use oxhttp::model::{Body, HeaderName, Request, Response, Status};
+use oxigraph::store::{StorageOptions, Store};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read};
+use std::mem::replace;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use url::form_urlencoded;
+
+/// The first path segments that are routes of the server and can't be dataset names
+pub const RESERVED_NAMES: [&str; 9] = [
+    "query",
+    "update",
+    "store",
+    "browse",
+    "fragments",
+    "logo.svg",
+    "metrics",
+    "health",
+    "datasets",
+];
+
+/// File written in the directory of the datasets created read-only with the administration API
+const READ_ONLY_MARKER: &str = "oxigraph_read_only";
+
+/// A store served by the server
+///
+/// The clones must be kept while the store is used: the directory of a dropped dataset is only deleted once all of them are gone.
+#[derive(Clone)]
+pub struct Dataset {
+    // Declared first to be dropped before the removal of the directory by `usage`
+    pub store: Store,
+    /// If the SPARQL updates and the Graph Store Protocol writes are rejected
+    pub read_only: bool,
+    /// The directory in which the store is persisted, `None` if it is in memory
+    pub location: Option<PathBuf>,
+    usage: Arc<DatasetUsage>,
+}
+
+impl Dataset {
+    pub fn open(
+        location: Option<PathBuf>,
+        read_only: bool,
+        options: StorageOptions,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            store: if let Some(location) = &location {
+                Store::open_with_options(location, options)
+            } else {
+                Store::new()
+            }?,
+            read_only,
+            location,
+            usage: Arc::default(),
+        })
+    }
+
+    /// Keeps the dataset until the body of the response has been sent if it is streamed
+    pub fn hold_until_sent(self, response: &mut Response) {
+        if response.body().len().is_none() {
+            let body = replace(response.body_mut(), Body::from(Vec::new()));
+            *response.body_mut() = Body::from_read(HeldBody {
+                body,
+                _dataset: self,
+            });
+        }
+    }
+}
+
+struct HeldBody {
+    // Declared first to drop its store before the dataset
+    body: Body,
+    _dataset: Dataset,
+}
+
+impl Read for HeldBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+/// Shared by all the clones of a dataset
+#[derive(Default)]
+struct DatasetUsage {
+    /// The directory to delete when the last clone is dropped and the set of the directories being deleted
+    removal: Mutex<Option<(PathBuf, Arc<Mutex<HashSet<PathBuf>>>)>>,
+}
+
+impl Drop for DatasetUsage {
+    fn drop(&mut self) {
+        if let Some((location, removing)) = self.removal.get_mut().unwrap().take() {
+            if let Err(e) = fs::remove_dir_all(&location) {
+                eprintln!(
+                    "Error while deleting the dataset directory {}: {}",
+                    location.display(),
+                    e
+                );
+            }
+            removing.lock().unwrap().remove(&location);
+        }
+    }
+}
+
+/// The named datasets, they could be created and dropped while the server is running
+pub struct Datasets {
+    datasets: RwLock<HashMap<String, Dataset>>,
+    /// The directory in which the datasets created with the administration API are persisted
+    ///
+    /// They are kept in memory if it is not set.
+    directory: Option<PathBuf>,
+    options: StorageOptions,
+    /// The directories of the dropped datasets that are still used
+    removing: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl Datasets {
+    /// Opens all the datasets persisted in the subdirectories of `directory`
+    pub fn open(directory: Option<PathBuf>, options: StorageOptions) -> io::Result<Self> {
+        let mut datasets = HashMap::new();
+        if let Some(directory) = &directory {
+            fs::create_dir_all(directory)?;
+            for entry in fs::read_dir(directory)? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                if let Some(name) = entry.file_name().to_str() {
+                    if is_valid_name(name) {
+                        let read_only = entry.path().join(READ_ONLY_MARKER).exists();
+                        datasets.insert(
+                            name.into(),
+                            Dataset::open(Some(entry.path()), read_only, options)?,
+                        );
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            datasets: RwLock::new(datasets),
+            directory,
+            options,
+            removing: Arc::default(),
+        })
+    }
+
+    /// Adds a dataset, replacing the one with the same name if it exists
+    pub fn insert(&self, name: &str, dataset: Dataset) -> io::Result<()> {
+        if !is_valid_name(name) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} is not a valid dataset name", name),
+            ));
+        }
+        self.datasets.write().unwrap().insert(name.into(), dataset);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<Dataset> {
+        self.datasets.read().unwrap().get(name).cloned()
+    }
+}
+
+/// Dataset names are made of ASCII letters, digits, `-` and `_` and must not be a route of the server
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+        && !RESERVED_NAMES.contains(&name)
+}
+
+/// Splits a request path between the dataset name and the path inside of the dataset
+///
+/// The name is `None` for the paths of the default dataset like `/query` or `/store/foo`.
+pub fn dataset_path(path: &str) -> (Option<&str>, &str) {
+    if let Some((name, rest)) = path
+        .strip_prefix('/')
+        .and_then(|path| path.find('/').map(|i| path.split_at(i)))
+    {
+        if is_valid_name(name) {
+            return (Some(name), rest);
+        }
+    }
+    (None, path)
+}
+
+/// Serves the `/datasets` administration API
+///
+/// * `GET /datasets` lists the datasets in JSON.
+/// * `PUT /datasets/{name}` creates a dataset, read-only if the `read_only` parameter is set.
+/// * `DELETE /datasets/{name}` drops a dataset. Its data is deleted if it has been persisted in the datasets directory.
+pub fn handle_admin_request(request: &Request, datasets: &Datasets) -> Response {
+    let path = request.url().path();
+    match (path, request.method().as_ref()) {
+        ("/datasets", "GET") => {
+            let datasets = datasets.datasets.read().unwrap();
+            let mut names = datasets.keys().collect::<Vec<_>>();
+            names.sort();
+            let body = names
+                .into_iter()
+                .map(|name| {
+                    let dataset = &datasets[name];
+                    let object = JsonObject::new()
+                        .with_string("name", name)
+                        .with_bool("read_only", dataset.read_only);
+                    if let Some(location) = &dataset.location {
+                        object.with_string("location", &location.to_string_lossy())
+                    } else {
+                        object
+                    }
+                    .finish()
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            Response::builder(Status::OK)
+                .with_header(HeaderName::CONTENT_TYPE, "application/json")
+                .unwrap()
+                .with_body(format!("[{}]", body))
+        }
+        (path, "PUT") if path.starts_with("/datasets/") => {
+            let name = &path["/datasets/".len()..];
+            if !is_valid_name(name) {
+                return bad_request(format!("{} is not a valid dataset name", name));
+            }
+            let mut read_only = false;
+            for (k, _) in form_urlencoded::parse(request.url().query().unwrap_or("").as_bytes()) {
+                match k.as_ref() {
+                    "read_only" => read_only = true,
+                    _ => return bad_request(format!("Unexpected parameter: {}", k)),
+                }
+            }
+            let mut datasets_by_name = datasets.datasets.write().unwrap();
+            if datasets_by_name.contains_key(name) {
+                return error(
+                    Status::CONFLICT,
+                    format!("The dataset {} already exists", name),
+                );
+            }
+            let location = datasets.directory.as_ref().map(|d| d.join(name));
+            if let Some(location) = &location {
+                if datasets.removing.lock().unwrap().contains(location) {
+                    return error(
+                        Status::CONFLICT,
+                        format!(
+                            "The previous dataset {} is still used and has not been deleted yet",
+                            name
+                        ),
+                    );
+                }
+            }
+            let dataset = match Dataset::open(location, read_only, datasets.options) {
+                Ok(dataset) => dataset,
+                Err(e) => return internal_server_error(e),
+            };
+            if let (true, Some(location)) = (read_only, &dataset.location) {
+                // The flag is kept when the server restarts
+                if let Err(e) = File::create(location.join(READ_ONLY_MARKER)) {
+                    return internal_server_error(e);
+                }
+            }
+            datasets_by_name.insert(name.into(), dataset);
+            Response::builder(Status::CREATED).build()
+        }
+        (path, "DELETE") if path.starts_with("/datasets/") => {
+            let name = &path["/datasets/".len()..];
+            let dataset = if let Some(dataset) = datasets.datasets.write().unwrap().remove(name) {
+                dataset
+            } else {
+                return error(
+                    Status::NOT_FOUND,
+                    format!("The dataset {} does not exist", name),
+                );
+            };
+            if let (Some(location), Some(directory)) = (&dataset.location, &datasets.directory) {
+                if location.parent() == Some(directory.as_path()) {
+                    // The directory is deleted once the requests using the dataset are done
+                    datasets.removing.lock().unwrap().insert(location.clone());
+                    *dataset.usage.removal.lock().unwrap() =
+                        Some((location.clone(), datasets.removing.clone()));
+                }
+            }
+            drop(dataset);
+            Response::builder(Status::NO_CONTENT).build()
+        }
+        _ => error(
+            Status::NOT_FOUND,
+            format!(
+                "{} {} is not supported by this server",
+                request.method(),
+                path
+            ),
+        ),
+    }
+}
@@ -0,0 +1,449 @@
+//! [Triple Pattern Fragments](https://linkeddatafragments.org/specification/triple-pattern-fragments/) endpoint, served under `/fragments`.
+//!
+//! The fragments are extended to quad patterns with the `graph` parameter, following the Quad Pattern Fragments interface.
+
+use crate::{
+    bad_request, base_url, content_negotiation, internal_server_error, url_query, DATASET_FORMATS,
+    GRAPH_FORMATS, SD_NAMESPACE, VOID_NAMESPACE,
+};
+use oxhttp::model::{HeaderName, Request, Response, Status};
+use oxigraph::io::{DatasetFormat, DatasetSerializer, GraphFormat, GraphSerializer};
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{
+    BlankNode, Graph, GraphName, Literal, NamedNode, Quad, Subject, Term, Triple,
+};
+use oxigraph::store::{StorageError, Store};
+use std::cmp::max;
+use std::io::{self, ErrorKind};
+use url::form_urlencoded;
+
+const HYDRA_NAMESPACE: &str = "http://www.w3.org/ns/hydra/core#";
+/// The prefixes used by the Turtle and TriG serializations
+const PREFIXES: [(&str, &str); 4] = [
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("hydra", HYDRA_NAMESPACE),
+    ("void", VOID_NAMESPACE),
+    ("sd", SD_NAMESPACE),
+];
+/// Number of quads in each page of a fragment
+const PAGE_SIZE: usize = 100;
+/// Number of matching quads above which the fragments total count is only estimated
+const COUNT_LIMIT: usize = 10_000;
+
+enum FragmentFormat {
+    Graph(GraphFormat),
+    Dataset(DatasetFormat),
+}
+
+/// Serves the page of the fragment selected by the `subject`, `predicate`, `object`, `graph` and `page` parameters
+///
+/// The matching quads are followed by the fragment metadata and the Hydra controls.
+/// With the dataset formats the metadata is written in the `{fragment URL}#metadata` named graph.
+pub fn handle_fragment_request(request: &Request, store: &Store) -> Response {
+    let format = match content_negotiation(
+        request,
+        &GRAPH_FORMATS
+            .map(GraphFormat::media_type)
+            .into_iter()
+            .chain(DATASET_FORMATS.map(DatasetFormat::media_type))
+            .collect::<Vec<_>>(),
+        |media_type| {
+            GraphFormat::from_media_type(media_type)
+                .map(FragmentFormat::Graph)
+                .or_else(|| DatasetFormat::from_media_type(media_type).map(FragmentFormat::Dataset))
+        },
+    ) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+    let pattern = match FragmentPattern::from_query(url_query(request)) {
+        Ok(pattern) => pattern,
+        Err(response) => return response,
+    };
+    let base = match base_url(request) {
+        Ok(base) => base,
+        Err(response) => return response,
+    };
+    let (quads, count, is_exact, has_next) = match pattern.evaluate(store) {
+        Ok(result) => result,
+        Err(e) => return internal_server_error(e),
+    };
+    let fragment = NamedNode::new_unchecked(pattern.url(&base, pattern.page));
+    let metadata = metadata(&base, &fragment, &pattern, count, is_exact, has_next);
+
+    let mut body = Vec::new();
+    let (result, media_type) = match format {
+        FragmentFormat::Graph(format) => (
+            PREFIXES
+                .into_iter()
+                .try_fold(
+                    GraphSerializer::from_format(format),
+                    |serializer, (name, iri)| serializer.with_prefix(name, iri),
+                )
+                .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))
+                .and_then(|serializer| serializer.pretty().triple_writer(&mut body))
+                .and_then(|mut writer| {
+                    for quad in &quads {
+                        writer.write(&Triple::from(quad.clone()))?;
+                    }
+                    for triple in &metadata {
+                        writer.write(triple)?;
+                    }
+                    writer.finish()
+                }),
+            format.media_type(),
+        ),
+        FragmentFormat::Dataset(format) => {
+            let metadata_graph =
+                NamedNode::new_unchecked(format!("{}#metadata", fragment.as_str()));
+            (
+                PREFIXES
+                    .into_iter()
+                    .try_fold(
+                        DatasetSerializer::from_format(format),
+                        |serializer, (name, iri)| serializer.with_prefix(name, iri),
+                    )
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))
+                    .and_then(|serializer| serializer.pretty().quad_writer(&mut body))
+                    .and_then(|mut writer| {
+                        for quad in &quads {
+                            writer.write(quad)?;
+                        }
+                        for triple in &metadata {
+                            writer.write(triple.in_graph(&metadata_graph))?;
+                        }
+                        writer.finish()
+                    }),
+                format.media_type(),
+            )
+        }
+    };
+    if let Err(e) = result {
+        return internal_server_error(e);
+    }
+    Response::builder(Status::OK)
+        .with_header(HeaderName::CONTENT_TYPE, media_type)
+        .unwrap()
+        .with_body(body)
+}
+
+/// Builds the fragment metadata and its Hydra controls
+///
+/// If the count is not exact, it is a lower bound published as an estimate, as allowed by the TPF specification.
+fn metadata(
+    base: &str,
+    fragment: &NamedNode,
+    pattern: &FragmentPattern,
+    count: usize,
+    is_exact: bool,
+    has_next: bool,
+) -> Graph {
+    let hydra = |name: &str| NamedNode::new_unchecked(format!("{}{}", HYDRA_NAMESPACE, name));
+    let void = |name: &str| NamedNode::new_unchecked(format!("{}{}", VOID_NAMESPACE, name));
+    let sd = |name: &str| NamedNode::new_unchecked(format!("{}{}", SD_NAMESPACE, name));
+
+    let mut graph = Graph::new();
+    let dataset = NamedNode::new_unchecked(format!("{}#dataset", base));
+    graph.insert(&Triple::new(dataset.clone(), rdf::TYPE, void("Dataset")));
+    graph.insert(&Triple::new(
+        dataset.clone(),
+        rdf::TYPE,
+        hydra("Collection"),
+    ));
+    graph.insert(&Triple::new(
+        dataset.clone(),
+        void("subset"),
+        fragment.clone(),
+    ));
+    let search = BlankNode::default();
+    graph.insert(&Triple::new(dataset, hydra("search"), search.clone()));
+    graph.insert(&Triple::new(
+        search.clone(),
+        hydra("template"),
+        Literal::new_simple_literal(format!("{}{{?subject,predicate,object,graph}}", base)),
+    ));
+    graph.insert(&Triple::new(
+        search.clone(),
+        hydra("variableRepresentation"),
+        hydra("ExplicitRepresentation"),
+    ));
+    for (variable, property) in [
+        ("subject", rdf::SUBJECT.into_owned()),
+        ("predicate", rdf::PREDICATE.into_owned()),
+        ("object", rdf::OBJECT.into_owned()),
+        ("graph", sd("graph")),
+    ] {
+        let mapping = BlankNode::default();
+        graph.insert(&Triple::new(
+            search.clone(),
+            hydra("mapping"),
+            mapping.clone(),
+        ));
+        graph.insert(&Triple::new(
+            mapping.clone(),
+            hydra("variable"),
+            Literal::new_simple_literal(variable),
+        ));
+        graph.insert(&Triple::new(mapping, hydra("property"), property));
+    }
+
+    graph.insert(&Triple::new(
+        fragment.clone(),
+        rdf::TYPE,
+        hydra("PartialCollectionView"),
+    ));
+    let total = Literal::from(i64::try_from(count).unwrap_or(i64::MAX));
+    graph.insert(&Triple::new(
+        fragment.clone(),
+        void("triples"),
+        total.clone(),
+    ));
+    graph.insert(&Triple::new(fragment.clone(), hydra("totalItems"), total));
+    if !is_exact {
+        graph.insert(&Triple::new(
+            fragment.clone(),
+            hydra("description"),
+            Literal::new_simple_literal(format!(
+                "At least {} quads match the pattern, the total count is an estimate",
+                count
+            )),
+        ));
+    }
+    graph.insert(&Triple::new(
+        fragment.clone(),
+        hydra("itemsPerPage"),
+        Literal::from(PAGE_SIZE as i64),
+    ));
+    graph.insert(&Triple::new(
+        fragment.clone(),
+        hydra("first"),
+        NamedNode::new_unchecked(pattern.url(base, 1)),
+    ));
+    if pattern.page > 1 {
+        graph.insert(&Triple::new(
+            fragment.clone(),
+            hydra("previous"),
+            NamedNode::new_unchecked(pattern.url(base, pattern.page - 1)),
+        ));
+    }
+    if has_next {
+        graph.insert(&Triple::new(
+            fragment.clone(),
+            hydra("next"),
+            NamedNode::new_unchecked(pattern.url(base, pattern.page + 1)),
+        ));
+    }
+    graph
+}
+
+struct FragmentPattern {
+    subject: Option<Subject>,
+    predicate: Option<NamedNode>,
+    object: Option<Term>,
+    graph_name: Option<GraphName>,
+    /// The page number, starting at 1
+    page: usize,
+}
+
+impl FragmentPattern {
+    /// Parses the URL query parameters, the terms use the Hydra explicit representation
+    ///
+    /// The empty parameters and the variables like `?s` match any term.
+    fn from_query(query: &[u8]) -> Result<Self, Response> {
+        let mut pattern = Self {
+            subject: None,
+            predicate: None,
+            object: None,
+            graph_name: None,
+            page: 1,
+        };
+        for (k, v) in form_urlencoded::parse(query) {
+            if v.is_empty() || v.starts_with('?') {
+                continue;
+            }
+            match k.as_ref() {
+                "subject" => {
+                    pattern.subject = Some(match parse_explicit_term(&v)? {
+                        Term::NamedNode(node) => node.into(),
+                        Term::BlankNode(node) => node.into(),
+                        _ => return Err(bad_request("The subject must be an IRI or a blank node")),
+                    })
+                }
+                "predicate" => {
+                    pattern.predicate = Some(match parse_explicit_term(&v)? {
+                        Term::NamedNode(node) => node,
+                        _ => return Err(bad_request("The predicate must be an IRI")),
+                    })
+                }
+                "object" => pattern.object = Some(parse_explicit_term(&v)?),
+                "graph" => {
+                    pattern.graph_name = Some(if v == "default" {
+                        GraphName::DefaultGraph
+                    } else {
+                        match parse_explicit_term(&v)? {
+                            Term::NamedNode(node) => node.into(),
+                            Term::BlankNode(node) => node.into(),
+                            _ => {
+                                return Err(bad_request(
+                                    "The graph must be an IRI, a blank node or default",
+                                ))
+                            }
+                        }
+                    })
+                }
+                "page" => {
+                    pattern.page = v
+                        .parse()
+                        .ok()
+                        .filter(|page| is_valid_page(*page))
+                        .ok_or_else(|| bad_request(format!("Invalid page number: {}", v)))?
+                }
+                _ => return Err(bad_request(format!("Unexpected parameter: {}", k))),
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// The URL of the given page of the fragment
+    fn url(&self, base: &str, page: usize) -> String {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        if let Some(subject) = &self.subject {
+            query.append_pair("subject", &explicit_representation(&subject.clone().into()));
+        }
+        if let Some(predicate) = &self.predicate {
+            query.append_pair("predicate", predicate.as_str());
+        }
+        if let Some(object) = &self.object {
+            query.append_pair("object", &explicit_representation(object));
+        }
+        match &self.graph_name {
+            Some(GraphName::NamedNode(graph)) => {
+                query.append_pair("graph", graph.as_str());
+            }
+            Some(GraphName::BlankNode(graph)) => {
+                query.append_pair("graph", &graph.to_string());
+            }
+            Some(GraphName::DefaultGraph) => {
+                query.append_pair("graph", "default");
+            }
+            None => (),
+        }
+        if page > 1 {
+            query.append_pair("page", &page.to_string());
+        }
+        let query = query.finish();
+        if query.is_empty() {
+            base.into()
+        } else {
+            format!("{}?{}", base, query)
+        }
+    }
+
+    /// Returns the quads of the page, the number of matching quads, if this number is exact and if there is a next page
+    ///
+    /// The quads are only counted up to `COUNT_LIMIT` or to the end of the page if it is further.
+    /// Above it, the returned count is a lower bound.
+    fn evaluate(&self, store: &Store) -> Result<(Vec<Quad>, usize, bool, bool), StorageError> {
+        // from_query ensures that the arithmetic does not overflow
+        let offset = (self.page - 1) * PAGE_SIZE;
+        let limit = max(COUNT_LIMIT, offset + PAGE_SIZE + 1);
+        let mut quads = Vec::new();
+        let mut count = 0;
+        let mut is_exact = true;
+        for quad in store.quads_for_pattern(
+            self.subject.as_ref().map(Subject::as_ref),
+            self.predicate.as_ref().map(NamedNode::as_ref),
+            self.object.as_ref().map(Term::as_ref),
+            self.graph_name.as_ref().map(GraphName::as_ref),
+        ) {
+            let quad = quad?;
+            if count >= offset && count < offset + PAGE_SIZE {
+                quads.push(quad);
+            }
+            count += 1;
+            if count >= limit {
+                is_exact = false;
+                break;
+            }
+        }
+        Ok((quads, count, is_exact, count > offset + PAGE_SIZE))
+    }
+}
+
+/// Checks that the page exists and that its bounds could be computed without overflow
+fn is_valid_page(page: usize) -> bool {
+    page.checked_sub(1)
+        .and_then(|page| page.checked_mul(PAGE_SIZE))
+        .and_then(|offset| offset.checked_add(PAGE_SIZE + 1))
+        .is_some()
+}
+
+/// Parses a term written in the [Hydra explicit representation](https://www.hydra-cg.com/spec/latest/core/#hydra:ExplicitRepresentation)
+///
+/// The blank nodes are written `_:id` like in N-Triples.
+fn parse_explicit_term(value: &str) -> Result<Term, Response> {
+    if let Some(literal) = value.strip_prefix('"') {
+        let (value, suffix) = literal
+            .rsplit_once('"')
+            .ok_or_else(|| bad_request(format!("Invalid literal: {}", value)))?;
+        if suffix.is_empty() {
+            Ok(Literal::new_simple_literal(value).into())
+        } else if let Some(language) = suffix.strip_prefix('@') {
+            Ok(Literal::new_language_tagged_literal(value, language)
+                .map_err(bad_request)?
+                .into())
+        } else if let Some(datatype) = suffix.strip_prefix("^^") {
+            let datatype = datatype
+                .strip_prefix('<')
+                .and_then(|datatype| datatype.strip_suffix('>'))
+                .unwrap_or(datatype);
+            Ok(
+                Literal::new_typed_literal(value, NamedNode::new(datatype).map_err(bad_request)?)
+                    .into(),
+            )
+        } else {
+            Err(bad_request(format!("Invalid literal suffix: {}", suffix)))
+        }
+    } else if let Some(id) = value.strip_prefix("_:") {
+        Ok(BlankNode::new(id).map_err(bad_request)?.into())
+    } else {
+        Ok(NamedNode::new(value).map_err(bad_request)?.into())
+    }
+}
+
+fn explicit_representation(term: &Term) -> String {
+    match term {
+        Term::NamedNode(node) => node.as_str().into(),
+        Term::BlankNode(node) => node.to_string(),
+        Term::Literal(literal) => {
+            if let Some(language) = literal.language() {
+                format!("\"{}\"@{}", literal.value(), language)
+            } else {
+                format!("\"{}\"^^{}", literal.value(), literal.datatype().as_str())
+            }
+        }
+        Term::Triple(triple) => triple.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_representation_roundtrip() {
+        for term in [
+            Term::from(NamedNode::new_unchecked("http://example.com/s")),
+            Literal::new_simple_literal("foo \"bar\"").into(),
+            Literal::new_language_tagged_literal_unchecked("foo", "en").into(),
+            Literal::from(1).into(),
+        ] {
+            assert_eq!(
+                parse_explicit_term(&explicit_representation(&term)).unwrap(),
+                term
+            );
+        }
+        assert!(parse_explicit_term("\"foo\"@").is_err());
+        assert!(parse_explicit_term("foo").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Helpers to render the server-side HTML pages.
+
+use oxhttp::model::{HeaderName, Response, Status};
+use oxigraph::model::vocab::xsd;
+use oxigraph::model::TermRef;
+
+/// Escapes a text to be included in HTML content or in a quoted attribute value
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a term, the IRIs being links built with `link`
+///
+/// Only the local paths and the HTTP(S) URLs are rendered as links: the other schemes like `javascript:` are unsafe.
+pub fn term(term: TermRef<'_>, link: impl Fn(&str) -> String) -> String {
+    match term {
+        TermRef::NamedNode(node) => {
+            let url = link(node.as_str());
+            if is_safe_link(&url) {
+                format!("<a href=\"{}\">{}</a>", escape(&url), escape(node.as_str()))
+            } else {
+                escape(node.as_str())
+            }
+        }
+        TermRef::BlankNode(node) => format!("<code>{}</code>", escape(&node.to_string())),
+        TermRef::Literal(literal) => {
+            let value = format!("&quot;{}&quot;", escape(literal.value()));
+            if let Some(language) = literal.language() {
+                format!("{}<small>@{}</small>", value, escape(language))
+            } else if literal.datatype() == xsd::STRING {
+                value
+            } else {
+                format!(
+                    "{}<small>^^{}</small>",
+                    value,
+                    self::term(literal.datatype().into(), link)
+                )
+            }
+        }
+        TermRef::Triple(triple) => format!("<code>{}</code>", escape(&triple.to_string())),
+    }
+}
+
+fn is_safe_link(url: &str) -> bool {
+    if url.starts_with('/') {
+        return !url.starts_with("//");
+    }
+    let url = url.to_ascii_lowercase();
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Builds a full HTML page response
+pub fn page(title: &str, body: &str) -> Response {
+    Response::builder(Status::OK)
+        .with_header(HeaderName::CONTENT_TYPE, "text/html; charset=utf-8")
+        .unwrap()
+        .with_body(format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<link rel=\"icon\" type=\"image/svg+xml\" href=\"/logo.svg\">\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n{}\n</body>\n</html>\n",
+            escape(title),
+            STYLE,
+            escape(title),
+            body
+        ))
+}
+
+const STYLE: &str = include_str!("../templates/style.css");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxigraph::model::NamedNodeRef;
+
+    #[test]
+    fn term_links() {
+        let link = |iri: &str| iri.to_owned();
+        assert_eq!(
+            term(
+                NamedNodeRef::new_unchecked("http://example.com/a").into(),
+                link
+            ),
+            "<a href=\"http://example.com/a\">http://example.com/a</a>"
+        );
+        assert_eq!(
+            term(
+                NamedNodeRef::new_unchecked("javascript:alert(1)").into(),
+                link
+            ),
+            "javascript:alert(1)"
+        );
+        assert_eq!(
+            term(
+                NamedNodeRef::new_unchecked("urn:isbn:0451450523").into(),
+                |iri: &str| format!("/browse/resource?iri={}", iri)
+            ),
+            "<a href=\"/browse/resource?iri=urn:isbn:0451450523\">urn:isbn:0451450523</a>"
+        );
+    }
+}
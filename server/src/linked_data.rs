@@ -0,0 +1,266 @@
+//! Linked Data frontend: the IRIs minted under the configured namespaces are dereferenced to their description.
+
+use crate::config::{LinkedDataConfig, LinkedDataMappingConfig};
+use crate::datasets::RESERVED_NAMES;
+use crate::{content_negotiation, error, html, internal_server_error, GRAPH_FORMATS};
+use oxhttp::model::{Body, HeaderName, Request, Response, Status};
+use oxigraph::io::{GraphFormat, GraphSerializer};
+use oxigraph::model::{
+    BlankNode, Graph, GraphNameRef, NamedNode, Subject, SubjectRef, Term, Triple, TripleRef,
+};
+use oxigraph::store::{StorageError, Store};
+use std::collections::HashSet;
+use std::io::{self, ErrorKind};
+use std::iter::once;
+
+/// The graphs in which the descriptions of the resources are looked for
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GraphScope {
+    DefaultGraph,
+    /// The default graph and all the named graphs
+    Union,
+    NamedGraph(NamedNode),
+}
+
+/// Maps the resource IRIs starting with `namespace` to the server paths starting with `path`
+struct Mapping {
+    namespace: String,
+    path: String,
+    /// If set the requests to `path` are redirected with `303 See Other` to the description served under this path
+    document_path: Option<String>,
+}
+
+pub struct LinkedDataOptions {
+    mappings: Vec<Mapping>,
+    scope: GraphScope,
+}
+
+impl LinkedDataOptions {
+    /// Returns `None` if no namespace is configured
+    pub fn from_config(config: LinkedDataConfig) -> io::Result<Option<Self>> {
+        if config.mappings.is_empty() {
+            return Ok(None);
+        }
+        let scope = match config.graph.as_deref() {
+            None | Some("default") => GraphScope::DefaultGraph,
+            Some("union") => GraphScope::Union,
+            Some(iri) => GraphScope::NamedGraph(NamedNode::new(iri).map_err(|e| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid Linked Data graph {}: {}", iri, e),
+                )
+            })?),
+        };
+        let mappings = config
+            .mappings
+            .into_iter()
+            .map(
+                |LinkedDataMappingConfig {
+                     namespace,
+                     path,
+                     document_path,
+                 }| {
+                    for path in once(path.as_str()).chain(document_path.as_deref()) {
+                        if !path.starts_with('/') || !path.ends_with('/') || path == "/" {
+                            return Err(io::Error::new(
+                                ErrorKind::InvalidInput,
+                                format!("The Linked Data path {} should start and end with / and not be the root", path),
+                            ));
+                        }
+                        let first_segment = path[1..].split('/').next().unwrap_or_default();
+                        if RESERVED_NAMES.contains(&first_segment) {
+                            return Err(io::Error::new(
+                                ErrorKind::InvalidInput,
+                                format!(
+                                    "The Linked Data path {} should not start with the server route /{}/",
+                                    path, first_segment
+                                ),
+                            ));
+                        }
+                    }
+                    NamedNode::new(&namespace).map_err(|e| {
+                        io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Invalid Linked Data namespace {}: {}", namespace, e),
+                        )
+                    })?;
+                    Ok(Mapping {
+                        namespace,
+                        path,
+                        document_path,
+                    })
+                },
+            )
+            .collect::<io::Result<_>>()?;
+        Ok(Some(Self { mappings, scope }))
+    }
+
+    /// Returns `None` if the request path is not handled by the Linked Data frontend
+    pub fn handle_request(&self, request: &Request, store: &Store) -> Option<Response> {
+        let method = request.method().as_ref();
+        if method != "GET" && method != "HEAD" {
+            return None;
+        }
+        let path = request.url().path();
+        for mapping in &self.mappings {
+            if let Some(local_name) = path.strip_prefix(&mapping.path) {
+                if let Some(document_path) = &mapping.document_path {
+                    let location = match request
+                        .url()
+                        .join(&format!("{}{}", document_path, local_name))
+                    {
+                        Ok(location) => location,
+                        Err(e) => return Some(internal_server_error(e)),
+                    };
+                    return Some(
+                        Response::builder(Status::SEE_OTHER)
+                            .with_header(HeaderName::LOCATION, location.as_str())
+                            .unwrap()
+                            .build(),
+                    );
+                }
+                return Some(self.describe(request, store, mapping, local_name));
+            }
+            if let Some(local_name) = mapping
+                .document_path
+                .as_deref()
+                .and_then(|document_path| path.strip_prefix(document_path))
+            {
+                return Some(self.describe(request, store, mapping, local_name));
+            }
+        }
+        None
+    }
+
+    fn describe(
+        &self,
+        request: &Request,
+        store: &Store,
+        mapping: &Mapping,
+        local_name: &str,
+    ) -> Response {
+        // The local name is still percent-encoded like in the IRIs
+        let resource = match NamedNode::new(format!("{}{}", mapping.namespace, local_name)) {
+            Ok(resource) => resource,
+            Err(e) => return error(Status::NOT_FOUND, e),
+        };
+        let format = match content_negotiation(
+            request,
+            &GRAPH_FORMATS
+                .map(GraphFormat::media_type)
+                .into_iter()
+                .chain(["text/html"])
+                .collect::<Vec<_>>(),
+            |media_type| {
+                if media_type == "text/html" {
+                    Some(None)
+                } else {
+                    GraphFormat::from_media_type(media_type).map(Some)
+                }
+            },
+        ) {
+            Ok(format) => format,
+            Err(response) => return response,
+        };
+        let description = match concise_bounded_description(store, &resource, &self.scope) {
+            Ok(description) => description,
+            Err(e) => return internal_server_error(e),
+        };
+        if description.is_empty() {
+            return error(
+                Status::NOT_FOUND,
+                format!("No description found for {}", resource),
+            );
+        }
+        let mut response = if let Some(format) = format {
+            let mut body = Vec::new();
+            let result = GraphSerializer::from_format(format)
+                .pretty()
+                .triple_writer(&mut body)
+                .and_then(|mut writer| {
+                    for triple in &description {
+                        writer.write(triple)?;
+                    }
+                    writer.finish()
+                });
+            if let Err(e) = result {
+                return internal_server_error(e);
+            }
+            Response::builder(Status::OK)
+                .with_header(HeaderName::CONTENT_TYPE, format.media_type())
+                .unwrap()
+                .with_body(body)
+        } else {
+            self.html_description(&resource, &description)
+        };
+        response.append_header(HeaderName::VARY, "Accept").unwrap();
+        if request.method().as_ref() == "HEAD" {
+            *response.body_mut() = Body::from(Vec::new());
+        }
+        response
+    }
+
+    fn html_description(&self, resource: &NamedNode, description: &Graph) -> Response {
+        let mut triples = description.iter().collect::<Vec<_>>();
+        // The triples about the resource itself first
+        triples.sort_by_key(|t| (t.subject != SubjectRef::from(resource), t.to_string()));
+        let link = |iri: &str| self.local_url(iri).unwrap_or_else(|| iri.to_owned());
+        let mut body = String::from(
+            "<table>\n<thead><tr><th>Subject</th><th>Predicate</th><th>Object</th></tr></thead>\n<tbody>\n",
+        );
+        for TripleRef {
+            subject,
+            predicate,
+            object,
+        } in triples
+        {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                html::term(subject.into(), link),
+                html::term(predicate.into(), link),
+                html::term(object, link)
+            ));
+        }
+        body.push_str("</tbody>\n</table>");
+        html::page(resource.as_str(), &body)
+    }
+
+    /// The path on this server of the description of the IRI if it is in one of the namespaces
+    pub fn local_url(&self, iri: &str) -> Option<String> {
+        self.mappings.iter().find_map(|mapping| {
+            iri.strip_prefix(&mapping.namespace)
+                .map(|local_name| format!("{}{}", mapping.path, local_name))
+        })
+    }
+}
+
+/// The [Concise Bounded Description](https://www.w3.org/Submission/CBD/) of a resource
+///
+/// It contains all the triples whose subject is the resource and, recursively, the triples whose subject is a blank node object of an included triple.
+pub fn concise_bounded_description(
+    store: &Store,
+    resource: &NamedNode,
+    scope: &GraphScope,
+) -> Result<Graph, StorageError> {
+    let graph_name = match scope {
+        GraphScope::DefaultGraph => Some(GraphNameRef::DefaultGraph),
+        GraphScope::Union => None,
+        GraphScope::NamedGraph(graph) => Some(graph.as_ref().into()),
+    };
+    let mut description = Graph::new();
+    let mut visited = HashSet::new();
+    let mut to_visit = vec![Subject::from(resource.clone())];
+    while let Some(subject) = to_visit.pop() {
+        if !visited.insert(subject.clone()) {
+            continue;
+        }
+        for quad in store.quads_for_pattern(Some(subject.as_ref()), None, None, graph_name) {
+            let quad = quad?;
+            if let Term::BlankNode(object) = &quad.object {
+                to_visit.push(BlankNode::clone(object).into());
+            }
+            description.insert(&Triple::from(quad));
+        }
+    }
+    Ok(description)
+}
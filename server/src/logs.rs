@@ -0,0 +1,253 @@
+//! Access and slow query logs.
+//!
+//! Both logs are written in the [JSON lines](https://jsonlines.org/) format, one object per event.
+
+use crate::QueryPlanCacheKey;
+use oxhttp::model::{Body, Request, Response};
+use std::fmt::{self, Write as _};
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+use std::mem::replace;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// A log file written in the JSON lines format
+#[derive(Clone)]
+pub struct JsonLinesLog {
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl JsonLinesLog {
+    /// Opens the log file in append mode, `-` is the standard error
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let output: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(io::stderr())
+        } else {
+            Box::new(OpenOptions::new().create(true).append(true).open(path)?)
+        };
+        Ok(Self {
+            output: Arc::new(Mutex::new(output)),
+        })
+    }
+
+    fn write(&self, object: JsonObject) {
+        let mut line = object.finish();
+        line.push('\n');
+        let mut output = self.output.lock().unwrap();
+        if let Err(e) = output
+            .write_all(line.as_bytes())
+            .and_then(|_| output.flush())
+        {
+            eprintln!("Error while writing a log entry: {}", e)
+        }
+    }
+}
+
+/// Logs the SPARQL queries that take more than the threshold to evaluate
+#[derive(Clone)]
+pub struct SlowQueryLog {
+    pub log: JsonLinesLog,
+    pub threshold: Duration,
+}
+
+impl SlowQueryLog {
+    /// Writes the entry if the query evaluation took more than the threshold
+    ///
+    /// The evaluation time includes the serialization of the results.
+    pub fn record(&self, key: &QueryPlanCacheKey, evaluation_time: Duration, results: u64) {
+        if evaluation_time < self.threshold {
+            return;
+        }
+        self.log.write(
+            JsonObject::new()
+                .with_number("time", unix_time())
+                .with_string("query", &key.query)
+                .with_strings("default_graph_uri", &key.default_graph_uris)
+                .with_strings("named_graph_uri", &key.named_graph_uris)
+                .with_string("dataset", &key.dataset)
+                .with_bool("union_default_graph", key.use_default_graph_as_union)
+                .with_number("evaluation_time", evaluation_time.as_secs_f64())
+                .with_number("results", results),
+        )
+    }
+}
+
+/// Writes an entry in the access log for each request
+///
+/// If the response body is streamed the entry is written when the body has been consumed,
+/// the duration then includes the time spent sending the body.
+pub fn log_access(
+    request: &mut Request,
+    log: Option<&JsonLinesLog>,
+    handler: impl FnOnce(&mut Request) -> Response,
+) -> Response {
+    let log = if let Some(log) = log {
+        log.clone()
+    } else {
+        return handler(request);
+    };
+    let start = Instant::now();
+    let method = request.method().as_ref().to_owned();
+    let path = request.url().path().to_owned();
+    let mut response = handler(request);
+    let entry = AccessLogEntry {
+        log,
+        start,
+        method,
+        path,
+        status: response.status().into(),
+    };
+    if let Some(len) = response.body().len() {
+        entry.write(len);
+    } else {
+        let body = replace(response.body_mut(), Body::from(Vec::new()));
+        *response.body_mut() = Body::from_read(LoggedBody {
+            body,
+            bytes: 0,
+            entry: Some(entry),
+        });
+    }
+    response
+}
+
+struct AccessLogEntry {
+    log: JsonLinesLog,
+    start: Instant,
+    method: String,
+    path: String,
+    status: u16,
+}
+
+impl AccessLogEntry {
+    fn write(self, bytes: u64) {
+        self.log.write(
+            JsonObject::new()
+                .with_number("time", unix_time())
+                .with_string("method", &self.method)
+                .with_string("path", &self.path)
+                .with_number("status", self.status)
+                .with_number("duration", self.start.elapsed().as_secs_f64())
+                .with_number("bytes", bytes),
+        )
+    }
+}
+
+/// A streamed body counting the bytes read from it and writing the access log entry when it is dropped
+struct LoggedBody {
+    body: Body,
+    bytes: u64,
+    entry: Option<AccessLogEntry>,
+}
+
+impl Read for LoggedBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.body.read(buf)?;
+        self.bytes += len as u64;
+        Ok(len)
+    }
+}
+
+impl Drop for LoggedBody {
+    fn drop(&mut self) {
+        if let Some(entry) = self.entry.take() {
+            entry.write(self.bytes)
+        }
+    }
+}
+
+fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// A JSON object being serialized
+pub struct JsonObject(String);
+
+impl JsonObject {
+    pub fn new() -> Self {
+        Self("{".into())
+    }
+
+    fn with_key(mut self, key: &str) -> Self {
+        if self.0.len() > 1 {
+            self.0.push(',');
+        }
+        write_json_string(&mut self.0, key);
+        self.0.push(':');
+        self
+    }
+
+    pub fn with_string(self, key: &str, value: &str) -> Self {
+        let mut object = self.with_key(key);
+        write_json_string(&mut object.0, value);
+        object
+    }
+
+    pub fn with_strings(self, key: &str, values: &[String]) -> Self {
+        let mut object = self.with_key(key);
+        object.0.push('[');
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                object.0.push(',');
+            }
+            write_json_string(&mut object.0, value);
+        }
+        object.0.push(']');
+        object
+    }
+
+    pub fn with_number(self, key: &str, value: impl fmt::Display) -> Self {
+        let mut object = self.with_key(key);
+        let _ = write!(object.0, "{}", value);
+        object
+    }
+
+    pub fn with_bool(self, key: &str, value: bool) -> Self {
+        self.with_number(key, value)
+    }
+
+    /// Closes the object and returns its serialization
+    pub fn finish(mut self) -> String {
+        self.0.push('}');
+        self.0
+    }
+}
+
+fn write_json_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(output, "\\u{:04X}", u32::from(c));
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_object() {
+        let object = JsonObject::new()
+            .with_string("query", "SELECT * WHERE { ?s ?p \"a\\b\n\u{1}\" }")
+            .with_strings("graphs", &["http://example.com/g".into()])
+            .with_bool("union", false)
+            .with_number("results", 12);
+        assert_eq!(
+            object.finish(),
+            "{\"query\":\"SELECT * WHERE { ?s ?p \\\"a\\\\b\\n\\u0001\\\" }\",\"graphs\":[\"http://example.com/g\"],\"union\":false,\"results\":12}"
+        );
+    }
+}
@@ -1,31 +1,119 @@
+use auth::{handle_auth, AuthOptions};
+use browser::handle_browser_request;
 use clap::{Parser, Subcommand};
-use flate2::read::MultiGzDecoder;
+use config::Config;
+use datasets::{dataset_path, handle_admin_request, Dataset, Datasets};
+use flate2::read::{GzEncoder, MultiGzDecoder};
+use flate2::Compression;
+use fragments::handle_fragment_request;
+use httpdate::{fmt_http_date, parse_http_date};
+use linked_data::LinkedDataOptions;
+use logs::{log_access, JsonLinesLog, SlowQueryLog};
 use oxhttp::model::{Body, HeaderName, HeaderValue, Request, Response, Status};
 use oxhttp::Server;
 use oxigraph::io::{DatasetFormat, DatasetSerializer, GraphFormat, GraphSerializer};
-use oxigraph::model::{GraphName, GraphNameRef, IriParseError, NamedNode, NamedOrBlankNode};
-use oxigraph::sparql::{Query, QueryResults, Update};
-use oxigraph::store::{BulkLoader, Store};
+use oxigraph::model::patch::Patch;
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{
+    BlankNode, Graph, GraphName, GraphNameRef, IriParseError, Literal, NamedNode, NamedOrBlankNode,
+    Triple,
+};
+use oxigraph::sparql::{
+    EvaluationError, PreparedQuery, Query, QueryOptions, QueryResults, StoreServiceHandler, Update,
+};
+use oxigraph::store::{
+    BulkLoader, GraphNameIter, GraphStatistics, GraphVersion, LoaderError, StorageError,
+    StorageOptions, Store, StoreStatistics, Transaction,
+};
 use oxiri::Iri;
 use rand::random;
 use rayon_core::ThreadPoolBuilder;
 use sparesults::{QueryResultsFormat, QueryResultsSerializer};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::cmp::{max, min};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fmt;
+use std::fmt::Write as _;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{self, BufReader, Error, ErrorKind, Read, Write};
-use std::path::PathBuf;
+use std::iter::{empty, once};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread::available_parallelism;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
+use tls::TlsOptions;
 use url::form_urlencoded;
+use zstd::stream::read::{Decoder as ZstdDecoder, Encoder as ZstdEncoder};
 
-const MAX_SPARQL_BODY_SIZE: u64 = 1_048_576;
-const HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+mod auth;
+mod browser;
+mod config;
+mod datasets;
+mod fragments;
+mod html;
+mod linked_data;
+mod logs;
+mod tls;
+
+const DEFAULT_BIND: &str = "localhost:7878";
+const DEFAULT_QUERY_PLAN_CACHE_SIZE: usize = 256;
+const DEFAULT_MAX_SPARQL_BODY_SIZE: u64 = 1_048_576;
+const DEFAULT_MAX_PATCH_BODY_SIZE: u64 = 104_857_600;
+const DEFAULT_MAX_STORE_BODY_SIZE: u64 = 1_073_741_824;
+const DEFAULT_HTTP_TIMEOUT: f64 = 60.;
+const DEFAULT_SLOW_QUERY_THRESHOLD: f64 = 1.;
+const DEFAULT_CORS_METHODS: [&str; 6] = ["GET", "HEAD", "POST", "PUT", "DELETE", "PATCH"];
+const DEFAULT_CORS_HEADERS: [&str; 6] = [
+    "Content-Type",
+    "Content-Encoding",
+    "Authorization",
+    "If-Match",
+    "If-None-Match",
+    "If-Modified-Since",
+];
 const HTML_ROOT_PAGE: &str = include_str!("../templates/query.html");
 const LOGO: &str = include_str!("../logo.svg");
+/// The supported formats, the first one is the default one
+const GRAPH_FORMATS: [GraphFormat; 3] = [
+    GraphFormat::NTriples,
+    GraphFormat::Turtle,
+    GraphFormat::RdfXml,
+];
+const DATASET_FORMATS: [DatasetFormat; 2] = [DatasetFormat::NQuads, DatasetFormat::TriG];
+const QUERY_RESULTS_FORMATS: [QueryResultsFormat; 4] = [
+    QueryResultsFormat::Json,
+    QueryResultsFormat::Xml,
+    QueryResultsFormat::Csv,
+    QueryResultsFormat::Tsv,
+];
+/// The extension functions supported on top of the SPARQL 1.1 ones
+const EXTENSION_FUNCTIONS: [&str; 18] = [
+    "http://www.w3.org/2005/xpath-functions/math#pi",
+    "http://www.w3.org/2005/xpath-functions/math#exp",
+    "http://www.w3.org/2005/xpath-functions/math#exp10",
+    "http://www.w3.org/2005/xpath-functions/math#log",
+    "http://www.w3.org/2005/xpath-functions/math#log10",
+    "http://www.w3.org/2005/xpath-functions/math#pow",
+    "http://www.w3.org/2005/xpath-functions/math#sqrt",
+    "http://www.w3.org/2005/xpath-functions/math#sin",
+    "http://www.w3.org/2005/xpath-functions/math#cos",
+    "http://www.w3.org/2005/xpath-functions/math#tan",
+    "http://www.w3.org/2005/xpath-functions/math#asin",
+    "http://www.w3.org/2005/xpath-functions/math#acos",
+    "http://www.w3.org/2005/xpath-functions/math#atan",
+    "http://www.w3.org/2005/xpath-functions/math#atan2",
+    "http://www.w3.org/2005/xpath-functions#string-join",
+    "http://www.w3.org/2005/xpath-functions#normalize-space",
+    "http://www.w3.org/2005/xpath-functions#format-number",
+    "http://www.w3.org/2005/xpath-functions#adjust-dateTime-to-timezone",
+];
+const SD_NAMESPACE: &str = "http://www.w3.org/ns/sparql-service-description#";
+const VOID_NAMESPACE: &str = "http://rdfs.org/ns/void#";
 
 #[derive(Parser)]
 #[clap(about, version)]
@@ -42,9 +130,116 @@ struct Args {
 enum Command {
     /// Start Oxigraph HTTP server.
     Serve {
-        /// Host and port to listen to.
-        #[clap(short, long, default_value = "localhost:7878", global = true)]
-        bind: String,
+        /// Host and port to listen to [default: localhost:7878].
+        #[clap(short, long, global = true)]
+        bind: Option<String>,
+        /// Number of query plans kept in cache by each server thread [default: 256].
+        ///
+        /// The plans are looked up by query text and dataset. Use 0 to disable the cache.
+        #[clap(long, global = true)]
+        query_plan_cache_size: Option<usize>,
+        /// Use the union of all the graphs as the default graph of the queries that do not set a dataset.
+        ///
+        /// `--union-default-graph=false` overrides the configuration file.
+        #[clap(
+            long,
+            global = true,
+            min_values = 0,
+            require_equals = true,
+            default_missing_value = "true",
+            value_name = "BOOL"
+        )]
+        union_default_graph: Option<bool>,
+        /// Maximal size in bytes of the SPARQL queries and updates sent in request bodies [default: 1048576].
+        #[clap(long, global = true)]
+        max_sparql_body_size: Option<u64>,
+        /// Maximal size in bytes of the RDF Patch documents sent to /store [default: 104857600].
+        #[clap(long, global = true)]
+        max_patch_body_size: Option<u64>,
+        /// Maximal size in bytes of the RDF documents sent to /store with PUT or POST [default: 1073741824].
+        #[clap(long, global = true)]
+        max_store_body_size: Option<u64>,
+        /// Maximal duration in seconds of the HTTP requests [default: 60].
+        #[clap(long, global = true)]
+        http_timeout: Option<f64>,
+        /// Mount the store persisted in a directory as a SPARQL service with the given IRI.
+        ///
+        /// The service is evaluated in-process by the queries using `SERVICE <IRI> { ... }`.
+        /// This option can be repeated to mount multiple stores.
+        #[clap(
+            long,
+            global = true,
+            number_of_values = 2,
+            multiple_occurrences = true,
+            value_names = &["IRI", "LOCATION"]
+        )]
+        service: Vec<String>,
+        /// Allow Cross-Origin Resource Sharing (CORS) requests to `/query`, `/update` and `/store` from this origin.
+        ///
+        /// Use `*` to allow all origins. This option can be repeated. CORS is disabled if it is not set.
+        #[clap(long, global = true, multiple_occurrences = true)]
+        cors_origin: Vec<String>,
+        /// HTTP method allowed in CORS requests. Use `*` to allow all methods [default: GET HEAD POST PUT DELETE PATCH].
+        #[clap(long, global = true, multiple_occurrences = true)]
+        cors_method: Vec<String>,
+        /// HTTP header allowed in CORS requests. Use `*` to allow all headers [default: Content-Type Content-Encoding Authorization If-Match If-None-Match If-Modified-Since].
+        #[clap(long, global = true, multiple_occurrences = true)]
+        cors_header: Vec<String>,
+        /// Allow CORS requests with credentials (cookies, authorization headers...).
+        ///
+        /// It can't be used with the `*` origin. `--cors-allow-credentials=false` overrides the configuration file.
+        #[clap(
+            long,
+            global = true,
+            min_values = 0,
+            require_equals = true,
+            default_missing_value = "true",
+            value_name = "BOOL"
+        )]
+        cors_allow_credentials: Option<bool>,
+        /// PEM file with the certificate chain to serve HTTPS instead of HTTP. Requires `--tls-key`.
+        ///
+        /// The certificate and the key are reloaded from their files when the server receives SIGHUP.
+        #[clap(long, global = true, parse(from_os_str))]
+        tls_cert: Option<PathBuf>,
+        /// PEM file with the private key of the TLS certificate.
+        #[clap(long, global = true, parse(from_os_str))]
+        tls_key: Option<PathBuf>,
+        /// PEM file with the certificate authorities to require HTTPS clients to authenticate with a certificate they signed.
+        #[clap(long, global = true, parse(from_os_str))]
+        tls_client_ca: Option<PathBuf>,
+        /// File in which append the access log, one JSON object per request. Use `-` for the standard error.
+        #[clap(long, global = true, parse(from_os_str))]
+        access_log: Option<PathBuf>,
+        /// File in which append the slow query log, one JSON object per slow query. Use `-` for the standard error.
+        #[clap(long, global = true, parse(from_os_str))]
+        slow_query_log: Option<PathBuf>,
+        /// Time in seconds above which a query is written to the slow query log [default: 1].
+        #[clap(long, global = true)]
+        slow_query_threshold: Option<f64>,
+        /// TOML configuration file. The command line options override its values.
+        ///
+        /// It also declares the named datasets served under `/{name}/`, the RocksDB options and the users.
+        #[clap(long, global = true, parse(from_os_str))]
+        config: Option<PathBuf>,
+        /// Directory in which persist the datasets created with the administration API.
+        ///
+        /// The datasets in its subdirectories are served when the server starts.
+        /// The datasets created with the administration API are kept in memory if it is not set.
+        #[clap(long, global = true, parse(from_os_str))]
+        datasets_directory: Option<PathBuf>,
+        /// Enable the `/datasets` administration API to create, list and drop datasets.
+        ///
+        /// `--admin-api=false` overrides the configuration file.
+        #[clap(
+            long,
+            global = true,
+            min_values = 0,
+            require_equals = true,
+            default_missing_value = "true",
+            value_name = "BOOL"
+        )]
+        admin_api: Option<bool>,
     },
     /// Load file(s) into the store.
     Load {
@@ -63,14 +258,9 @@ enum Command {
 
 pub fn main() -> std::io::Result<()> {
     let matches = Args::parse();
-    let store = if let Some(path) = &matches.location {
-        Store::open(path)
-    } else {
-        Store::new()
-    }?;
-
     match matches.command {
         Command::Load { file, lenient } => {
+            let store = open_store(matches.location.as_deref(), StorageOptions::default())?;
             ThreadPoolBuilder::new()
                 .num_threads(max(1, available_parallelism()?.get() / 2))
                 .thread_name(|i| format!("Oxigraph bulk loader thread {}", i))
@@ -125,19 +315,217 @@ pub fn main() -> std::io::Result<()> {
                 });
             Ok(())
         }
-        Command::Serve { bind } => {
-            let mut server = Server::new(move |request| handle_request(request, store.clone()));
-            server.set_global_timeout(HTTP_TIMEOUT);
-            server
-                .set_server_name(concat!("Oxigraph/", env!("CARGO_PKG_VERSION")))
-                .unwrap();
-            eprintln!("Listening for requests at http://{}", &bind);
-            server.listen(bind)?;
-            Ok(())
+        Command::Serve {
+            bind,
+            query_plan_cache_size,
+            union_default_graph,
+            max_sparql_body_size,
+            max_patch_body_size,
+            max_store_body_size,
+            http_timeout,
+            service,
+            cors_origin,
+            cors_method,
+            cors_header,
+            cors_allow_credentials,
+            tls_cert,
+            tls_key,
+            tls_client_ca,
+            access_log,
+            slow_query_log,
+            slow_query_threshold,
+            config,
+            datasets_directory,
+            admin_api,
+        } => {
+            let config = if let Some(config) = config {
+                Config::load(&config)?
+            } else {
+                Config::default()
+            };
+            // The statistics are used by the metrics
+            let mut storage_options = StorageOptions::default().with_statistics();
+            if let Some(size) = config.rocksdb.block_cache_size {
+                storage_options = storage_options.with_block_cache_size(size);
+            }
+            if let Some(max_open_files) = config.rocksdb.max_open_files {
+                storage_options = storage_options.with_max_open_files(max_open_files);
+            }
+            if let Some(size) = config.rocksdb.write_buffer_size {
+                storage_options = storage_options.with_write_buffer_size(size);
+            }
+            let store = open_store(
+                matches.location.or(config.location).as_deref(),
+                storage_options,
+            )?;
+            let bind = bind.or(config.bind).unwrap_or_else(|| DEFAULT_BIND.into());
+            let http_timeout = duration_from_secs(
+                http_timeout
+                    .or(config.timeouts.http)
+                    .unwrap_or(DEFAULT_HTTP_TIMEOUT),
+                "--http-timeout",
+            )?;
+            let admin_api = admin_api.unwrap_or(config.admin_api);
+            let datasets = Datasets::open(
+                datasets_directory.or(config.datasets_directory),
+                storage_options,
+            )?;
+            for dataset in config.datasets {
+                datasets.insert(
+                    &dataset.name,
+                    Dataset::open(dataset.location, dataset.read_only, storage_options)?,
+                )?;
+            }
+            let tls_client_ca = tls_client_ca.or(config.tls.client_ca);
+            let tls = match (tls_cert.or(config.tls.cert), tls_key.or(config.tls.key)) {
+                (Some(cert), Some(key)) => Some(TlsOptions {
+                    cert,
+                    key,
+                    client_ca: tls_client_ca,
+                }),
+                (None, None) if tls_client_ca.is_none() => None,
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "--tls-cert and --tls-key should be set together to serve HTTPS",
+                    ))
+                }
+            };
+            let cors = CorsOptions {
+                origins: if cors_origin.is_empty() {
+                    config.cors.origins
+                } else {
+                    cors_origin
+                },
+                methods: if cors_method.is_empty() {
+                    config.cors.methods.unwrap_or_else(|| {
+                        DEFAULT_CORS_METHODS.iter().map(|m| (*m).into()).collect()
+                    })
+                } else {
+                    cors_method
+                },
+                headers: if cors_header.is_empty() {
+                    config.cors.headers.unwrap_or_else(|| {
+                        DEFAULT_CORS_HEADERS.iter().map(|h| (*h).into()).collect()
+                    })
+                } else {
+                    cors_header
+                },
+                allow_credentials: cors_allow_credentials.unwrap_or(config.cors.allow_credentials),
+            };
+            if cors.allow_credentials && cors.origins.iter().any(|o| o == "*") {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "CORS requests with credentials can't be allowed from all origins",
+                ));
+            }
+            let auth = AuthOptions {
+                users: config
+                    .auth
+                    .users
+                    .into_iter()
+                    .map(|user| (user.name, user.password))
+                    .collect(),
+                anonymous_read: config.auth.anonymous_read,
+            };
+            let services = service
+                .chunks(2)
+                .map(|service| {
+                    let name = NamedNode::new(&service[0])
+                        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+                    Ok((
+                        name,
+                        Store::open_with_options(&service[1], storage_options)?,
+                    ))
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            let slow_query_threshold = duration_from_secs(
+                slow_query_threshold
+                    .or(config.log.slow_query_threshold)
+                    .unwrap_or(DEFAULT_SLOW_QUERY_THRESHOLD),
+                "--slow-query-threshold",
+            )?;
+            let access_log = access_log
+                .or(config.log.access_log)
+                .map(|path| JsonLinesLog::open(&path))
+                .transpose()?;
+            let slow_query_log = slow_query_log
+                .or(config.log.slow_query_log)
+                .map(|path| {
+                    Ok::<_, Error>(SlowQueryLog {
+                        log: JsonLinesLog::open(&path)?,
+                        threshold: slow_query_threshold,
+                    })
+                })
+                .transpose()?;
+            let options = EndpointOptions {
+                services,
+                query_plan_cache_size: query_plan_cache_size
+                    .or(config.query_plan_cache_size)
+                    .unwrap_or(DEFAULT_QUERY_PLAN_CACHE_SIZE),
+                slow_query_log,
+                max_sparql_body_size: max_sparql_body_size
+                    .or(config.limits.max_sparql_body_size)
+                    .unwrap_or(DEFAULT_MAX_SPARQL_BODY_SIZE),
+                max_patch_body_size: max_patch_body_size
+                    .or(config.limits.max_patch_body_size)
+                    .unwrap_or(DEFAULT_MAX_PATCH_BODY_SIZE),
+                max_store_body_size: max_store_body_size
+                    .or(config.limits.max_store_body_size)
+                    .unwrap_or(DEFAULT_MAX_STORE_BODY_SIZE),
+                union_default_graph: union_default_graph.unwrap_or(config.union_default_graph),
+                linked_data: LinkedDataOptions::from_config(config.linked_data)?,
+                statistics: StatisticsCache::default(),
+            };
+            let metrics = RequestMetrics::default();
+            let handler: tls::Handler = Arc::new(move |request| {
+                log_access(request, access_log.as_ref(), |request| {
+                    handle_cors(request, &cors, |request| {
+                        handle_auth(request, &auth, |request| {
+                            handle_monitoring(request, &store, &metrics, |request| {
+                                handle_dataset_request(
+                                    request, &store, &datasets, admin_api, &options,
+                                )
+                            })
+                        })
+                    })
+                })
+            });
+            let server_name = concat!("Oxigraph/", env!("CARGO_PKG_VERSION"));
+            if let Some(tls) = tls {
+                eprintln!("Listening for requests at https://{}", &bind);
+                tls::listen(&bind, tls, handler, server_name, http_timeout)
+            } else {
+                let mut server = Server::new(move |request| handler(request));
+                server.set_global_timeout(http_timeout);
+                server.set_server_name(server_name).unwrap();
+                eprintln!("Listening for requests at http://{}", &bind);
+                server.listen(bind)?;
+                Ok(())
+            }
         }
     }
 }
 
+fn open_store(location: Option<&Path>, options: StorageOptions) -> io::Result<Store> {
+    Ok(if let Some(location) = location {
+        Store::open_with_options(location, options)
+    } else {
+        Store::new()
+    }?)
+}
+
+fn duration_from_secs(secs: f64, option: &str) -> io::Result<Duration> {
+    if secs.is_finite() && secs >= 0. {
+        Ok(Duration::from_secs_f64(secs))
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} should be a positive number of seconds", option),
+        ))
+    }
+}
+
 fn bulk_load(loader: BulkLoader, file: &str, reader: impl Read) -> io::Result<()> {
     let (_, extension) = file.rsplit_once('.').ok_or_else(|| io::Error::new(
         ErrorKind::InvalidInput,
@@ -160,8 +548,128 @@ fn bulk_load(loader: BulkLoader, file: &str, reader: impl Read) -> io::Result<()
     }
 }
 
-fn handle_request(request: &mut Request, store: Store) -> Response {
-    match (request.url().path(), request.method().as_ref()) {
+/// The settings of the SPARQL endpoints, shared by all the datasets
+#[derive(Default)]
+struct EndpointOptions {
+    /// The stores mounted as SPARQL services
+    services: Vec<(NamedNode, Store)>,
+    query_plan_cache_size: usize,
+    slow_query_log: Option<SlowQueryLog>,
+    max_sparql_body_size: u64,
+    max_patch_body_size: u64,
+    max_store_body_size: u64,
+    /// If the default graph of the queries is the union of all the graphs when no dataset is set
+    union_default_graph: bool,
+    /// The namespaces of the default store dereferenced as Linked Data
+    linked_data: Option<LinkedDataOptions>,
+    statistics: StatisticsCache,
+}
+
+/// The statistics of the datasets published in the service descriptions
+///
+/// Computing them requires a full scan so they are kept until a write to any dataset.
+#[derive(Default)]
+struct StatisticsCache {
+    /// Incremented after each write
+    generation: AtomicU64,
+    /// The statistics by dataset name with the generation at which their computation started
+    statistics: Mutex<HashMap<String, (u64, Arc<StoreStatistics>)>>,
+}
+
+impl StatisticsCache {
+    fn get(&self, dataset: &str, store: &Store) -> Result<Arc<StoreStatistics>, StorageError> {
+        let generation = self.generation.load(Ordering::Acquire);
+        if let Some((cached_generation, statistics)) = self.statistics.lock().unwrap().get(dataset)
+        {
+            if *cached_generation == generation {
+                return Ok(Arc::clone(statistics));
+            }
+        }
+        let statistics = Arc::new(store.statistics()?);
+        self.statistics
+            .lock()
+            .unwrap()
+            .insert(dataset.into(), (generation, Arc::clone(&statistics)));
+        Ok(statistics)
+    }
+
+    /// Called after the writes: the statistics computed before are not used anymore
+    fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Routes the requests to the administration API, to a named dataset or to the default store
+fn handle_dataset_request(
+    request: &mut Request,
+    store: &Store,
+    datasets: &Datasets,
+    admin_api: bool,
+    options: &EndpointOptions,
+) -> Response {
+    // The Linked Data paths take precedence over the dataset names
+    if let Some(response) = options
+        .linked_data
+        .as_ref()
+        .and_then(|linked_data| linked_data.handle_request(request, store))
+    {
+        return response;
+    }
+    let path = request.url().path();
+    if path == "/datasets" || path.starts_with("/datasets/") {
+        return if admin_api {
+            let response = handle_admin_request(request, datasets);
+            if request.method().as_ref() != "GET" {
+                // The dataset might have been replaced
+                options.statistics.invalidate();
+            }
+            response
+        } else {
+            error(Status::NOT_FOUND, "The administration API is not enabled")
+        };
+    }
+    let is_write = is_write_request(dataset_path(path).1, request.method().as_ref());
+    let response = match dataset_path(path).0 {
+        Some(name) => match datasets.get(name) {
+            Some(dataset) => {
+                let mut response =
+                    handle_request(request, dataset.store.clone(), dataset.read_only, options);
+                dataset.hold_until_sent(&mut response);
+                response
+            }
+            None => error(
+                Status::NOT_FOUND,
+                format!("The dataset {} does not exist", name),
+            ),
+        },
+        None => handle_request(request, store.clone(), false, options),
+    };
+    if is_write {
+        // The write is done when the response is returned
+        options.statistics.invalidate();
+    }
+    response
+}
+
+/// If the request changes the content of the dataset, `path` is relative to the dataset
+fn is_write_request(path: &str, method: &str) -> bool {
+    path == "/update"
+        || (path.starts_with("/store") && matches!(method, "PUT" | "POST" | "DELETE" | "PATCH"))
+}
+
+fn handle_request(
+    request: &mut Request,
+    store: Store,
+    read_only: bool,
+    options: &EndpointOptions,
+) -> Response {
+    let (dataset, path) = dataset_path(request.url().path());
+    let dataset_prefix = dataset.map_or_else(String::new, |name| format!("/{}", name));
+    let path = path.to_owned();
+    if read_only && is_write_request(&path, request.method().as_ref()) {
+        return error(Status::FORBIDDEN, "The dataset is read-only");
+    }
+    match (path.as_str(), request.method().as_ref()) {
         ("/", "HEAD") => Response::builder(Status::OK)
             .with_header(HeaderName::CONTENT_TYPE, "text_html")
             .unwrap()
@@ -178,16 +686,24 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
             .with_header(HeaderName::CONTENT_TYPE, "image/svg+xml")
             .unwrap()
             .with_body(LOGO),
-        ("/query", "GET") => {
-            configure_and_evaluate_sparql_query(store, &[url_query(request)], None, request)
+        ("/fragments", "GET") => handle_fragment_request(request, &store),
+        (path, "GET") if path == "/browse" || path.starts_with("/browse/") => {
+            handle_browser_request(request, &store, &dataset_prefix, path)
         }
+        ("/query", "GET") => configure_and_evaluate_sparql_query(
+            store,
+            &[url_query(request)],
+            None,
+            request,
+            options,
+        ),
         ("/query", "POST") => {
             if let Some(content_type) = content_type(request) {
                 if content_type == "application/sparql-query" {
                     let mut buffer = String::new();
                     if let Err(e) = request
                         .body_mut()
-                        .take(MAX_SPARQL_BODY_SIZE)
+                        .take(options.max_sparql_body_size)
                         .read_to_string(&mut buffer)
                     {
                         return bad_request(e);
@@ -197,12 +713,13 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
                         &[url_query(request)],
                         Some(buffer),
                         request,
+                        options,
                     )
                 } else if content_type == "application/x-www-form-urlencoded" {
                     let mut buffer = Vec::new();
                     if let Err(e) = request
                         .body_mut()
-                        .take(MAX_SPARQL_BODY_SIZE)
+                        .take(options.max_sparql_body_size)
                         .read_to_end(&mut buffer)
                     {
                         return bad_request(e);
@@ -212,6 +729,7 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
                         &[url_query(request), &buffer],
                         None,
                         request,
+                        options,
                     )
                 } else {
                     unsupported_media_type(&content_type)
@@ -224,10 +742,12 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
             if let Some(content_type) = content_type(request) {
                 if content_type == "application/sparql-update" {
                     let mut buffer = String::new();
-                    if let Err(e) = request
-                        .body_mut()
-                        .take(MAX_SPARQL_BODY_SIZE)
-                        .read_to_string(&mut buffer)
+                    if let Err(e) = match decoded_body(request) {
+                        Ok(body) => body,
+                        Err(response) => return response,
+                    }
+                    .take(options.max_sparql_body_size)
+                    .read_to_string(&mut buffer)
                     {
                         return bad_request(e);
                     }
@@ -236,13 +756,16 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
                         &[url_query(request)],
                         Some(buffer),
                         request,
+                        &options.services,
                     )
                 } else if content_type == "application/x-www-form-urlencoded" {
                     let mut buffer = Vec::new();
-                    if let Err(e) = request
-                        .body_mut()
-                        .take(MAX_SPARQL_BODY_SIZE)
-                        .read_to_end(&mut buffer)
+                    if let Err(e) = match decoded_body(request) {
+                        Ok(body) => body,
+                        Err(response) => return response,
+                    }
+                    .take(options.max_sparql_body_size)
+                    .read_to_end(&mut buffer)
                     {
                         return bad_request(e);
                     }
@@ -251,6 +774,7 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
                         &[url_query(request), &buffer],
                         None,
                         request,
+                        &options.services,
                     )
                 } else {
                     unsupported_media_type(&content_type)
@@ -264,68 +788,88 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
                 Ok(target) => target,
                 Err(error) => return error,
             } {
-                if !match &target {
-                    NamedGraphName::DefaultGraph => true,
-                    NamedGraphName::NamedNode(target) => match store.contains_named_graph(target) {
-                        Ok(r) => r,
-                        Err(e) => return internal_server_error(e),
-                    },
-                } {
+                let validators = match store_validators(&store, Some(&target)) {
+                    Ok(validators) => validators,
+                    Err(e) => return internal_server_error(e),
+                };
+                if !validators.exists {
                     return error(
                         Status::NOT_FOUND,
                         format!("The graph {} does not exists", GraphName::from(target)),
                     );
                 }
+                if let Err(response) = evaluate_preconditions(request, &validators) {
+                    return response;
+                }
                 let format = match graph_content_negotiation(request) {
                     Ok(format) => format,
                     Err(response) => return response,
                 };
+                let serializer = match serialization_options(request)
+                    .and_then(|options| options.graph_serializer(format))
+                {
+                    Ok(serializer) => serializer,
+                    Err(response) => return response,
+                };
                 let triples = store.quads_for_pattern(
                     None,
                     None,
                     None,
                     Some(GraphName::from(target).as_ref()),
                 );
-                ReadForWrite::build_response(
-                    move |w| {
-                        Ok((
-                            GraphSerializer::from_format(format).triple_writer(w)?,
-                            triples,
-                        ))
-                    },
-                    |(mut writer, mut triples)| {
-                        Ok(if let Some(t) = triples.next() {
-                            writer.write(&t?.into())?;
-                            Some((writer, triples))
-                        } else {
-                            writer.finish()?;
-                            None
-                        })
-                    },
-                    format.media_type(),
+                with_validators(
+                    ReadForWrite::build_response(
+                        move |w| Ok((serializer.triple_writer(w)?, triples)),
+                        |(mut writer, mut triples)| {
+                            Ok(if let Some(t) = triples.next() {
+                                writer.write(&t?.into())?;
+                                Some((writer, triples))
+                            } else {
+                                writer.finish()?;
+                                None
+                            })
+                        },
+                        format.media_type(),
+                        request,
+                    ),
+                    &validators,
+                    request,
                 )
             } else {
+                let validators = match store_validators(&store, None) {
+                    Ok(validators) => validators,
+                    Err(e) => return internal_server_error(e),
+                };
+                if let Err(response) = evaluate_preconditions(request, &validators) {
+                    return response;
+                }
                 let format = match dataset_content_negotiation(request) {
                     Ok(format) => format,
                     Err(response) => return response,
                 };
-                ReadForWrite::build_response(
-                    move |w| {
-                        Ok((
-                            DatasetSerializer::from_format(format).quad_writer(w)?,
-                            store.iter(),
-                        ))
-                    },
-                    |(mut writer, mut quads)| {
-                        Ok(if let Some(q) = quads.next() {
-                            writer.write(&q?)?;
-                            Some((writer, quads))
-                        } else {
-                            writer.finish()?;
-                            None
-                        })
-                    },
-                    format.media_type(),
+                let serializer = match serialization_options(request)
+                    .and_then(|options| options.dataset_serializer(format))
+                {
+                    Ok(serializer) => serializer,
+                    Err(response) => return response,
+                };
+                with_validators(
+                    ReadForWrite::build_response(
+                        move |w| Ok((serializer.quad_writer(w)?, store.iter())),
+                        |(mut writer, mut quads)| {
+                            Ok(if let Some(q) = quads.next() {
+                                writer.write(&q?)?;
+                                Some((writer, quads))
+                            } else {
+                                writer.finish()?;
+                                None
+                            })
+                        },
+                        format.media_type(),
+                        request,
+                    ),
+                    &validators,
+                    request,
                 )
             }
         }
@@ -336,57 +880,67 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
                     Err(error) => return error,
                 } {
                     if let Some(format) = GraphFormat::from_media_type(&content_type) {
-                        let new = !match &target {
-                            NamedGraphName::NamedNode(target) => {
-                                if match store.contains_named_graph(target) {
-                                    Ok(r) => r,
-                                    Err(e) => return internal_server_error(e),
-                                } {
-                                    if let Err(e) = store.clear_graph(target) {
-                                        return internal_server_error(e);
+                        let body = match buffered_body(request, options.max_store_body_size) {
+                            Ok(body) => body,
+                            Err(response) => return response,
+                        };
+                        let (new, validators) =
+                            match transactional_write(request, &store, Some(&target), |t| {
+                                let new = !match &target {
+                                    NamedGraphName::NamedNode(target) => {
+                                        if t.contains_named_graph(target)? {
+                                            t.clear_graph(target)?;
+                                            true
+                                        } else {
+                                            t.insert_named_graph(target)?;
+                                            false
+                                        }
                                     }
-                                    true
-                                } else {
-                                    if let Err(e) = store.insert_named_graph(target) {
-                                        return internal_server_error(e);
+                                    NamedGraphName::DefaultGraph => {
+                                        t.clear_graph(GraphNameRef::DefaultGraph)?;
+                                        true
                                     }
-                                    false
-                                }
-                            }
-                            NamedGraphName::DefaultGraph => {
-                                if let Err(e) = store.clear_graph(GraphNameRef::DefaultGraph) {
-                                    return internal_server_error(e);
-                                }
-                                true
-                            }
-                        };
-                        if let Err(e) = store.load_graph(
-                            BufReader::new(request.body_mut()),
-                            format,
-                            GraphName::from(target).as_ref(),
-                            None,
-                        ) {
-                            return bad_request(e);
-                        }
-                        Response::builder(if new {
-                            Status::CREATED
-                        } else {
-                            Status::NO_CONTENT
-                        })
-                        .build()
+                                };
+                                t.load_graph(
+                                    body.as_slice(),
+                                    format,
+                                    GraphName::from(target.clone()).as_ref(),
+                                    None,
+                                )?;
+                                Ok(new)
+                            }) {
+                                Ok(result) => result,
+                                Err(response) => return response,
+                            };
+                        with_validators(
+                            Response::builder(if new {
+                                Status::CREATED
+                            } else {
+                                Status::NO_CONTENT
+                            })
+                            .build(),
+                            &validators,
+                            request,
+                        )
                     } else {
                         unsupported_media_type(&content_type)
                     }
                 } else if let Some(format) = DatasetFormat::from_media_type(&content_type) {
-                    if let Err(e) = store.clear() {
-                        return internal_server_error(e);
-                    }
-                    if let Err(e) =
-                        store.load_dataset(BufReader::new(request.body_mut()), format, None)
-                    {
-                        return internal_server_error(e);
+                    let body = match buffered_body(request, options.max_store_body_size) {
+                        Ok(body) => body,
+                        Err(response) => return response,
+                    };
+                    match transactional_write(request, &store, None, |t| {
+                        t.clear()?;
+                        t.load_dataset(body.as_slice(), format, None)
+                    }) {
+                        Ok(((), validators)) => with_validators(
+                            Response::builder(Status::NO_CONTENT).build(),
+                            &validators,
+                            request,
+                        ),
+                        Err(response) => response,
                     }
-                    Response::builder(Status::NO_CONTENT).build()
                 } else {
                     unsupported_media_type(&content_type)
                 }
@@ -395,36 +949,39 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
             }
         }
         (path, "DELETE") if path.starts_with("/store") => {
-            if let Some(target) = match store_target(request) {
+            let target = match store_target(request) {
                 Ok(target) => target,
                 Err(error) => return error,
-            } {
-                match target {
-                    NamedGraphName::DefaultGraph => {
-                        if let Err(e) = store.clear_graph(GraphNameRef::DefaultGraph) {
-                            return internal_server_error(e);
-                        }
-                    }
-                    NamedGraphName::NamedNode(target) => {
-                        if match store.contains_named_graph(&target) {
-                            Ok(r) => r,
-                            Err(e) => return internal_server_error(e),
-                        } {
-                            if let Err(e) = store.remove_named_graph(&target) {
-                                return internal_server_error(e);
-                            }
-                        } else {
-                            return error(
-                                Status::NOT_FOUND,
-                                format!("The graph {} does not exists", target),
-                            );
-                        }
+            };
+            let validators = match store_validators(&store, target.as_ref()) {
+                Ok(validators) => validators,
+                Err(e) => return internal_server_error(e),
+            };
+            if !validators.exists {
+                if let Some(target) = target {
+                    return error(
+                        Status::NOT_FOUND,
+                        format!("The graph {} does not exists", GraphName::from(target)),
+                    );
+                }
+            }
+            match transactional_write(request, &store, target.as_ref(), |t| {
+                match &target {
+                    Some(NamedGraphName::DefaultGraph) => t.clear_graph(GraphNameRef::DefaultGraph),
+                    Some(NamedGraphName::NamedNode(target)) => {
+                        t.remove_named_graph(target).map(|_| ())
                     }
+                    None => t.clear(),
                 }
-            } else if let Err(e) = store.clear() {
-                return internal_server_error(e);
+                .map_err(LoaderError::from)
+            }) {
+                Ok(((), validators)) => with_validators(
+                    Response::builder(Status::NO_CONTENT).build(),
+                    &validators,
+                    request,
+                ),
+                Err(response) => response,
             }
-            Response::builder(Status::NO_CONTENT).build()
         }
         (path, "POST") if path.starts_with("/store") => {
             if let Some(content_type) = content_type(request) {
@@ -433,50 +990,73 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
                     Err(error) => return error,
                 } {
                     if let Some(format) = GraphFormat::from_media_type(&content_type) {
-                        let new = !match &target {
-                            NamedGraphName::NamedNode(target) => {
-                                match store.contains_named_graph(target) {
-                                    Ok(r) => r,
-                                    Err(e) => return internal_server_error(e),
-                                }
-                            }
-                            NamedGraphName::DefaultGraph => true,
+                        let body = match buffered_body(request, options.max_store_body_size) {
+                            Ok(body) => body,
+                            Err(response) => return response,
                         };
-                        if let Err(e) = store.load_graph(
-                            BufReader::new(request.body_mut()),
-                            format,
-                            GraphName::from(target).as_ref(),
-                            None,
-                        ) {
-                            return bad_request(e);
-                        }
-                        Response::builder(if new {
-                            Status::CREATED
-                        } else {
-                            Status::NO_CONTENT
-                        })
-                        .build()
+                        let (new, validators) =
+                            match transactional_write(request, &store, Some(&target), |t| {
+                                let new = !match &target {
+                                    NamedGraphName::NamedNode(target) => {
+                                        t.contains_named_graph(target)?
+                                    }
+                                    NamedGraphName::DefaultGraph => true,
+                                };
+                                t.load_graph(
+                                    body.as_slice(),
+                                    format,
+                                    GraphName::from(target.clone()).as_ref(),
+                                    None,
+                                )?;
+                                Ok(new)
+                            }) {
+                                Ok(result) => result,
+                                Err(response) => return response,
+                            };
+                        with_validators(
+                            Response::builder(if new {
+                                Status::CREATED
+                            } else {
+                                Status::NO_CONTENT
+                            })
+                            .build(),
+                            &validators,
+                            request,
+                        )
                     } else {
                         unsupported_media_type(&content_type)
                     }
                 } else if let Some(format) = DatasetFormat::from_media_type(&content_type) {
-                    if let Err(e) =
-                        store.load_dataset(BufReader::new(request.body_mut()), format, None)
-                    {
-                        return bad_request(e);
+                    let body = match buffered_body(request, options.max_store_body_size) {
+                        Ok(body) => body,
+                        Err(response) => return response,
+                    };
+                    match transactional_write(request, &store, None, |t| {
+                        t.load_dataset(body.as_slice(), format, None)
+                    }) {
+                        Ok(((), validators)) => with_validators(
+                            Response::builder(Status::NO_CONTENT).build(),
+                            &validators,
+                            request,
+                        ),
+                        Err(response) => response,
                     }
-                    Response::builder(Status::NO_CONTENT).build()
                 } else if let Some(format) = GraphFormat::from_media_type(&content_type) {
-                    let graph =
-                        match resolve_with_base(request, &format!("/store/{:x}", random::<u128>()))
-                        {
-                            Ok(graph) => graph,
-                            Err(e) => return e,
-                        };
-                    if let Err(e) =
-                        store.load_graph(BufReader::new(request.body_mut()), format, &graph, None)
-                    {
-                        return bad_request(e);
+                    let graph = match resolve_with_base(
+                        request,
+                        &format!("{}/store/{:x}", dataset_prefix, random::<u128>()),
+                    ) {
+                        Ok(graph) => graph,
+                        Err(e) => return e,
+                    };
+                    let body = match buffered_body(request, options.max_store_body_size) {
+                        Ok(body) => body,
+                        Err(response) => return response,
+                    };
+                    if let Err(response) = transactional_write(request, &store, None, |t| {
+                        t.load_graph(body.as_slice(), format, &graph, None)
+                    }) {
+                        return response;
                     }
                     Response::builder(Status::CREATED)
                         .with_header(HeaderName::LOCATION, graph.into_string())
@@ -489,27 +1069,71 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
                 bad_request("No Content-Type given")
             }
         }
+        ("/store", "PATCH") => {
+            if let Some(content_type) = content_type(request) {
+                if content_type != "application/rdf-patch" && content_type != "text/rdf-patch" {
+                    return unsupported_media_type(&content_type);
+                }
+                if request.url().query().map_or(false, |q| !q.is_empty()) {
+                    return bad_request(
+                        "PATCH requests apply to the full dataset and do not take parameters",
+                    );
+                }
+                let mut body = Vec::new();
+                match decoded_body(request) {
+                    Ok(decoded) => {
+                        if let Err(e) = decoded
+                            .take(options.max_patch_body_size + 1)
+                            .read_to_end(&mut body)
+                        {
+                            return bad_request(e);
+                        }
+                    }
+                    Err(response) => return response,
+                }
+                if body.len() as u64 > options.max_patch_body_size {
+                    // Truncating the patch could apply only a part of it
+                    return error(
+                        Status::PAYLOAD_TOO_LARGE,
+                        format!(
+                            "The RDF Patch is bigger than the limit of {} bytes",
+                            options.max_patch_body_size
+                        ),
+                    );
+                }
+                let patch = match Patch::from_reader(body.as_slice()) {
+                    Ok(patch) => patch,
+                    Err(e) => return bad_request(e),
+                };
+                if let Err(e) = store.apply_patch(&patch) {
+                    return internal_server_error(e);
+                }
+                Response::builder(Status::NO_CONTENT).build()
+            } else {
+                bad_request("No Content-Type given")
+            }
+        }
         (path, "HEAD") if path.starts_with("/store") => {
-            if let Some(target) = match store_target(request) {
+            let target = match store_target(request) {
                 Ok(target) => target,
                 Err(error) => return error,
-            } {
-                if !match &target {
-                    NamedGraphName::DefaultGraph => true,
-                    NamedGraphName::NamedNode(target) => match store.contains_named_graph(target) {
-                        Ok(r) => r,
-                        Err(e) => return internal_server_error(e),
-                    },
-                } {
+            };
+            let validators = match store_validators(&store, target.as_ref()) {
+                Ok(validators) => validators,
+                Err(e) => return internal_server_error(e),
+            };
+            if !validators.exists {
+                if let Some(target) = target {
                     return error(
                         Status::NOT_FOUND,
                         format!("The graph {} does not exists", GraphName::from(target)),
                     );
                 }
-                Response::builder(Status::OK).build()
-            } else {
-                Response::builder(Status::OK).build()
             }
+            if let Err(response) = evaluate_preconditions(request, &validators) {
+                return response;
+            }
+            with_validators(Response::builder(Status::OK).build(), &validators, request)
         }
         _ => error(
             Status::NOT_FOUND,
@@ -522,122 +1146,695 @@ fn handle_request(request: &mut Request, store: Store) -> Response {
     }
 }
 
-fn base_url(request: &Request) -> Result<String, Response> {
-    let mut url = request.url().clone();
-    if let Some(host) = request.url().host_str() {
-        url.set_host(Some(host)).map_err(bad_request)?;
-    }
-    url.set_query(None);
-    url.set_fragment(None);
-    Ok(url.into())
-}
-
-fn resolve_with_base(request: &Request, url: &str) -> Result<NamedNode, Response> {
-    Ok(NamedNode::new_unchecked(
-        Iri::parse(base_url(request)?)
-            .map_err(bad_request)?
-            .resolve(url)
-            .map_err(bad_request)?
-            .into_inner(),
-    ))
+/// Cross-Origin Resource Sharing (CORS) configuration
+#[derive(Default)]
+struct CorsOptions {
+    /// The allowed origins, CORS is disabled if empty
+    origins: Vec<String>,
+    methods: Vec<String>,
+    headers: Vec<String>,
+    allow_credentials: bool,
 }
 
-fn url_query(request: &Request) -> &[u8] {
-    request.url().query().unwrap_or("").as_bytes()
+/// Answers the CORS preflight requests and adds the CORS headers to the responses of `/query`, `/update` and `/store`
+fn handle_cors(
+    request: &mut Request,
+    cors: &CorsOptions,
+    handler: impl FnOnce(&mut Request) -> Response,
+) -> Response {
+    let path = dataset_path(request.url().path()).1;
+    if cors.origins.is_empty()
+        || !(path == "/query"
+            || path == "/update"
+            || path.starts_with("/store")
+            || path == "/fragments")
+    {
+        return handler(request);
+    }
+    let mut response = cors_response(request, cors, handler);
+    if !cors.origins.iter().any(|o| o == "*") {
+        // The response depends on the origin, even if it is not allowed or not set, so caches must not share it between origins
+        response.append_header(HeaderName::VARY, "Origin").unwrap();
+    }
+    response
 }
 
-fn configure_and_evaluate_sparql_query(
-    store: Store,
-    encoded: &[&[u8]],
-    mut query: Option<String>,
-    request: &Request,
+/// Builds the response of a request to a CORS enabled path
+fn cors_response(
+    request: &mut Request,
+    cors: &CorsOptions,
+    handler: impl FnOnce(&mut Request) -> Response,
 ) -> Response {
-    let mut default_graph_uris = Vec::new();
-    let mut named_graph_uris = Vec::new();
-    let mut use_default_graph_as_union = false;
-    for encoded in encoded {
-        for (k, v) in form_urlencoded::parse(encoded) {
-            match k.as_ref() {
-                "query" => {
-                    if query.is_some() {
-                        return bad_request("Multiple query parameters provided");
-                    }
-                    query = Some(v.into_owned())
-                }
-                "default-graph-uri" => default_graph_uris.push(v.into_owned()),
-                "union-default-graph" => use_default_graph_as_union = true,
-                "named-graph-uri" => named_graph_uris.push(v.into_owned()),
-                _ => (),
+    let origin = if let Some(origin) = request.header(&HeaderName::ORIGIN) {
+        match origin.to_str() {
+            Ok(origin) => origin.to_owned(),
+            Err(_) => return bad_request("Invalid Origin header"),
+        }
+    } else {
+        return handler(request); // Not a CORS request
+    };
+    let is_origin_allowed = cors.origins.iter().any(|o| o == "*" || *o == origin);
+    if request.method().as_ref() == "OPTIONS" {
+        if let Some(method) = request.header(&HeaderName::ACCESS_CONTROL_REQUEST_METHOD) {
+            // Preflight request
+            let method = match method.to_str() {
+                Ok(method) => method.trim().to_owned(),
+                Err(_) => return bad_request("Invalid Access-Control-Request-Method header"),
+            };
+            let headers = match request
+                .header(&HeaderName::ACCESS_CONTROL_REQUEST_HEADERS)
+                .map(|h| h.to_str())
+                .transpose()
+            {
+                Ok(headers) => headers
+                    .unwrap_or("")
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|h| !h.is_empty())
+                    .collect::<Vec<_>>(),
+                Err(_) => return bad_request("Invalid Access-Control-Request-Headers header"),
+            };
+            if !is_origin_allowed {
+                return error(
+                    Status::FORBIDDEN,
+                    format!("CORS requests from {} are not allowed", origin),
+                );
+            }
+            if !cors.methods.iter().any(|m| m == "*" || *m == method) {
+                return error(
+                    Status::FORBIDDEN,
+                    format!("CORS requests with the {} method are not allowed", method),
+                );
             }
+            if let Some(header) = headers.iter().find(|h| {
+                !cors
+                    .headers
+                    .iter()
+                    .any(|a| a == "*" || a.eq_ignore_ascii_case(h))
+            }) {
+                return error(
+                    Status::FORBIDDEN,
+                    format!("CORS requests with the {} header are not allowed", header),
+                );
+            }
+            // We only echo the requested method and headers because "*" is not a wildcard for requests with credentials
+            let mut response = Response::builder(Status::NO_CONTENT)
+                .with_header(HeaderName::ACCESS_CONTROL_ALLOW_METHODS, method)
+                .unwrap()
+                .with_header(HeaderName::ACCESS_CONTROL_MAX_AGE, "86400")
+                .unwrap()
+                .build();
+            if !headers.is_empty() {
+                response
+                    .append_header(HeaderName::ACCESS_CONTROL_ALLOW_HEADERS, headers.join(", "))
+                    .unwrap();
+            }
+            add_cors_headers(&mut response, cors, origin);
+            return response;
         }
     }
-    if let Some(query) = query {
-        evaluate_sparql_query(
-            store,
-            query,
-            use_default_graph_as_union,
-            default_graph_uris,
-            named_graph_uris,
-            request,
-        )
-    } else {
-        bad_request("You should set the 'query' parameter")
+    let mut response = handler(request);
+    if is_origin_allowed {
+        response
+            .append_header(HeaderName::ACCESS_CONTROL_EXPOSE_HEADERS, "ETag, Location")
+            .unwrap();
+        add_cors_headers(&mut response, cors, origin);
     }
+    response
 }
 
-fn evaluate_sparql_query(
-    store: Store,
-    query: String,
-    use_default_graph_as_union: bool,
-    default_graph_uris: Vec<String>,
-    named_graph_uris: Vec<String>,
-    request: &Request,
+fn add_cors_headers(response: &mut Response, cors: &CorsOptions, origin: String) {
+    if cors.origins.iter().any(|o| o == "*") {
+        // The server checks at startup that credentials are not allowed with the * origin
+        response
+            .append_header(HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+            .unwrap();
+    } else {
+        response
+            .append_header(HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN, origin)
+            .unwrap();
+    }
+    if cors.allow_credentials {
+        response
+            .append_header(HeaderName::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true")
+            .unwrap();
+    }
+}
+
+/// Number of SPARQL queries being evaluated, including the streaming of their results
+static ACTIVE_QUERIES: AtomicU64 = AtomicU64::new(0);
+/// Number of SPARQL queries whose evaluation failed
+static QUERY_EVALUATION_ERRORS: AtomicU64 = AtomicU64::new(0);
+/// The HTTP methods recorded in the metrics, the other ones are recorded as `other`
+const KNOWN_METHODS: [&str; 7] = ["GET", "HEAD", "POST", "PUT", "DELETE", "PATCH", "OPTIONS"];
+/// Upper bounds in seconds of the request duration histogram buckets
+const DURATION_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1., 2.5, 5., 10.];
+
+/// Counts a query in [`ACTIVE_QUERIES`] until it is dropped and then writes it to the slow query log if needed
+///
+/// It is kept until all the results are written.
+struct ActiveQuery {
+    start: Instant,
+    results: Cell<u64>,
+    slow_query_log: Option<(SlowQueryLog, QueryPlanCacheKey)>,
+}
+
+impl ActiveQuery {
+    fn start(slow_query_log: Option<(SlowQueryLog, QueryPlanCacheKey)>) -> Self {
+        ACTIVE_QUERIES.fetch_add(1, Ordering::Relaxed);
+        Self {
+            start: Instant::now(),
+            results: Cell::new(0),
+            slow_query_log,
+        }
+    }
+
+    fn add_result(&self) {
+        self.results.set(self.results.get() + 1);
+    }
+}
+
+impl Drop for ActiveQuery {
+    fn drop(&mut self) {
+        ACTIVE_QUERIES.fetch_sub(1, Ordering::Relaxed);
+        if let Some((log, key)) = &self.slow_query_log {
+            log.record(key, self.start.elapsed(), self.results.get());
+        }
+    }
+}
+
+fn query_evaluation_error(error: EvaluationError) -> io::Error {
+    QUERY_EVALUATION_ERRORS.fetch_add(1, Ordering::Relaxed);
+    error.into()
+}
+
+/// The number of requests and their durations, by route, method and status
+#[derive(Default)]
+struct RequestMetrics {
+    requests: Mutex<HashMap<(&'static str, &'static str, u16), RequestDurations>>,
+}
+
+#[derive(Default)]
+struct RequestDurations {
+    count: u64,
+    sum: f64,
+    buckets: [u64; DURATION_BUCKETS.len()],
+}
+
+impl RequestMetrics {
+    fn record(&self, route: &'static str, method: &str, status: u16, duration: Duration) {
+        // The methods are sent by the clients: the other ones are grouped to bound the number of series
+        let method = KNOWN_METHODS
+            .into_iter()
+            .find(|m| *m == method)
+            .unwrap_or("other");
+        let duration = duration.as_secs_f64();
+        let mut requests = self.requests.lock().unwrap();
+        let durations = requests.entry((route, method, status)).or_default();
+        durations.count += 1;
+        durations.sum += duration;
+        for (bucket, bound) in durations.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if duration <= bound {
+                *bucket += 1;
+            }
+        }
+    }
+}
+
+/// Serves the `/metrics` and `/health/*` endpoints and records the metrics of the other requests
+///
+/// The duration of a request is the time to build its response: the streaming of the body is not included.
+/// It is called after the authentication so the metrics are not public.
+fn handle_monitoring(
+    request: &mut Request,
+    store: &Store,
+    metrics: &RequestMetrics,
+    handler: impl FnOnce(&mut Request) -> Response,
 ) -> Response {
-    let mut query = match Query::parse(
-        &query,
-        Some(&match base_url(request) {
-            Ok(url) => url,
-            Err(r) => return r,
-        }),
-    ) {
-        Ok(query) => query,
-        Err(e) => return bad_request(e),
+    match (request.url().path(), request.method().as_ref()) {
+        ("/metrics", "GET") => return metrics_response(store, metrics),
+        ("/health/live", "GET" | "HEAD") => return Response::builder(Status::OK).build(),
+        ("/health/ready", "GET" | "HEAD") => {
+            // We check that the storage could be read
+            return match store.is_empty() {
+                Ok(_) => Response::builder(Status::OK).build(),
+                Err(e) => error(Status::SERVICE_UNAVAILABLE, e),
+            };
+        }
+        _ => (),
+    }
+    let route = match dataset_path(request.url().path()).1 {
+        "/" => "/",
+        "/logo.svg" => "/logo.svg",
+        "/query" => "/query",
+        "/update" => "/update",
+        path if path.starts_with("/store") => "/store",
+        path if path.starts_with("/browse") => "/browse",
+        "/fragments" => "/fragments",
+        _ => "other",
     };
+    let method = request.method().as_ref().to_owned();
+    let start = Instant::now();
+    let response = handler(request);
+    metrics.record(route, &method, response.status().into(), start.elapsed());
+    response
+}
 
-    if use_default_graph_as_union {
-        if !default_graph_uris.is_empty() || !named_graph_uris.is_empty() {
-            return bad_request(
-                "default-graph-uri or named-graph-uri and union-default-graph should not be set at the same time"
+/// Writes the metrics using the [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/)
+fn metrics_response(store: &Store, metrics: &RequestMetrics) -> Response {
+    let mut body = String::new();
+    {
+        let requests = metrics.requests.lock().unwrap();
+        let mut keys = requests.keys().collect::<Vec<_>>();
+        keys.sort();
+        write_metric_header(
+            &mut body,
+            "oxigraph_http_requests_total",
+            "counter",
+            "Number of HTTP requests by route, method and status.",
+        );
+        for key in &keys {
+            let (route, method, status) = key;
+            let _ = writeln!(
+                body,
+                "oxigraph_http_requests_total{{route=\"{}\",method=\"{}\",status=\"{}\"}} {}",
+                route, method, status, requests[key].count
             );
         }
-        query.dataset_mut().set_default_graph_as_union()
-    } else if !default_graph_uris.is_empty() || !named_graph_uris.is_empty() {
-        query.dataset_mut().set_default_graph(
-            match default_graph_uris
-                .into_iter()
-                .map(|e| Ok(NamedNode::new(e)?.into()))
-                .collect::<Result<Vec<GraphName>, IriParseError>>()
-            {
-                Ok(default_graph_uris) => default_graph_uris,
-                Err(e) => return bad_request(e),
-            },
+        write_metric_header(
+            &mut body,
+            "oxigraph_http_request_duration_seconds",
+            "histogram",
+            "Time to build the HTTP responses, without the streaming of their bodies.",
         );
-        query.dataset_mut().set_available_named_graphs(
-            match named_graph_uris
-                .into_iter()
-                .map(|e| Ok(NamedNode::new(e)?.into()))
-                .collect::<Result<Vec<NamedOrBlankNode>, IriParseError>>()
-            {
-                Ok(named_graph_uris) => named_graph_uris,
-                Err(e) => return bad_request(e),
-            },
+        for key in &keys {
+            let (route, method, status) = key;
+            let labels = format!(
+                "route=\"{}\",method=\"{}\",status=\"{}\"",
+                route, method, status
+            );
+            let durations = &requests[key];
+            for (bucket, bound) in durations.buckets.iter().zip(DURATION_BUCKETS) {
+                let _ = writeln!(
+                    body,
+                    "oxigraph_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, bucket
+                );
+            }
+            let _ = writeln!(
+                body,
+                "oxigraph_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, durations.count
+            );
+            let _ = writeln!(
+                body,
+                "oxigraph_http_request_duration_seconds_sum{{{}}} {}",
+                labels, durations.sum
+            );
+            let _ = writeln!(
+                body,
+                "oxigraph_http_request_duration_seconds_count{{{}}} {}",
+                labels, durations.count
+            );
+        }
+    }
+    write_metric(
+        &mut body,
+        "oxigraph_active_queries",
+        "gauge",
+        "Number of SPARQL queries being evaluated.",
+        ACTIVE_QUERIES.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut body,
+        "oxigraph_query_evaluation_errors_total",
+        "counter",
+        "Number of SPARQL query evaluations that failed.",
+        QUERY_EVALUATION_ERRORS.load(Ordering::Relaxed),
+    );
+    let storage = store.storage_metrics();
+    write_metric(
+        &mut body,
+        "oxigraph_store_quads",
+        "gauge",
+        "Estimated number of quads in the store.",
+        storage.estimated_quads,
+    );
+    write_metric(
+        &mut body,
+        "oxigraph_rocksdb_block_cache_hits_total",
+        "counter",
+        "Number of RocksDB block cache hits.",
+        storage.block_cache_hits,
+    );
+    write_metric(
+        &mut body,
+        "oxigraph_rocksdb_block_cache_misses_total",
+        "counter",
+        "Number of RocksDB block cache misses.",
+        storage.block_cache_misses,
+    );
+    let block_cache_lookups = storage.block_cache_hits + storage.block_cache_misses;
+    if block_cache_lookups > 0 {
+        write_metric(
+            &mut body,
+            "oxigraph_rocksdb_block_cache_hit_ratio",
+            "gauge",
+            "Ratio of the RocksDB block cache lookups that are hits.",
+            storage.block_cache_hits as f64 / block_cache_lookups as f64,
+        );
+    }
+    write_metric(
+        &mut body,
+        "oxigraph_rocksdb_compaction_pending",
+        "gauge",
+        "1 if at least one RocksDB compaction is pending, 0 otherwise.",
+        u8::from(storage.compaction_pending),
+    );
+    write_metric(
+        &mut body,
+        "oxigraph_rocksdb_estimated_pending_compaction_bytes",
+        "gauge",
+        "Estimated number of bytes the pending RocksDB compactions have to rewrite.",
+        storage.estimated_pending_compaction_bytes,
+    );
+    write_metric_header(
+        &mut body,
+        "oxigraph_rocksdb_sst_files_bytes",
+        "gauge",
+        "Size of the RocksDB SST files by column family.",
+    );
+    let mut sst_files_size = storage.sst_files_size.into_iter().collect::<Vec<_>>();
+    sst_files_size.sort();
+    for (column_family, size) in sst_files_size {
+        let _ = writeln!(
+            body,
+            "oxigraph_rocksdb_sst_files_bytes{{column_family=\"{}\"}} {}",
+            column_family, size
         );
     }
+    Response::builder(Status::OK)
+        .with_header(HeaderName::CONTENT_TYPE, "text/plain; version=0.0.4")
+        .unwrap()
+        .with_body(body)
+}
 
-    let results = match store.query(query) {
-        Ok(results) => results,
+fn write_metric_header(body: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(body, "# HELP {} {}", name, help);
+    let _ = writeln!(body, "# TYPE {} {}", name, kind);
+}
+
+fn write_metric(body: &mut String, name: &str, kind: &str, help: &str, value: impl fmt::Display) {
+    write_metric_header(body, name, kind, help);
+    let _ = writeln!(body, "{} {}", name, value);
+}
+
+fn base_url(request: &Request) -> Result<String, Response> {
+    let mut url = request.url().clone();
+    if let Some(host) = request.url().host_str() {
+        url.set_host(Some(host)).map_err(bad_request)?;
+    }
+    url.set_query(None);
+    url.set_fragment(None);
+    Ok(url.into())
+}
+
+fn resolve_with_base(request: &Request, url: &str) -> Result<NamedNode, Response> {
+    Ok(NamedNode::new_unchecked(
+        Iri::parse(base_url(request)?)
+            .map_err(bad_request)?
+            .resolve(url)
+            .map_err(bad_request)?
+            .into_inner(),
+    ))
+}
+
+fn url_query(request: &Request) -> &[u8] {
+    request.url().query().unwrap_or("").as_bytes()
+}
+
+fn configure_and_evaluate_sparql_query(
+    store: Store,
+    encoded: &[&[u8]],
+    mut query: Option<String>,
+    request: &Request,
+    options: &EndpointOptions,
+) -> Response {
+    let mut default_graph_uris = Vec::new();
+    let mut named_graph_uris = Vec::new();
+    let mut use_default_graph_as_union = false;
+    for encoded in encoded {
+        for (k, v) in form_urlencoded::parse(encoded) {
+            match k.as_ref() {
+                "query" => {
+                    if query.is_some() {
+                        return bad_request("Multiple query parameters provided");
+                    }
+                    query = Some(v.into_owned())
+                }
+                "default-graph-uri" => default_graph_uris.push(v.into_owned()),
+                "union-default-graph" => use_default_graph_as_union = true,
+                "named-graph-uri" => named_graph_uris.push(v.into_owned()),
+                _ => (),
+            }
+        }
+    }
+    if let Some(query) = query {
+        evaluate_sparql_query(
+            store,
+            QueryPlanCacheKey {
+                query,
+                dataset: dataset_path(request.url().path())
+                    .0
+                    .unwrap_or_default()
+                    .to_owned(),
+                base_iri: match base_url(request) {
+                    Ok(url) => url,
+                    Err(r) => return r,
+                },
+                use_default_graph_as_union,
+                default_graph_uris,
+                named_graph_uris,
+            },
+            request,
+            options,
+        )
+    } else if request.method().as_ref() == "GET" {
+        service_description(&store, request, options)
+    } else {
+        bad_request("You should set the 'query' parameter")
+    }
+}
+
+/// Builds the [SPARQL 1.1 Service Description](https://www.w3.org/TR/sparql11-service-description/) of the query endpoint
+///
+/// The default dataset is described using [VoID](https://www.w3.org/TR/void/) statistics.
+fn service_description(store: &Store, request: &Request, options: &EndpointOptions) -> Response {
+    let format = match graph_content_negotiation(request) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+    let endpoint = match base_url(request).and_then(|url| NamedNode::new(url).map_err(bad_request))
+    {
+        Ok(endpoint) => endpoint,
+        Err(response) => return response,
+    };
+    let dataset_name = dataset_path(request.url().path()).0.unwrap_or_default();
+    let statistics = match options.statistics.get(dataset_name, store) {
+        Ok(statistics) => statistics,
         Err(e) => return internal_server_error(e),
     };
+    let sd = |name: &str| NamedNode::new_unchecked(format!("{}{}", SD_NAMESPACE, name));
+    let void = |name: &str| NamedNode::new_unchecked(format!("{}{}", VOID_NAMESPACE, name));
+
+    let mut graph = Graph::new();
+    let service = BlankNode::default();
+    graph.insert(&Triple::new(service.clone(), rdf::TYPE, sd("Service")));
+    graph.insert(&Triple::new(service.clone(), sd("endpoint"), endpoint));
+    graph.insert(&Triple::new(
+        service.clone(),
+        sd("supportedLanguage"),
+        sd("SPARQL11Query"),
+    ));
+    for feature in ["BasicFederatedQuery", "EmptyGraphs"] {
+        graph.insert(&Triple::new(service.clone(), sd("feature"), sd(feature)));
+    }
+    for format in QUERY_RESULTS_FORMATS.map(QueryResultsFormat::iri) {
+        graph.insert(&Triple::new(
+            service.clone(),
+            sd("resultFormat"),
+            NamedNode::new_unchecked(format),
+        ));
+    }
+    for format in GRAPH_FORMATS.map(GraphFormat::iri) {
+        graph.insert(&Triple::new(
+            service.clone(),
+            sd("resultFormat"),
+            NamedNode::new_unchecked(format),
+        ));
+    }
+    for format in GRAPH_FORMATS
+        .map(GraphFormat::iri)
+        .into_iter()
+        .chain(DATASET_FORMATS.map(DatasetFormat::iri))
+    {
+        graph.insert(&Triple::new(
+            service.clone(),
+            sd("inputFormat"),
+            NamedNode::new_unchecked(format),
+        ));
+    }
+    for function in EXTENSION_FUNCTIONS {
+        let function = NamedNode::new_unchecked(function);
+        graph.insert(&Triple::new(
+            service.clone(),
+            sd("extensionFunction"),
+            function.clone(),
+        ));
+        graph.insert(&Triple::new(function, rdf::TYPE, sd("Function")));
+    }
+
+    let dataset = BlankNode::default();
+    graph.insert(&Triple::new(service, sd("defaultDataset"), dataset.clone()));
+    graph.insert(&Triple::new(dataset.clone(), rdf::TYPE, sd("Dataset")));
+    graph.insert(&Triple::new(dataset.clone(), rdf::TYPE, void("Dataset")));
+    add_void_statistics(&mut graph, &dataset, &statistics.dataset);
+    let default_graph = BlankNode::default();
+    graph.insert(&Triple::new(
+        dataset.clone(),
+        sd("defaultGraph"),
+        default_graph.clone(),
+    ));
+    graph.insert(&Triple::new(default_graph.clone(), rdf::TYPE, sd("Graph")));
+    add_void_statistics(&mut graph, &default_graph, &statistics.default_graph);
+    for (name, statistics) in &statistics.named_graphs {
+        let named_graph = BlankNode::default();
+        graph.insert(&Triple::new(
+            dataset.clone(),
+            sd("namedGraph"),
+            named_graph.clone(),
+        ));
+        graph.insert(&Triple::new(
+            named_graph.clone(),
+            rdf::TYPE,
+            sd("NamedGraph"),
+        ));
+        graph.insert(&Triple::new(named_graph.clone(), sd("name"), name.clone()));
+        let inner_graph = BlankNode::default();
+        graph.insert(&Triple::new(named_graph, sd("graph"), inner_graph.clone()));
+        graph.insert(&Triple::new(inner_graph.clone(), rdf::TYPE, sd("Graph")));
+        add_void_statistics(&mut graph, &inner_graph, statistics);
+    }
+
+    let mut body = Vec::new();
+    let result = GraphSerializer::from_format(format)
+        .triple_writer(&mut body)
+        .and_then(|mut writer| {
+            for triple in &graph {
+                writer.write(triple)?;
+            }
+            writer.finish()
+        });
+    if let Err(e) = result {
+        return internal_server_error(e);
+    }
+    Response::builder(Status::OK)
+        .with_header(HeaderName::CONTENT_TYPE, format.media_type())
+        .unwrap()
+        .with_body(body)
+}
+
+fn add_void_statistics(graph: &mut Graph, node: &BlankNode, statistics: &GraphStatistics) {
+    let void = |name: &str| NamedNode::new_unchecked(format!("{}{}", VOID_NAMESPACE, name));
+    let count = |value: usize| Literal::from(i64::try_from(value).unwrap_or(i64::MAX));
+    graph.insert(&Triple::new(
+        node.clone(),
+        void("triples"),
+        count(statistics.triples),
+    ));
+    graph.insert(&Triple::new(
+        node.clone(),
+        void("distinctSubjects"),
+        count(statistics.distinct_subjects),
+    ));
+    graph.insert(&Triple::new(
+        node.clone(),
+        void("distinctObjects"),
+        count(statistics.distinct_objects),
+    ));
+    graph.insert(&Triple::new(
+        node.clone(),
+        void("properties"),
+        count(statistics.properties.len()),
+    ));
+    graph.insert(&Triple::new(
+        node.clone(),
+        void("classes"),
+        count(statistics.classes.len()),
+    ));
+    for (property, triples) in &statistics.properties {
+        let partition = BlankNode::default();
+        graph.insert(&Triple::new(
+            node.clone(),
+            void("propertyPartition"),
+            partition.clone(),
+        ));
+        graph.insert(&Triple::new(
+            partition.clone(),
+            void("property"),
+            property.clone(),
+        ));
+        graph.insert(&Triple::new(partition, void("triples"), count(*triples)));
+    }
+    for (class, entities) in &statistics.classes {
+        let partition = BlankNode::default();
+        graph.insert(&Triple::new(
+            node.clone(),
+            void("classPartition"),
+            partition.clone(),
+        ));
+        graph.insert(&Triple::new(
+            partition.clone(),
+            void("class"),
+            class.clone(),
+        ));
+        graph.insert(&Triple::new(partition, void("entities"), count(*entities)));
+    }
+}
+
+fn evaluate_sparql_query(
+    store: Store,
+    key: QueryPlanCacheKey,
+    request: &Request,
+    options: &EndpointOptions,
+) -> Response {
+    let slow_query_log = options
+        .slow_query_log
+        .as_ref()
+        .map(|log| (log.clone(), key.clone()));
+    let cached = QUERY_PLAN_CACHE.with(|cache| cache.borrow_mut().get(&key));
+    let query = if let Some(query) = cached {
+        query
+    } else {
+        let query = match prepare_sparql_query(&store, &key, options) {
+            Ok(query) => Rc::new(query),
+            Err(response) => return response,
+        };
+        QUERY_PLAN_CACHE.with(|cache| {
+            cache
+                .borrow_mut()
+                .insert(key, query.clone(), options.query_plan_cache_size)
+        });
+        query
+    };
+    let active_query = ActiveQuery::start(slow_query_log);
+    let results = match store.query_prepared(&query, empty()) {
+        Ok(results) => results,
+        Err(e) => {
+            QUERY_EVALUATION_ERRORS.fetch_add(1, Ordering::Relaxed);
+            return internal_server_error(e);
+        }
+    };
     match results {
         QueryResults::Solutions(solutions) => {
             let format = match query_results_content_negotiation(request) {
@@ -652,9 +1849,10 @@ fn evaluate_sparql_query(
                         solutions,
                     ))
                 },
-                |(mut writer, mut solutions)| {
+                move |(mut writer, mut solutions)| {
                     Ok(if let Some(solution) = solutions.next() {
-                        writer.write(&solution?)?;
+                        writer.write(&solution.map_err(query_evaluation_error)?)?;
+                        active_query.add_result();
                         Some((writer, solutions))
                     } else {
                         writer.finish()?;
@@ -662,6 +1860,7 @@ fn evaluate_sparql_query(
                     })
                 },
                 format.media_type(),
+                request,
             )
         }
         QueryResults::Boolean(result) => {
@@ -675,6 +1874,7 @@ fn evaluate_sparql_query(
             {
                 return internal_server_error(e);
             }
+            active_query.add_result();
             Response::builder(Status::OK)
                 .with_header(HeaderName::CONTENT_TYPE, format.media_type())
                 .unwrap()
@@ -692,9 +1892,10 @@ fn evaluate_sparql_query(
                         triples,
                     ))
                 },
-                |(mut writer, mut triples)| {
+                move |(mut writer, mut triples)| {
                     Ok(if let Some(t) = triples.next() {
-                        writer.write(&t?)?;
+                        writer.write(&t.map_err(query_evaluation_error)?)?;
+                        active_query.add_result();
                         Some((writer, triples))
                     } else {
                         writer.finish()?;
@@ -702,16 +1903,120 @@ fn evaluate_sparql_query(
                     })
                 },
                 format.media_type(),
+                request,
             )
         }
     }
 }
 
+fn prepare_sparql_query(
+    store: &Store,
+    key: &QueryPlanCacheKey,
+    options: &EndpointOptions,
+) -> Result<PreparedQuery, Response> {
+    let mut query = Query::parse(&key.query, Some(&key.base_iri)).map_err(bad_request)?;
+    if key.use_default_graph_as_union {
+        if !key.default_graph_uris.is_empty() || !key.named_graph_uris.is_empty() {
+            return Err(bad_request(
+                "default-graph-uri or named-graph-uri and union-default-graph should not be set at the same time"
+            ));
+        }
+        query.dataset_mut().set_default_graph_as_union()
+    } else if !key.default_graph_uris.is_empty() || !key.named_graph_uris.is_empty() {
+        query.dataset_mut().set_default_graph(
+            key.default_graph_uris
+                .iter()
+                .map(|e| Ok(NamedNode::new(e)?.into()))
+                .collect::<Result<Vec<GraphName>, IriParseError>>()
+                .map_err(bad_request)?,
+        );
+        query.dataset_mut().set_available_named_graphs(
+            key.named_graph_uris
+                .iter()
+                .map(|e| Ok(NamedNode::new(e)?.into()))
+                .collect::<Result<Vec<NamedOrBlankNode>, IriParseError>>()
+                .map_err(bad_request)?,
+        );
+    } else if options.union_default_graph && query.dataset().is_default_dataset() {
+        // The server default only applies if neither the request nor the query set the dataset
+        query.dataset_mut().set_default_graph_as_union()
+    }
+    store
+        .prepare_query_opt(query, query_options(&options.services))
+        .map_err(internal_server_error)
+}
+
+/// The query options with the stores mounted as services
+fn query_options(services: &[(NamedNode, Store)]) -> QueryOptions {
+    if services.is_empty() {
+        return QueryOptions::default();
+    }
+    let mut handler = StoreServiceHandler::new();
+    for (name, store) in services {
+        handler = handler.with_store(name.clone(), store.clone());
+    }
+    QueryOptions::default().with_service_handler(handler)
+}
+
+thread_local! {
+    static QUERY_PLAN_CACHE: RefCell<QueryPlanCache> = RefCell::default();
+}
+
+/// The query text and the protocol parameters that are changing the query plan
+#[derive(Eq, PartialEq, Hash, Clone)]
+struct QueryPlanCacheKey {
+    query: String,
+    /// The name of the queried dataset, empty for the default one
+    dataset: String,
+    base_iri: String,
+    use_default_graph_as_union: bool,
+    default_graph_uris: Vec<String>,
+    named_graph_uris: Vec<String>,
+}
+
+/// A least recently used cache of prepared queries
+///
+/// Prepared queries are not `Send` so each server thread has its own cache.
+#[derive(Default)]
+struct QueryPlanCache {
+    entries: HashMap<QueryPlanCacheKey, (Rc<PreparedQuery>, u64)>,
+    clock: u64,
+}
+
+impl QueryPlanCache {
+    fn get(&mut self, key: &QueryPlanCacheKey) -> Option<Rc<PreparedQuery>> {
+        let (query, last_use) = self.entries.get_mut(key)?;
+        self.clock += 1;
+        *last_use = self.clock;
+        Some(query.clone())
+    }
+
+    fn insert(&mut self, key: QueryPlanCacheKey, query: Rc<PreparedQuery>, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= capacity {
+            // Linear scan: the cache is small and evictions are rare compared to lookups
+            let least_recently_used = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_use))| *last_use)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = least_recently_used {
+                self.entries.remove(&key);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(key, (query, self.clock));
+    }
+}
+
 fn configure_and_evaluate_sparql_update(
     store: Store,
     encoded: &[&[u8]],
     mut update: Option<String>,
     request: &Request,
+    services: &[(NamedNode, Store)],
 ) -> Response {
     let mut use_default_graph_as_union = false;
     let mut default_graph_uris = Vec::new();
@@ -740,6 +2045,7 @@ fn configure_and_evaluate_sparql_update(
             default_graph_uris,
             named_graph_uris,
             request,
+            services,
         )
     } else {
         bad_request("You should set the 'update' parameter")
@@ -753,6 +2059,7 @@ fn evaluate_sparql_update(
     default_graph_uris: Vec<String>,
     named_graph_uris: Vec<String>,
     request: &Request,
+    services: &[(NamedNode, Store)],
 ) -> Response {
     let mut update = match Update::parse(
         &update,
@@ -809,20 +2116,21 @@ fn evaluate_sparql_update(
             using.set_available_named_graphs(named_graph_uris.clone());
         }
     }
-    if let Err(e) = store.update(update) {
+    if let Err(e) = store.update_opt(update, query_options(services)) {
         return internal_server_error(e);
     }
     Response::builder(Status::NO_CONTENT).build()
 }
 
 fn store_target(request: &Request) -> Result<Option<NamedGraphName>, Response> {
-    if request.url().path() == "/store" {
+    if dataset_path(request.url().path()).1 == "/store" {
         let mut graph = None;
         let mut default = false;
         for (k, v) in form_urlencoded::parse(request.url().query().unwrap_or("").as_bytes()) {
             match k.as_ref() {
                 "graph" => graph = Some(v.into_owned()),
                 "default" => default = true,
+                "pretty" | "prefix" | "base" => (), // Serialization options
                 _ => {
                     return Err(bad_request(format!("Unexpected parameter: {}", k)));
                 }
@@ -850,6 +2158,81 @@ fn store_target(request: &Request) -> Result<Option<NamedGraphName>, Response> {
     }
 }
 
+/// Turtle and TriG serialization options given with the `pretty`, `prefix` and `base` query parameters
+///
+/// Only `pretty` buffers the full output in memory, the prefixes and the base IRI are applied while streaming.
+#[derive(Default)]
+struct SerializationOptions {
+    prefixes: Vec<(String, String)>,
+    base_iri: Option<String>,
+    pretty: bool,
+}
+
+impl SerializationOptions {
+    fn graph_serializer(self, format: GraphFormat) -> Result<GraphSerializer, Response> {
+        let mut serializer = GraphSerializer::from_format(format);
+        for (prefix_name, prefix_iri) in self.prefixes {
+            serializer = serializer
+                .with_prefix(prefix_name, prefix_iri)
+                .map_err(bad_request)?;
+        }
+        if let Some(base_iri) = self.base_iri {
+            serializer = serializer.with_base_iri(base_iri).map_err(bad_request)?;
+        }
+        Ok(if self.pretty {
+            serializer.pretty()
+        } else {
+            serializer
+        })
+    }
+
+    fn dataset_serializer(self, format: DatasetFormat) -> Result<DatasetSerializer, Response> {
+        let mut serializer = DatasetSerializer::from_format(format);
+        for (prefix_name, prefix_iri) in self.prefixes {
+            serializer = serializer
+                .with_prefix(prefix_name, prefix_iri)
+                .map_err(bad_request)?;
+        }
+        if let Some(base_iri) = self.base_iri {
+            serializer = serializer.with_base_iri(base_iri).map_err(bad_request)?;
+        }
+        Ok(if self.pretty {
+            serializer.pretty()
+        } else {
+            serializer
+        })
+    }
+}
+
+fn serialization_options(request: &Request) -> Result<SerializationOptions, Response> {
+    let mut options = SerializationOptions::default();
+    for (k, v) in form_urlencoded::parse(request.url().query().unwrap_or("").as_bytes()) {
+        match k.as_ref() {
+            "pretty" => options.pretty = true,
+            "prefix" => {
+                let (prefix_name, prefix_iri) = v.split_once(':').ok_or_else(|| {
+                    bad_request(format!(
+                        "The prefix parameter should be of the form name:iri, found {}",
+                        v
+                    ))
+                })?;
+                if !prefix_name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                {
+                    return Err(bad_request(format!("Invalid prefix name: {}", prefix_name)));
+                }
+                options
+                    .prefixes
+                    .push((prefix_name.into(), prefix_iri.into()));
+            }
+            "base" => options.base_iri = Some(v.into_owned()),
+            _ => (),
+        }
+    }
+    Ok(options)
+}
+
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 enum NamedGraphName {
     NamedNode(NamedNode),
@@ -865,43 +2248,255 @@ impl From<NamedGraphName> for GraphName {
     }
 }
 
-fn graph_content_negotiation(request: &Request) -> Result<GraphFormat, Response> {
-    content_negotiation(
-        request,
-        &[
-            GraphFormat::NTriples.media_type(),
-            GraphFormat::Turtle.media_type(),
-            GraphFormat::RdfXml.media_type(),
-        ],
-        GraphFormat::from_media_type,
-    )
+/// The validators of the current state of a Graph Store Protocol target
+struct Validators {
+    etag: String,
+    last_modified: Option<SystemTime>,
+    exists: bool,
 }
 
-fn dataset_content_negotiation(request: &Request) -> Result<DatasetFormat, Response> {
-    content_negotiation(
-        request,
-        &[
-            DatasetFormat::NQuads.media_type(),
-            DatasetFormat::TriG.media_type(),
-        ],
-        DatasetFormat::from_media_type,
-    )
+/// Read access to the graph versions, either from the store or from a write transaction
+trait GraphVersions {
+    fn contains_named_graph(&self, graph_name: &NamedNode) -> Result<bool, StorageError>;
+
+    fn graph_version(
+        &self,
+        graph_name: GraphNameRef<'_>,
+    ) -> Result<Option<GraphVersion>, StorageError>;
+
+    fn named_graphs(&self) -> GraphNameIter;
 }
 
-fn query_results_content_negotiation(request: &Request) -> Result<QueryResultsFormat, Response> {
-    content_negotiation(
-        request,
-        &[
-            QueryResultsFormat::Json.media_type(),
-            QueryResultsFormat::Xml.media_type(),
-            QueryResultsFormat::Csv.media_type(),
-            QueryResultsFormat::Tsv.media_type(),
-        ],
-        QueryResultsFormat::from_media_type,
-    )
+impl GraphVersions for Store {
+    fn contains_named_graph(&self, graph_name: &NamedNode) -> Result<bool, StorageError> {
+        Store::contains_named_graph(self, graph_name)
+    }
+
+    fn graph_version(
+        &self,
+        graph_name: GraphNameRef<'_>,
+    ) -> Result<Option<GraphVersion>, StorageError> {
+        Store::graph_version(self, graph_name)
+    }
+
+    fn named_graphs(&self) -> GraphNameIter {
+        Store::named_graphs(self)
+    }
 }
 
-fn content_negotiation<F>(
+impl GraphVersions for Transaction<'_> {
+    fn contains_named_graph(&self, graph_name: &NamedNode) -> Result<bool, StorageError> {
+        Transaction::contains_named_graph(self, graph_name)
+    }
+
+    fn graph_version(
+        &self,
+        graph_name: GraphNameRef<'_>,
+    ) -> Result<Option<GraphVersion>, StorageError> {
+        Transaction::graph_version(self, graph_name)
+    }
+
+    fn named_graphs(&self) -> GraphNameIter {
+        Transaction::named_graphs(self)
+    }
+}
+
+/// Builds the validators of a graph from its version or of the full dataset if `target` is `None`
+fn store_validators(
+    store: &impl GraphVersions,
+    target: Option<&NamedGraphName>,
+) -> Result<Validators, StorageError> {
+    if let Some(target) = target {
+        let exists = match target {
+            NamedGraphName::DefaultGraph => true,
+            NamedGraphName::NamedNode(target) => store.contains_named_graph(target)?,
+        };
+        let version = store.graph_version(GraphName::from(target.clone()).as_ref())?;
+        Ok(Validators {
+            etag: format!("\"{}\"", version.map_or(0, |v| v.version)),
+            last_modified: version.map(|v| v.last_modified),
+            exists,
+        })
+    } else {
+        // The dataset tag is a hash of the versions of all the graphs.
+        // There is no Last-Modified date because removed graphs are not taken into account.
+        let mut hasher = DefaultHasher::new();
+        for graph_name in once(Ok(GraphName::DefaultGraph))
+            .chain(store.named_graphs().map(|g| g.map(GraphName::from)))
+        {
+            let graph_name = graph_name?;
+            let version = store.graph_version(graph_name.as_ref())?;
+            graph_name.hash(&mut hasher);
+            version.map(|v| v.version).hash(&mut hasher);
+        }
+        Ok(Validators {
+            etag: format!("\"{:x}\"", hasher.finish()),
+            last_modified: None,
+            exists: true,
+        })
+    }
+}
+
+/// Evaluates the `If-Match`, `If-None-Match` and `If-Modified-Since` headers of the request
+fn evaluate_preconditions(request: &Request, validators: &Validators) -> Result<(), Response> {
+    let is_read = matches!(request.method().as_ref(), "GET" | "HEAD");
+    if let Some(if_match) = request.header(&HeaderName::IF_MATCH) {
+        let if_match = if_match
+            .to_str()
+            .map_err(|_| bad_request("Invalid If-Match header"))?;
+        if !etag_matches(if_match, validators, true) {
+            return Err(error(
+                Status::PRECONDITION_FAILED,
+                "The If-Match precondition failed",
+            ));
+        }
+    }
+    if let Some(if_none_match) = request.header(&HeaderName::IF_NONE_MATCH) {
+        let if_none_match = if_none_match
+            .to_str()
+            .map_err(|_| bad_request("Invalid If-None-Match header"))?;
+        if etag_matches(if_none_match, validators, false) {
+            return Err(if is_read {
+                with_validators(
+                    Response::builder(Status::NOT_MODIFIED).build(),
+                    validators,
+                    request,
+                )
+            } else {
+                error(
+                    Status::PRECONDITION_FAILED,
+                    "The If-None-Match precondition failed",
+                )
+            });
+        }
+    } else if is_read {
+        // Invalid dates are ignored as required by RFC 7232
+        if let Some(since) = request
+            .header(&HeaderName::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_http_date(v).ok())
+        {
+            if validators
+                .last_modified
+                .map_or(false, |last_modified| last_modified <= since)
+            {
+                return Err(with_validators(
+                    Response::builder(Status::NOT_MODIFIED).build(),
+                    validators,
+                    request,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Appends the content coding to an entity tag, e.g. `"3"` becomes `"3-gzip"`
+fn coded_etag(etag: &str, encoding: Option<ContentEncoding>) -> String {
+    match encoding {
+        Some(ContentEncoding::Gzip) => format!("{}-gzip\"", etag.trim_end_matches('"')),
+        Some(ContentEncoding::Zstd) => format!("{}-zstd\"", etag.trim_end_matches('"')),
+        None => etag.into(),
+    }
+}
+
+/// Checks if an `If-Match` or `If-None-Match` header value matches the current entity tag
+fn etag_matches(header: &str, validators: &Validators, strong: bool) -> bool {
+    if !validators.exists {
+        return false;
+    }
+    if header.trim() == "*" {
+        return true;
+    }
+    // The tags of all the content codings of the current state match
+    let is_current = |tag: &str| {
+        [
+            None,
+            Some(ContentEncoding::Gzip),
+            Some(ContentEncoding::Zstd),
+        ]
+        .into_iter()
+        .any(|encoding| tag == coded_etag(&validators.etag, encoding))
+    };
+    header.split(',').map(str::trim).any(|tag| {
+        if let Some(tag) = tag.strip_prefix("W/") {
+            !strong && is_current(tag)
+        } else {
+            is_current(tag)
+        }
+    })
+}
+
+/// Evaluates the preconditions of a write request and applies `write` in the same transaction
+///
+/// The graph versions are locked by the transaction:
+/// if a concurrent request writes the same target, one of the two transactions is retried and evaluates the preconditions again.
+/// Returns the result of `write` and the validators of the target after the write.
+fn transactional_write<T>(
+    request: &Request,
+    store: &Store,
+    target: Option<&NamedGraphName>,
+    write: impl Fn(&mut Transaction<'_>) -> Result<T, LoaderError>,
+) -> Result<(T, Validators), Response> {
+    store
+        .transaction(|mut transaction| -> Result<_, LoaderError> {
+            if let Err(response) =
+                evaluate_preconditions(request, &store_validators(&transaction, target)?)
+            {
+                return Ok(Err(response));
+            }
+            let result = write(&mut transaction)?;
+            Ok(Ok((result, store_validators(&transaction, target)?)))
+        })
+        .map_err(|e| match e {
+            LoaderError::Parsing(e) => bad_request(e),
+            LoaderError::Storage(e) => internal_server_error(e),
+        })?
+}
+
+/// Adds the validators to the response
+///
+/// The representations of reads are compressed following the `Accept-Encoding` header:
+/// the content coding is appended to the entity tag so that each coding gets its own strong tag.
+fn with_validators(mut response: Response, validators: &Validators, request: &Request) -> Response {
+    let etag = match request.method().as_ref() {
+        "GET" | "HEAD" => coded_etag(&validators.etag, encoding_content_negotiation(request)),
+        _ => validators.etag.clone(),
+    };
+    response.append_header(HeaderName::ETAG, etag).unwrap();
+    if let Some(last_modified) = validators.last_modified {
+        response
+            .append_header(HeaderName::LAST_MODIFIED, fmt_http_date(last_modified))
+            .unwrap();
+    }
+    response
+}
+
+fn graph_content_negotiation(request: &Request) -> Result<GraphFormat, Response> {
+    content_negotiation(
+        request,
+        &GRAPH_FORMATS.map(GraphFormat::media_type),
+        GraphFormat::from_media_type,
+    )
+}
+
+fn dataset_content_negotiation(request: &Request) -> Result<DatasetFormat, Response> {
+    content_negotiation(
+        request,
+        &DATASET_FORMATS.map(DatasetFormat::media_type),
+        DatasetFormat::from_media_type,
+    )
+}
+
+fn query_results_content_negotiation(request: &Request) -> Result<QueryResultsFormat, Response> {
+    content_negotiation(
+        request,
+        &QUERY_RESULTS_FORMATS.map(QueryResultsFormat::media_type),
+        QueryResultsFormat::from_media_type,
+    )
+}
+
+fn content_negotiation<F>(
     request: &Request,
     supported: &[&str],
     parse: impl Fn(&str) -> Option<F>,
@@ -967,6 +2562,93 @@ fn content_negotiation<F>(
     parse(result).ok_or_else(|| error(Status::INTERNAL_SERVER_ERROR, "Unknown media type"))
 }
 
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+enum ContentEncoding {
+    Gzip,
+    Zstd,
+}
+
+/// Picks the compression of the response from the `Accept-Encoding` header, `None` meaning no compression
+fn encoding_content_negotiation(request: &Request) -> Option<ContentEncoding> {
+    let header = request
+        .header(&HeaderName::ACCEPT_ENCODING)?
+        .to_str()
+        .ok()?;
+    let mut gzip_score = None;
+    let mut zstd_score = None;
+    let mut default_score = None;
+    for possible in header.split(',') {
+        let (possible, parameters) = possible.split_once(';').unwrap_or((possible, ""));
+        let mut score = 1.;
+        for parameter in parameters.split(';') {
+            if let Some(s) = parameter.trim().strip_prefix("q=") {
+                score = f32::from_str(s.trim()).unwrap_or(0.);
+            }
+        }
+        match possible.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => gzip_score = Some(score),
+            "zstd" => zstd_score = Some(score),
+            "*" => default_score = Some(score),
+            _ => (),
+        }
+    }
+    let gzip_score = gzip_score.or(default_score).unwrap_or(0.);
+    let zstd_score = zstd_score.or(default_score).unwrap_or(0.);
+    if zstd_score > 0. && zstd_score >= gzip_score {
+        Some(ContentEncoding::Zstd)
+    } else if gzip_score > 0. {
+        Some(ContentEncoding::Gzip)
+    } else {
+        None
+    }
+}
+
+/// Reads the full decoded body of a request if it is not bigger than `limit` bytes
+///
+/// Graph Store writes are done in a transaction that might be retried on conflicts so their body must be replayable.
+fn buffered_body(request: &mut Request, limit: u64) -> Result<Vec<u8>, Response> {
+    let mut body = Vec::new();
+    decoded_body(request)?
+        .take(limit + 1)
+        .read_to_end(&mut body)
+        .map_err(bad_request)?;
+    if body.len() as u64 > limit {
+        // The limit applies to the decoded body to protect against decompression bombs
+        return Err(error(
+            Status::PAYLOAD_TOO_LARGE,
+            format!(
+                "The request body is bigger than the limit of {} bytes",
+                limit
+            ),
+        ));
+    }
+    Ok(body)
+}
+
+/// The request body decompressed according to the `Content-Encoding` header
+fn decoded_body(request: &mut Request) -> Result<Box<dyn Read + '_>, Response> {
+    let encoding = if let Some(encoding) = request.header(&HeaderName::CONTENT_ENCODING) {
+        encoding
+            .to_str()
+            .map_err(|_| bad_request("The Content-Encoding header should be a valid ASCII string"))?
+            .trim()
+            .to_ascii_lowercase()
+    } else {
+        String::new()
+    };
+    match encoding.as_str() {
+        "" | "identity" => Ok(Box::new(request.body_mut())),
+        "gzip" | "x-gzip" => Ok(Box::new(MultiGzDecoder::new(request.body_mut()))),
+        "zstd" => Ok(Box::new(
+            ZstdDecoder::new(request.body_mut()).map_err(internal_server_error)?,
+        )),
+        _ => Err(error(
+            Status::UNSUPPORTED_MEDIA_TYPE,
+            format!("Unsupported Content-Encoding: {}", encoding),
+        )),
+    }
+}
+
 fn content_type(request: &Request) -> Option<String> {
     let value = request.header(&HeaderName::CONTENT_TYPE)?.to_str().ok()?;
     Some(
@@ -1014,21 +2696,41 @@ impl<O: 'static, U: (Fn(O) -> std::io::Result<Option<O>>) + 'static> ReadForWrit
         initial_state_builder: impl FnOnce(ReadForWriteWriter) -> std::io::Result<O>,
         add_more_data: U,
         content_type: &'static str,
+        request: &Request,
     ) -> Response {
         let buffer = Rc::new(RefCell::new(Vec::new()));
-        match initial_state_builder(ReadForWriteWriter {
+        let body = match initial_state_builder(ReadForWriteWriter {
             buffer: buffer.clone(),
         }) {
-            Ok(state) => Response::builder(Status::OK)
-                .with_header(HeaderName::CONTENT_TYPE, content_type)
+            Ok(state) => Self {
+                buffer,
+                position: 0,
+                add_more_data,
+                state: Some(state),
+            },
+            Err(e) => return internal_server_error(e),
+        };
+        let response = Response::builder(Status::OK)
+            .with_header(HeaderName::CONTENT_TYPE, content_type)
+            .unwrap()
+            .with_header(HeaderName::VARY, "Accept-Encoding")
+            .unwrap();
+        match encoding_content_negotiation(request) {
+            Some(ContentEncoding::Gzip) => response
+                .with_header(HeaderName::CONTENT_ENCODING, "gzip")
                 .unwrap()
-                .with_body(Body::from_read(Self {
-                    buffer,
-                    position: 0,
-                    add_more_data,
-                    state: Some(state),
-                })),
-            Err(e) => internal_server_error(e),
+                .with_body(Body::from_read(GzEncoder::new(
+                    body,
+                    Compression::default(),
+                ))),
+            Some(ContentEncoding::Zstd) => match ZstdEncoder::new(body, 0) {
+                Ok(body) => response
+                    .with_header(HeaderName::CONTENT_ENCODING, "zstd")
+                    .unwrap()
+                    .with_body(Body::from_read(body)),
+                Err(e) => internal_server_error(e),
+            },
+            None => response.with_body(Body::from_read(body)),
         }
     }
 }
@@ -1084,6 +2786,9 @@ impl Write for ReadForWriteWriter {
 mod tests {
     use super::*;
     use oxhttp::model::Method;
+    use oxigraph::io::GraphParser;
+    use oxigraph::model::{NamedNodeRef, Quad, QuadRef, TermRef};
+    use std::{env, fs};
 
     #[test]
     fn get_ui() {
@@ -1111,6 +2816,107 @@ mod tests {
         ServerTest::new().test_status(request, Status::BAD_REQUEST)
     }
 
+    #[test]
+    fn patch_dataset() {
+        let server = ServerTest::new();
+        let request = Request::builder(Method::PATCH, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::CONTENT_TYPE, "application/rdf-patch")
+            .unwrap()
+            .with_body("TX .\nA <http://example.com> <http://example.com> <http://example.com> <http://example.com> .\nTC .\nTX .\nA <http://example.com> <http://example.com> \"aborted\" .\nTA .");
+        server.test_status(request, Status::NO_CONTENT);
+        let request = Request::builder(Method::GET, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::ACCEPT, "application/n-quads")
+            .unwrap()
+            .build();
+        server.test_body(
+            request,
+            "<http://example.com> <http://example.com> <http://example.com> <http://example.com> .\n",
+        );
+    }
+
+    #[test]
+    fn store_post_too_large() {
+        let mut server = ServerTest::new();
+        server.options.max_store_body_size = 64;
+        let request = Request::builder(
+            Method::POST,
+            "http://localhost/store?default".parse().unwrap(),
+        )
+        .with_header(HeaderName::CONTENT_TYPE, "application/n-triples")
+        .unwrap()
+        .with_body("<http://example.com> <http://example.com> <http://example.com> .\n<http://example.com> <http://example.com> \"too large\" .");
+        server.test_status(request, Status::PAYLOAD_TOO_LARGE);
+        let request = Request::builder(Method::GET, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::ACCEPT, "application/n-quads")
+            .unwrap()
+            .build();
+        server.test_body(request, "");
+    }
+
+    #[test]
+    fn patch_too_large() {
+        let mut server = ServerTest::new();
+        server.options.max_patch_body_size = 64;
+        let request = Request::builder(Method::PATCH, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::CONTENT_TYPE, "application/rdf-patch")
+            .unwrap()
+            .with_body("A <http://example.com> <http://example.com> <http://example.com> .\nA <http://example.com> <http://example.com> \"too large\" .");
+        server.test_status(request, Status::PAYLOAD_TOO_LARGE);
+        let request = Request::builder(Method::GET, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::ACCEPT, "application/n-quads")
+            .unwrap()
+            .build();
+        server.test_body(request, "");
+    }
+
+    #[test]
+    fn get_pretty_graph() {
+        let server = ServerTest::new();
+        let request = Request::builder(
+            Method::PUT,
+            "http://localhost/store?default".parse().unwrap(),
+        )
+        .with_header(HeaderName::CONTENT_TYPE, "text/turtle")
+        .unwrap()
+        .with_body("<http://example.com/s> a [ <http://example.com/p> (1 2) ] .");
+        server.test_status(request, Status::NO_CONTENT);
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/store?default&pretty&prefix=ex:http://example.com/"
+                .parse()
+                .unwrap(),
+        )
+        .with_header(HeaderName::ACCEPT, "text/turtle")
+        .unwrap()
+        .build();
+        server.test_body(
+            request,
+            "@prefix ex: <http://example.com/> .\n\nex:s a [ ex:p ( 1 2 ) ] .\n",
+        );
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/store?default&prefix=ex".parse().unwrap(),
+        )
+        .with_header(HeaderName::ACCEPT, "text/turtle")
+        .unwrap()
+        .build();
+        server.test_status(request, Status::BAD_REQUEST);
+    }
+
+    #[test]
+    fn patch_wrong_file() {
+        let request = Request::builder(Method::PATCH, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::CONTENT_TYPE, "application/rdf-patch")
+            .unwrap()
+            .with_body("TX .\nA <http://example.com> .\nTC .");
+        ServerTest::new().test_status(request, Status::BAD_REQUEST);
+        let request = Request::builder(Method::PATCH, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::CONTENT_TYPE, "text/turtle")
+            .unwrap()
+            .build();
+        ServerTest::new().test_status(request, Status::UNSUPPORTED_MEDIA_TYPE)
+    }
+
     #[test]
     fn post_unsupported_file() {
         let request = Request::builder(Method::POST, "http://localhost/store".parse().unwrap())
@@ -1145,6 +2951,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn query_plan_cache() {
+        let store = Store::new().unwrap();
+        let key = |query: &str| QueryPlanCacheKey {
+            query: query.to_owned(),
+            dataset: String::new(),
+            base_iri: "http://localhost/query".to_owned(),
+            use_default_graph_as_union: false,
+            default_graph_uris: Vec::new(),
+            named_graph_uris: Vec::new(),
+        };
+        let prepare = |query: &str| Rc::new(store.prepare_query(query).unwrap());
+        let mut cache = QueryPlanCache::default();
+        cache.insert(key("ASK {}"), prepare("ASK {}"), 2);
+        cache.insert(key("ASK { ?s ?p ?o }"), prepare("ASK { ?s ?p ?o }"), 2);
+        assert!(cache.get(&key("ASK {}")).is_some());
+        cache.insert(key("SELECT * {}"), prepare("SELECT * {}"), 2);
+        assert!(cache.get(&key("ASK {}")).is_some());
+        assert!(cache.get(&key("ASK { ?s ?p ?o }")).is_none());
+        assert!(cache.get(&key("SELECT * {}")).is_some());
+        cache.insert(key("ASK { ?s ?p ?o }"), prepare("ASK { ?s ?p ?o }"), 0);
+        assert!(cache.get(&key("ASK { ?s ?p ?o }")).is_none());
+    }
+
     #[test]
     fn get_query_accept_star() {
         let request = Request::builder(
@@ -1272,13 +3102,104 @@ mod tests {
     }
 
     #[test]
-    fn get_without_query() {
+    fn post_without_query() {
         ServerTest::new().test_status(
-            Request::builder(Method::GET, "http://localhost/query".parse().unwrap()).build(),
+            Request::builder(Method::POST, "http://localhost/query".parse().unwrap())
+                .with_header(
+                    HeaderName::CONTENT_TYPE,
+                    "application/x-www-form-urlencoded",
+                )
+                .unwrap()
+                .build(),
             Status::BAD_REQUEST,
         );
     }
 
+    #[test]
+    fn get_service_description() {
+        let server = ServerTest::new();
+        let request = Request::builder(Method::POST, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::CONTENT_TYPE, "application/trig")
+            .unwrap()
+            .with_body("<http://example.com/s> a <http://example.com/C> . <http://example.com/g> { <http://example.com/s> <http://example.com/p> 1 }");
+        server.test_status(request, Status::NO_CONTENT);
+
+        let request = Request::builder(Method::GET, "http://localhost/query".parse().unwrap())
+            .with_header(HeaderName::ACCEPT, "application/n-triples")
+            .unwrap()
+            .build();
+        let mut response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).unwrap();
+        let description = Graph::from_iter(
+            GraphParser::from_format(GraphFormat::NTriples)
+                .read_triples(body.as_bytes())
+                .unwrap()
+                .map(|t| t.unwrap()),
+        );
+        let sd = |name: &str| NamedNode::new_unchecked(format!("{}{}", SD_NAMESPACE, name));
+        let void = |name: &str| NamedNode::new_unchecked(format!("{}{}", VOID_NAMESPACE, name));
+        assert_eq!(
+            description
+                .subjects_for_predicate_object(rdf::TYPE, &sd("Service"))
+                .count(),
+            1
+        );
+        assert!(description.contains(&Triple::new(
+            description
+                .subject_for_predicate_object(rdf::TYPE, &sd("Service"))
+                .unwrap()
+                .into_owned(),
+            sd("endpoint"),
+            NamedNode::new_unchecked("http://localhost/query")
+        )));
+        assert_eq!(
+            description
+                .subjects_for_predicate_object(
+                    &sd("name"),
+                    &NamedNode::new_unchecked("http://example.com/g")
+                )
+                .count(),
+            1
+        );
+        let dataset = description
+            .subject_for_predicate_object(rdf::TYPE, &void("Dataset"))
+            .unwrap();
+        assert_eq!(
+            description
+                .object_for_subject_predicate(dataset, &void("triples"))
+                .map(TermRef::into_owned),
+            Some(Literal::from(2).into())
+        );
+        assert_eq!(
+            description
+                .triples_for_predicate(&void("classPartition"))
+                .count(),
+            2
+        );
+
+        // The cached statistics are updated after a write
+        server.test_status(
+            Request::builder(Method::POST, "http://localhost/update".parse().unwrap())
+                .with_header(HeaderName::CONTENT_TYPE, "application/sparql-update")
+                .unwrap()
+                .with_body("INSERT DATA { <http://example.com/s> <http://example.com/p> 2 }"),
+            Status::NO_CONTENT,
+        );
+        let mut response = server.exec(
+            Request::builder(Method::GET, "http://localhost/query".parse().unwrap())
+                .with_header(HeaderName::ACCEPT, "application/n-triples")
+                .unwrap()
+                .build(),
+        );
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).unwrap();
+        assert!(body.contains(
+            "<http://rdfs.org/ns/void#triples> \"3\"^^<http://www.w3.org/2001/XMLSchema#integer>"
+        ));
+    }
+
     #[test]
     fn post_query() {
         let request = Request::builder(Method::POST, "http://localhost/query".parse().unwrap())
@@ -1322,6 +3243,27 @@ mod tests {
         ServerTest::new().test_status(request, Status::OK)
     }
 
+    #[test]
+    fn post_federated_query_local_store() {
+        let service = Store::new().unwrap();
+        service
+            .insert(QuadRef::new(
+                NamedNodeRef::new_unchecked("http://example.com/s"),
+                NamedNodeRef::new_unchecked("http://example.com/p"),
+                NamedNodeRef::new_unchecked("http://example.com/o"),
+                GraphNameRef::DefaultGraph,
+            ))
+            .unwrap();
+        let server = ServerTest::new().with_service("http://example.com/service", service);
+        let request = Request::builder(Method::POST, "http://localhost/query".parse().unwrap())
+            .with_header(HeaderName::CONTENT_TYPE, "application/sparql-query")
+            .unwrap()
+            .with_header(HeaderName::ACCEPT, "text/csv")
+            .unwrap()
+            .with_body("SELECT ?s WHERE { SERVICE <http://example.com/service> { ?s ?p ?o } }");
+        server.test_body(request, "s\r\nhttp://example.com/s");
+    }
+
     #[test]
     fn post_update() {
         let request = Request::builder(Method::POST, "http://localhost/update".parse().unwrap())
@@ -1654,19 +3596,1071 @@ mod tests {
         server.test_status(request, Status::OK);
     }
 
-    struct ServerTest {
-        store: Store,
-    }
+    #[test]
+    fn graph_store_conditional_requests() {
+        let server = ServerTest::new();
+        let request = Request::builder(
+            Method::PUT,
+            "http://localhost/store/person/1.ttl".parse().unwrap(),
+        )
+        .with_header(HeaderName::CONTENT_TYPE, "text/turtle; charset=utf-8")
+        .unwrap()
+        .with_body("<http://example.com/s> <http://example.com/p> <http://example.com/o> .");
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::CREATED);
+        let etag = response
+            .header(&HeaderName::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
 
-    impl ServerTest {
-        fn new() -> Self {
-            Self {
-                store: Store::new().unwrap(),
-            }
-        }
+        // GET returns the same validators
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/store/person/1.ttl".parse().unwrap(),
+        )
+        .build();
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        assert_eq!(
+            response
+                .header(&HeaderName::ETAG)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            etag
+        );
+        assert!(response.header(&HeaderName::LAST_MODIFIED).is_some());
 
-        fn exec(&self, mut request: Request) -> Response {
-            handle_request(&mut request, self.store.clone())
+        // GET with the current tag
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/store/person/1.ttl".parse().unwrap(),
+        )
+        .with_header(HeaderName::IF_NONE_MATCH, etag.as_str())
+        .unwrap()
+        .build();
+        server.test_status(request, Status::NOT_MODIFIED);
+
+        // Compressed representations have their own tag
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/store/person/1.ttl".parse().unwrap(),
+        )
+        .with_header(HeaderName::ACCEPT_ENCODING, "gzip")
+        .unwrap()
+        .build();
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        let gzip_etag = response
+            .header(&HeaderName::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert_ne!(gzip_etag, etag);
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/store/person/1.ttl".parse().unwrap(),
+        )
+        .with_header(HeaderName::ACCEPT_ENCODING, "gzip")
+        .unwrap()
+        .with_header(HeaderName::IF_NONE_MATCH, gzip_etag.as_str())
+        .unwrap()
+        .build();
+        server.test_status(request, Status::NOT_MODIFIED);
+
+        // PUT only if the graph does not exist
+        let request = Request::builder(
+            Method::PUT,
+            "http://localhost/store/person/1.ttl".parse().unwrap(),
+        )
+        .with_header(HeaderName::CONTENT_TYPE, "text/turtle; charset=utf-8")
+        .unwrap()
+        .with_header(HeaderName::IF_NONE_MATCH, "*")
+        .unwrap()
+        .build();
+        server.test_status(request, Status::PRECONDITION_FAILED);
+
+        // POST with the current tag
+        let request = Request::builder(
+            Method::POST,
+            "http://localhost/store/person/1.ttl".parse().unwrap(),
+        )
+        .with_header(HeaderName::CONTENT_TYPE, "text/turtle; charset=utf-8")
+        .unwrap()
+        .with_header(HeaderName::IF_MATCH, etag.as_str())
+        .unwrap()
+        .with_body("<http://example.com/s> <http://example.com/p> <http://example.com/o2> .");
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::NO_CONTENT);
+        assert_ne!(
+            response
+                .header(&HeaderName::ETAG)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            etag
+        );
+
+        // DELETE with a stale tag
+        let request = Request::builder(
+            Method::DELETE,
+            "http://localhost/store/person/1.ttl".parse().unwrap(),
+        )
+        .with_header(HeaderName::IF_MATCH, etag.as_str())
+        .unwrap()
+        .build();
+        server.test_status(request, Status::PRECONDITION_FAILED);
+
+        // GET of the dataset with the current tag
+        let request = Request::builder(Method::GET, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::ACCEPT, "application/n-quads")
+            .unwrap()
+            .build();
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        let dataset_etag = response
+            .header(&HeaderName::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let request = Request::builder(Method::GET, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::ACCEPT, "application/n-quads")
+            .unwrap()
+            .with_header(HeaderName::IF_NONE_MATCH, dataset_etag.as_str())
+            .unwrap()
+            .build();
+        server.test_status(request, Status::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn cors() {
+        let server = ServerTest::new().with_cors(CorsOptions {
+            origins: vec!["http://example.com".into()],
+            methods: vec!["GET".into(), "POST".into()],
+            headers: vec!["Content-Type".into()],
+            allow_credentials: true,
+        });
+
+        // Preflight
+        let request = Request::builder(Method::OPTIONS, "http://localhost/query".parse().unwrap())
+            .with_header(HeaderName::ORIGIN, "http://example.com")
+            .unwrap()
+            .with_header(HeaderName::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .unwrap()
+            .with_header(HeaderName::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .unwrap()
+            .build();
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::NO_CONTENT);
+        for (name, value) in [
+            (
+                HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN,
+                "http://example.com",
+            ),
+            (HeaderName::ACCESS_CONTROL_ALLOW_METHODS, "POST"),
+            (HeaderName::ACCESS_CONTROL_ALLOW_HEADERS, "content-type"),
+            (HeaderName::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true"),
+            (HeaderName::VARY, "Origin"),
+        ] {
+            assert_eq!(response.header(&name).unwrap().to_str().unwrap(), value);
+        }
+
+        // Preflight with a not allowed method
+        let request = Request::builder(Method::OPTIONS, "http://localhost/store".parse().unwrap())
+            .with_header(HeaderName::ORIGIN, "http://example.com")
+            .unwrap()
+            .with_header(HeaderName::ACCESS_CONTROL_REQUEST_METHOD, "DELETE")
+            .unwrap()
+            .build();
+        server.test_status(request, Status::FORBIDDEN);
+
+        // Actual request
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/query?query=ASK%20%7B%7D".parse().unwrap(),
+        )
+        .with_header(HeaderName::ORIGIN, "http://example.com")
+        .unwrap()
+        .build();
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        assert_eq!(
+            response
+                .header(&HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "http://example.com"
+        );
+
+        // Request from an other origin
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/query?query=ASK%20%7B%7D".parse().unwrap(),
+        )
+        .with_header(HeaderName::ORIGIN, "http://example.org")
+        .unwrap()
+        .build();
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        assert!(response
+            .header(&HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+        assert_eq!(
+            response
+                .header(&HeaderName::VARY)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "Origin"
+        );
+
+        // Request without origin
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/query?query=ASK%20%7B%7D".parse().unwrap(),
+        )
+        .build();
+        let response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        assert_eq!(
+            response
+                .header(&HeaderName::VARY)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "Origin"
+        );
+    }
+
+    #[test]
+    fn monitoring() {
+        let server = ServerTest::new();
+        server.test_status(
+            Request::builder(Method::GET, "http://localhost/health/live".parse().unwrap()).build(),
+            Status::OK,
+        );
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/health/ready".parse().unwrap(),
+            )
+            .build(),
+            Status::OK,
+        );
+        server.test_status(
+            Request::builder(
+                Method::POST,
+                "http://localhost/store?default".parse().unwrap(),
+            )
+            .with_header(HeaderName::CONTENT_TYPE, "text/turtle")
+            .unwrap()
+            .with_body("<http://example.com> <http://example.com> <http://example.com> ."),
+            Status::NO_CONTENT,
+        );
+        server.test_body(
+            Request::builder(
+                Method::GET,
+                "http://localhost/query?query=ASK%7B%3Fs%20%3Fp%20%3Fo%7D"
+                    .parse()
+                    .unwrap(),
+            )
+            .with_header(HeaderName::ACCEPT, "application/sparql-results+json")
+            .unwrap()
+            .build(),
+            "{\"head\":{},\"boolean\":true}",
+        );
+
+        let mut response = server.exec(
+            Request::builder(Method::GET, "http://localhost/metrics".parse().unwrap()).build(),
+        );
+        assert_eq!(response.status(), Status::OK);
+        assert_eq!(
+            response
+                .header(&HeaderName::CONTENT_TYPE)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "text/plain; version=0.0.4"
+        );
+        let mut metrics = String::new();
+        response.body_mut().read_to_string(&mut metrics).unwrap();
+        for line in [
+            "oxigraph_http_requests_total{route=\"/store\",method=\"POST\",status=\"204\"} 1",
+            "oxigraph_http_requests_total{route=\"/query\",method=\"GET\",status=\"200\"} 1",
+            "oxigraph_http_request_duration_seconds_count{route=\"/query\",method=\"GET\",status=\"200\"} 1",
+            "# TYPE oxigraph_active_queries gauge",
+            "# TYPE oxigraph_store_quads gauge",
+            "# TYPE oxigraph_rocksdb_sst_files_bytes gauge",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "{} not in {}", line, metrics);
+        }
+        // The health checks are not recorded
+        assert!(!metrics.contains("/health"));
+    }
+
+    #[test]
+    fn logs() {
+        let access_log_path =
+            env::temp_dir().join(format!("oxigraph-access-{}.log", random::<u64>()));
+        let slow_query_log_path =
+            env::temp_dir().join(format!("oxigraph-slow-query-{}.log", random::<u64>()));
+        let mut server = ServerTest::new();
+        server.access_log = Some(JsonLinesLog::open(&access_log_path).unwrap());
+        server.options.slow_query_log = Some(SlowQueryLog {
+            log: JsonLinesLog::open(&slow_query_log_path).unwrap(),
+            threshold: Duration::from_secs(0),
+        });
+        server.test_body(
+            Request::builder(
+                Method::GET,
+                "http://localhost/query?query=SELECT%20*%20%7B%7D&named-graph-uri=http://example.com/g"
+                    .parse()
+                    .unwrap(),
+            )
+            .with_header(HeaderName::ACCEPT, "text/csv")
+            .unwrap()
+            .build(),
+            "\r\n\r\n",
+        );
+
+        let access_log = fs::read_to_string(&access_log_path).unwrap();
+        fs::remove_file(&access_log_path).unwrap();
+        assert!(
+            access_log.starts_with("{\"time\":"),
+            "Unexpected access log {}",
+            access_log
+        );
+        assert!(
+            access_log
+                .contains(",\"method\":\"GET\",\"path\":\"/query\",\"status\":200,\"duration\":"),
+            "Unexpected access log {}",
+            access_log
+        );
+        assert!(
+            access_log.ends_with(",\"bytes\":4}\n"),
+            "Unexpected access log {}",
+            access_log
+        );
+
+        let slow_query_log = fs::read_to_string(&slow_query_log_path).unwrap();
+        fs::remove_file(&slow_query_log_path).unwrap();
+        assert!(
+            slow_query_log.contains(",\"query\":\"SELECT * {}\",\"default_graph_uri\":[],\"named_graph_uri\":[\"http://example.com/g\"],\"dataset\":\"\",\"union_default_graph\":false,\"evaluation_time\":"),
+            "Unexpected slow query log {}",
+            slow_query_log
+        );
+        assert!(
+            slow_query_log.ends_with(",\"results\":1}\n"),
+            "Unexpected slow query log {}",
+            slow_query_log
+        );
+    }
+
+    #[test]
+    fn named_datasets() {
+        let server = ServerTest::new();
+        server.test_status(
+            Request::builder(Method::PUT, "http://localhost/datasets/a".parse().unwrap()).build(),
+            Status::CREATED,
+        );
+        server.test_status(
+            Request::builder(Method::PUT, "http://localhost/datasets/a".parse().unwrap()).build(),
+            Status::CONFLICT,
+        );
+        server.test_status(
+            Request::builder(
+                Method::PUT,
+                "http://localhost/datasets/b?read_only".parse().unwrap(),
+            )
+            .build(),
+            Status::CREATED,
+        );
+        server.test_status(
+            Request::builder(
+                Method::PUT,
+                "http://localhost/datasets/store".parse().unwrap(),
+            )
+            .build(),
+            Status::BAD_REQUEST,
+        );
+        server.test_body(
+            Request::builder(Method::GET, "http://localhost/datasets".parse().unwrap()).build(),
+            "[{\"name\":\"a\",\"read_only\":false},{\"name\":\"b\",\"read_only\":true}]",
+        );
+
+        // Each dataset has its own content
+        server.test_status(
+            Request::builder(Method::POST, "http://localhost/a/update".parse().unwrap())
+                .with_header(HeaderName::CONTENT_TYPE, "application/sparql-update")
+                .unwrap()
+                .with_body("INSERT DATA { <http://example.com> <http://example.com> <http://example.com> }"),
+            Status::NO_CONTENT,
+        );
+        let ask = |dataset: &str| {
+            Request::builder(
+                Method::GET,
+                format!(
+                    "http://localhost{}/query?query=ASK%7B%3Fs%20%3Fp%20%3Fo%7D",
+                    dataset
+                )
+                .parse()
+                .unwrap(),
+            )
+            .with_header(HeaderName::ACCEPT, "text/csv")
+            .unwrap()
+            .build()
+        };
+        server.test_body(ask("/a"), "true");
+        server.test_body(ask(""), "false");
+        server.test_body(ask("/b"), "false");
+
+        // Graph Store Protocol
+        let response = server.exec(
+            Request::builder(Method::POST, "http://localhost/a/store".parse().unwrap())
+                .with_header(HeaderName::CONTENT_TYPE, "text/turtle")
+                .unwrap()
+                .with_body("<http://example.com> <http://example.com> <http://example.com> ."),
+        );
+        assert_eq!(response.status(), Status::CREATED);
+        assert!(response
+            .header(&HeaderName::LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("http://localhost/a/store/"));
+
+        // Read-only datasets
+        server.test_status(
+            Request::builder(Method::POST, "http://localhost/b/update".parse().unwrap())
+                .with_header(HeaderName::CONTENT_TYPE, "application/sparql-update")
+                .unwrap()
+                .with_body("INSERT DATA { <http://example.com> <http://example.com> <http://example.com> }"),
+            Status::FORBIDDEN,
+        );
+        server.test_status(
+            Request::builder(
+                Method::DELETE,
+                "http://localhost/b/store?default".parse().unwrap(),
+            )
+            .build(),
+            Status::FORBIDDEN,
+        );
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/b/store?default".parse().unwrap(),
+            )
+            .build(),
+            Status::OK,
+        );
+
+        // Drop
+        server.test_status(
+            Request::builder(
+                Method::DELETE,
+                "http://localhost/datasets/a".parse().unwrap(),
+            )
+            .build(),
+            Status::NO_CONTENT,
+        );
+        server.test_status(ask("/a"), Status::NOT_FOUND);
+        server.test_status(
+            Request::builder(
+                Method::DELETE,
+                "http://localhost/datasets/a".parse().unwrap(),
+            )
+            .build(),
+            Status::NOT_FOUND,
+        );
+    }
+
+    #[test]
+    fn auth() {
+        let mut server = ServerTest::new();
+        server.auth = AuthOptions {
+            users: [("alice".to_owned(), "secret".to_owned())]
+                .into_iter()
+                .collect(),
+            anonymous_read: true,
+        };
+        let update = |authorization: Option<&str>| {
+            let mut request =
+                Request::builder(Method::POST, "http://localhost/update".parse().unwrap())
+                    .with_header(HeaderName::CONTENT_TYPE, "application/sparql-update")
+                    .unwrap();
+            if let Some(authorization) = authorization {
+                request = request
+                    .with_header(HeaderName::AUTHORIZATION, authorization)
+                    .unwrap();
+            }
+            request.with_body(
+                "INSERT DATA { <http://example.com> <http://example.com> <http://example.com> }",
+            )
+        };
+        let response = server.exec(update(None));
+        assert_eq!(response.status(), Status::UNAUTHORIZED);
+        assert!(response.header(&HeaderName::WWW_AUTHENTICATE).is_some());
+        server.test_status(
+            update(Some("Basic YWxpY2U6d3Jvbmc=")), // alice:wrong
+            Status::UNAUTHORIZED,
+        );
+        server.test_status(
+            update(Some("Basic YWxpY2U6c2VjcmV0")), // alice:secret
+            Status::NO_CONTENT,
+        );
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/query?query=ASK%7B%3Fs%20%3Fp%20%3Fo%7D"
+                    .parse()
+                    .unwrap(),
+            )
+            .build(),
+            Status::OK,
+        );
+        server.test_status(
+            Request::builder(Method::GET, "http://localhost/datasets".parse().unwrap()).build(),
+            Status::UNAUTHORIZED,
+        );
+        server.test_status(
+            Request::builder(Method::GET, "http://localhost/metrics".parse().unwrap()).build(),
+            Status::UNAUTHORIZED,
+        );
+        server.test_status(
+            Request::builder(Method::GET, "http://localhost/health/live".parse().unwrap()).build(),
+            Status::OK,
+        );
+    }
+
+    #[test]
+    fn union_default_graph_by_default() {
+        let mut server = ServerTest::new();
+        server.options.union_default_graph = true;
+        server.test_status(
+            Request::builder(Method::POST, "http://localhost/update".parse().unwrap())
+                .with_header(HeaderName::CONTENT_TYPE, "application/sparql-update")
+                .unwrap()
+                .with_body("INSERT DATA { GRAPH <http://example.com/g> { <http://example.com> <http://example.com> <http://example.com> } }"),
+            Status::NO_CONTENT,
+        );
+        let ask = |query: &str| {
+            Request::builder(
+                Method::GET,
+                format!("http://localhost/query?query={}", query)
+                    .parse()
+                    .unwrap(),
+            )
+            .with_header(HeaderName::ACCEPT, "text/csv")
+            .unwrap()
+            .build()
+        };
+        server.test_body(ask("ASK%7B%3Fs%20%3Fp%20%3Fo%7D"), "true");
+        // The dataset of the query is kept
+        server.test_body(
+            ask("ASK%20FROM%20%3Chttp%3A%2F%2Fexample.com%2Fh%3E%20%7B%3Fs%20%3Fp%20%3Fo%7D"),
+            "false",
+        );
+    }
+
+    #[test]
+    fn linked_data() {
+        let mut server = ServerTest::new();
+        server.options.linked_data = LinkedDataOptions::from_config(config::LinkedDataConfig {
+            graph: None,
+            mappings: vec![config::LinkedDataMappingConfig {
+                namespace: "http://example.com/id/".into(),
+                path: "/id/".into(),
+                document_path: Some("/doc/".into()),
+            }],
+        })
+        .unwrap();
+        server
+            .store
+            .load_graph(
+                b"<http://example.com/id/a> <http://example.com/p> [ <http://example.com/p> <http://example.com/id/b> ] ; <http://example.com/p> \"a\" .\n<http://example.com/id/b> <http://example.com/p> <http://example.com/id/c> .".as_ref(),
+                GraphFormat::Turtle,
+                GraphNameRef::DefaultGraph,
+                None,
+            )
+            .unwrap();
+
+        // Redirection to the document
+        let response = server
+            .exec(Request::builder(Method::GET, "http://localhost/id/a".parse().unwrap()).build());
+        assert_eq!(response.status(), Status::SEE_OTHER);
+        assert_eq!(
+            response
+                .header(&HeaderName::LOCATION)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "http://localhost/doc/a"
+        );
+
+        // Concise bounded description, following the blank nodes
+        let mut response = server.exec(
+            Request::builder(Method::GET, "http://localhost/doc/a".parse().unwrap())
+                .with_header(HeaderName::ACCEPT, "application/n-triples")
+                .unwrap()
+                .build(),
+        );
+        assert_eq!(response.status(), Status::OK);
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).unwrap();
+        assert_eq!(body.lines().count(), 3);
+        assert!(!body.contains("<http://example.com/id/c>"));
+
+        // HTML view linking to the local paths
+        let mut response = server.exec(
+            Request::builder(Method::GET, "http://localhost/doc/a".parse().unwrap())
+                .with_header(HeaderName::ACCEPT, "text/html")
+                .unwrap()
+                .build(),
+        );
+        assert_eq!(response.status(), Status::OK);
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).unwrap();
+        assert!(body.contains("href=\"/id/b\""));
+
+        // Unknown resource
+        server.test_status(
+            Request::builder(Method::GET, "http://localhost/doc/d".parse().unwrap()).build(),
+            Status::NOT_FOUND,
+        );
+        // The other routes are still served
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/store?default".parse().unwrap(),
+            )
+            .build(),
+            Status::OK,
+        );
+
+        // The server routes can't be shadowed
+        assert!(LinkedDataOptions::from_config(config::LinkedDataConfig {
+            graph: None,
+            mappings: vec![config::LinkedDataMappingConfig {
+                namespace: "http://example.com/id/".into(),
+                path: "/store/".into(),
+                document_path: None,
+            }],
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn browser() {
+        let server = ServerTest::new();
+        let ex = NamedNodeRef::new("http://example.com/s").unwrap();
+        let g = NamedNodeRef::new("http://example.com/g").unwrap();
+        for i in 0..150 {
+            server
+                .store
+                .insert(QuadRef::new(
+                    ex,
+                    ex,
+                    &Literal::from(i),
+                    GraphNameRef::DefaultGraph,
+                ))
+                .unwrap();
+        }
+        server.store.insert(QuadRef::new(ex, ex, ex, g)).unwrap();
+        let get = |url: &str| {
+            let mut response = server.exec(
+                Request::builder(
+                    Method::GET,
+                    format!("http://localhost{}", url).parse().unwrap(),
+                )
+                .build(),
+            );
+            let mut body = String::new();
+            response.body_mut().read_to_string(&mut body).unwrap();
+            assert_eq!(response.status(), Status::OK, "Error message: {}", body);
+            body
+        };
+
+        // Graphs with their number of quads
+        let body = get("/browse");
+        assert!(body.contains("<td>150</td>"));
+        assert!(body.contains("&lt;http://example.com/g&gt;</a></td><td>1</td>"));
+
+        // Resource with incoming and outgoing links to the resource pages
+        let body = get("/browse/resource?iri=http%3A%2F%2Fexample.com%2Fs");
+        assert!(body.contains("href=\"/browse/resource?iri=http%3A%2F%2Fexample.com%2Fs\""));
+        assert!(body.contains("<h2>Incoming</h2>"));
+
+        // Paging
+        let body = get("/browse/quads?graph=default&subject=%3Chttp%3A%2F%2Fexample.com%2Fs%3E");
+        assert_eq!(body.matches("<tr><td>").count(), 100);
+        assert!(body.contains(">Next</a>"));
+        let body =
+            get("/browse/quads?graph=default&subject=%3Chttp%3A%2F%2Fexample.com%2Fs%3E&page=1");
+        assert_eq!(body.matches("<tr><td>").count(), 50);
+        assert!(!body.contains(">Next</a>"));
+        assert!(body.contains(">Previous</a>"));
+
+        // Invalid pattern
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/browse/quads?predicate=%22foo%22"
+                    .parse()
+                    .unwrap(),
+            )
+            .build(),
+            Status::BAD_REQUEST,
+        );
+
+        // Too big page
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/browse/quads?page=18446744073709551615"
+                    .parse()
+                    .unwrap(),
+            )
+            .build(),
+            Status::BAD_REQUEST,
+        );
+    }
+
+    #[test]
+    fn fragments() {
+        let server = ServerTest::new();
+        let ex = NamedNodeRef::new("http://example.com/s").unwrap();
+        let g = NamedNodeRef::new("http://example.com/g").unwrap();
+        for i in 0..150 {
+            server
+                .store
+                .insert(QuadRef::new(ex, ex, &Literal::from(i), g))
+                .unwrap();
+        }
+        server
+            .store
+            .insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))
+            .unwrap();
+        let fragment = |query: &str, format: DatasetFormat| {
+            let mut response = server.exec(
+                Request::builder(
+                    Method::GET,
+                    format!("http://localhost/fragments?{}", query)
+                        .parse()
+                        .unwrap(),
+                )
+                .with_header(HeaderName::ACCEPT, format.media_type())
+                .unwrap()
+                .build(),
+            );
+            let mut body = String::new();
+            response.body_mut().read_to_string(&mut body).unwrap();
+            assert_eq!(response.status(), Status::OK, "Error message: {}", body);
+            let store = Store::new().unwrap();
+            store.load_dataset(body.as_bytes(), format, None).unwrap();
+            store
+        };
+        let hydra = |name: &str| {
+            NamedNode::new(format!("http://www.w3.org/ns/hydra/core#{}", name)).unwrap()
+        };
+
+        // First page of a quad pattern with its controls
+        let page = fragment(
+            "subject=http%3A%2F%2Fexample.com%2Fs&graph=http%3A%2F%2Fexample.com%2Fg",
+            DatasetFormat::TriG,
+        );
+        assert_eq!(
+            page.quads_for_pattern(None, None, None, Some(g.into()))
+                .count(),
+            100
+        );
+        let url = NamedNode::new(
+            "http://localhost/fragments?subject=http%3A%2F%2Fexample.com%2Fs&graph=http%3A%2F%2Fexample.com%2Fg",
+        )
+        .unwrap();
+        let metadata = NamedNode::new(format!("{}#metadata", url.as_str())).unwrap();
+        assert!(page
+            .contains(QuadRef::new(
+                &url,
+                &hydra("totalItems"),
+                &Literal::from(150),
+                &metadata
+            ))
+            .unwrap());
+        assert!(page
+            .contains(QuadRef::new(
+                &url,
+                &hydra("next"),
+                &NamedNode::new(format!("{}&page=2", url.as_str())).unwrap(),
+                &metadata
+            ))
+            .unwrap());
+
+        // Last page
+        let page = fragment(
+            "subject=http%3A%2F%2Fexample.com%2Fs&graph=http%3A%2F%2Fexample.com%2Fg&page=2",
+            DatasetFormat::NQuads,
+        );
+        assert_eq!(
+            page.quads_for_pattern(None, None, None, Some(g.into()))
+                .count(),
+            50
+        );
+        assert_eq!(
+            page.quads_for_pattern(None, Some(hydra("next").as_ref()), None, None)
+                .count(),
+            0
+        );
+
+        // Literal object in the default graph
+        let page = fragment("object=%2210%22%5E%5Ehttp%3A%2F%2Fwww.w3.org%2F2001%2FXMLSchema%23integer&graph=default", DatasetFormat::NQuads);
+        assert_eq!(
+            page.quads_for_pattern(None, Some(hydra("totalItems").as_ref()), None, None)
+                .next()
+                .unwrap()
+                .unwrap()
+                .object,
+            Literal::from(0).into()
+        );
+
+        // Triple format
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/fragments?predicate=http%3A%2F%2Fexample.com%2Fs"
+                    .parse()
+                    .unwrap(),
+            )
+            .with_header(HeaderName::ACCEPT, "text/turtle")
+            .unwrap()
+            .build(),
+            Status::OK,
+        );
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/fragments?predicate=%22foo%22"
+                    .parse()
+                    .unwrap(),
+            )
+            .build(),
+            Status::BAD_REQUEST,
+        );
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/fragments?page=18446744073709551615"
+                    .parse()
+                    .unwrap(),
+            )
+            .build(),
+            Status::BAD_REQUEST,
+        );
+    }
+
+    #[test]
+    fn fragments_estimated_count() {
+        let server = ServerTest::new();
+        let ex = NamedNode::new("http://example.com/s").unwrap();
+        server
+            .store
+            .bulk_loader()
+            .load_quads((0..10_001).map(|i| {
+                Quad::new(
+                    ex.clone(),
+                    ex.clone(),
+                    Literal::from(i),
+                    GraphName::DefaultGraph,
+                )
+            }))
+            .unwrap();
+        let mut response = server.exec(
+            Request::builder(
+                Method::GET,
+                "http://localhost/fragments?graph=default".parse().unwrap(),
+            )
+            .with_header(HeaderName::ACCEPT, "application/n-quads")
+            .unwrap()
+            .build(),
+        );
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).unwrap();
+        assert_eq!(response.status(), Status::OK, "Error message: {}", body);
+        let page = Store::new().unwrap();
+        page.load_dataset(body.as_bytes(), DatasetFormat::NQuads, None)
+            .unwrap();
+        let hydra = |name: &str| {
+            NamedNode::new(format!("http://www.w3.org/ns/hydra/core#{}", name)).unwrap()
+        };
+        assert_eq!(
+            page.quads_for_pattern(None, Some(hydra("totalItems").as_ref()), None, None)
+                .next()
+                .unwrap()
+                .unwrap()
+                .object,
+            Literal::from(10_000).into()
+        );
+        assert_eq!(
+            page.quads_for_pattern(None, Some(hydra("description").as_ref()), None, None)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn compression() {
+        let server = ServerTest::new();
+
+        // gzip upload
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(b"<http://example.com/s> <http://example.com/p> <http://example.com/o> .")
+            .unwrap();
+        let request = Request::builder(
+            Method::PUT,
+            "http://localhost/store?default".parse().unwrap(),
+        )
+        .with_header(HeaderName::CONTENT_TYPE, "application/n-triples")
+        .unwrap()
+        .with_header(HeaderName::CONTENT_ENCODING, "gzip")
+        .unwrap()
+        .with_body(encoder.finish().unwrap());
+        server.test_status(request, Status::NO_CONTENT);
+
+        // zstd upload
+        let request = Request::builder(Method::POST, "http://localhost/update".parse().unwrap())
+            .with_header(HeaderName::CONTENT_TYPE, "application/sparql-update")
+            .unwrap()
+            .with_header(HeaderName::CONTENT_ENCODING, "zstd")
+            .unwrap()
+            .with_body(
+                zstd::encode_all(
+                    b"INSERT DATA { <http://example.com/s> <http://example.com/p> <http://example.com/o2> }".as_ref(),
+                    0,
+                )
+                .unwrap(),
+            );
+        server.test_status(request, Status::NO_CONTENT);
+
+        // Unsupported encoding
+        let request = Request::builder(
+            Method::POST,
+            "http://localhost/store?default".parse().unwrap(),
+        )
+        .with_header(HeaderName::CONTENT_TYPE, "application/n-triples")
+        .unwrap()
+        .with_header(HeaderName::CONTENT_ENCODING, "br")
+        .unwrap()
+        .build();
+        server.test_status(request, Status::UNSUPPORTED_MEDIA_TYPE);
+
+        // gzip download
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/store?default".parse().unwrap(),
+        )
+        .with_header(HeaderName::ACCEPT, "application/n-triples")
+        .unwrap()
+        .with_header(HeaderName::ACCEPT_ENCODING, "zstd;q=0.5, gzip")
+        .unwrap()
+        .build();
+        let mut response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        assert_eq!(
+            response
+                .header(&HeaderName::CONTENT_ENCODING)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "gzip"
+        );
+        let mut body = String::new();
+        MultiGzDecoder::new(response.body_mut())
+            .read_to_string(&mut body)
+            .unwrap();
+        assert_eq!(body.lines().count(), 2);
+
+        // zstd download
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/store?default".parse().unwrap(),
+        )
+        .with_header(HeaderName::ACCEPT, "application/n-triples")
+        .unwrap()
+        .with_header(HeaderName::ACCEPT_ENCODING, "gzip, zstd")
+        .unwrap()
+        .build();
+        let mut response = server.exec(request);
+        assert_eq!(response.status(), Status::OK);
+        let mut body = String::new();
+        ZstdDecoder::new(response.body_mut())
+            .unwrap()
+            .read_to_string(&mut body)
+            .unwrap();
+        assert_eq!(body.lines().count(), 2);
+    }
+
+    struct ServerTest {
+        store: Store,
+        options: EndpointOptions,
+        cors: CorsOptions,
+        auth: AuthOptions,
+        metrics: RequestMetrics,
+        access_log: Option<JsonLinesLog>,
+        datasets: Datasets,
+    }
+
+    impl ServerTest {
+        fn new() -> Self {
+            Self {
+                store: Store::new().unwrap(),
+                options: EndpointOptions {
+                    query_plan_cache_size: 16,
+                    max_sparql_body_size: DEFAULT_MAX_SPARQL_BODY_SIZE,
+                    max_patch_body_size: DEFAULT_MAX_PATCH_BODY_SIZE,
+                    max_store_body_size: DEFAULT_MAX_STORE_BODY_SIZE,
+                    ..EndpointOptions::default()
+                },
+                cors: CorsOptions::default(),
+                auth: AuthOptions::default(),
+                metrics: RequestMetrics::default(),
+                access_log: None,
+                datasets: Datasets::open(None, StorageOptions::default()).unwrap(),
+            }
+        }
+
+        fn with_cors(mut self, cors: CorsOptions) -> Self {
+            self.cors = cors;
+            self
+        }
+
+        fn with_service(mut self, name: &str, store: Store) -> Self {
+            self.options
+                .services
+                .push((NamedNode::new(name).unwrap(), store));
+            self
+        }
+
+        fn exec(&self, mut request: Request) -> Response {
+            log_access(&mut request, self.access_log.as_ref(), |request| {
+                handle_cors(request, &self.cors, |request| {
+                    handle_auth(request, &self.auth, |request| {
+                        handle_monitoring(request, &self.store, &self.metrics, |request| {
+                            handle_dataset_request(
+                                request,
+                                &self.store,
+                                &self.datasets,
+                                true,
+                                &self.options,
+                            )
+                        })
+                    })
+                })
+            })
         }
 
         fn test_status(&self, request: Request, expected_status: Status) {
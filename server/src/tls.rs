@@ -0,0 +1,453 @@
+//! HTTPS support.
+//!
+//! oxhttp only serves plain HTTP so the TLS connections are terminated with rustls
+//! and the HTTP/1.1 requests they carry are decoded in-process by this module.
+//! The decrypted content is never sent to another socket.
+
+use oxhttp::model::{Body, HeaderName, Method, Request, Response, Status};
+use rustls::server::AllowAnyAuthenticatedClient;
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+use rustls_pemfile::Item;
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+const BUFFER_SIZE: usize = 16 * 1024;
+const MAX_HEAD_SIZE: u64 = 64 * 1024;
+/// Size of the request bodies left unread by the handler that are skipped to keep the connection alive
+const MAX_DRAINED_BODY_SIZE: u64 = 1024 * 1024;
+
+/// The function answering to the HTTP requests
+pub type Handler = Arc<dyn Fn(&mut Request) -> Response + Send + Sync>;
+
+/// The certificates used to serve HTTPS
+#[derive(Clone)]
+pub struct TlsOptions {
+    /// PEM file with the certificate chain
+    pub cert: PathBuf,
+    /// PEM file with the private key
+    pub key: PathBuf,
+    /// PEM file with the certificate authorities the client certificates must be signed by, if they are required
+    pub client_ca: Option<PathBuf>,
+}
+
+impl TlsOptions {
+    /// Loads the certificates from the files
+    fn server_config(&self) -> io::Result<Arc<ServerConfig>> {
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = if let Some(client_ca) = &self.client_ca {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(client_ca)? {
+                roots.add(&cert).map_err(|e| {
+                    invalid_data(format!(
+                        "Invalid client certificate authority in {}: {:?}",
+                        client_ca.display(),
+                        e
+                    ))
+                })?;
+            }
+            builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots))
+        } else {
+            builder.with_no_client_auth()
+        };
+        let mut config = builder
+            .with_single_cert(load_certs(&self.cert)?, load_private_key(&self.key)?)
+            .map_err(invalid_data)?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+}
+
+/// Accepts TLS connections on `bind` and answers to the HTTP requests they carry with `handler`
+///
+/// On Unix the certificates are reloaded from the files when the process receives `SIGHUP`.
+/// The connections idle for more than `timeout` are closed.
+pub fn listen(
+    bind: &str,
+    options: TlsOptions,
+    handler: Handler,
+    server_name: &'static str,
+    timeout: Duration,
+) -> io::Result<()> {
+    let config = Arc::new(RwLock::new(options.server_config()?));
+    #[cfg(unix)]
+    reload_on_sighup(options, config.clone())?;
+    for client in TcpListener::bind(bind)?.incoming() {
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => {
+                eprintln!("Error while accepting a TLS connection: {}", e);
+                continue;
+            }
+        };
+        let config = config.read().unwrap().clone();
+        let handler = handler.clone();
+        thread::spawn(move || {
+            if let Err(e) = serve_connection(client, config, &handler, server_name, timeout) {
+                eprintln!("Error on a TLS connection: {}", e)
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn reload_on_sighup(options: TlsOptions, config: Arc<RwLock<Arc<ServerConfig>>>) -> io::Result<()> {
+    use signal_hook::consts::SIGHUP;
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new(&[SIGHUP])?;
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            match options.server_config() {
+                Ok(new_config) => {
+                    *config.write().unwrap() = new_config;
+                    eprintln!("TLS certificates reloaded");
+                }
+                Err(e) => eprintln!(
+                    "Error while reloading the TLS certificates, the previous ones are kept: {}",
+                    e
+                ),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// The decrypted connection, shared between the request body reader and the response writer
+type Stream = Rc<RefCell<BufReader<StreamOwned<ServerConnection, TcpStream>>>>;
+
+/// Answers to the HTTP/1.1 requests sent through a TLS connection until it is closed
+fn serve_connection(
+    client: TcpStream,
+    config: Arc<ServerConfig>,
+    handler: &Handler,
+    server_name: &str,
+    timeout: Duration,
+) -> io::Result<()> {
+    client.set_read_timeout(Some(timeout))?;
+    client.set_write_timeout(Some(timeout))?;
+    let connection = ServerConnection::new(config).map_err(invalid_data)?;
+    let stream: Stream = Rc::new(RefCell::new(BufReader::with_capacity(
+        BUFFER_SIZE,
+        StreamOwned::new(connection, client),
+    )));
+    loop {
+        let head = match read_head(&mut *stream.borrow_mut()) {
+            Ok(Some(head)) => head,
+            Ok(None) => return Ok(()),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Ok(()); // Idle connection
+            }
+            Err(e) if e.kind() == ErrorKind::InvalidInput => {
+                return write_response(&stream, bad_request(e), false, server_name, false);
+            }
+            Err(e) => return Err(e),
+        };
+        let (mut request, is_body_read, keep_alive) = match parse_request(&head, &stream) {
+            Ok(request) => request,
+            Err(e) => return write_response(&stream, bad_request(e), false, server_name, false),
+        };
+        let is_head = request.method().as_ref() == "HEAD";
+        let response = handler(&mut request);
+        // The next request could only be read if the body of this one has been fully read
+        io::copy(
+            &mut request.body_mut().take(MAX_DRAINED_BODY_SIZE),
+            &mut io::sink(),
+        )?;
+        let keep_alive = keep_alive && is_body_read.get();
+        drop(request);
+        write_response(&stream, response, is_head, server_name, keep_alive)?;
+        if !keep_alive {
+            return Ok(());
+        }
+    }
+}
+
+/// Reads the request line and the headers, returns `None` if the connection is closed before a new request
+fn read_head(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut head = Vec::new();
+    loop {
+        let len = (&mut *reader)
+            .take(MAX_HEAD_SIZE - head.len() as u64)
+            .read_until(b'\n', &mut head)?;
+        if len == 0 {
+            return if head.is_empty() {
+                Ok(None)
+            } else if head.len() as u64 >= MAX_HEAD_SIZE {
+                Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "The request headers are too big",
+                ))
+            } else {
+                Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "The connection has been closed in the middle of a request",
+                ))
+            };
+        }
+        if head == b"\r\n" || head == b"\n" {
+            head.clear(); // Empty lines before the request line are ignored
+        } else if head.ends_with(b"\n\r\n") || head.ends_with(b"\n\n") {
+            return Ok(Some(head));
+        }
+    }
+}
+
+/// Builds the request from its head, its body being read from the stream
+///
+/// Also returns a flag set once the body has been fully read and if the connection should be kept alive.
+fn parse_request(head: &[u8], stream: &Stream) -> Result<(Request, Rc<Cell<bool>>, bool), String> {
+    let mut headers = [httparse::EMPTY_HEADER; 128];
+    let mut parsed = httparse::Request::new(&mut headers);
+    match parsed.parse(head) {
+        Ok(httparse::Status::Complete(_)) => (),
+        Ok(httparse::Status::Partial) => return Err("Incomplete request".into()),
+        Err(e) => return Err(format!("Invalid request: {}", e)),
+    }
+    let method = parsed
+        .method
+        .unwrap_or_default()
+        .parse::<Method>()
+        .map_err(|e| format!("Invalid method: {}", e))?;
+    let target = parsed.path.unwrap_or_default();
+    let url = if target.starts_with('/') {
+        let host = parsed
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case("host"))
+            .and_then(|h| str::from_utf8(h.value).ok())
+            .ok_or("The Host header is required")?;
+        Url::parse(&format!("https://{}{}", host, target))
+    } else {
+        Url::parse(target)
+    }
+    .map_err(|e| format!("Invalid request target {}: {}", target, e))?;
+    if url.scheme() != "https" {
+        return Err(format!("Invalid request target {}", target));
+    }
+
+    let mut builder = Request::builder(method, url);
+    let mut content_length = None;
+    let mut transfer_codings = Vec::new();
+    let mut expects_continue = false;
+    let mut keep_alive = parsed.version == Some(1);
+    for header in parsed.headers.iter() {
+        let value = str::from_utf8(header.value)
+            .map_err(|_| format!("The {} header value is not valid UTF-8", header.name))?;
+        if header.name.eq_ignore_ascii_case("content-length") {
+            content_length = Some(
+                value
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid Content-Length: {}", value))?,
+            );
+        } else if header.name.eq_ignore_ascii_case("transfer-encoding") {
+            transfer_codings.extend(value.split(',').map(|c| c.trim().to_ascii_lowercase()));
+        } else if header.name.eq_ignore_ascii_case("expect") {
+            expects_continue = value.trim().eq_ignore_ascii_case("100-continue");
+        } else if header.name.eq_ignore_ascii_case("connection") {
+            keep_alive &= !value.to_ascii_lowercase().contains("close");
+        }
+        builder = builder
+            .with_header(
+                header
+                    .name
+                    .parse::<HeaderName>()
+                    .map_err(|e| format!("Invalid header name {}: {}", header.name, e))?,
+                value,
+            )
+            .map_err(|e| format!("Invalid {} header value: {}", header.name, e))?;
+    }
+    // We reject the ambiguous framings that could be used for request smuggling (RFC 7230 section 3.3.3)
+    let is_chunked = !transfer_codings.is_empty();
+    if is_chunked {
+        if content_length.is_some() {
+            return Err(
+                "The Content-Length and Transfer-Encoding headers must not be both set".into(),
+            );
+        }
+        if transfer_codings.last().map(String::as_str) != Some("chunked") {
+            return Err(format!(
+                "The final transfer coding must be chunked, found {}",
+                transfer_codings.join(", ")
+            ));
+        }
+    }
+    let content_length = content_length.unwrap_or(0);
+    let is_body_read = Rc::new(Cell::new(!is_chunked && content_length == 0));
+    if expects_continue && !is_body_read.get() {
+        stream
+            .borrow_mut()
+            .get_mut()
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .map_err(|e| e.to_string())?;
+    }
+    let body = BodyReader {
+        stream: stream.clone(),
+        remaining: (!is_chunked).then(|| content_length),
+        is_chunked,
+        is_read: is_body_read.clone(),
+    };
+    Ok((
+        builder.with_body(Body::from_read(body)),
+        is_body_read,
+        keep_alive,
+    ))
+}
+
+/// Reads a request body from the connection, with a known length or using the chunked transfer encoding
+struct BodyReader {
+    stream: Stream,
+    /// Number of bytes left in the body or in the current chunk, `None` if the next chunk size has to be read
+    remaining: Option<u64>,
+    is_chunked: bool,
+    is_read: Rc<Cell<bool>>,
+}
+
+impl Read for BodyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.is_read.get() || buf.is_empty() {
+            return Ok(0);
+        }
+        let mut stream = self.stream.borrow_mut();
+        let remaining = if let Some(remaining) = self.remaining {
+            remaining
+        } else {
+            let mut line = String::new();
+            (&mut *stream).take(1024).read_line(&mut line)?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = u64::from_str_radix(size, 16)
+                .map_err(|_| invalid_data(format!("Invalid chunk size: {}", size)))?;
+            if size == 0 {
+                // Last chunk, we skip the trailers
+                loop {
+                    line.clear();
+                    if (&mut *stream).take(MAX_HEAD_SIZE).read_line(&mut line)? == 0
+                        || line.trim().is_empty()
+                    {
+                        break;
+                    }
+                }
+                self.is_read.set(true);
+                return Ok(0);
+            }
+            size
+        };
+        let max = usize::try_from(remaining)
+            .unwrap_or(usize::MAX)
+            .min(buf.len());
+        let len = stream.read(&mut buf[..max])?;
+        if len == 0 {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "The connection has been closed in the middle of a request body",
+            ));
+        }
+        let remaining = remaining - len as u64;
+        if remaining > 0 {
+            self.remaining = Some(remaining);
+        } else if self.is_chunked {
+            // We skip the line break after the chunk data
+            (&mut *stream).take(2).read_line(&mut String::new())?;
+            self.remaining = None;
+        } else {
+            self.is_read.set(true);
+        }
+        Ok(len)
+    }
+}
+
+fn write_response(
+    stream: &Stream,
+    mut response: Response,
+    is_head: bool,
+    server_name: &str,
+    keep_alive: bool,
+) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} \r\n", u16::from(response.status())).into_bytes();
+    for (name, value) in response.headers() {
+        head.extend_from_slice(name.as_bytes());
+        head.extend_from_slice(b": ");
+        head.extend_from_slice(value);
+        head.extend_from_slice(b"\r\n");
+    }
+    head.extend_from_slice(format!("Server: {}\r\n", server_name).as_bytes());
+    if !keep_alive {
+        head.extend_from_slice(b"Connection: close\r\n");
+    }
+    let length = response.body().len();
+    if let Some(length) = length {
+        head.extend_from_slice(format!("Content-Length: {}\r\n", length).as_bytes());
+    } else if !is_head {
+        head.extend_from_slice(b"Transfer-Encoding: chunked\r\n");
+    }
+    head.extend_from_slice(b"\r\n");
+
+    let mut stream = stream.borrow_mut();
+    let writer = stream.get_mut();
+    writer.write_all(&head)?;
+    if !is_head {
+        if length.is_some() {
+            io::copy(response.body_mut(), writer)?;
+        } else {
+            let mut buffer = vec![0; BUFFER_SIZE];
+            loop {
+                let len = response.body_mut().read(&mut buffer)?;
+                writer.write_all(format!("{:X}\r\n", len).as_bytes())?;
+                writer.write_all(&buffer[..len])?;
+                writer.write_all(b"\r\n")?;
+                if len == 0 {
+                    break;
+                }
+            }
+        }
+    }
+    if !keep_alive {
+        writer.conn.send_close_notify();
+    }
+    writer.flush()
+}
+
+fn bad_request(message: impl ToString) -> Response {
+    Response::builder(Status::BAD_REQUEST).with_body(message.to_string())
+}
+
+fn load_certs(path: &Path) -> io::Result<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(path)?))?;
+    if certs.is_empty() {
+        return Err(invalid_data(format!(
+            "No certificate found in {}",
+            path.display()
+        )));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &Path) -> io::Result<PrivateKey> {
+    for item in rustls_pemfile::read_all(&mut BufReader::new(File::open(path)?))? {
+        match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => {
+                return Ok(PrivateKey(key))
+            }
+            _ => (),
+        }
+    }
+    Err(invalid_data(format!(
+        "No private key found in {}",
+        path.display()
+    )))
+}
+
+fn invalid_data(error: impl Into<Box<dyn Error + Send + Sync>>) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, error)
+}
@@ -67,6 +67,14 @@ pub fn register_sparql_tests(evaluator: &mut TestEvaluator) {
         "https://github.com/oxigraph/oxigraph/tests#NegativeTsvResultsSyntaxTest",
         evaluate_negative_tsv_result_syntax_test,
     );
+    evaluator.register(
+        "https://github.com/oxigraph/oxigraph/tests#PositiveCsvResultsSyntaxTest",
+        evaluate_positive_csv_result_syntax_test,
+    );
+    evaluator.register(
+        "https://github.com/oxigraph/oxigraph/tests#NegativeCsvResultsSyntaxTest",
+        evaluate_negative_csv_result_syntax_test,
+    );
 }
 
 fn evaluate_positive_syntax_test(test: &Test) -> Result<()> {
@@ -135,6 +143,18 @@ fn evaluate_negative_tsv_result_syntax_test(test: &Test) -> Result<()> {
     }
 }
 
+fn evaluate_positive_csv_result_syntax_test(test: &Test) -> Result<()> {
+    result_syntax_check(test, QueryResultsFormat::Csv)
+}
+
+fn evaluate_negative_csv_result_syntax_test(test: &Test) -> Result<()> {
+    if result_syntax_check(test, QueryResultsFormat::Csv).is_ok() {
+        Err(anyhow!("Oxigraph parses even if it should not {}.", test))
+    } else {
+        Ok(())
+    }
+}
+
 fn result_syntax_check(test: &Test, format: QueryResultsFormat) -> Result<()> {
     let results_file = test
         .action
@@ -340,6 +360,11 @@ fn load_sparql_query_result(url: &str) -> Result<StaticQueryResults> {
             QueryResults::read(read_file(url)?, QueryResultsFormat::Tsv)?,
             false,
         )
+    } else if url.ends_with(".csv") {
+        StaticQueryResults::from_query_results(
+            QueryResults::read(read_file(url)?, QueryResultsFormat::Csv)?,
+            false,
+        )
     } else {
         StaticQueryResults::from_graph(load_graph(url)?)
     }